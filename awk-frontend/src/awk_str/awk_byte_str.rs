@@ -37,12 +37,6 @@ impl AwkByteStr {
     pub fn new(bytes: Vec<u8>) -> AwkByteStr {
         Self { bytes }
     }
-    fn new_rc(bytes: Vec<u8>) -> RcAwkStr {
-        RcAwkStr::new(AwkByteStr::new(bytes))
-    }
-    fn with_capacity(cap: usize) -> AwkByteStr {
-        Self { bytes: Vec::with_capacity(cap) }
-    }
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
     }
@@ -63,7 +57,7 @@ impl AwkByteStr {
         AwkByteStr::new(self.bytes.to_ascii_uppercase())
     }
     pub fn push_str(&mut self, other: &[u8]) {
-        self.bytes.extend_from_slice(&other)
+        self.bytes.extend_from_slice(other)
     }
     pub fn as_mut_vec(&mut self) -> &mut Vec<u8> {
         &mut self.bytes
@@ -78,7 +72,7 @@ impl AwkByteStr {
         RcAwkStr::new(self)
     }
     pub fn truthy(&self) -> bool {
-        self.bytes.len() != 0
+        !self.bytes.is_empty()
     }
     pub fn done(self) -> Vec<u8> {
         self.bytes
@@ -59,10 +59,6 @@ impl AwkStr {
         std::mem::swap(self.get_mut_awkstr(), &mut byte_str);
     }
 
-    pub fn clone(&self) -> Self {
-        Self { backing: Rc::new(AwkByteStr::new(self.backing.bytes().to_vec())) }
-    }
-
     pub fn done(self) -> Vec<u8> {
         // Relies on invariant that backing has strong count 1 weak count 0
         let res = unwrap_err(Rc::try_unwrap(self.backing));
@@ -70,6 +66,13 @@ impl AwkStr {
     }
 }
 
+// Deep copy, the backing Rc must stay unique for done() and get_mut_awkstr
+impl Clone for AwkStr {
+    fn clone(&self) -> Self {
+        Self { backing: Rc::new(AwkByteStr::new(self.backing.bytes().to_vec())) }
+    }
+}
+
 impl Debug for AwkStr {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.backing)
@@ -0,0 +1,8 @@
+mod awk_byte_str;
+#[allow(clippy::module_inception)]
+mod awk_str;
+mod rc_awk_str;
+
+pub use crate::awk_str::awk_str::AwkStr;
+pub use crate::awk_str::rc_awk_str::RcAwkStr;
+pub use crate::awk_str::awk_byte_str::AwkByteStr;
@@ -1,3 +1,4 @@
+use std::mem::ManuallyDrop;
 use std::ops::Deref;
 use std::rc::Rc;
 use crate::awk_str::awk_byte_str::AwkByteStr;
@@ -17,15 +18,18 @@ impl Deref for RcAwkStr {
 }
 
 impl RcAwkStr {
+    /// # Safety
+    /// The pointer must be passed back to from_raw or the string is leaked
     pub unsafe fn into_raw(self) -> *const AwkByteStr {
         Rc::into_raw(self.str)
     }
+    /// # Safety
+    /// string must come from into_raw and still be live. The pointer keeps its reference,
+    /// the returned RcAwkStr holds a new one.
     pub unsafe fn from_raw(string: *const AwkByteStr) -> RcAwkStr {
-        let original = unsafe { Rc::from_raw(string) };
-        let copy = original.clone();
-        Rc::into_raw(original);
+        let original = ManuallyDrop::new(unsafe { Rc::from_raw(string) });
         Self {
-            str: copy
+            str: Rc::clone(&original)
         }
     }
     pub fn rc(str: Rc<AwkByteStr>) -> Self {
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 
 pub trait SymbolMappingValue {
-    fn create(symbol: &Symbol, idx: usize) -> Self;
+    fn create(idx: usize) -> Self;
 }
 
 // Maps Symbols into a generic type. In this impl it's structs wrapping a usize identifier.
@@ -12,6 +12,12 @@ pub struct SymbolMapping<T: SymbolMappingValue> {
     mapping: HashMap<Symbol, T>,
 }
 
+impl<T: SymbolMappingValue> Default for SymbolMapping<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: SymbolMappingValue> SymbolMapping<T> {
     pub fn new() -> Self {
         Self {
@@ -19,11 +25,9 @@ impl<T: SymbolMappingValue> SymbolMapping<T> {
         }
     }
     pub fn insert(&mut self, symbol: &Symbol) {
-        if self.mapping.contains_key(&symbol) {
-            return;
-        } else {
-            self.mapping.insert(symbol.clone(), T::create(symbol,self.mapping.len()))
-        };
+        if !self.mapping.contains_key(symbol) {
+            self.mapping.insert(symbol.clone(), T::create(self.mapping.len()));
+        }
     }
 
     pub fn get(&self, symbol: &Symbol) -> Option<&T> {
@@ -41,4 +45,8 @@ impl<T: SymbolMappingValue> SymbolMapping<T> {
     pub fn len(&self) -> usize {
         self.mapping.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.mapping.is_empty()
+    }
 }
@@ -1,5 +1,4 @@
 use std::iter::Peekable;
-use std::str::Chars;
 use crate::printable_error::PrintableError;
 use crate::util::unwrap;
 
@@ -13,7 +12,7 @@ const SEVEN: char = 0x37 as char;
 // https://pubs.opengroup.org/onlinepubs/009604499/utilities/awk.html "Table: Escape Sequences in awk"
 
 // The starting " is already consumed from the iterator and a non-escaped closing quote must exist or returns Err
-pub fn escaped_string_reader(characters: &mut Peekable<Chars>) -> Result<Vec<u8>, PrintableError> {
+pub fn escaped_string_reader(characters: &mut Peekable<impl Iterator<Item = char>>) -> Result<Vec<u8>, PrintableError> {
     let msg = match escaped_reader::<'"'>(characters, false) {
         Ok(v) => return Ok(v),
         Err(err) => {
@@ -29,7 +28,9 @@ pub fn escaped_string_reader(characters: &mut Peekable<Chars>) -> Result<Vec<u8>
 }
 
 // The starting / is already consumed from the iterator and a non-escaped closing / must exist or returns Err
-pub fn escaped_regex_reader(characters: &mut Peekable<Chars>) -> Result<Vec<u8>, PrintableError> {
+// Not used by the lexer yet, which reads regex literals in Lexer::regex
+#[allow(dead_code)]
+pub fn escaped_regex_reader(characters: &mut Peekable<impl Iterator<Item = char>>) -> Result<Vec<u8>, PrintableError> {
     let msg = match escaped_reader::<'/'>(characters, true) {
         Ok(v) => return Ok(v),
         Err(err) => {
@@ -44,6 +45,24 @@ pub fn escaped_regex_reader(characters: &mut Peekable<Chars>) -> Result<Vec<u8>,
     Err(PrintableError::new(msg))
 }
 
+// Escapes in -v and command line assignments. There is no terminator, the whole arg is the value.
+// A NUL can't appear in a command line argument so it's used as the terminator.
+pub fn escaped_cli_reader(value: &str) -> Result<Vec<u8>, PrintableError> {
+    let mut terminated = value.to_string();
+    terminated.push('\0');
+    let msg = match escaped_reader::<'\0'>(&mut terminated.chars().peekable(), false) {
+        Ok(v) => return Ok(v),
+        Err(err) => {
+            match err {
+                EscapingError::UnknownEscapeSeq(chr) => format!("\\{} is an unknown awk escape sequence in `{}`", chr, value),
+                EscapingError::NewLine => format!("Command line assignment `{}` may not contain a line break", value),
+                EscapingError::Unterminated => format!("Command line assignment `{}` may not end in a single \\", value),
+                EscapingError::ForwardSlash => format!("\\/ is an unknown awk escape sequence in `{}`", value),
+            }
+        }
+    };
+    Err(PrintableError::new(msg))
+}
 
 enum EscapingError {
     UnknownEscapeSeq(char),
@@ -54,7 +73,7 @@ enum EscapingError {
 
 // Used for ERE and string parsing by swapping terminator.
 // escaped_fwd_slash is false for string but true for regex
-fn escaped_reader<const TERMINATOR: char>(characters: &mut Peekable<Chars>, escaped_fwd_slash: bool) -> Result<Vec<u8>, EscapingError> {
+fn escaped_reader<const TERMINATOR: char>(characters: &mut Peekable<impl Iterator<Item = char>>, escaped_fwd_slash: bool) -> Result<Vec<u8>, EscapingError> {
     let mut output = vec![];
     let mut escaped = false;
     let mut scratch_bytes: [u8; 4] = [0; 4];
@@ -89,7 +108,7 @@ fn escaped_reader<const TERMINATOR: char>(characters: &mut Peekable<Chars>, esca
                 return Err(EscapingError::NewLine)
             } else {
                 let str = char.encode_utf8(&mut scratch_bytes);
-                let bytes_used = str.as_bytes().len();
+                let bytes_used = str.len();
                 debug_assert!(bytes_used > 0);
                 debug_assert!(bytes_used <= 4);
                 output.extend_from_slice(&scratch_bytes[0..bytes_used])
@@ -112,16 +131,16 @@ fn saturating_octal_parse(char1: u8, char2: u8, char3: u8) -> u8 {
     c1.saturating_add(c2).saturating_add(char3)
 }
 
-fn next_is_octal(characters: &mut Peekable<Chars>) -> Option<u8> {
+fn next_is_octal(characters: &mut Peekable<impl Iterator<Item = char>>) -> Option<u8> {
     if let Some(peeked) = characters.peek() {
-        if (ZERO..=SEVEN).contains(&peeked) {
+        if (ZERO..=SEVEN).contains(peeked) {
             return Some(unwrap(characters.next()) as u8 - ZERO as u8)
         }
     }
     None
 }
 
-fn octal_escape(char1: u8, characters: &mut Peekable<Chars>) -> u8 {
+fn octal_escape(char1: u8, characters: &mut Peekable<impl Iterator<Item = char>>) -> u8 {
     if let Some(char2) = next_is_octal(characters) {
         if let Some(char3) = next_is_octal(characters) {
             saturating_octal_parse(char1, char2, char3)
@@ -2,10 +2,13 @@ mod types;
 mod escaped_string_reader;
 
 pub use types::{BinOp, LogicalOp, MathOp, Token, TokenType};
-pub use escaped_string_reader::escaped_string_reader;
+pub use escaped_string_reader::{escaped_cli_reader, escaped_string_reader};
 
 use crate::{PrintableError, Symbolizer};
+use crate::printable_error::Span;
+use std::cell::Cell;
 use std::iter::Peekable;
+use std::rc::Rc;
 use std::str::Chars;
 
 use crate::awk_str::{AwkStr, RcAwkStr};
@@ -18,6 +21,13 @@ pub fn lex(str: &str, symbolizer: &mut Symbolizer) -> LexerResult {
     Ok(lexer.tokens)
 }
 
+// Like lex but also returns where each token starts, for parse_spanned
+pub fn lex_spanned(str: &str, symbolizer: &mut Symbolizer) -> Result<(Vec<Token>, Vec<Span>), PrintableError> {
+    let mut lexer = Lexer::new(str, symbolizer);
+    lexer.scan_tokens()?;
+    Ok((lexer.tokens, lexer.spans))
+}
+
 #[cfg(test)]
 fn lex_test(str: &str, symbolizer: &mut Symbolizer) -> LexerResult {
     let mut lexer = Lexer::new(str, symbolizer);
@@ -25,26 +35,55 @@ fn lex_test(str: &str, symbolizer: &mut Symbolizer) -> LexerResult {
     Ok(lexer.tokens)
 }
 
+// The chars of the program. Remembers where the last char it handed out came from, the lexer
+// reads that through position since the Peekable wrapping this hides it.
+struct Source<'a> {
+    chars: Chars<'a>,
+    next: Span,
+    last: Rc<Cell<Span>>,
+}
+
+impl<'a> Iterator for Source<'a> {
+    type Item = char;
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.last.set(self.next);
+        if c == '\n' {
+            self.next = Span::new(self.next.line + 1, 1);
+        } else {
+            self.next.column += 1;
+        }
+        Some(c)
+    }
+}
+
 struct Lexer<'a, 'b> {
-    src: Peekable<Chars<'a>>,
+    src: Peekable<Source<'a>>,
+    position: Rc<Cell<Span>>,
+    // Start of the token being scanned
+    start: Span,
     buffer: String,
-    line: usize,
     tokens: Vec<Token>,
+    spans: Vec<Span>,
     symbolizer: &'b mut Symbolizer,
 }
 
 impl<'a, 'b> Lexer<'a, 'b> {
     fn new(src: &'a str, symbolizer: &'b mut Symbolizer) -> Lexer<'a, 'b> {
+        let position = Rc::new(Cell::new(Span::new(1, 1)));
+        let source = Source { chars: src.chars(), next: Span::new(1, 1), last: position.clone() };
         Lexer {
-            src: src.chars().peekable(),
-            line: 0,
+            src: source.peekable(),
+            position,
+            start: Span::new(1, 1),
             tokens: Vec::with_capacity(1000),
+            spans: Vec::with_capacity(1000),
             buffer: String::with_capacity(30),
             symbolizer,
         }
     }
     fn is_at_end(&mut self) -> bool {
-        !self.src.peek().is_some()
+        self.src.peek().is_none()
     }
     fn collect_buffer(&mut self) -> String {
         let mut string: String = String::new();
@@ -59,6 +98,7 @@ impl<'a, 'b> Lexer<'a, 'b> {
 
     fn add_token(&mut self, tt: Token) {
         self.tokens.push(tt);
+        self.spans.push(self.start);
     }
 
     fn regex(&mut self) -> Result<(), PrintableError> {
@@ -66,10 +106,6 @@ impl<'a, 'b> Lexer<'a, 'b> {
         // a ~ /match/'
         self.buffer.clear();
         while self.peek() != '/' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
-            }
-
             self.advance();
         }
 
@@ -83,21 +119,21 @@ impl<'a, 'b> Lexer<'a, 'b> {
         let regex = self.collect_buffer();
         self.advance();
         self.add_token(Token::Regex(AwkStr::new_string(regex).rc()));
-        return Ok(());
+        Ok(())
     }
     fn number(&mut self) -> Result<Token, PrintableError> {
-        while self.peek().is_digit(10) {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
-        if self.matches('.') {}
-        while self.peek().is_digit(10) {
+        self.matches('.');
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
         // Awk allows floats like 3e but rust won't parse them.
         // If we read an e but no numbers after we need to drop the e from the buffer
         let mut buf_len = self.buffer.len();
         if self.matches('e') {
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
                 buf_len = self.buffer.len()
             }
@@ -108,7 +144,7 @@ impl<'a, 'b> Lexer<'a, 'b> {
         match num.parse::<f64>() {
             Ok(float) => Ok(Token::NumberF64(float)),
             Err(_) => {
-                return Err(PrintableError::new(format!("Unable to parse f64 `{}`", num)));
+                Err(PrintableError::new(format!("Unable to parse f64 `{}`", num)))
             }
         }
     }
@@ -149,6 +185,14 @@ impl<'a, 'b> Lexer<'a, 'b> {
             self.add_token(Token::Break);
         } else if src_lowercase == "printf" {
             self.add_token(Token::Printf);
+        } else if src_lowercase == "getline" {
+            self.add_token(Token::Getline);
+        } else if src_lowercase == "beginfile" {
+            self.add_token(Token::BeginFile);
+        } else if src_lowercase == "endfile" {
+            self.add_token(Token::EndFile);
+        } else if src_lowercase == "nextfile" {
+            self.add_token(Token::NextFile);
         } else {
             let ident = self.symbolizer.get_from_string(src);
             self.add_token(Token::Ident(ident));
@@ -206,9 +250,7 @@ impl<'a, 'b> Lexer<'a, 'b> {
             '|' => {
                 let tt = match self.matches('|') {
                     true => Token::LogicalOp(LogicalOp::Or),
-                    false => {
-                        return Err(PrintableError::new("| must be followed by ||".to_string()))
-                    }
+                    false => Token::Pipe,
                 };
                 self.add_token(tt);
             }
@@ -291,9 +333,9 @@ impl<'a, 'b> Lexer<'a, 'b> {
             '\r' => (),
             '\t' => (),
             ' ' => (),
-            '\n' => self.line += 1,
+            '\n' => (),
             _ => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     let num = self.number()?;
                     self.add_token(num);
                 } else if c.is_alphabetic() {
@@ -322,21 +364,38 @@ impl<'a, 'b> Lexer<'a, 'b> {
                     self.src.next();
                     return true;
                 }
-                return false;
+                false
             }
         }
     }
 
     fn scan_tokens(&mut self) -> Result<Vec<Token>, PrintableError> {
         while !self.is_at_end() {
-            self.scan_token()?;
+            // is_at_end peeked so position is the first char of this token
+            self.start = self.position.get();
+            let start = self.start;
+            self.scan_token().map_err(|err| err.at(start))?;
             self.buffer.clear();
         }
-        self.tokens.push(Token::EOF);
+        self.start = self.position.get();
+        self.add_token(Token::EOF);
         Ok(self.tokens.clone())
     }
 }
 
+#[test]
+fn test_spans() {
+    let mut symbolizer = Symbolizer::new();
+    let (tokens, spans) = lex_spanned("BEGIN {\n  x = \"a\\tb\" }", &mut symbolizer).unwrap();
+    assert_eq!(tokens.len(), spans.len());
+    assert_eq!(
+        spans,
+        vec![Span::new(1, 1), Span::new(1, 7), Span::new(2, 3), Span::new(2, 5), Span::new(2, 7), Span::new(2, 14), Span::new(2, 14)]
+    );
+    let err = lex("BEGIN { x = \"abc", &mut symbolizer).unwrap_err();
+    assert_eq!(err.span, Some(Span::new(1, 13)));
+}
+
 #[test]
 fn test_braces() {
     let mut symbolizer = Symbolizer::new();
@@ -562,6 +621,41 @@ fn test_begin_end() {
     );
 }
 
+#[test]
+fn test_getline() {
+    let mut symbolizer = Symbolizer::new();
+    let str = "\"cmd\" | getline x || y";
+    assert_eq!(
+        lex_test(str, &mut symbolizer).unwrap(),
+        vec![
+            Token::String(RcAwkStr::new_bytes(b"cmd".to_vec())),
+            Token::Pipe,
+            Token::Getline,
+            Token::Ident(symbolizer.get("x")),
+            Token::LogicalOp(LogicalOp::Or),
+            Token::Ident(symbolizer.get("y")),
+            Token::EOF
+        ]
+    );
+}
+
+#[test]
+fn test_begin_end_file() {
+    let mut symbolizer = Symbolizer::new();
+    let str = "BEGINFILE beginfile ENDFILE endfile nextfile";
+    assert_eq!(
+        lex_test(str, &mut symbolizer).unwrap(),
+        vec![
+            Token::BeginFile,
+            Token::BeginFile,
+            Token::EndFile,
+            Token::EndFile,
+            Token::NextFile,
+            Token::EOF
+        ]
+    );
+}
+
 #[test]
 fn test_ident() {
     let mut symbolizer = Symbolizer::new();
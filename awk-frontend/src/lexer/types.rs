@@ -110,6 +110,12 @@ pub enum Token {
     In,
     Comma,
     Break,
+    Getline,
+    // | before getline
+    Pipe,
+    BeginFile,
+    EndFile,
+    NextFile,
 }
 
 // bitflags for very fast comparisons / union membership tests
@@ -166,6 +172,11 @@ pub enum TokenType {
     Comma = 0b100000000000000000000000000000000000000000000000,
     Function = 0b1000000000000000000000000000000000000000000000000,
     Break = 0b10000000000000000000000000000000000000000000000000,
+    Getline = 0b100000000000000000000000000000000000000000000000000,
+    Pipe = 0b1000000000000000000000000000000000000000000000000000,
+    BeginFile = 0b10000000000000000000000000000000000000000000000000000,
+    EndFile = 0b100000000000000000000000000000000000000000000000000000,
+    NextFile = 0b1000000000000000000000000000000000000000000000000000000,
 }
 
 impl Token {
@@ -227,6 +238,11 @@ impl Token {
             Token::In => TokenType::In,
             Token::Comma => TokenType::Comma,
             Token::Break => TokenType::Break,
+            Token::Getline => TokenType::Getline,
+            Token::Pipe => TokenType::Pipe,
+            Token::BeginFile => TokenType::BeginFile,
+            Token::EndFile => TokenType::EndFile,
+            Token::NextFile => TokenType::NextFile,
             Token::Printf => TokenType::Printf,
         }
     }
@@ -284,6 +300,11 @@ impl TokenType {
             TokenType::In => "in",
             TokenType::Comma => ",",
             TokenType::Break => "break",
+            TokenType::Getline => "getline",
+            TokenType::Pipe => "|",
+            TokenType::BeginFile => "BEGINFILE",
+            TokenType::EndFile => "ENDFILE",
+            TokenType::NextFile => "nextfile",
             TokenType::Printf => "printf",
         }
     }
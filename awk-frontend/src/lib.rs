@@ -0,0 +1,20 @@
+// Lexer, parser, and type inference shared by the awk backends. Each backend takes the
+// TypedProgram returned by analyze and compiles it however it likes.
+
+pub mod awk_str;
+pub mod global_scalars;
+pub mod lexer;
+pub mod parser;
+pub mod printable_error;
+pub mod specials;
+pub mod stack_counter;
+pub mod stackt;
+pub mod symbolizer;
+pub mod typing;
+pub mod util;
+
+pub use crate::lexer::{lex, lex_spanned};
+pub use crate::parser::{parse, parse_spanned};
+pub use crate::printable_error::{PrintableError, Span};
+pub use crate::symbolizer::Symbolizer;
+pub use crate::typing::analyze;
@@ -2,29 +2,35 @@ mod test;
 mod transformer;
 mod types;
 mod variable;
+mod walk;
 
 use crate::lexer::{BinOp, LogicalOp, MathOp, Token, TokenType};
-use crate::parser::transformer::transform;
-use crate::printable_error::PrintableError;
+use crate::parser::transformer::{transform, transform_with_file_rules};
+use crate::printable_error::{PrintableError, Span};
 use crate::symbolizer::Symbol;
-use crate::typing::BuiltinFunc;
-use crate::{AnalysisResults, Symbolizer};
+use crate::typing::AnalysisResults;
+use crate::Symbolizer;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
 pub use crate::parser::types::PatternAction;
-pub use types::{Arg, ArgT, Expr, LValue, Function, ScalarType, Stmt, TypedExpr};
+pub use types::{Arg, ArgT, Expr, GetlineSource, LValue, Function, ScalarType, Stmt, TypedExpr};
 pub use variable::Variable;
+pub use walk::{walk_expr, walk_stmt};
 pub use crate::specials::{SclSpecial, ArrSpecial};
 
 // Pattern Action Type
 // Normal eg: $1 == "a" { doSomething() }
 // Begin 'BEGIN { ... }'
 // End  'END { .... }'
+// BeginFile 'BEGINFILE { ... }'
+// EndFile 'ENDFILE { ... }'
 enum PAType {
     Normal(PatternAction),
     Begin(Stmt),
     End(Stmt),
+    BeginFile(Stmt),
+    EndFile(Stmt),
 }
 
 #[derive(Debug, PartialEq)]
@@ -36,7 +42,7 @@ pub struct Program {
 
 impl Program {
     #[cfg(test)]
-    fn new_action_only(name: Symbol, action: Stmt, mut symbolizer: Symbolizer) -> Program {
+    fn new_action_only(name: Symbol, action: Stmt, symbolizer: Symbolizer) -> Program {
         let body = transform(vec![], vec![], vec![PatternAction::new_action_only(action)]);
         let mut functions = HashMap::new();
         functions.insert(name.clone(), Function::new(name, vec![], body).unwrap());
@@ -52,9 +58,11 @@ impl Program {
         ends: Vec<Stmt>,
         pas: Vec<PatternAction>,
         parsed_functions: Vec<Function>,
-        mut symbolizer: Symbolizer,
+        symbolizer: Symbolizer,
     ) -> Program {
-        let body = transform(begins, ends, pas);
+        Program::with_body(transform(begins, ends, pas), parsed_functions, symbolizer)
+    }
+    fn with_body(body: Stmt, parsed_functions: Vec<Function>, mut symbolizer: Symbolizer) -> Program {
         let main_fn_name = symbolizer.get("main function");
         let main = Function::main(body, &mut symbolizer);
         let mut functions = HashMap::new();
@@ -74,11 +82,11 @@ impl Display for Program {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         // Tests will print the program and compare it with another string
         // keep function order consistent by sorting.
-        let mut sorted: Vec<Symbol> = self.functions.iter().map(|(sym, _)| sym.clone()).collect();
+        let mut sorted: Vec<Symbol> = self.functions.keys().cloned().collect();
         sorted.sort();
         for func_name in &sorted {
             let func = self.functions.get(func_name).unwrap();
-            write!(f, "{}\n", func)?;
+            writeln!(f, "{}", func)?;
         }
         Ok(())
     }
@@ -106,9 +114,16 @@ const STRING_CONCAT_SKIPS: u64 = TokenType::InplaceAssign as u64
     | TokenType::In as u64
     | TokenType::LeftBracket as u64
     | TokenType::RightBracket as u64
-    | TokenType::Printf as u64;
+    | TokenType::Printf as u64
+    | TokenType::Pipe as u64;
 
 pub fn parse(tokens: Vec<Token>, symbolizer: &mut Symbolizer) -> Result<Program, PrintableError> {
+    parse_spanned(tokens, &[], symbolizer)
+}
+
+// spans are where each token starts (see lex_spanned). Errors point at the token the parser
+// was looking at when it gave up.
+pub fn parse_spanned(tokens: Vec<Token>, spans: &[Span], symbolizer: &mut Symbolizer) -> Result<Program, PrintableError> {
     let sub = symbolizer.get("sub");
     let gsub = symbolizer.get("gsub");
     let mut parser = Parser {
@@ -118,7 +133,11 @@ pub fn parse(tokens: Vec<Token>, symbolizer: &mut Symbolizer) -> Result<Program,
         sub,
         gsub,
     };
-    parser.parse()
+    let result = parser.parse();
+    result.map_err(|err| match spans.get(parser.current).or(spans.last()) {
+        Some(span) => err.at(*span),
+        None => err,
+    })
 }
 
 struct Parser<'a> {
@@ -146,6 +165,8 @@ impl<'a> Parser<'a> {
     fn parse(&mut self) -> Result<Program, PrintableError> {
         let mut begins = vec![];
         let mut ends = vec![];
+        let mut begin_files = vec![];
+        let mut end_files = vec![];
         let mut pattern_actions = vec![];
         let mut functions = vec![];
         while !self.is_at_end() {
@@ -182,16 +203,13 @@ impl<'a> Parser<'a> {
                     PAType::Normal(pa) => pattern_actions.push(pa),
                     PAType::Begin(pa) => begins.push(pa),
                     PAType::End(pa) => ends.push(pa),
+                    PAType::BeginFile(pa) => begin_files.push(pa),
+                    PAType::EndFile(pa) => end_files.push(pa),
                 };
             }
         }
-        Ok(Program::new(
-            begins,
-            ends,
-            pattern_actions,
-            functions,
-            self.symbolizer.clone(),
-        ))
+        let body = transform_with_file_rules(begins, begin_files, pattern_actions, end_files, ends);
+        Ok(Program::with_body(body, functions, self.symbolizer.clone()))
     }
 
     fn check(&mut self, typ: TokenType) -> bool {
@@ -214,15 +232,6 @@ impl<'a> Parser<'a> {
         )))
     }
 
-    // Caller guarantees that last token parsed was an ident
-    fn prior_indent_name_infallible(&mut self) -> Symbol {
-        if let Some(Token::Ident(name)) = self.previous() {
-            name.clone()
-        } else {
-            unreachable!()
-        }
-    }
-
     // The next N tokens type's match tokens arg
     fn matches_series(&mut self, tokens: &[TokenType]) -> bool {
         for (idx, tt) in tokens.iter().enumerate() {
@@ -232,7 +241,13 @@ impl<'a> Parser<'a> {
             }
         }
         self.current += tokens.len();
-        return true;
+        true
+    }
+
+    // for (var in array) rather than for (init; test; incr)
+    fn is_for_in(&self) -> bool {
+        let series = [TokenType::Ident, TokenType::In, TokenType::Ident, TokenType::RightParen];
+        series.iter().enumerate().all(|(idx, tt)| self.peek_at(self.current + idx).ttype() == *tt)
     }
 
     // The current token is present in the tokens bitflags
@@ -303,6 +318,18 @@ impl<'a> Parser<'a> {
             let pa = PAType::End(self.stmts()?);
             self.consume(TokenType::RightBrace, "End action should end with '}'")?;
             pa
+        } else if self.matches(flags!(TokenType::BeginFile)) {
+            // BEGINFILE { print FILENAME; }
+            self.consume(TokenType::LeftBrace, "Expected a `{` after a BEGINFILE")?;
+            let pa = PAType::BeginFile(self.stmts()?);
+            self.consume(TokenType::RightBrace, "BEGINFILE action should end with '}'")?;
+            pa
+        } else if self.matches(flags!(TokenType::EndFile)) {
+            // ENDFILE { print FNR; }
+            self.consume(TokenType::LeftBrace, "Expected a `{` after a ENDFILE")?;
+            let pa = PAType::EndFile(self.stmts()?);
+            self.consume(TokenType::RightBrace, "ENDFILE action should end with '}'")?;
+            pa
         } else {
             let test = self.expression()?;
             if self.matches(flags!(TokenType::LeftBrace)) {
@@ -353,30 +380,36 @@ impl<'a> Parser<'a> {
             Stmt::Printf { fstring, args }
         } else if self.matches(flags!(TokenType::Break)) {
             Stmt::Break
+        } else if self.matches(flags!(TokenType::NextFile)) {
+            Stmt::NextFile
         } else if self.matches(flags!(TokenType::For)) {
             self.consume(TokenType::LeftParen, "Expected a `(` after the for keyword")?;
-            let init = self.stmt()?;
-            self.consume(
-                TokenType::Semicolon,
-                "Expected a `;` after for loop init statement",
-            )?;
-            let test = self.expression()?;
-            self.consume(
-                TokenType::Semicolon,
-                "Expected a `;` after for loop test statement",
-            )?;
-            let incr = self.stmt()?;
-            self.consume(TokenType::RightParen, "Expected a `)` to end for loop")?;
-            self.consume(
-                TokenType::LeftBrace,
-                "Expected a `{` to begin for loop body",
-            )?;
-            let body = self.stmts()?;
-            self.consume(TokenType::RightBrace, "Expected a `}` after for loop body")?;
-            Stmt::Group(vec![
-                init,
-                Stmt::While(test, Box::new(Stmt::Group(vec![body, incr]))),
-            ])
+            if self.is_for_in() {
+                self.for_in()?
+            } else {
+                let init = self.stmt()?;
+                self.consume(
+                    TokenType::Semicolon,
+                    "Expected a `;` after for loop init statement",
+                )?;
+                let test = self.expression()?;
+                self.consume(
+                    TokenType::Semicolon,
+                    "Expected a `;` after for loop test statement",
+                )?;
+                let incr = self.stmt()?;
+                self.consume(TokenType::RightParen, "Expected a `)` to end for loop")?;
+                self.consume(
+                    TokenType::LeftBrace,
+                    "Expected a `{` to begin for loop body",
+                )?;
+                let body = self.stmts()?;
+                self.consume(TokenType::RightBrace, "Expected a `}` after for loop body")?;
+                Stmt::Group(vec![
+                    init,
+                    Stmt::While(test, Box::new(Stmt::Group(vec![body, incr]))),
+                ])
+            }
         } else if self.peek_next().ttype() == TokenType::Eq {
             let str = if let Token::Ident(str) = self.consume(TokenType::Ident, "Expected identifier before `=`")?
             {
@@ -432,6 +465,20 @@ impl<'a> Parser<'a> {
         Ok(Stmt::Group(stmts))
     }
 
+    fn for_in(&mut self) -> Result<Stmt, PrintableError> {
+        let var = self.ident_consume("Expected a variable in for (var in array)")?;
+        self.consume(TokenType::In, "Expected `in` after the for loop variable")?;
+        let array = self.ident_consume("Expected an array name after `in`")?;
+        self.consume(TokenType::RightParen, "Expected a `)` to end for loop")?;
+        self.consume(
+            TokenType::LeftBrace,
+            "Expected a `{` to begin for loop body",
+        )?;
+        let body = self.stmts()?;
+        self.consume(TokenType::RightBrace, "Expected a `}` after for loop body")?;
+        Ok(Stmt::ForIn { var: var.into(), array, body: Box::new(body) })
+    }
+
     fn if_stmt(&mut self) -> Result<Stmt, PrintableError> {
         self.consume(TokenType::LeftParen, "Expected `(` after if")?;
         let predicate = self.expression()?;
@@ -502,12 +549,42 @@ impl<'a> Parser<'a> {
                 unreachable!()
             }
         }
+        if is_array_index && self.matches(flags!(TokenType::InplaceAssign)) {
+            // a[k] += v is a[k] = a[k] + v. The indices are evaluated twice.
+            let math_op = if let Token::InplaceEq(math_op) = self.previous().unwrap() {
+                math_op
+            } else {
+                unreachable!()
+            };
+            if let Expr::ArrayIndex { name, indices } = expr.expr {
+                let current = Expr::array_index(name.clone(), indices.clone());
+                let value = Expr::MathOp(Box::new(current.into()), math_op, Box::new(self.assignment()?));
+                return Ok(Expr::ArrayAssign {
+                    name,
+                    indices,
+                    value: Box::new(value.into()),
+                }
+                    .into());
+            } else {
+                unreachable!()
+            }
+        }
+        if let Expr::Column(_) = &expr.expr {
+            if self.matches(flags!(TokenType::Eq)) {
+                if let Expr::Column(column) = expr.expr {
+                    let value = Box::new(self.assignment()?);
+                    return Ok(Expr::ColumnAssign { column, value }.into());
+                } else {
+                    unreachable!()
+                }
+            }
+        }
         Ok(expr)
     }
 
     fn ternary(&mut self) -> Result<TypedExpr, PrintableError> {
         let cond = self.logical_or()?;
-        while self.matches(flags!(TokenType::Question)) {
+        if self.matches(flags!(TokenType::Question)) {
             let expr1 = self.ternary()?;
             self.consume(
                 TokenType::Colon,
@@ -600,7 +677,7 @@ impl<'a> Parser<'a> {
             } else {
                 unreachable!("compiler bug consumed ident but got something else")
             };
-            expr = Expr::in_array(ident, vec![expr.into()]).into();
+            expr = Expr::in_array(ident, vec![expr]).into();
         }
         Ok(expr)
     }
@@ -638,7 +715,7 @@ impl<'a> Parser<'a> {
     }
 
     fn compare(&mut self) -> Result<TypedExpr, PrintableError> {
-        let mut expr = self.string_concat()?;
+        let mut expr = self.pipe_getline()?;
         while self.matches(flags!(
             TokenType::GreaterEq,
             TokenType::Greater,
@@ -656,11 +733,33 @@ impl<'a> Parser<'a> {
                 Token::BinOp(BinOp::EqEq) => BinOp::EqEq,
                 _ => unreachable!("Parser bug in compare matches function"),
             };
-            expr = Expr::BinOp(Box::new(expr), op, Box::new(self.string_concat()?)).into()
+            expr = Expr::BinOp(Box::new(expr), op, Box::new(self.pipe_getline()?)).into()
         }
         Ok(expr)
     }
 
+    fn pipe_getline(&mut self) -> Result<TypedExpr, PrintableError> {
+        // "echo " x | getline runs "echo " x, the pipe binds looser than concatenation
+        let mut expr = self.string_concat()?;
+        while self.matches(flags!(TokenType::Pipe)) {
+            self.consume(TokenType::Getline, "| must be followed by getline, output pipes are not supported")?;
+            let var = self.getline_var()?;
+            expr = Expr::Getline { source: GetlineSource::Command(Box::new(expr)), var }.into();
+        }
+        Ok(expr)
+    }
+
+    // The optional lvalue after getline
+    fn getline_var(&mut self) -> Result<Option<LValue>, PrintableError> {
+        if !matches!(self.peek().ttype(), TokenType::Ident | TokenType::Column) {
+            return Ok(None);
+        }
+        match LValue::try_from(self.column()?.expr) {
+            Ok(lvalue) => Ok(Some(lvalue)),
+            Err(()) => Err(PrintableError::new("getline can only read into an lvalue: a variable `B`, an index into an array `B[1]`, or a column `$3`.")),
+        }
+    }
+
     #[inline(always)]
     fn types_contain(bitflag_union: u64, flag: u64) -> bool {
         bitflag_union & flag != 0
@@ -708,10 +807,10 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> Result<TypedExpr, PrintableError> {
-        if !(self.peek().ttype() == TokenType::Minus
-            && self.peek_next().ttype() == TokenType::Minus)
-            && !(self.peek().ttype() == TokenType::Plus
-            && self.peek_next().ttype() == TokenType::Plus)
+        if (self.peek().ttype() != TokenType::Minus
+            || self.peek_next().ttype() != TokenType::Minus)
+            && (self.peek().ttype() != TokenType::Plus
+            || self.peek_next().ttype() != TokenType::Plus)
             && self.matches(flags!(TokenType::Minus, TokenType::Plus, TokenType::Bang))
         {
             let p = self.previous().unwrap().ttype();
@@ -739,70 +838,40 @@ impl<'a> Parser<'a> {
     }
 
     fn pre_op(&mut self) -> Result<TypedExpr, PrintableError> {
-        if self.matches_series(&[TokenType::Plus, TokenType::Plus, TokenType::Ident])
-        {
-            let name = self.prior_indent_name_infallible();
-            let var_expr = Expr::var_expr(name.clone()).into();
-            let increment = Expr::MathOp(
-                Box::new(var_expr),
-                MathOp::Plus,
-                Box::new(Expr::NumberF64(1.0).into()),
-            )
-                .into();
-
-            return Ok(Expr::assign(name, Box::new(increment)).into());
-        } else if self.matches_series(&[TokenType::Minus, TokenType::Minus, TokenType::Ident]) {
-            let name = self.prior_indent_name_infallible();
-            let var = Expr::var_expr(name.clone()).into();
-            let decrement = Expr::MathOp(
-                Box::new(var),
-                MathOp::Minus,
-                Box::new(Expr::NumberF64(1.0).into()),
-            )
-                .into();
-
-            return Ok(Expr::assign(name, Box::new(decrement)).into());
+        // ++a --a ++a[1] ++$1
+        for (ttype, increment) in [(TokenType::Plus, true), (TokenType::Minus, false)] {
+            let operand = self.peek_at(self.current + 2).ttype();
+            if self.peek().ttype() == ttype && self.peek_next().ttype() == ttype
+                && (operand == TokenType::Ident || operand == TokenType::Column) {
+                self.current += 2;
+                let expr = self.column()?;
+                let lvalue = match LValue::try_from(expr.expr) {
+                    Ok(lvalue) => lvalue,
+                    Err(()) => return Err(PrintableError::new(format!("{} must be applied to an lvalue: a variable `B`, an index into an array `B[1]`, or a column `$3`.", if increment { "++" } else { "--" }))),
+                };
+                return Ok(Expr::IncDec { lvalue, increment, pre: true }.into());
+            }
         }
-
         self.post_op()
     }
 
     fn post_op(&mut self) -> Result<TypedExpr, PrintableError> {
-        let mut expr = self.column()?;
-
-        if let Expr::Variable(_) = &expr.expr {
-            // Check enum variant before cloning it since the clone is expensive
-            if let Expr::Variable(name) = expr.expr.clone() {
-                if self.matches_series(&[TokenType::Plus, TokenType::Plus]) {
-                    let increment = Expr::MathOp(
-                        Box::new(expr),
-                        MathOp::Plus,
-                        Box::new(Expr::NumberF64(1.0).into()),
-                    ).into();
-                    let assign = Expr::ScalarAssign(name, Box::new(increment)).into();
-                    expr = Expr::MathOp(
-                        Box::new(assign),
-                        MathOp::Minus,
-                        Box::new(Expr::NumberF64(1.0).into()),
-                    ).into();
-                } else if self.matches_series(&[TokenType::Minus, TokenType::Minus]) {
-                    let decrement = Expr::MathOp(
-                        Box::new(expr),
-                        MathOp::Minus,
-                        Box::new(Expr::NumberF64(1.0).into()),
-                    ).into();
-                    let assign = Expr::ScalarAssign(name, Box::new(decrement)).into();
-                    expr = Expr::MathOp(
-                        Box::new(assign),
-                        MathOp::Plus,
-                        Box::new(Expr::NumberF64(1.0).into()),
-                    ).into();
-                }
-            } else {
-                unreachable!()
-            }
-        }
-        Ok(expr)
+        // a++ a-- a[1]++ $1++
+        let expr = self.column()?;
+        match &expr.expr {
+            Expr::Variable(_) | Expr::ArrayIndex { .. } | Expr::Column(_) => {}
+            _ => return Ok(expr),
+        }
+        let increment = if self.matches_series(&[TokenType::Plus, TokenType::Plus]) {
+            true
+        } else if self.matches_series(&[TokenType::Minus, TokenType::Minus]) {
+            false
+        } else {
+            return Ok(expr);
+        };
+        // Checked variant above
+        let lvalue = LValue::try_from(expr.expr).unwrap();
+        Ok(Expr::IncDec { lvalue, increment, pre: false }.into())
     }
 
     fn column(&mut self) -> Result<TypedExpr, PrintableError> {
@@ -831,7 +900,7 @@ impl<'a> Parser<'a> {
                 self.consume(TokenType::LeftParen, "Expected to parse a left paren here")?;
                 let expr = self.expression()?;
                 self.consume(TokenType::RightParen, "Missing closing ')' after group")?;
-                expr.into()
+                expr
             }
             Token::Ident(name) => {
                 self.consume(TokenType::Ident, "Expected to parse an ident here")?;
@@ -852,6 +921,17 @@ impl<'a> Parser<'a> {
                 self.consume(TokenType::Regex, "Expected to parse a string here")?;
                 Expr::Regex(string).into()
             }
+            Token::Getline => {
+                self.consume(TokenType::Getline, "Expected to parse getline here")?;
+                let var = self.getline_var()?;
+                // getline < "a" "b" reads from "a", like other awks the file is a single primary
+                let source = if self.matches(flags!(TokenType::Less)) {
+                    GetlineSource::File(Box::new(self.column()?))
+                } else {
+                    GetlineSource::Input
+                };
+                Expr::Getline { source, var }.into()
+            }
             t => return Err(PrintableError::new(format!("Unexpected token {:?} {}", t, TokenType::name(t.ttype()))))
         })
     }
@@ -3,9 +3,11 @@ mod parser_tests {
     use crate::awk_str::{RcAwkStr};
     use crate::lexer::Token;
     use crate::lexer::{BinOp, LogicalOp, MathOp};
-    use crate::parser::{SclSpecial, parse, Expr, Function, PatternAction, Program, Stmt, TypedExpr};
+    use crate::parser::{SclSpecial, parse, Expr, Function, GetlineSource, LValue, PatternAction, Program, Stmt, TypedExpr};
 
-    use crate::lexer::lex;
+    use crate::lexer::{lex, lex_spanned};
+    use crate::parser::parse_spanned;
+    use crate::printable_error::Span;
     use crate::symbolizer::Symbolizer;
 
     macro_rules! num {
@@ -556,6 +558,18 @@ mod parser_tests {
         assert_eq!(actual, sprogram!(expected, &mut symbolizer))
     }
 
+    #[test]
+    fn test_for_in_loop() {
+        actual!(actual, "{ for (k in arr) { print k } }", symbolizer);
+        let k = symbolizer.get("k");
+        let expected = Stmt::ForIn {
+            var: k.clone().into(),
+            array: symbolizer.get("arr"),
+            body: Box::new(Stmt::Print(texpr!(Expr::var_expr(k)))),
+        };
+        assert_eq!(actual, sprogram!(expected, &mut symbolizer))
+    }
+
     #[test]
     fn test_logical_and() {
         actual!(actual, "{ a && b && c }", symbolizer);
@@ -921,4 +935,51 @@ mod parser_tests {
             )
         )
     }
+
+    #[test]
+    fn test_getline() {
+        actual!(actual, "BEGIN { getline; getline x < \"f\" \"g\"; \"a\" \"b\" | getline $1 }", symbolizer);
+        let x = symbolizer.get("x");
+        let string = |s: &str| texpr!(Expr::String(RcAwkStr::new_bytes(s.as_bytes().to_vec())));
+        let body = Stmt::Group(vec![
+            Stmt::Expr(texpr!(Expr::Getline { source: GetlineSource::Input, var: None })),
+            // The file is a single primary so "g" is concatenated with the result
+            Stmt::Expr(texpr!(Expr::Concatenation(vec![
+                texpr!(Expr::Getline { source: GetlineSource::File(Box::new(string("f"))), var: Some(LValue::Variable(x.into())) }),
+                string("g"),
+            ]))),
+            // The command is the whole concatenation
+            Stmt::Expr(texpr!(Expr::Getline {
+                source: GetlineSource::Command(btexpr!(Expr::Concatenation(vec![string("a"), string("b")]))),
+                var: Some(LValue::Column(bnum!(1.0))),
+            })),
+        ]);
+        assert_eq!(actual, Program::new(vec![body], vec![], vec![], vec![], symbolizer.clone()));
+    }
+
+    #[test]
+    fn test_begin_end_file() {
+        actual!(actual, "BEGINFILE { print 1; nextfile } { print 2 } ENDFILE { print 3 } END { print 4 }", symbolizer);
+        let files = Stmt::Files {
+            begin_file: Box::new(Stmt::Group(vec![Stmt::Print(num!(1.0)), Stmt::NextFile])),
+            records: Box::new(Stmt::Print(num!(2.0))),
+            end_file: Box::new(Stmt::Print(num!(3.0))),
+        };
+        assert_eq!(
+            actual,
+            Program::with_body(Stmt::Group(vec![files, Stmt::Print(num!(4.0))]), vec![], symbolizer.clone())
+        )
+    }
+
+    #[test]
+    fn test_error_span() {
+        let mut symbolizer = Symbolizer::new();
+        let (tokens, spans) = lex_spanned("BEGIN { print 1 }\n{ x = }", &mut symbolizer).unwrap();
+        let err = parse_spanned(tokens, &spans, &mut symbolizer).unwrap_err();
+        assert_eq!(err.span, Some(Span::new(2, 7)));
+
+        let (tokens, spans) = lex_spanned("BEGIN { print 1", &mut symbolizer).unwrap();
+        let err = parse_spanned(tokens, &spans, &mut symbolizer).unwrap_err();
+        assert_eq!(err.span, Some(Span::new(1, 15)));
+    }
 }
@@ -0,0 +1,53 @@
+use crate::parser::{PatternAction, Stmt, TypedExpr};
+use crate::parser::Expr;
+
+fn group_vec_of_stmt(mut stmts: Vec<Stmt>) -> Stmt {
+    if stmts.len() == 1 {
+        stmts.pop().unwrap()
+    } else {
+        Stmt::Group(stmts)
+    }
+}
+
+// Turn a program into just a single Stmt
+pub fn transform(begins: Vec<Stmt>, ends: Vec<Stmt>, pas: Vec<PatternAction>) -> Stmt {
+    transform_with_file_rules(begins, vec![], pas, vec![], ends)
+}
+
+// BEGINFILE and ENDFILE rules make the record loop a loop over files as well
+pub fn transform_with_file_rules(begins: Vec<Stmt>,
+                                 begin_files: Vec<Stmt>,
+                                 pas: Vec<PatternAction>,
+                                 end_files: Vec<Stmt>,
+                                 ends: Vec<Stmt>) -> Stmt {
+    let mut prog = begins;
+    let mut every_line_stms = vec![];
+    for pattern in pas {
+        let stmt = if let Some(test) = pattern.pattern {
+            Stmt::If(test, Box::new(pattern.action), None)
+        } else {
+            pattern.action
+        };
+        every_line_stms.push(stmt)
+    }
+    if !begin_files.is_empty() || !end_files.is_empty() {
+        let files = Stmt::Files {
+            begin_file: Box::new(group_vec_of_stmt(begin_files)),
+            records: Box::new(group_vec_of_stmt(every_line_stms)),
+            end_file: Box::new(group_vec_of_stmt(end_files)),
+        };
+        prog.push(files);
+    } else if !every_line_stms.is_empty() || !ends.is_empty() {
+        // END rules see NR and the last record so the input is read even with no other rules
+        let line_loop = Stmt::While(
+            TypedExpr::new(Expr::NextLine),
+            Box::new(group_vec_of_stmt(every_line_stms)),
+        );
+        prog.push(line_loop);
+    }
+
+    for end in ends {
+        prog.push(end);
+    }
+    group_vec_of_stmt(prog)
+}
@@ -17,18 +17,6 @@ pub enum ScalarType {
     Var = 0b0000_0011,
 }
 
-#[derive(PartialEq, PartialOrd, Clone, Copy, Debug)]
-pub enum AwkT {
-    Scalar(ScalarType),
-    Array,
-}
-
-impl Into<AwkT> for ScalarType {
-    fn into(self) -> AwkT {
-        AwkT::Scalar(self)
-    }
-}
-
 #[derive(Debug, PartialOrd, PartialEq, Clone)]
 pub enum Stmt {
     Expr(TypedExpr),
@@ -36,12 +24,25 @@ pub enum Stmt {
     Group(Vec<Stmt>),
     If(TypedExpr, Box<Stmt>, Option<Box<Stmt>>),
     While(TypedExpr, Box<Stmt>),
+    ForIn {
+        var: Variable,
+        array: Symbol,
+        body: Box<Stmt>,
+    },
     Printf {
         fstring: TypedExpr,
         args: Vec<TypedExpr>,
     },
     Break,
     Return(Option<TypedExpr>),
+    // Reading input when there are BEGINFILE or ENDFILE rules. For every file: begin_file,
+    // records for each of its records, then end_file.
+    Files {
+        begin_file: Box<Stmt>,
+        records: Box<Stmt>,
+        end_file: Box<Stmt>,
+    },
+    NextFile,
 }
 
 impl Display for Stmt {
@@ -78,9 +79,16 @@ impl Display for Stmt {
             Stmt::While(test, body) => {
                 write!(f, "while {} {{{}}} ", test, body)?;
             }
+            Stmt::ForIn { var, array, body } => {
+                write!(f, "for ({} in {}) {{{}}} ", var, array, body)?;
+            }
             Stmt::Break => write!(f, "break")?,
+            Stmt::Files { begin_file, records, end_file } => {
+                write!(f, "beginfile {{{}}} while next line {{{}}} endfile {{{}}} ", begin_file, records, end_file)?;
+            }
+            Stmt::NextFile => write!(f, "nextfile")?,
         };
-        write!(f, "\n")
+        writeln!(f)
     }
 }
 
@@ -123,9 +131,9 @@ impl TypedExpr {
     }
 }
 
-impl Into<TypedExpr> for Expr {
-    fn into(self) -> TypedExpr {
-        TypedExpr::new(self)
+impl From<Expr> for TypedExpr {
+    fn from(val: Expr) -> Self {
+        TypedExpr::new(val)
     }
 }
 
@@ -137,6 +145,16 @@ pub enum Expr {
         indices: Vec<TypedExpr>,
         value: Box<TypedExpr>,
     },
+    ColumnAssign {
+        column: Box<TypedExpr>,
+        value: Box<TypedExpr>,
+    },
+    // ++ and -- on any lvalue
+    IncDec {
+        lvalue: LValue,
+        increment: bool, // true => ++ else --
+        pre: bool, // true => ++a else a++
+    },
     NumberF64(f64),
     String(RcAwkStr),
     Regex(RcAwkStr),
@@ -167,6 +185,19 @@ pub enum Expr {
         string: LValue,
         global: bool, // true => gsub() else sub()
     },
+    // Reads a record into var, or $0 without one. 1 if a record was read, 0 at the end of the
+    // input and -1 if the file or command couldn't be opened.
+    Getline {
+        source: GetlineSource,
+        var: Option<LValue>,
+    },
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub enum GetlineSource {
+    Input, // getline, the next record of the main input
+    File(Box<TypedExpr>), // getline < file
+    Command(Box<TypedExpr>), // cmd | getline
 }
 
 impl Expr {
@@ -198,9 +229,9 @@ impl Expr {
         }
     }
 }
-impl Into<Expr> for Variable {
-    fn into(self) -> Expr {
-        Expr::Variable(self)
+impl From<Variable> for Expr {
+    fn from(val: Variable) -> Self {
+        Expr::Variable(val)
     }
 }
 
@@ -228,9 +259,9 @@ impl Display for LValue {
     }
 }
 
-impl Into<Expr> for LValue {
-    fn into(self) -> Expr {
-        match self {
+impl From<LValue> for Expr {
+    fn from(val: LValue) -> Self {
+        match val {
             LValue::Variable(var) => Expr::Variable(var),
             LValue::ArrayIndex { name, indices } => Expr::ArrayIndex { name, indices },
             LValue::Column(expr) => Expr::Column(expr)
@@ -332,11 +363,33 @@ impl Display for Expr {
                 display_comma_sep_list(f, indices)?;
                 write!(f, "] = {}", value)
             }
+            Expr::ColumnAssign { column, value } => write!(f, "${} = {}", column, value),
+            Expr::IncDec { lvalue, increment, pre } => {
+                let op = if *increment { "++" } else { "--" };
+                if *pre {
+                    write!(f, "{}{}", op, lvalue)
+                } else {
+                    write!(f, "{}{}", lvalue, op)
+                }
+            }
 
             Expr::CallSub { ere: arg1, replacement: arg2, string: arg3, global } => {
                 let name = if *global { "gsub" } else { "sub" };
                 write!(f, "{}({},{},{})", name, arg1, arg2, arg3)
             }
+            Expr::Getline { source, var } => {
+                if let GetlineSource::Command(cmd) = source {
+                    write!(f, "{} | ", cmd)?;
+                }
+                write!(f, "getline")?;
+                if let Some(var) = var {
+                    write!(f, " {}", var)?;
+                }
+                if let GetlineSource::File(file) = source {
+                    write!(f, " < {}", file)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -363,17 +416,21 @@ pub struct Arg {
     pub name: Symbol,
     pub typ: ArgT,
     pub builtin_optional: bool, // Like split(string, arr [, fs] ) this arguments is an optional for a builtin. All args for user functions are optional.
+    pub builtin_either: bool, // Like length(x) this builtin arg may be a scalar or an array. Its typ stays Unknown.
 }
 
 impl Arg {
     pub fn new(name: Symbol, typ: ArgT) -> Self {
-        Self { name, typ, builtin_optional: false }
+        Self { name, typ, builtin_optional: false, builtin_either: false }
     }
     pub fn new_optional(name: Symbol, typ: ArgT) -> Self {
-        Self { name, typ, builtin_optional: true }
+        Self { name, typ, builtin_optional: true, builtin_either: false }
+    }
+    pub fn new_scl(name: Symbol) -> Self { Self { name, typ: ArgT::Scalar, builtin_optional: false, builtin_either: false } }
+    pub fn new_arr(name: Symbol) -> Self { Self { name, typ: ArgT::Array, builtin_optional: false, builtin_either: false } }
+    pub fn new_either(name: Symbol, builtin_optional: bool) -> Self {
+        Self { name, typ: ArgT::Unknown, builtin_optional, builtin_either: true }
     }
-    pub fn new_scl(name: Symbol) -> Self { Self { name, typ: ArgT::Scalar, builtin_optional: false } }
-    pub fn new_arr(name: Symbol) -> Self { Self { name, typ: ArgT::Array, builtin_optional: false } }
 }
 
 impl Display for Arg {
@@ -397,7 +454,7 @@ fn assert_safe_name(name: &Symbol, used_as: &str) -> Result<(), PrintableError>
             used_as,
         )));
     }
-    if let Ok(_) = SclSpecial::try_from(name.to_str()) {
+    if SclSpecial::try_from(name.to_str()).is_ok() {
         return Err(PrintableError::new(format!("Cannot use `{}` as a {} since it is a special awk variable", name, used_as)));
     }
     Ok(())
@@ -417,7 +474,7 @@ impl Function {
             assert_safe_name(arg, "function argument name")?;
         }
         Ok(Function {
-            name: name.into(),
+            name,
             args,
             body,
         })
@@ -0,0 +1,101 @@
+use crate::parser::{Expr, GetlineSource, LValue, Stmt, TypedExpr};
+
+// Visit every expression in a statement, children before their parents
+pub fn walk_stmt(stmt: &mut Stmt, f: &mut dyn FnMut(&mut TypedExpr)) {
+    match stmt {
+        Stmt::Expr(expr) | Stmt::Print(expr) => walk_expr(expr, f),
+        Stmt::Group(stmts) => {
+            for stmt in stmts {
+                walk_stmt(stmt, f);
+            }
+        }
+        Stmt::If(test, if_so, if_not) => {
+            walk_expr(test, f);
+            walk_stmt(if_so, f);
+            if let Some(if_not) = if_not {
+                walk_stmt(if_not, f);
+            }
+        }
+        Stmt::While(test, body) => {
+            walk_expr(test, f);
+            walk_stmt(body, f);
+        }
+        Stmt::ForIn { body, .. } => walk_stmt(body, f),
+        Stmt::Printf { fstring, args } => {
+            walk_expr(fstring, f);
+            walk_exprs(args, f);
+        }
+        Stmt::Return(Some(expr)) => walk_expr(expr, f),
+        Stmt::Files { begin_file, records, end_file } => {
+            walk_stmt(begin_file, f);
+            walk_stmt(records, f);
+            walk_stmt(end_file, f);
+        }
+        Stmt::Return(None) | Stmt::Break | Stmt::NextFile => {}
+    }
+}
+
+fn walk_exprs(exprs: &mut [TypedExpr], f: &mut dyn FnMut(&mut TypedExpr)) {
+    for expr in exprs {
+        walk_expr(expr, f);
+    }
+}
+
+fn walk_lvalue(lvalue: &mut LValue, f: &mut dyn FnMut(&mut TypedExpr)) {
+    match lvalue {
+        LValue::Variable(_var) => {}
+        LValue::ArrayIndex { indices, .. } => walk_exprs(indices, f),
+        LValue::Column(column) => walk_expr(column, f),
+    }
+}
+
+pub fn walk_expr(expr: &mut TypedExpr, f: &mut dyn FnMut(&mut TypedExpr)) {
+    match &mut expr.expr {
+        Expr::ScalarAssign(_var, value) => walk_expr(value, f),
+        Expr::ArrayAssign { indices, value, .. } => {
+            walk_exprs(indices, f);
+            walk_expr(value, f);
+        }
+        Expr::ColumnAssign { column, value } => {
+            walk_expr(column, f);
+            walk_expr(value, f);
+        }
+        Expr::IncDec { lvalue, .. } => walk_lvalue(lvalue, f),
+        Expr::Concatenation(exprs) => walk_exprs(exprs, f),
+        Expr::BinOp(lhs, _, rhs) => {
+            walk_expr(lhs, f);
+            walk_expr(rhs, f);
+        }
+        Expr::MathOp(lhs, _, rhs) => {
+            walk_expr(lhs, f);
+            walk_expr(rhs, f);
+        }
+        Expr::LogicalOp(lhs, _, rhs) => {
+            walk_expr(lhs, f);
+            walk_expr(rhs, f);
+        }
+        Expr::Column(column) => walk_expr(column, f),
+        Expr::Ternary(cond, if_so, if_not) => {
+            walk_expr(cond, f);
+            walk_expr(if_so, f);
+            walk_expr(if_not, f);
+        }
+        Expr::ArrayIndex { indices, .. } | Expr::InArray { indices, .. } => walk_exprs(indices, f),
+        Expr::Call { args, .. } => walk_exprs(args, f),
+        Expr::CallSub { ere, replacement, string, .. } => {
+            walk_expr(ere, f);
+            walk_expr(replacement, f);
+            walk_lvalue(string, f);
+        }
+        Expr::Getline { source, var } => {
+            if let GetlineSource::File(expr) | GetlineSource::Command(expr) = source {
+                walk_expr(expr, f);
+            }
+            if let Some(var) = var {
+                walk_lvalue(var, f);
+            }
+        }
+        Expr::NumberF64(_) | Expr::String(_) | Expr::Regex(_) | Expr::Variable(_) | Expr::NextLine => {}
+    }
+    f(expr);
+}
@@ -0,0 +1,48 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub struct PrintableError {
+    pub msg: String,
+    // Where in the program source the error is. Only lexing and parsing errors have one.
+    pub span: Option<Span>,
+}
+
+// A position in the program source. Both are 1 based and column counts chars not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize) -> Self {
+        Span { line, column }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {} column {}", self.line, self.column)
+    }
+}
+
+impl Display for PrintableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl PrintableError {
+    pub fn new<S: Into<String>>(msg: S) -> Self {
+        PrintableError { msg: msg.into(), span: None }
+    }
+
+    // Errors raised deep in the lexer or parser don't know their position, the caller
+    // attaches it on the way out. An existing span is more precise so it's kept.
+    pub fn at(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+}
@@ -2,10 +2,9 @@
 // and global array storage in the vm
 
 use std::fmt::{Display, Formatter};
-use crate::parser::ScalarType;
 
 
-pub const NUM_SCL_SPECIALS: usize = 14;
+pub const NUM_SCL_SPECIALS: usize = 17;
 
 pub const FNR_VAL: usize = 3;
 pub const NF_VAL: usize = 4;
@@ -39,6 +38,9 @@ pub enum SclSpecial {
     // Other specials
     SUBSEP = 12,
     ARGC = 13,
+    ERRNO = 14,
+    FPAT = 15,
+    FIELDWIDTHS = 16,
 }
 
 impl Display for SclSpecial {
@@ -54,7 +56,7 @@ impl TryFrom<&str> for SclSpecial {
         if let Some((_, (_, special))) = SCL_SPECIAL_MAP
             .iter()
             .enumerate()
-            .find(|(_idx, (name, special))| name == &value) {
+            .find(|(_idx, (name, _special))| name == &value) {
             Ok(*special)
         } else {
             Err(())
@@ -70,6 +72,7 @@ type ArrSpecialMapT = &'static [(&'static str, ArrSpecial)];
 const ARR_SPECIAL_MAP: ArrSpecialMapT = &[
     ("ARGV", ArrSpecial::ARGV),
     ("ENVIRON", ArrSpecial::ENVIRON),
+    ("PROCINFO", ArrSpecial::PROCINFO),
 ];
 const SCL_SPECIAL_MAP: SclSpecialMapT = &[
     ("FS", SclSpecial::FS),
@@ -86,22 +89,26 @@ const SCL_SPECIAL_MAP: SclSpecialMapT = &[
     ("RSTART", SclSpecial::RSTART),
     ("SUBSEP", SclSpecial::SUBSEP),
     ("ARGC", SclSpecial::ARGC),
+    ("ERRNO", SclSpecial::ERRNO),
+    ("FPAT", SclSpecial::FPAT),
+    ("FIELDWIDTHS", SclSpecial::FIELDWIDTHS),
 ];
 
 
 impl SclSpecial {
     pub const fn variants() -> SclSpecialMapT {
-        &SCL_SPECIAL_MAP
+        SCL_SPECIAL_MAP
     }
 }
 
 pub enum ArrSpecial {
     ARGV = 0,
     ENVIRON = 1,
+    PROCINFO = 2,
 }
 
 impl ArrSpecial {
     pub fn variants() -> ArrSpecialMapT {
-        &ARR_SPECIAL_MAP
+        ARR_SPECIAL_MAP
     }
 }
\ No newline at end of file
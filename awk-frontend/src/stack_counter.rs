@@ -3,12 +3,18 @@ use std::ops::{Index, IndexMut};
 use crate::parser::ScalarType;
 use crate::stackt::StackT;
 
-#[derive(Eq, PartialEq, Copy, Clone)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub struct StackCounter {
     stacks: [usize; 4],
 }
 
 
+impl Default for StackCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StackCounter {
     pub fn new() -> Self {
         Self { stacks: [0, 0, 0, 0] }
@@ -8,11 +8,11 @@ pub enum StackT {
     Array,
 }
 
-const VARIANTS: &'static [StackT; 4] = &[StackT::Var,StackT::Str,StackT::Num, StackT::Array];
+const VARIANTS: &[StackT; 4] = &[StackT::Var,StackT::Str,StackT::Num, StackT::Array];
 
 impl StackT {
     pub fn iter() -> &'static [StackT; 4] {
-        &VARIANTS
+        VARIANTS
     }
 }
 
@@ -29,9 +29,9 @@ impl TryInto<ScalarType> for StackT {
     }
 }
 
-impl Into<StackT> for ScalarType {
-    fn into(self) -> StackT {
-        match self {
+impl From<ScalarType> for StackT {
+    fn from(val: ScalarType) -> Self {
+        match val {
             ScalarType::Str => StackT::Str,
             ScalarType::Num => StackT::Num,
             ScalarType::Var => StackT::Var,
@@ -12,10 +12,7 @@ struct WeaklyHeldStr {
 
 impl WeaklyHeldStr {
     fn upgrade(self) -> Option<Symbol> {
-        match self.w.upgrade() {
-            None => None,
-            Some(sym) => Some(Symbol { sym }),
-        }
+        self.w.upgrade().map(|sym| Symbol { sym })
     }
 }
 
@@ -67,7 +64,7 @@ struct SymbolizerInner {
     last: LruCache<String, Symbol>,
 }
 
-#[derive(Clone, Debug, Hash, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialOrd, Ord)]
 pub struct Symbol {
     pub sym: Rc<String>,
 }
@@ -93,12 +90,25 @@ impl PartialEq<Self> for Symbol {
 
 impl Eq for Symbol {}
 
+// Symbols that are eq point to the same string so hashing the string agrees with eq
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sym.hash(state)
+    }
+}
+
 impl Display for Symbol {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str(&format!("{}", self.sym))
     }
 }
 
+impl Default for Symbolizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Symbolizer {
     pub fn new() -> Self {
         Symbolizer {
@@ -1,5 +1,6 @@
 use crate::symbolizer::Symbol;
 use crate::typing::builtin_funcs::builtin_func::NUM_BUILTIN_VARIANTS;
+use crate::typing::builtin_funcs::native_func::NativeFunc;
 use crate::typing::builtin_funcs::typed_builtin::TypedBuiltin;
 use crate::typing::structs::Call;
 use crate::typing::{BuiltinFunc, TypedUserFunction};
@@ -29,6 +30,8 @@ pub struct BuiltinFactory {
     shared: Rc<BuiltinShared>,
     cache: [Option<Rc<TypedBuiltin>>; NUM_BUILTIN_VARIANTS],
     names: [Symbol; NUM_BUILTIN_VARIANTS],
+    // Functions registered by the embedding program, their index is their id
+    natives: Vec<(NativeFunc, Rc<TypedBuiltin>)>,
     symbolizer: Symbolizer,
 }
 
@@ -38,16 +41,32 @@ impl BuiltinFactory {
             shared: Rc::new(BuiltinShared::new()),
             cache: [
                 None, None, None, None, None, None, None, None, None, None, None, None, None, None,
-                None, None, None, None, None
+                None, None, None, None, None, None, None, None, None, None, None, None, None, None,
+                None, None, None, None, None, None
             ],
             names: BuiltinFunc::names_as_symbols(&mut symbolizer),
+            natives: vec![],
             symbolizer,
         }
     }
+    pub fn add_native(&mut self, native: NativeFunc) {
+        let name = self.symbolizer.get(&native.name);
+        let args = native.typed_args(&mut self.symbolizer);
+        let typed_builtin = Rc::new(TypedBuiltin::new(name, args, None, self.shared.clone()));
+        self.natives.push((native, typed_builtin));
+    }
+    pub fn native(&self, name: &Symbol) -> Option<(usize, &NativeFunc, Rc<TypedBuiltin>)> {
+        let id = self.natives.iter().position(|(native, _)| native.name == name.to_str())?;
+        let (native, typed_builtin) = &self.natives[id];
+        Some((id, native, typed_builtin.clone()))
+    }
+    pub fn natives(&self) -> impl Iterator<Item=&NativeFunc> {
+        self.natives.iter().map(|(native, _)| native)
+    }
     pub fn get(&mut self, builtin: BuiltinFunc) -> Rc<TypedBuiltin> {
         unsafe {
             // Safe as long as NUM_BUILTIN_VARIANTS is correct and builtin is actually an enum variant not some other number
-            debug_assert!((builtin as i64) < 21 && (builtin as i64) >= 0);
+            debug_assert!((builtin as i64) < NUM_BUILTIN_VARIANTS as i64 && (builtin as i64) >= 0);
             if let Some(builtin) = self.cache.get_unchecked(builtin as usize) {
                 builtin.clone()
             } else {
@@ -56,7 +75,7 @@ impl BuiltinFactory {
                 let typed_builtin = Rc::new(TypedBuiltin::new(
                     name.clone(),
                     args,
-                    builtin,
+                    Some(builtin),
                     self.shared.clone(),
                 ));
                 self.cache[builtin as usize] = Some(typed_builtin.clone());
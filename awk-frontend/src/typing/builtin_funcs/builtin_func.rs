@@ -1,32 +1,47 @@
-use crate::parser::{Arg, ArgT};
+use crate::parser::{Arg, ArgT, ScalarType};
 use crate::printable_error::PrintableError;
 use crate::symbolizer::Symbol;
 use crate::Symbolizer;
 use std::fmt::{Display, Formatter};
 
-pub const NUM_BUILTIN_VARIANTS: usize = 19;
+pub const NUM_BUILTIN_VARIANTS: usize = 34;
 
 #[derive(Debug, Clone, Copy)]
 pub enum BuiltinFunc {
+    And,
+    Asort,
+    Asorti,
     Atan2,
     Close,
+    Compl,
     Cos,
     Exp,
+    Gensub,
     // Gsub Handled separately from rest of the builtin type system since their out variables are complex
     // Sub ^ same
     Substr,
     Index,
     Int,
+    Isarray,
     Length,
+    Lshift,
     Log,
     Matches,
+    Mktime,
+    Or,
     Rand,
+    Rshift,
     Sin,
     Split,
     Sprintf,
     Sqrt,
     Srand,
+    Strftime,
+    Strtonum,
     System,
+    Systime,
+    Typeof,
+    Xor,
     Tolower,
     Toupper, // Must stay as last (see builtin_factory.rs)
 }
@@ -43,85 +58,161 @@ impl BuiltinFunc {
             BuiltinFunc::Rand => vec![],
             BuiltinFunc::Srand => vec![Arg::new_optional(s.get("rand-arg-0"), ArgT::Scalar)],
             BuiltinFunc::Atan2 => vec![Arg::new_scl(s.get("atan2-arg-0")), Arg::new_scl(s.get("atan2-arg-1"))],
-            BuiltinFunc::Length => vec![Arg::new_optional(s.get("length-arg-0"), ArgT::Scalar)],
+            BuiltinFunc::Length => vec![Arg::new_either(s.get("length-arg-0"), true)],
             BuiltinFunc::Tolower => vec![Arg::new_scl(s.get("lower-arg-0"))],
             BuiltinFunc::Toupper => vec![Arg::new_scl(s.get("upper-arg-0"))],
             BuiltinFunc::Split => vec![Arg::new_scl(s.get("split-arg-0")), Arg::new_arr(s.get("split-arg-1")), Arg::new_optional(s.get("split-arg-2"), ArgT::Scalar)],
             BuiltinFunc::Substr => vec![Arg::new_scl(s.get("substr-arg-0")), Arg::new_scl(s.get("substr-arg-1")), Arg::new_optional(s.get("substr-arg-2"), ArgT::Scalar)],
             BuiltinFunc::Index => vec![Arg::new_scl(s.get("index-arg-0")), Arg::new_scl(s.get("index-arg-1"))],
             BuiltinFunc::Matches => vec![Arg::new_scl(s.get("match-arg-0")), Arg::new_scl(s.get("match-arg-1"))],
+            BuiltinFunc::Gensub => vec![Arg::new_scl(s.get("gensub-arg-0")), Arg::new_scl(s.get("gensub-arg-1")), Arg::new_scl(s.get("gensub-arg-2")), Arg::new_optional(s.get("gensub-arg-3"), ArgT::Scalar)],
+            BuiltinFunc::Asort => vec![Arg::new_arr(s.get("asort-arg-0")), Arg::new_optional(s.get("asort-arg-1"), ArgT::Array), Arg::new_optional(s.get("asort-arg-2"), ArgT::Scalar)],
+            BuiltinFunc::Asorti => vec![Arg::new_arr(s.get("asorti-arg-0")), Arg::new_optional(s.get("asorti-arg-1"), ArgT::Array), Arg::new_optional(s.get("asorti-arg-2"), ArgT::Scalar)],
+            BuiltinFunc::Mktime => vec![Arg::new_scl(s.get("mktime-arg-0")), Arg::new_optional(s.get("mktime-arg-1"), ArgT::Scalar)],
+            BuiltinFunc::Strftime => vec![Arg::new_optional(s.get("strftime-arg-0"), ArgT::Scalar), Arg::new_optional(s.get("strftime-arg-1"), ArgT::Scalar), Arg::new_optional(s.get("strftime-arg-2"), ArgT::Scalar)],
+            BuiltinFunc::Systime => vec![],
+            BuiltinFunc::And => vec![Arg::new_scl(s.get("and-arg-0")), Arg::new_scl(s.get("and-arg-1"))],
+            BuiltinFunc::Or => vec![Arg::new_scl(s.get("or-arg-0")), Arg::new_scl(s.get("or-arg-1"))],
+            BuiltinFunc::Xor => vec![Arg::new_scl(s.get("xor-arg-0")), Arg::new_scl(s.get("xor-arg-1"))],
+            BuiltinFunc::Lshift => vec![Arg::new_scl(s.get("lshift-arg-0")), Arg::new_scl(s.get("lshift-arg-1"))],
+            BuiltinFunc::Rshift => vec![Arg::new_scl(s.get("rshift-arg-0")), Arg::new_scl(s.get("rshift-arg-1"))],
+            BuiltinFunc::Compl => vec![Arg::new_scl(s.get("compl-arg-0"))],
+            BuiltinFunc::Strtonum => vec![Arg::new_scl(s.get("strtonum-arg-0"))],
+            BuiltinFunc::Isarray => vec![Arg::new_either(s.get("isarray-arg-0"), false)],
+            BuiltinFunc::Typeof => vec![Arg::new_either(s.get("typeof-arg-0"), false)],
             BuiltinFunc::Sprintf => todo!(),
             BuiltinFunc::Close => todo!(),
             BuiltinFunc::System => todo!(),
         }
     }
+    // The type of the value returned when the typing passes can know it. Other builtins are
+    // typed as Var.
+    pub fn returns(&self) -> Option<ScalarType> {
+        match self {
+            BuiltinFunc::And
+            | BuiltinFunc::Or
+            | BuiltinFunc::Xor
+            | BuiltinFunc::Lshift
+            | BuiltinFunc::Rshift
+            | BuiltinFunc::Compl
+            | BuiltinFunc::Strtonum
+            | BuiltinFunc::Isarray => Some(ScalarType::Num),
+            BuiltinFunc::Typeof => Some(ScalarType::Str),
+            _ => None,
+        }
+    }
     pub fn names_as_symbols(s: &mut Symbolizer) -> [Symbol; NUM_BUILTIN_VARIANTS] {
         [
+            s.get("and"),
+            s.get("asort"),
+            s.get("asorti"),
             s.get("atan2"),
             s.get("close"),
+            s.get("compl"),
             s.get("cos"),
             s.get("exp"),
+            s.get("gensub"),
             s.get("substr"),
             s.get("index"),
             s.get("int"),
+            s.get("isarray"),
             s.get("length"),
+            s.get("lshift"),
             s.get("log"),
             s.get("match"),
+            s.get("mktime"),
+            s.get("or"),
             s.get("rand"),
+            s.get("rshift"),
             s.get("sin"),
             s.get("split"),
             s.get("sprintf"),
             s.get("sqrt"),
             s.get("srand"),
+            s.get("strftime"),
+            s.get("strtonum"),
             s.get("system"),
+            s.get("systime"),
+            s.get("typeof"),
+            s.get("xor"),
             s.get("tolower"),
             s.get("toupper"),
         ]
     }
     pub fn to_str(&self) -> &'static str {
         match self {
+            BuiltinFunc::And => "And",
+            BuiltinFunc::Asort => "Asort",
+            BuiltinFunc::Asorti => "Asorti",
             BuiltinFunc::Atan2 => "Atan2",
             BuiltinFunc::Close => "Close",
+            BuiltinFunc::Compl => "Compl",
             BuiltinFunc::Cos => "Cos",
             BuiltinFunc::Exp => "Exp",
+            BuiltinFunc::Gensub => "Gensub",
             BuiltinFunc::Index => "Index",
             BuiltinFunc::Int => "Int",
+            BuiltinFunc::Isarray => "Isarray",
             BuiltinFunc::Length => "Length",
             BuiltinFunc::Log => "Log",
+            BuiltinFunc::Lshift => "Lshift",
             BuiltinFunc::Matches => "Match",
+            BuiltinFunc::Mktime => "Mktime",
+            BuiltinFunc::Or => "Or",
             BuiltinFunc::Rand => "Rand",
+            BuiltinFunc::Rshift => "Rshift",
             BuiltinFunc::Sin => "Sin",
             BuiltinFunc::Split => "Split",
             BuiltinFunc::Sprintf => "Sprintf",
             BuiltinFunc::Sqrt => "Sqrt",
             BuiltinFunc::Srand => "Srand",
+            BuiltinFunc::Strftime => "Strftime",
+            BuiltinFunc::Strtonum => "Strtonum",
             BuiltinFunc::Substr => "Substr",
             BuiltinFunc::System => "System",
+            BuiltinFunc::Systime => "Systime",
             BuiltinFunc::Tolower => "Tolower",
             BuiltinFunc::Toupper => "Toupper",
+            BuiltinFunc::Typeof => "Typeof",
+            BuiltinFunc::Xor => "Xor",
         }
     }
     pub fn get(value: &str) -> Option<BuiltinFunc> {
         let res = match value {
+            "and" => BuiltinFunc::And,
+            "asort" => BuiltinFunc::Asort,
+            "asorti" => BuiltinFunc::Asorti,
             "atan2" => BuiltinFunc::Atan2,
             "close" => BuiltinFunc::Close,
+            "compl" => BuiltinFunc::Compl,
             "cos" => BuiltinFunc::Cos,
             "exp" => BuiltinFunc::Exp,
+            "gensub" => BuiltinFunc::Gensub,
             "index" => BuiltinFunc::Index,
             "int" => BuiltinFunc::Int,
+            "isarray" => BuiltinFunc::Isarray,
             "length" => BuiltinFunc::Length,
             "log" => BuiltinFunc::Log,
+            "lshift" => BuiltinFunc::Lshift,
             "match" => BuiltinFunc::Matches,
+            "mktime" => BuiltinFunc::Mktime,
+            "or" => BuiltinFunc::Or,
             "rand" => BuiltinFunc::Rand,
+            "rshift" => BuiltinFunc::Rshift,
             "sin" => BuiltinFunc::Sin,
             "split" => BuiltinFunc::Split,
             "sprintf" => BuiltinFunc::Sprintf,
             "sqrt" => BuiltinFunc::Sqrt,
             "srand" => BuiltinFunc::Srand,
+            "strftime" => BuiltinFunc::Strftime,
+            "strtonum" => BuiltinFunc::Strtonum,
             "substr" => BuiltinFunc::Substr,
             "system" => BuiltinFunc::System,
+            "systime" => BuiltinFunc::Systime,
             "tolower" => BuiltinFunc::Tolower,
             "toupper" => BuiltinFunc::Toupper,
+            "typeof" => BuiltinFunc::Typeof,
+            "xor" => BuiltinFunc::Xor,
             _ => return None,
         };
         Some(res)
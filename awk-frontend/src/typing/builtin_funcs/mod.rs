@@ -1,6 +1,8 @@
 mod builtin_factory;
 mod builtin_func;
+mod native_func;
 mod typed_builtin;
 
 pub use builtin_factory::BuiltinFactory;
 pub use builtin_func::BuiltinFunc;
+pub use native_func::{NativeArgT, NativeFunc};
@@ -0,0 +1,35 @@
+use crate::parser::{Arg, ScalarType};
+use crate::Symbolizer;
+
+// Type of an arg to a native function. Scalars are converted to the declared type before the call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeArgT {
+    Num,
+    Str,
+    Array,
+}
+
+// A function provided by the program embedding the interpreter rather than written in awk.
+// Typed like a builtin, each backend decides how to call it. Every arg is required.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NativeFunc {
+    pub name: String,
+    pub args: Vec<NativeArgT>,
+    pub returns: ScalarType,
+}
+
+impl NativeFunc {
+    pub fn new<S: Into<String>>(name: S, args: Vec<NativeArgT>, returns: ScalarType) -> Self {
+        Self { name: name.into(), args, returns }
+    }
+
+    pub fn typed_args(&self, s: &mut Symbolizer) -> Vec<Arg> {
+        self.args.iter().enumerate().map(|(idx, typ)| {
+            let name = s.get(&format!("{}-arg-{}", self.name, idx));
+            match typ {
+                NativeArgT::Num | NativeArgT::Str => Arg::new_scl(name),
+                NativeArgT::Array => Arg::new_arr(name),
+            }
+        }).collect()
+    }
+}
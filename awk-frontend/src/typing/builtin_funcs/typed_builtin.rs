@@ -13,7 +13,8 @@ use std::rc::Rc;
 #[derive(Debug)]
 pub struct TypedBuiltin {
     args: RefCell<Vec<Arg>>,
-    builtin: BuiltinFunc,
+    // None for native functions
+    builtin: Option<BuiltinFunc>,
     min_arity: usize,
     name: Symbol,
     shared: Rc<BuiltinShared>, // Shared empty callers and calls sets between all builtins
@@ -23,7 +24,7 @@ impl TypedBuiltin {
     pub fn new(
         name: Symbol,
         args: Vec<Arg>,
-        builtin: BuiltinFunc,
+        builtin: Option<BuiltinFunc>,
         shared: Rc<BuiltinShared>,
     ) -> Self {
         let min_arity = args.iter().filter(|a| !a.builtin_optional).count();
@@ -39,7 +40,10 @@ impl TypedBuiltin {
 
 impl Display for TypedBuiltin {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "builtin-func-{}", self.builtin)
+        match self.builtin {
+            Some(builtin) => write!(f, "builtin-func-{}", builtin),
+            None => write!(f, "native-func-{}", self.name),
+        }
     }
 }
 
@@ -73,16 +77,16 @@ impl ITypedFunction for TypedBuiltin {
     fn reverse_call(
         &self,
         _link: &Call,
-        _args: &Vec<Arg>,
+        _args: &[Arg],
         _analysis: &mut AnalysisResults,
     ) -> Result<HashSet<Symbol>, PrintableError> {
         Ok(HashSet::new())
     }
 
-    fn receive_call(&self, call: &Vec<ArgT>) -> Result<HashSet<Symbol>, PrintableError> {
+    fn receive_call(&self, call: &[ArgT]) -> Result<HashSet<Symbol>, PrintableError> {
         let mut builtin_args = self.args.borrow_mut();
         reconcile(
-            call.as_slice(),
+            call,
             builtin_args.as_mut_slice(),
             self.name.clone(),
             &mut |_idx| {},
@@ -1,8 +1,5 @@
 use std::fmt::{Debug, Formatter};
-use std::ops::Deref;
 use crate::global_scalars::SymbolMappingValue;
-use crate::parser::{SclSpecial};
-use crate::symbolizer::Symbol;
 
 // These wrappers help prevent mixing of ids between arrays and scalars
 
@@ -16,13 +13,13 @@ pub struct GlobalScalarId {
 }
 
 impl SymbolMappingValue for GlobalArrayId {
-    fn create(sym: &Symbol, id: usize) -> Self {
+    fn create(id: usize) -> Self {
         Self { id }
     }
 }
 
 impl SymbolMappingValue for GlobalScalarId {
-    fn create(sym: &Symbol, id: usize) -> Self {
+    fn create(id: usize) -> Self {
         Self { id }
     }
 }
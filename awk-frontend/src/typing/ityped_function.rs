@@ -2,7 +2,8 @@ use crate::parser::{Arg, ArgT};
 use crate::symbolizer::Symbol;
 use crate::typing::structs::Call;
 use crate::typing::TypedUserFunction;
-use crate::{AnalysisResults, PrintableError};
+use crate::typing::AnalysisResults;
+use crate::PrintableError;
 use hashbrown::HashSet;
 use std::cell::Ref;
 use std::fmt::{Debug, Display};
@@ -23,14 +24,14 @@ pub trait ITypedFunction: Debug + Display {
     fn reverse_call(
         &self,
         link: &Call,
-        args: &Vec<Arg>,
+        args: &[Arg],
         analysis: &mut AnalysisResults,
     ) -> Result<HashSet<Symbol>, PrintableError>;
 
     // We are function B
     // A --> B
     // A has new information about the link from A to B
-    fn receive_call(&self, call: &Vec<ArgT>) -> Result<HashSet<Symbol>, PrintableError>;
+    fn receive_call(&self, call: &[ArgT]) -> Result<HashSet<Symbol>, PrintableError>;
 }
 impl PartialEq for dyn ITypedFunction {
     fn eq(&self, other: &Self) -> bool {
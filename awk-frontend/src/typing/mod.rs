@@ -0,0 +1,31 @@
+mod builtin_funcs;
+mod ityped_function;
+mod passes;
+mod reconcile;
+mod structs;
+#[cfg(test)]
+mod tests;
+mod ids;
+
+pub use builtin_funcs::{BuiltinFunc, NativeArgT, NativeFunc};
+pub use structs::{
+    AnalysisResults, FunctionMap, ITypedFunction, MapT, TypedProgram, TypedUserFunction,
+};
+pub use ids::{GlobalScalarId, GlobalArrayId};
+
+use crate::parser::Program;
+use crate::printable_error::PrintableError;
+use passes::{array_type_pass, function_pass, inference_pass};
+pub use passes::return_type_pass;
+use crate::Symbolizer;
+
+pub fn analyze(stmt: Program, symbolizer: &mut Symbolizer) -> Result<TypedProgram, PrintableError> {
+    analyze_with_natives(stmt, symbolizer, vec![])
+}
+
+// natives are functions provided by the program embedding the interpreter, see NativeFunc
+pub fn analyze_with_natives(stmt: Program, symbolizer: &mut Symbolizer, natives: Vec<NativeFunc>) -> Result<TypedProgram, PrintableError> {
+    let mut prog = inference_pass(function_pass(stmt, symbolizer, natives)?)?;
+    array_type_pass(&mut prog, symbolizer);
+    Ok(prog)
+}
@@ -0,0 +1,146 @@
+use std::rc::Rc;
+use hashbrown::HashMap;
+use crate::parser::{walk_stmt, ArgT, ArrSpecial, Expr, LValue, ScalarType, Variable};
+use crate::symbolizer::Symbol;
+use crate::typing::{BuiltinFunc, FunctionMap, ITypedFunction, NativeArgT, TypedProgram, TypedUserFunction};
+use crate::typing::passes::function_pass::FunctionAnalysis;
+use crate::Symbolizer;
+
+// Infer the type of the elements of every array. An array passed to a function is the same
+// array as the function's param so arrays and params connected by calls are grouped and share
+// one type: the merge of every value stored into any of them. Reading an element that doesn't
+// exist gives the uninitialized value so reads are never used to narrow a type.
+
+struct ArrayGroups {
+    // Union-find over global arrays (by id) followed by every array param
+    parents: Vec<usize>,
+    params: HashMap<(Symbol, usize), usize>,
+}
+
+impl ArrayGroups {
+    fn find(&mut self, node: usize) -> usize {
+        let parent = self.parents[node];
+        if parent == node {
+            return node;
+        }
+        let root = self.find(parent);
+        self.parents[node] = root;
+        root
+    }
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+    fn node(&self, prog: &TypedProgram, func: &TypedUserFunction, name: &Symbol) -> usize {
+        if let Some(idx) = func.user_func_args().iter().position(|a| a.name == *name && a.typ == ArgT::Array) {
+            self.params[&(func.name(), idx)]
+        } else {
+            prog.global_analysis.global_arrays.get(name).expect("compiler bug in typing pass global array not found").id
+        }
+    }
+}
+
+// Type of a value stored into an array element. None if it never produces a value.
+fn value_type(functions: &FunctionMap, value: &Expr, typ: ScalarType) -> Option<ScalarType> {
+    if let Expr::Call { target, .. } = value {
+        if let Some(func) = functions.get_user_function(target) {
+            return func.inferred_return_type();
+        }
+    }
+    Some(typ)
+}
+
+pub fn array_type_pass(prog: &mut TypedProgram, symbolizer: &mut Symbolizer) {
+    let functions: Vec<Rc<TypedUserFunction>> = prog.functions.user_functions().values().cloned().collect();
+    let globals = prog.global_analysis.global_arrays.len();
+    let mut groups = ArrayGroups { parents: (0..globals).collect(), params: HashMap::new() };
+    for func in functions.iter() {
+        for (idx, arg) in func.user_func_args().iter().enumerate() {
+            if arg.typ == ArgT::Array {
+                groups.params.insert((func.name(), idx), groups.parents.len());
+                groups.parents.push(groups.parents.len());
+            }
+        }
+    }
+
+    // Group arrays with the params they are passed to, and record each value stored
+    let mut stores: Vec<(usize, Option<ScalarType>)> = vec![];
+    for (name, _special) in ArrSpecial::variants() {
+        let id = prog.global_analysis.global_arrays.get(&symbolizer.get(name)).unwrap().id;
+        stores.push((id, Some(ScalarType::Str)));
+    }
+    for func in functions.iter() {
+        walk_stmt(&mut func.function().body, &mut |expr| {
+            match &expr.expr {
+                Expr::ArrayAssign { name, value, .. } => {
+                    let typ = value_type(&prog.functions, &value.expr, value.typ);
+                    stores.push((groups.node(prog, func, name), typ));
+                }
+                Expr::IncDec { lvalue: LValue::ArrayIndex { name, .. }, .. } => {
+                    stores.push((groups.node(prog, func, name), Some(ScalarType::Num)));
+                }
+                Expr::CallSub { string: LValue::ArrayIndex { name, .. }, .. }
+                | Expr::Getline { var: Some(LValue::ArrayIndex { name, .. }), .. } => {
+                    stores.push((groups.node(prog, func, name), Some(ScalarType::Str)));
+                }
+                Expr::Call { target, args } => {
+                    if prog.functions.get_user_function(target).is_some() {
+                        for (idx, arg) in args.iter().enumerate() {
+                            if let (Expr::Variable(Variable::User(name)), Some(&param)) = (&arg.expr, groups.params.get(&(target.clone(), idx))) {
+                                let node = groups.node(prog, func, name);
+                                groups.union(node, param);
+                            }
+                        }
+                    } else if matches!(BuiltinFunc::get(target.to_str()), Some(BuiltinFunc::Split)) {
+                        if let Expr::Variable(Variable::User(name)) = &args[1].expr {
+                            stores.push((groups.node(prog, func, name), Some(ScalarType::Str)));
+                        }
+                    } else if let Some(sort @ (BuiltinFunc::Asort | BuiltinFunc::Asorti)) = BuiltinFunc::get(target.to_str()) {
+                        // The sorted values (asort) or indices (asorti) go into dest, or replace src without one
+                        let (dest, typ) = match (sort, args.get(1)) {
+                            (BuiltinFunc::Asort, Some(dest)) => (dest, ScalarType::Var),
+                            (BuiltinFunc::Asort, None) => return,
+                            (_, dest) => (dest.unwrap_or(&args[0]), ScalarType::Str),
+                        };
+                        if let Expr::Variable(Variable::User(name)) = &dest.expr {
+                            stores.push((groups.node(prog, func, name), Some(typ)));
+                        }
+                    } else if let Some((_id, native)) = prog.functions.native(target) {
+                        // Native functions can store anything into their array args
+                        for (arg, typ) in args.iter().zip(native.args.iter()) {
+                            if let (Expr::Variable(Variable::User(name)), NativeArgT::Array) = (&arg.expr, typ) {
+                                stores.push((groups.node(prog, func, name), Some(ScalarType::Var)));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        });
+    }
+
+    let mut types: Vec<Option<ScalarType>> = vec![None; groups.parents.len()];
+    for (node, typ) in stores {
+        let root = groups.find(node);
+        types[root] = match (types[root], typ) {
+            (Some(a), Some(b)) => Some(FunctionAnalysis::merge_types(&a, &b)),
+            (a, None) => a,
+            (None, b) => b,
+        };
+    }
+    // Arrays that are never stored into are empty, Var is as good as any other type
+    let node_types: Vec<ScalarType> = (0..groups.parents.len())
+        .map(|node| types[groups.find(node)].unwrap_or(ScalarType::Var))
+        .collect();
+
+    prog.global_analysis.array_types = node_types[..globals].to_vec();
+    for func in functions.iter() {
+        let params: Vec<usize> = func.user_func_args().iter().enumerate()
+            .filter(|(_idx, arg)| arg.typ == ArgT::Array)
+            .map(|(idx, _arg)| idx)
+            .collect();
+        for idx in params {
+            func.set_elem_type(idx, node_types[groups.params[&(func.name(), idx)]]);
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use std::collections::HashSet;
 use crate::global_scalars::{SymbolMapping};
-use crate::parser::{ArgT, ArrSpecial, LValue, Program, ScalarType, SclSpecial, Stmt, TypedExpr, Variable};
+use crate::parser::{ArgT, ArrSpecial, Expr, GetlineSource, LValue, Program, ScalarType, Stmt, TypedExpr, Variable};
 use crate::symbolizer::Symbol;
 use crate::typing::structs::{Call, CallArg, FunctionMap, TypedUserFunction};
-use crate::typing::{AnalysisResults, GlobalArrayId, ITypedFunction, MapT, TypedProgram};
-use crate::{Expr, PrintableError, Symbolizer};
+use crate::typing::{AnalysisResults, BuiltinFunc, GlobalArrayId, ITypedFunction, MapT, NativeFunc, TypedProgram};
+use crate::{PrintableError, Symbolizer};
 use hashbrown::{HashMap};
 use std::rc::Rc;
 use crate::awk_str::RcAwkStr;
@@ -16,7 +16,7 @@ pub struct FunctionAnalysis {
     functions: FunctionMap,
 }
 
-pub fn function_pass(prog: Program, symbolizer: &mut Symbolizer) -> Result<TypedProgram, PrintableError> {
+pub fn function_pass(prog: Program, symbolizer: &mut Symbolizer, natives: Vec<NativeFunc>) -> Result<TypedProgram, PrintableError> {
     let mut functions = HashMap::new();
     for (name, function) in prog.functions {
         functions.insert(name, Rc::new(TypedUserFunction::new(function)));
@@ -28,11 +28,15 @@ pub fn function_pass(prog: Program, symbolizer: &mut Symbolizer) -> Result<Typed
         global_arrays.insert(&symbolizer.get(name))
     }
 
+    let mut function_map = FunctionMap::new(functions, &prog.symbolizer);
+    for native in natives {
+        function_map.add_native(native);
+    }
     let analysis = FunctionAnalysis {
         global_scalars: MapT::new(),
         global_arrays,
         str_consts: Default::default(),
-        functions: FunctionMap::new(functions, &prog.symbolizer),
+        functions: function_map,
     };
     analysis.analyze_program()
 }
@@ -112,7 +116,7 @@ impl FunctionAnalysis {
     ) -> Result<(), PrintableError> {
         let var = match var {
             Variable::User(sym) => sym,
-            Variable::Special(special) => {
+            Variable::Special(_special) => {
                 // For now all special awk variables are variable type since they can be
                 // reset in ways the type checker doesn't expect right now.
                 // Bail here so the types of the global special scalars are always variable.
@@ -153,7 +157,7 @@ impl FunctionAnalysis {
                 var
             )));
         }
-        self.global_arrays.insert(&var);
+        self.global_arrays.insert(var);
         Ok(())
     }
 
@@ -177,7 +181,7 @@ impl FunctionAnalysis {
                 }
                 self.analyze_expr(fstring, function, false)?;
             }
-            Stmt::Break => {}
+            Stmt::Break | Stmt::NextFile => {}
             Stmt::Expr(expr) => self.analyze_expr(expr, function, false)?,
             Stmt::Print(expr) => self.analyze_expr(expr, function, false)?,
             Stmt::Group(grouping) => {
@@ -223,6 +227,36 @@ impl FunctionAnalysis {
                 self.global_scalars =
                     FunctionAnalysis::merge_maps(&[&after_test_map, &after_body_map, &pre_map]);
             }
+            Stmt::ForIn { var, array, body } => {
+                // Like a while loop whose test assigns the next key (always a string) to var
+                self.use_as_array(array, function)?;
+                let pre_map = self.global_scalars.clone();
+                for _ in 0..2 {
+                    self.use_as_scalar(var, ScalarType::Str, function)?;
+                    let after_test_map = self.global_scalars.clone();
+                    self.analyze_stmt(body, function)?;
+                    let after_body_map = self.global_scalars.clone();
+                    self.global_scalars =
+                        FunctionAnalysis::merge_maps(&[&after_test_map, &after_body_map, &pre_map]);
+                }
+            }
+            Stmt::Files { begin_file, records, end_file } => {
+                // A loop over the files whose body is BEGINFILE, a loop over the records, then ENDFILE
+                let pre_map = self.global_scalars.clone();
+                for _ in 0..2 {
+                    self.analyze_stmt(begin_file, function)?;
+                    let pre_records_map = self.global_scalars.clone();
+                    for _ in 0..2 {
+                        self.analyze_stmt(records, function)?;
+                        let after_records_map = self.global_scalars.clone();
+                        self.global_scalars =
+                            FunctionAnalysis::merge_maps(&[&after_records_map, &pre_records_map]);
+                    }
+                    self.analyze_stmt(end_file, function)?;
+                    let after_end_map = self.global_scalars.clone();
+                    self.global_scalars = FunctionAnalysis::merge_maps(&[&after_end_map, &pre_map]);
+                }
+            }
         }
         Ok(())
     }
@@ -263,7 +297,14 @@ impl FunctionAnalysis {
                 };
                 let call = Call::new(function.clone(), target_func.clone(), call_args.collect());
                 function.add_call(call);
-                target_func.add_caller(function.clone())
+                target_func.add_caller(function.clone());
+                if self.functions.get_user_function(target).is_none() {
+                    if let Some((_id, native)) = self.functions.native(target) {
+                        expr.typ = native.returns;
+                    } else if let Some(returns) = BuiltinFunc::get(target.to_str()).and_then(|builtin| builtin.returns()) {
+                        expr.typ = returns;
+                    }
+                }
             }
             Expr::CallSub { ere, replacement, string, global: _global } => {
                 self.analyze_expr(ere, function, false)?;
@@ -331,6 +372,8 @@ impl FunctionAnalysis {
                                     var
                                 )));
                             }
+                            // Variables passed to functions aren't analyzed here so this is a scalar use
+                            self.user_as_scalar_sym(var, ScalarType::Var, function)?;
                         } else if self.global_arrays.contains_key(var) && is_returned {
                             return Err(PrintableError::new(format!("fatal: attempted to use array {} in scalar context", var)));
                         } else if let Some(typ) = self.global_scalars.get(var) {
@@ -354,11 +397,11 @@ impl FunctionAnalysis {
             Expr::Concatenation(vals) => {
                 expr.typ = ScalarType::Str;
                 for val in vals {
-                    self.analyze_expr(val, &function, false)?;
+                    self.analyze_expr(val, function, false)?;
                 }
             }
             Expr::ArrayIndex { indices, name } => {
-                self.use_as_array(name, &function)?;
+                self.use_as_array(name, function)?;
                 for idx in indices {
                     self.analyze_expr(idx, function, false)?;
                 }
@@ -381,15 +424,54 @@ impl FunctionAnalysis {
                 self.analyze_expr(value, function, false)?;
                 expr.typ = value.typ;
             }
+            Expr::ColumnAssign { column, value } => {
+                self.analyze_expr(value, function, false)?;
+                self.analyze_expr(column, function, false)?;
+                expr.typ = ScalarType::Str;
+            }
+            Expr::IncDec { lvalue, .. } => {
+                match lvalue {
+                    LValue::Variable(var) => self.use_as_scalar(var, ScalarType::Num, function)?,
+                    LValue::ArrayIndex { name, indices } => {
+                        self.use_as_array(name, function)?;
+                        for idx in indices {
+                            self.analyze_expr(idx, function, false)?;
+                        }
+                    }
+                    LValue::Column(col) => self.analyze_expr(col, function, false)?,
+                }
+                expr.typ = ScalarType::Num;
+            }
+            Expr::Getline { source, var } => {
+                if let GetlineSource::File(expr) | GetlineSource::Command(expr) = source {
+                    self.analyze_expr(expr, function, false)?;
+                }
+                match var {
+                    Some(LValue::Variable(var)) => {
+                        // Like an if, the var is only assigned when a record is read
+                        let not_read_map = self.global_scalars.clone();
+                        self.use_as_scalar(var, ScalarType::Str, function)?;
+                        self.global_scalars = FunctionAnalysis::merge_maps(&[&self.global_scalars, &not_read_map]);
+                    }
+                    Some(LValue::ArrayIndex { name, indices }) => {
+                        self.use_as_array(name, function)?;
+                        for idx in indices {
+                            self.analyze_expr(idx, function, false)?;
+                        }
+                    }
+                    Some(LValue::Column(col)) => self.analyze_expr(col, function, false)?,
+                    None => {}
+                }
+                expr.typ = ScalarType::Num;
+            }
         };
         Ok(())
     }
 
     fn merge_maps(children: &[&MapT]) -> MapT {
         let mut merged = vec![];
-        for var in children.into_iter()
-            .map(|map| map.into_iter().map(|(k, _value)| k.clone()))
-            .flatten() {
+        for var in children.iter()
+            .flat_map(|map| map.into_iter().map(|(k, _value)| k.clone())) {
             if merged.iter().find(|(var_name, _v)| *var_name == var).is_some() { continue; };
             // Invariant: at least one map contains `var` and thus typ will be assigned a non-0
             // value at least one in the loop leaving it as a valid ScalarType enum.
@@ -399,7 +481,7 @@ impl FunctionAnalysis {
                     None => ScalarType::Var,
                     Some(typ) => *typ,
                 };
-                typ = map_typ as i32 | typ;
+                typ |= map_typ as i32;
             }
             let scalar_type = unsafe { std::mem::transmute::<i32, ScalarType>(typ) };
             debug_assert!(scalar_type == ScalarType::Var || scalar_type == ScalarType::Str || scalar_type == ScalarType::Num);
@@ -408,10 +490,10 @@ impl FunctionAnalysis {
             merged.push((var, scalar_type));
         }
         let merged_immutable = MapT::new();
-        let map = merged_immutable.insert_many(merged.into_iter());
-        map
+        
+        merged_immutable.insert_many(merged)
     }
-    fn merge_types(a: &ScalarType, b: &ScalarType) -> ScalarType {
+    pub fn merge_types(a: &ScalarType, b: &ScalarType) -> ScalarType {
         unsafe { std::mem::transmute::<i32, ScalarType>(*a as i32 | *b as i32) }
     }
 }
@@ -0,0 +1,182 @@
+use crate::parser::{ArgT, Expr, ScalarType, Stmt, TypedExpr};
+use crate::symbolizer::Symbol;
+use crate::typing::passes::function_pass::FunctionAnalysis;
+use crate::typing::structs::{Call, CallArg, FunctionMap};
+use crate::typing::{ITypedFunction, TypedProgram};
+use crate::PrintableError;
+use hashbrown::HashSet;
+
+fn propogate(
+    program: &mut TypedProgram,
+    link: &Call,
+) -> Result<(HashSet<Symbol>, HashSet<Symbol>), PrintableError> {
+    let caller_arg_types = link.src.get_call_types(&program.global_analysis, link);
+
+    let dest = link.target.clone();
+    let src = link.src.clone();
+
+    if link.args.len() < dest.min_arity() {
+        return Err(PrintableError::new(format!(
+            "Function `{}` accepts a min of {} arguments but was called with {} from function `{}`",
+            dest.name(),
+            dest.min_arity(),
+            link.args.len(),
+            link.src.name()
+        )));
+    }
+    if link.args.len() > dest.args().len() {
+        return Err(PrintableError::new(format!(
+            "Function `{}` accepts a max of {} arguments but was called with {} from function `{}`",
+            dest.name(),
+            dest.args().len(),
+            link.args.len(),
+            link.src.name()
+        )));
+    }
+
+    let updated_in_dest = dest.receive_call(&caller_arg_types)?;
+    let updated_in_src = src.reverse_call(link, &dest.args(), &mut program.global_analysis)?;
+    Ok((updated_in_dest, updated_in_src))
+}
+
+pub fn inference_pass(mut prog: TypedProgram) -> Result<TypedProgram, PrintableError> {
+    loop {
+        let mut calls: Vec<Call> = vec![];
+
+        // Push every call between functions onto a stack as a link between them
+        for (_name, func) in prog.functions.user_functions().iter() {
+            for call in func.calls().iter() {
+                calls.push(call.clone());
+            }
+        }
+
+        while let Some(call) = calls.pop() {
+            // While there are links left to analyze propogate any information in the source of the link to the destination
+            let (updated_in_dest, updated_in_src) = propogate(&mut prog, &call)?;
+
+            // If the destination updated any of its symbols push all of the destination's calls
+            // that use those symbols back onto the stack to re-propogate
+            if !updated_in_dest.is_empty() {
+                for call in call.target.calls().iter() {
+                    if call.uses_any(&updated_in_dest) {
+                        calls.push(call.clone())
+                    }
+                }
+            }
+
+            if updated_in_src.is_empty() {
+                continue;
+            }
+
+            // Loop through functions who call source
+            for caller in call.src.callers().iter() {
+                for call_to_source in caller.calls().iter()
+                    .filter(|call_to_src| call_to_src.target.name() == call.src.name())
+                {
+                    // And push them back on the stack
+                    calls.push(call_to_source.clone());
+                }
+            }
+        }
+
+        // Only after nothing else could type them, see type_either_args
+        if !type_either_args(&prog)? {
+            break;
+        }
+    }
+    return_type_pass(&prog.functions);
+    Ok(prog)
+}
+
+// A variable only ever passed to builtins like length(x) that take a scalar or an array gets
+// no type from them. Function args left untyped are made scalars since awk's default is a
+// scalar and, unlike untyped globals, the function can't read them otherwise. True if any changed.
+fn type_either_args(prog: &TypedProgram) -> Result<bool, PrintableError> {
+    let mut changed = false;
+    for (_name, func) in prog.functions.user_functions().iter() {
+        for call in func.calls().iter() {
+            for (call_arg, target_arg) in call.args.iter().zip(call.target.args().iter()) {
+                let name = match call_arg {
+                    CallArg::Variable(name) if target_arg.builtin_either => name,
+                    _ => continue,
+                };
+                let untyped = func.user_func_args().iter().any(|arg| arg.name == *name && arg.typ == ArgT::Unknown);
+                if untyped {
+                    func.set_arg_type(name, ArgT::Scalar)?;
+                    changed = true;
+                }
+            }
+        }
+    }
+    Ok(changed)
+}
+
+fn merge(a: Option<ScalarType>, b: Option<ScalarType>) -> Option<ScalarType> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(FunctionAnalysis::merge_types(&a, &b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+// Type of a returned value. Calls to user functions are typed by the callee's return type so far.
+fn returned_type(expr: &TypedExpr, functions: &FunctionMap) -> Option<ScalarType> {
+    match &expr.expr {
+        Expr::Call { target, .. } => match functions.get_user_function(target) {
+            Some(func) => func.inferred_return_type(),
+            None => Some(expr.typ),
+        },
+        Expr::Ternary(_test, if_so, if_not) => merge(returned_type(if_so, functions), returned_type(if_not, functions)),
+        _ => Some(expr.typ),
+    }
+}
+
+fn stmt_return_type(stmt: &Stmt, functions: &FunctionMap) -> Option<ScalarType> {
+    match stmt {
+        // A bare return returns the uninitialized value
+        Stmt::Return(None) => Some(ScalarType::Var),
+        Stmt::Return(Some(ret)) => returned_type(ret, functions),
+        Stmt::Group(stmts) => stmts.iter().fold(None, |typ, stmt| merge(typ, stmt_return_type(stmt, functions))),
+        Stmt::If(_test, if_so, if_not) => {
+            let if_not = if_not.as_ref().and_then(|stmt| stmt_return_type(stmt, functions));
+            merge(stmt_return_type(if_so, functions), if_not)
+        }
+        Stmt::While(_, body) | Stmt::ForIn { body, .. } => stmt_return_type(body, functions),
+        Stmt::Files { begin_file, records, end_file } => {
+            let typ = merge(stmt_return_type(begin_file, functions), stmt_return_type(records, functions));
+            merge(typ, stmt_return_type(end_file, functions))
+        }
+        Stmt::Expr(_) | Stmt::Print(_) | Stmt::Printf { .. } | Stmt::Break | Stmt::NextFile => None,
+    }
+}
+
+fn always_returns(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_) => true,
+        Stmt::Group(stmts) => stmts.last().is_some_and(always_returns),
+        Stmt::If(_test, if_so, Some(if_not)) => always_returns(if_so) && always_returns(if_not),
+        _ => false,
+    }
+}
+
+// Infer return types as a fixpoint. Types only widen (None -> Str|Num -> Var) so this terminates.
+pub fn return_type_pass(functions: &FunctionMap) {
+    loop {
+        let mut changed = false;
+        for (_name, func) in functions.user_functions().iter() {
+            let typ = {
+                let function = func.function();
+                let typ = stmt_return_type(&function.body, functions);
+                // Falling off the end returns the uninitialized value
+                if always_returns(&function.body) { typ } else { merge(typ, Some(ScalarType::Var)) }
+            };
+            if typ != func.inferred_return_type() {
+                func.set_return_type(typ);
+                changed = true;
+            }
+        }
+        if !changed {
+            return;
+        }
+    }
+}
@@ -0,0 +1,7 @@
+mod array_type_pass;
+mod function_pass;
+mod inference_pass;
+
+pub use array_type_pass::array_type_pass;
+pub use function_pass::function_pass;
+pub use inference_pass::{inference_pass, return_type_pass};
@@ -10,8 +10,6 @@ pub fn reconcile(
     func_name: Symbol,
     update_callback: &mut dyn FnMut(Symbol),
 ) -> Result<(), PrintableError> {
-    if call_args.len() != func_args.len() {
-    }
     for idx in 0..max(func_args.len(), call_args.len()) {
         let func_arg = func_args.get_mut(idx);
         let call_arg = call_args.get(idx);
@@ -45,6 +43,10 @@ pub fn reconcile(
 }
 
 fn reconcile_one_arg(func_arg: &mut Arg, call_arg: &ArgT, update_callback: &mut dyn FnMut(Symbol)) -> Result<(), PrintableError> {
+    // Accepts anything and is shared by every call so it must not take on the caller's type
+    if func_arg.builtin_either {
+        return Ok(());
+    }
     match (func_arg.typ, call_arg) {
         // Mismatch
         (ArgT::Scalar, ArgT::Array) => {
@@ -5,7 +5,6 @@ use crate::symbolizer::Symbol;
 use immutable_chunkmap::map::Map;
 use std::fmt::Debug;
 use crate::awk_str::{RcAwkStr};
-use crate::Symbolizer;
 use crate::typing::{GlobalArrayId, GlobalScalarId};
 
 pub type MapT = Map<Symbol, ScalarType, 256>;
@@ -14,6 +13,8 @@ pub type MapT = Map<Symbol, ScalarType, 256>;
 pub struct AnalysisResults {
     pub global_scalars: SymbolMapping<GlobalScalarId>,
     pub global_arrays: SymbolMapping<GlobalArrayId>,
+    // Type of the elements of each global array by id. Set by the array type pass.
+    pub array_types: Vec<ScalarType>,
     pub str_consts: HashSet<RcAwkStr>,
 }
 
@@ -22,6 +23,7 @@ impl AnalysisResults {
         Self {
             global_scalars: SymbolMapping::new(),
             global_arrays: SymbolMapping::new(),
+            array_types: vec![],
             str_consts: HashSet::new(),
         }
     }
@@ -29,9 +31,11 @@ impl AnalysisResults {
     pub fn new(global_scalars: SymbolMapping<GlobalScalarId>,
                global_arrays: SymbolMapping<GlobalArrayId>,
                str_consts: HashSet<RcAwkStr>,) -> Self {
+        let array_types = vec![ScalarType::Var; global_arrays.len()];
         Self {
             global_scalars,
             global_arrays,
+            array_types,
             str_consts,
         }
     }
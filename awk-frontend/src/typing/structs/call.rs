@@ -9,6 +9,7 @@ pub struct Call {
     pub args: Vec<CallArg>,
     pub src: Rc<dyn ITypedFunction>,
 }
+
 impl PartialEq for Call {
     fn eq(&self, other: &Self) -> bool {
         self.target.name() == other.target.name()
@@ -16,6 +17,7 @@ impl PartialEq for Call {
             && self.src.name() == other.src.name()
     }
 }
+
 impl Clone for Call {
     fn clone(&self) -> Self {
         Self {
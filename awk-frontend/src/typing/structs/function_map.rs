@@ -1,5 +1,5 @@
 use crate::symbolizer::Symbol;
-use crate::typing::builtin_funcs::BuiltinFactory;
+use crate::typing::builtin_funcs::{BuiltinFactory, NativeFunc};
 use crate::typing::ityped_function::ITypedFunction;
 use crate::typing::{BuiltinFunc, TypedUserFunction};
 use crate::Symbolizer;
@@ -29,16 +29,15 @@ impl FunctionMap {
         self.functions_by_id.get(&id)
     }
     pub fn get_id(&self, name: &Symbol) -> Option<usize> {
-        match self.functions_by_id.iter().find(|(_k,v)| v.name() == *name) {
-            None => None,
-            Some((k,_v)) => Some(*k),
-        }
+        self.functions_by_id.iter().find(|(_k,v)| v.name() == *name).map(|(k,_v)| *k)
     }
-    pub fn get<'a>(&mut self, name: &Symbol) -> Option<Rc<dyn ITypedFunction>> {
+    pub fn get(&mut self, name: &Symbol) -> Option<Rc<dyn ITypedFunction>> {
         match self.functions.get(name) {
             None => {
                 if let Some(builtin) = BuiltinFunc::get(name.to_str()) {
                     Some(self.builtin_factory.get(builtin))
+                } else if let Some((_id, _native, typed)) = self.builtin_factory.native(name) {
+                    Some(typed)
                 } else {
                     None
                 }
@@ -46,18 +45,38 @@ impl FunctionMap {
             Some(boxed) => Some(boxed.clone()),
         }
     }
-    pub fn get_user_function<'a>(&self, name: &Symbol) -> Option<Rc<TypedUserFunction>> {
-        match self.functions.get(name) {
-            None => None,
-            Some(boxed) => Some(boxed.clone()),
-        }
+    // Native functions are typed like builtins. Awk functions with the same name take precedence.
+    pub fn add_native(&mut self, native: NativeFunc) {
+        self.builtin_factory.add_native(native)
+    }
+    pub fn native(&self, name: &Symbol) -> Option<(usize, &NativeFunc)> {
+        self.builtin_factory.native(name).map(|(id, native, _typed)| (id, native))
+    }
+    pub fn native_by_id(&self, id: usize) -> Option<&NativeFunc> {
+        self.builtin_factory.natives().nth(id)
+    }
+    pub fn natives(&self) -> impl Iterator<Item=&NativeFunc> {
+        self.builtin_factory.natives()
+    }
+    pub fn get_user_function(&self, name: &Symbol) -> Option<Rc<TypedUserFunction>> {
+        self.functions.get(name).cloned()
     }
     pub fn user_functions(&self) -> &HashMap<Symbol, Rc<TypedUserFunction>> {
         &self.functions
     }
+    // Add a function created after typing, returns its id
+    pub fn insert(&mut self, func: Rc<TypedUserFunction>) -> usize {
+        let id = self.functions_by_id.len();
+        self.functions.insert(func.name(), func.clone());
+        self.functions_by_id.insert(id, func);
+        id
+    }
     pub fn len(&self) -> usize {
         self.functions.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
     pub fn user_functions_iter(&self) -> Iter<'_, Symbol, Rc<TypedUserFunction>> {
         self.functions.iter()
     }
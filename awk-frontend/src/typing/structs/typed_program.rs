@@ -20,7 +20,7 @@ impl Display for TypedProgram {
         sorted.sort();
         for func_name in &sorted {
             let func = self.functions.get_user_function(func_name).unwrap();
-            write!(f, "{}\n", func)?;
+            writeln!(f, "{}", func)?;
         }
         Ok(())
     }
@@ -1,28 +1,35 @@
-use crate::parser::{Arg, ArgT, Function, ScalarType, Variable};
+use crate::parser::{Arg, ArgT, Function, ScalarType, Stmt};
 use crate::symbolizer::Symbol;
 use crate::typing::ityped_function::{ITypedFunction};
 use crate::typing::reconcile::reconcile;
 use crate::typing::structs::{Call, CallArg};
-use crate::{AnalysisResults, PrintableError};
+use crate::typing::AnalysisResults;
+use crate::PrintableError;
 use hashbrown::HashSet;
 use std::cell::{Ref, RefCell, RefMut};
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
+use crate::stack_counter::StackCounter;
+use crate::stackt::StackT;
 
 #[derive(Debug)]
 pub struct TypedUserFunction {
     func: RefCell<Function>,
     callers: RefCell<HashSet<Rc<TypedUserFunction>>>,
     calls: RefCell<Vec<Call>>,
-    #[allow(dead_code)]
-    return_type: RefCell<ScalarType>,
+    // None until a return type is inferred
+    return_type: RefCell<Option<ScalarType>>,
     args: RefCell<Vec<Arg>>,
     name: Symbol,
 
+    // Type of each scalar arg. Always Var unless this is a specialized clone, see compiler::specialize
+    arg_types: Vec<ScalarType>,
+    // Type of the elements of each array arg, Var for other args. Set by the array type pass.
+    elem_types: RefCell<Vec<ScalarType>>,
+
     // caches
-    num_scalar_args: RefCell<Option<usize>>,
-    num_array_args: RefCell<Option<usize>>,
+    arg_stacks: RefCell<Option<StackCounter>>,
 }
 
 impl Hash for TypedUserFunction {
@@ -50,7 +57,8 @@ impl ITypedFunction for TypedUserFunction {
         self.args.borrow()
     }
     fn min_arity(&self) -> usize {
-        self.func.borrow().args.len()
+        // Args the caller leaves off are locals
+        0
     }
     fn add_caller(&self, caller: Rc<TypedUserFunction>) {
         let mut callers = self.callers.borrow_mut();
@@ -71,7 +79,7 @@ impl ITypedFunction for TypedUserFunction {
             .iter()
             .map(|arg| match arg {
                 CallArg::Variable(name) => {
-                    TypedUserFunction::get_type(global_analysis, &self, &name)
+                    TypedUserFunction::get_type(global_analysis, self, name)
                 }
                 CallArg::Scalar => ArgT::Scalar,
             })
@@ -81,7 +89,7 @@ impl ITypedFunction for TypedUserFunction {
     fn reverse_call(
         &self,
         link: &Call,
-        args: &Vec<Arg>,
+        args: &[Arg],
         analysis: &mut AnalysisResults,
     ) -> Result<HashSet<Symbol>, PrintableError> {
         // Used in this case:
@@ -93,8 +101,8 @@ impl ITypedFunction for TypedUserFunction {
         for (call_arg, function_arg) in link.args.iter().zip(args) {
             if let CallArg::Variable(name) = call_arg {
                 let updated_sym = match function_arg.typ {
-                    ArgT::Scalar => self.use_as_scalar(&name, analysis)?,
-                    ArgT::Array => self.use_as_array(&name, analysis)?,
+                    ArgT::Scalar => self.use_as_scalar(name, analysis)?,
+                    ArgT::Array => self.use_as_array(name, analysis)?,
                     ArgT::Unknown => None,
                 };
                 if let Some(updated_sym) = updated_sym {
@@ -104,7 +112,7 @@ impl ITypedFunction for TypedUserFunction {
         }
         Ok(updated)
     }
-    fn receive_call(&self, call: &Vec<ArgT>) -> Result<HashSet<Symbol>, PrintableError> {
+    fn receive_call(&self, call: &[ArgT]) -> Result<HashSet<Symbol>, PrintableError> {
         // Used in this case:
         //      function arg_unknown(a) { ...  a not used here weirdly ... }
         //      BEGIN { c = 1; arg_unknown(c); }
@@ -113,8 +121,8 @@ impl ITypedFunction for TypedUserFunction {
         let mut function_args = self.args.borrow_mut();
         let mut updated_in_dest = HashSet::new();
         reconcile(
-            call.as_slice(),
-            &mut *function_args,
+            call,
+            &mut function_args,
             self.name.clone(),
             &mut |sym| {
                 updated_in_dest.insert(sym);
@@ -130,51 +138,142 @@ impl TypedUserFunction {
         let args = func
             .args
             .iter()
-            .map(|sym| Arg::new(sym.clone(), ArgT::Unknown))
+            .map(|sym| Arg::new_optional(sym.clone(), ArgT::Unknown))
             .collect();
+        let arg_types: Vec<ScalarType> = func.args.iter().map(|_| ScalarType::Var).collect();
+        let elem_types = RefCell::new(arg_types.clone());
         Self {
             func: RefCell::new(func),
             callers: RefCell::new(HashSet::new()),
             calls: RefCell::new(vec![]),
-            return_type: RefCell::new(ScalarType::Var),
+            return_type: RefCell::new(None),
             args: RefCell::new(args),
             name,
-            num_scalar_args: RefCell::new(None),
-            num_array_args: RefCell::new(None),
+            arg_types,
+            elem_types,
+            arg_stacks: RefCell::new(None),
         }
     }
 
+    // A copy of this function whose scalar args have the given types. Only called after typing.
+    pub fn specialize(&self, name: Symbol, arg_types: Vec<ScalarType>) -> Self {
+        let mut func = self.func.borrow().clone();
+        func.name = name.clone();
+        Self {
+            func: RefCell::new(func),
+            callers: RefCell::new(HashSet::new()),
+            calls: RefCell::new(vec![]),
+            return_type: RefCell::new(None),
+            args: RefCell::new(self.args.borrow().clone()),
+            name,
+            arg_types,
+            elem_types: RefCell::new(self.elem_types.borrow().clone()),
+            arg_stacks: RefCell::new(None),
+        }
+    }
+
+    // A function loaded from already compiled bytecode. It has no body, only the types a backend
+    // needs to call it.
+    pub fn precompiled(name: Symbol, args: Vec<Arg>, arg_types: Vec<ScalarType>, elem_types: Vec<ScalarType>, return_type: ScalarType) -> Self {
+        let func = Function {
+            name: name.clone(),
+            args: args.iter().map(|arg| arg.name.clone()).collect(),
+            body: Stmt::Group(vec![]),
+        };
+        Self {
+            func: RefCell::new(func),
+            callers: RefCell::new(HashSet::new()),
+            calls: RefCell::new(vec![]),
+            return_type: RefCell::new(Some(return_type)),
+            args: RefCell::new(args),
+            name,
+            arg_types,
+            elem_types: RefCell::new(elem_types),
+            arg_stacks: RefCell::new(None),
+        }
+    }
+
+    pub fn arg_types(&self) -> &[ScalarType] {
+        &self.arg_types
+    }
+
     pub fn is_main(&self) -> bool {
         self.name.sym.as_str() == "main function"
     }
 
+    // Functions without an inferred return type (only return calls to themselves) return Var
+    pub fn return_type(&self) -> ScalarType {
+        self.return_type.borrow().unwrap_or(ScalarType::Var)
+    }
+    pub fn inferred_return_type(&self) -> Option<ScalarType> {
+        *self.return_type.borrow()
+    }
+    pub fn set_return_type(&self, typ: Option<ScalarType>) {
+        *self.return_type.borrow_mut() = typ;
+    }
+
+    // The stack an arg is passed on, None for args that are never used
+    pub fn arg_stack(&self, idx: usize) -> Option<StackT> {
+        match self.args.borrow()[idx].typ {
+            ArgT::Scalar => Some(self.arg_types[idx].into()),
+            ArgT::Array => Some(StackT::Array),
+            ArgT::Unknown => None,
+        }
+    }
+
+    // Type of a scalar arg and its index among the args passed on the same stack
+    pub fn scalar_arg(&self, sym: &Symbol) -> Option<(ScalarType, usize)> {
+        let args = self.args.borrow();
+        let idx = args.iter().position(|a| a.name == *sym && a.typ == ArgT::Scalar)?;
+        let typ = self.arg_types[idx];
+        let stack_idx = (0..idx).filter(|i| args[*i].typ == ArgT::Scalar && self.arg_types[*i] == typ).count();
+        Some((typ, stack_idx))
+    }
+    // Index of a Var scalar arg. Only these can be assigned to.
     pub fn scalar_arg_idx(&self, sym: &Symbol) -> Option<usize> {
-        self.args.borrow().iter().filter(|a| a.typ == ArgT::Scalar).position(|a| a.name == *sym)
+        match self.scalar_arg(sym) {
+            Some((ScalarType::Var, idx)) => Some(idx),
+            _ => None,
+        }
+    }
+    // Element type of an array arg
+    pub fn elem_type(&self, sym: &Symbol) -> Option<ScalarType> {
+        let idx = self.args.borrow().iter().position(|a| a.name == *sym && a.typ == ArgT::Array)?;
+        Some(self.elem_types.borrow()[idx])
+    }
+    pub fn elem_types(&self) -> Ref<'_, Vec<ScalarType>> {
+        self.elem_types.borrow()
+    }
+    pub fn set_elem_type(&self, idx: usize, typ: ScalarType) {
+        self.elem_types.borrow_mut()[idx] = typ;
     }
     pub fn array_arg_idx(&self, sym: &Symbol) -> Option<usize> {
         self.args.borrow().iter().filter(|a| a.typ == ArgT::Array).position(|a| a.name == *sym)
     }
 
-    pub fn num_scalar_args(&self) -> usize {
-        let mut num_scalar_args = self.num_scalar_args.borrow_mut();
-        if let Some(count) = *num_scalar_args {
-            count
+    // Number of args passed on each stack
+    pub fn arg_stacks(&self) -> StackCounter {
+        let mut arg_stacks = self.arg_stacks.borrow_mut();
+        if let Some(counts) = *arg_stacks {
+            counts
         } else {
-            let count = self.args.borrow().iter().filter(|a| a.typ == ArgT::Scalar).count();
-            let _ = num_scalar_args.insert(count);
-            count
+            let mut counts = StackCounter::new();
+            for idx in 0..self.arg_types.len() {
+                if let Some(stack) = self.arg_stack(idx) {
+                    counts = counts.set(stack, 1);
+                }
+            }
+            let _ = arg_stacks.insert(counts);
+            counts
         }
     }
 
+    pub fn num_scalar_args(&self) -> usize {
+        self.arg_stacks().count(StackT::Var)
+    }
+
     pub fn num_array_args(&self) -> usize {
-        let mut num_array_args = self.num_array_args.borrow_mut();
-        if let Some(count) = *num_array_args {
-            count
-        } else {
-            let count = self.args.borrow().iter().filter(|a| a.typ == ArgT::Array).count();
-            let _ = num_array_args.insert(count);
-            count
-        }
+        self.arg_stacks().count(StackT::Array)
     }
 
     pub fn user_func_args(&self) -> Ref<'_, Vec<Arg>> {
@@ -200,7 +299,7 @@ impl TypedUserFunction {
     pub fn get_arg_idx_and_type(&self, name: &Symbol) -> Option<(usize, ArgT)> {
         let args = self.args.borrow();
         if let Some((idx, arg)) = args.iter().enumerate().find(|(_idx, a)| a.name == *name) {
-            Some((idx, arg.typ.clone()))
+            Some((idx, arg.typ))
         } else {
             None
         }
@@ -231,8 +330,8 @@ impl TypedUserFunction {
                 var
             )));
         }
-        global_analysis.global_arrays.insert(&var);
-        return Ok(Some(var.clone()));
+        global_analysis.global_arrays.insert(var);
+        Ok(Some(var.clone()))
     }
     fn use_as_scalar(
         &self,
@@ -259,8 +358,8 @@ impl TypedUserFunction {
                 var
             )));
         }
-        global_analysis.global_scalars.insert(&var);
-        return Ok(Some(var.clone()));
+        global_analysis.global_scalars.insert(var);
+        Ok(Some(var.clone()))
     }
 
     pub fn function(&self) -> RefMut<'_, Function> {
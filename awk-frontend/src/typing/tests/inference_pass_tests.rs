@@ -1,9 +1,9 @@
 #[cfg(test)]
 mod inference_tests {
-    use crate::parser::ArgT;
+    use crate::parser::{ArgT, ScalarType};
     use crate::typing::structs::{Call, CallArg};
     use crate::typing::tests::tests::test_exception;
-    use crate::typing::{function_pass, inference_pass};
+    use crate::typing::{array_type_pass, function_pass, inference_pass};
     use crate::typing::{ITypedFunction, TypedProgram};
     use crate::Symbolizer;
 
@@ -16,7 +16,7 @@ mod inference_tests {
         use crate::{lex, parse};
         let mut symbolizer = Symbolizer::new();
         let prog =
-            function_pass(parse(lex(prog, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer)
+            function_pass(parse(lex(prog, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer, vec![])
                 .unwrap();
         (prog, symbolizer)
     }
@@ -480,4 +480,152 @@ mod inference_tests {
     fn test_to_upper() {
         test_exception("BEGIN { arr[0] = 1; toupper(arr) }", "in a scalar context");
     }
+
+    #[test]
+    fn test_length_scalar_or_array() {
+        // One program can pass both, the builtin's arg doesn't take on the first caller's type
+        let (prog, mut symbolizer) = fully_typed_prog(
+            "function f(x) { return length(x) } BEGIN { a[1] = 1; s = 1; print length(a) length(s) f(a) }",
+        );
+        let f = prog.functions.get_user_function(&symbolizer.get("f")).unwrap();
+        assert_eq!(f.args()[0].typ, ArgT::Array);
+        assert!(prog.global_analysis.global_arrays.contains_key(&symbolizer.get("a")));
+        assert!(prog.global_analysis.global_scalars.contains_key(&symbolizer.get("s")));
+        fully_typed_prog("BEGIN { s = 1; a[1] = 1; print isarray(s) typeof(a) isarray(a) typeof(s) }");
+
+        // Nothing else types x so it defaults to a scalar
+        let (prog, mut symbolizer) = fully_typed_prog("function g(x) { return length(x) } BEGIN { print g() }");
+        let g = prog.functions.get_user_function(&symbolizer.get("g")).unwrap();
+        assert_eq!(g.args()[0].typ, ArgT::Scalar);
+    }
+
+    #[test]
+    fn test_bitwise_builtins_return_num() {
+        let (prog, mut symbolizer) = fully_typed_prog(
+            "function f(x) { return and(x, 1) } function g(x) { return strtonum(x) } BEGIN { f(\"a\"); g(\"0x1\") }",
+        );
+        for name in ["f", "g"] {
+            let func = prog.functions.get_user_function(&symbolizer.get(name)).unwrap();
+            assert_eq!(func.return_type(), ScalarType::Num);
+        }
+        test_exception("BEGIN { arr[0] = 1; compl(arr) }", "in a scalar context");
+    }
+
+    #[test]
+    fn test_arg_read_as_scalar() {
+        let (prog, mut symbolizer) =
+            fully_typed_prog("function helper(a, local) { return a + local } BEGIN { helper(1) }");
+        let helper = symbolizer.get("helper");
+        let helper = prog.functions.get_user_function(&helper).unwrap();
+        assert_eq!(helper.args()[0].typ, ArgT::Scalar);
+        assert_eq!(helper.args()[1].typ, ArgT::Scalar);
+    }
+
+    #[test]
+    fn test_local_array_arg() {
+        let (prog, mut symbolizer) =
+            fully_typed_prog("function helper(a, local) { local[a] = 1 } BEGIN { helper(1) }");
+        let helper = symbolizer.get("helper");
+        assert_eq!(prog.functions.get_user_function(&helper).unwrap().args()[1].typ, ArgT::Array);
+    }
+
+    #[test]
+    fn test_too_many_args() {
+        test_exception("function helper(a) { } BEGIN { helper(1, 2) }", "accepts a max of 1 arguments");
+    }
+
+    fn return_type(prog: &str, func: &str) -> ScalarType {
+        let (prog, mut symbolizer) = fully_typed_prog(prog);
+        prog.functions.get_user_function(&symbolizer.get(func)).unwrap().return_type()
+    }
+
+    #[test]
+    fn test_return_types() {
+        assert_eq!(return_type("function f(x) { return x * x } BEGIN { f(1) }", "f"), ScalarType::Num);
+        assert_eq!(return_type("function f(x) { return x \"a\" } BEGIN { f(1) }", "f"), ScalarType::Str);
+        assert_eq!(return_type("function f(x) { return x } BEGIN { f(1) }", "f"), ScalarType::Var);
+        assert_eq!(return_type("function f(x) { if (x) { return 1 } return \"a\" } BEGIN { f(1) }", "f"), ScalarType::Var);
+        assert_eq!(return_type("function f(x) { if (x) { return 1 } else { return 2 } } BEGIN { f(1) }", "f"), ScalarType::Num);
+    }
+
+    #[test]
+    fn test_return_type_falls_off_end() {
+        assert_eq!(return_type("function f(x) { if (x) { return 1 } } BEGIN { f(1) }", "f"), ScalarType::Var);
+        assert_eq!(return_type("function f(x) { return } BEGIN { f(1) }", "f"), ScalarType::Var);
+    }
+
+    #[test]
+    fn test_return_type_through_calls() {
+        let prog = "function a(x) { return b(x) } function b(x) { return c(x) } function c(x) { return x + 1 } BEGIN { a(1) }";
+        assert_eq!(return_type(prog, "a"), ScalarType::Num);
+        let prog = "function fact(n) { if (n <= 1) { return 1 } return n * fact(n - 1) } BEGIN { fact(1) }";
+        assert_eq!(return_type(prog, "fact"), ScalarType::Num);
+        let prog = "function f(n) { if (n) { return f(n - 1) } return \"a\" } BEGIN { f(1) }";
+        assert_eq!(return_type(prog, "f"), ScalarType::Str);
+    }
+
+    fn array_types(prog: &str, arrays: &[&str]) -> Vec<ScalarType> {
+        let (mut prog, mut symbolizer) = fully_typed_prog(prog);
+        array_type_pass(&mut prog, &mut symbolizer);
+        arrays.iter().map(|name| {
+            let id = prog.global_analysis.global_arrays.get(&symbolizer.get(name)).unwrap();
+            prog.global_analysis.array_types[id.id]
+        }).collect()
+    }
+
+    #[test]
+    fn test_array_types() {
+        use ScalarType::{Num, Str, Var};
+        assert_eq!(array_types("{ a[$1] += $3; b[$1]++; c[$1] = $2; d[1] = 1; d[2] = \"x\" }", &["a", "b", "c", "d"]), vec![Num, Num, Str, Var]);
+        assert_eq!(array_types("BEGIN { a[1] = 1; split(\"a b\", b); c[1] = \"1\"; sub(\"1\", \"2\", c[1]) }", &["a", "b", "c"]), vec![Num, Str, Str]);
+        assert_eq!(array_types("BEGIN { a[1] = b[1]; c[1] = (1 ? 2 : 3) }", &["a", "c"]), vec![Var, Num]);
+        assert_eq!(array_types("function f(x) { return x + 1 } function g() { return \"a\" } BEGIN { a[1] = f(1); b[1] = g() }", &["a", "b"]), vec![Num, Str]);
+    }
+
+    #[test]
+    fn test_array_types_through_calls() {
+        use ScalarType::{Num, Var};
+        let prog = "function add(arr, k) { arr[k]++ } BEGIN { add(a, 1); add(b, 2); c[1] = 1 }";
+        assert_eq!(array_types(prog, &["a", "b", "c"]), vec![Num, Num, Num]);
+        let prog = "function set(arr, k, v) { arr[k] = v } BEGIN { set(a, 1, 1); a[2] = 2; b[1] = 1 }";
+        assert_eq!(array_types(prog, &["a", "b"]), vec![Var, Num]);
+        let prog = "function f(arr) { arr[1] = \"s\" } function g(arr) { f(arr) } BEGIN { g(a); b[1] = 1; g(b) }";
+        assert_eq!(array_types(prog, &["a", "b"]), vec![Var, Var]);
+
+        let (mut typed, mut symbolizer) = fully_typed_prog("function f(x,  loc) { loc[x]++; return loc[x] } BEGIN { f(1) }");
+        array_type_pass(&mut typed, &mut symbolizer);
+        let f = typed.functions.get_user_function(&symbolizer.get("f")).unwrap();
+        assert_eq!(f.elem_type(&symbolizer.get("loc")), Some(Num));
+        assert_eq!(f.elem_type(&symbolizer.get("x")), None);
+    }
+
+    #[test]
+    fn test_native_functions() {
+        use crate::typing::{analyze_with_natives, NativeArgT, NativeFunc};
+        use crate::{lex, parse};
+        use ScalarType::{Num, Var};
+        let natives = || vec![
+            NativeFunc::new("hash64", vec![NativeArgT::Str], Num),
+            NativeFunc::new("fill", vec![NativeArgT::Array, NativeArgT::Num], Num),
+        ];
+        let typed = |prog: &str| {
+            let mut symbolizer = Symbolizer::new();
+            let ast = parse(lex(prog, &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
+            analyze_with_natives(ast, &mut symbolizer, natives()).map(|prog| (prog, symbolizer))
+        };
+
+        let (prog, mut symbolizer) = typed("function f(x) { return hash64(x) } BEGIN { a[1] = hash64(\"a\"); fill(b, 1); b[2] = 2; f(1) }").unwrap();
+        let types: Vec<ScalarType> = ["a", "b"].iter().map(|name| {
+            let id = prog.global_analysis.global_arrays.get(&symbolizer.get(name)).unwrap();
+            prog.global_analysis.array_types[id.id]
+        }).collect();
+        assert_eq!(types, vec![Num, Var]);
+        let f = prog.functions.get_user_function(&symbolizer.get("f")).unwrap();
+        assert_eq!(f.return_type(), Num);
+        assert_eq!(prog.functions.native(&symbolizer.get("fill")).map(|(id, _native)| id), Some(1));
+
+        assert!(typed("BEGIN { hash64() }").err().unwrap().msg.contains("accepts a min of 1 arguments"));
+        assert!(typed("BEGIN { x = 1; fill(x, 1) }").is_err());
+        assert!(typed("BEGIN { missing(1) }").err().unwrap().msg.contains("does not exist"));
+    }
 }
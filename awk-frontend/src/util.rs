@@ -0,0 +1,23 @@
+use std::fmt::Debug;
+
+// Checked in debug builds, unchecked in release builds
+#[cfg(debug_assertions)]
+pub fn unwrap_err<T,S: Debug>(value: Result<T,S>) -> T {
+    value.unwrap()
+}
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+pub fn unwrap_err<T,S: Debug>(value: Result<T,S>) -> T {
+    unsafe { value.unwrap_unchecked() }
+}
+
+
+#[cfg(debug_assertions)]
+pub fn unwrap<T>(value: Option<T>) -> T {
+    value.unwrap()
+}
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+pub fn unwrap<T>(value: Option<T>) -> T {
+    unsafe { value.unwrap_unchecked() }
+}
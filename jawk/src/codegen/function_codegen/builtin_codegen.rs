@@ -1,6 +1,6 @@
 use crate::codegen::function_codegen::FunctionCodegen;
 use crate::codegen::ValueT;
-use crate::parser::{Expr, TypedExpr};
+use crate::parser::{Expr, TypedExpr, Variable};
 use crate::printable_error::PrintableError;
 use crate::typing::BuiltinFunc;
 use gnu_libjit::Value;
@@ -103,7 +103,7 @@ impl<'a> FunctionCodegen<'a> {
             }
             BuiltinFunc::Split => {
                 let str = self.arg_to_str(args, 0)?;
-                let array = if let Expr::Variable(sym) = &args[1].expr {
+                let array = if let Expr::Variable(Variable::User(sym)) = &args[1].expr {
                     let array = self.function_scope.get_array(&mut self.function, &sym)?;
                     array
                 } else {
@@ -126,6 +126,13 @@ impl<'a> FunctionCodegen<'a> {
             }
             BuiltinFunc::Close => todo!(),
             BuiltinFunc::Matches => todo!(),
+            BuiltinFunc::Gensub => Err(PrintableError::new("gensub is not supported by the jit")),
+            BuiltinFunc::Asort | BuiltinFunc::Asorti => Err(PrintableError::new("asort and asorti are not supported by the jit")),
+            BuiltinFunc::Mktime | BuiltinFunc::Strftime | BuiltinFunc::Systime => Err(PrintableError::new("systime, mktime and strftime are not supported by the jit")),
+            BuiltinFunc::And | BuiltinFunc::Or | BuiltinFunc::Xor | BuiltinFunc::Lshift | BuiltinFunc::Rshift | BuiltinFunc::Compl =>
+                Err(PrintableError::new("bitwise builtins are not supported by the jit")),
+            BuiltinFunc::Strtonum => Err(PrintableError::new("strtonum is not supported by the jit")),
+            BuiltinFunc::Isarray | BuiltinFunc::Typeof => Err(PrintableError::new("isarray and typeof are not supported by the jit")),
             BuiltinFunc::Sprintf => todo!(),
             BuiltinFunc::Substr => {
                 let string = self.arg_to_str(args, 0)?;
@@ -38,8 +38,8 @@ impl<'a> FunctionCodegen<'a> {
         emit_string_code: fn(&mut Function, &mut dyn Runtime, &ValueT) -> Value,
     ) -> Value {
         match input_type {
-            ScalarType::String => return emit_string_code(&mut self.function, self.runtime, input),
-            ScalarType::Float => return emit_float_code(&mut self.function, self.runtime, input),
+            ScalarType::Str => return emit_string_code(&mut self.function, self.runtime, input),
+            ScalarType::Num => return emit_float_code(&mut self.function, self.runtime, input),
             _ => {}
         }
         let mut temp_storage = if is_ptr {
@@ -64,7 +64,7 @@ impl<'a> FunctionCodegen<'a> {
     }
 
     pub fn val_to_float(&mut self, value: &ValueT, typ: ScalarType) -> Value {
-        if typ == ScalarType::Float {
+        if typ == ScalarType::Num {
             return value.float.clone();
         }
 
@@ -82,7 +82,7 @@ impl<'a> FunctionCodegen<'a> {
     }
 
     pub fn val_to_string(&mut self, value: &ValueT, typ: ScalarType) -> Value {
-        if typ == ScalarType::String {
+        if typ == ScalarType::Str {
             return value.pointer.clone();
         }
         self.cases(value, typ, true, float_to_string, string_to_string)
@@ -96,7 +96,7 @@ impl<'a> FunctionCodegen<'a> {
     pub fn drop(&mut self, value: &Value) {
         self.drop_if_str(
             ValueT::new(self.string_tag(), self.zero_f(), value.clone()),
-            ScalarType::String,
+            ScalarType::Str,
         );
     }
 
@@ -197,7 +197,7 @@ impl<'a> FunctionCodegen<'a> {
                               new_value_typ: ScalarType,
                               side_effect_only: bool) -> Result<ValueT, PrintableError> {
         let old_value = self.function_scope.get_scalar(&mut self.function, var)?.clone();
-        self.drop_if_str(old_value, ScalarType::Variable);
+        self.drop_if_str(old_value, ScalarType::Var);
         self.function_scope.set_scalar(&mut self.function, &var, &new_value);
         if side_effect_only {
             Ok(self.no_op_value())
@@ -4,7 +4,8 @@ use crate::codegen::function_scope::FunctionScope;
 use crate::codegen::globals::Globals;
 use crate::codegen::{ValuePtrT, ValueT, Tag};
 use crate::lexer::{LogicalOp, MathOp};
-use crate::parser::{ArgT, LValue, ScalarType, Stmt, TypedExpr};
+use crate::parser::{walk_stmt, ArgT, LValue, ScalarType, Stmt, TypedExpr, Variable};
+use crate::awk_str::AwkStr;
 use crate::runtime::Runtime;
 use crate::symbolizer::Symbol;
 use crate::typing::{BuiltinFunc, ITypedFunction, TypedUserFunction};
@@ -114,9 +115,9 @@ impl<'a> FunctionCodegen<'a> {
     ) -> Result<(), PrintableError> {
         let zero = self.function.create_int_constant(0);
 
-        for global in func.globals_used().iter() {
+        for global in self.globals_used(func) {
             // Pull all needed globals into function locals
-            self.function_scope.get_scalar(&mut self.function, global)?;
+            self.function_scope.get_scalar(&mut self.function, &global)?;
         }
 
         let parser_func = func.function();
@@ -138,13 +139,13 @@ impl<'a> FunctionCodegen<'a> {
                 let value = self
                     .function_scope
                     .get_scalar(&mut self.function, &global)?;
-                self.drop_if_str(value, ScalarType::Variable)
+                self.drop_if_str(value, ScalarType::Var)
             }
         }
 
         for (_name, value) in self.function_scope.args() {
             self.runtime
-                .free_if_string(&mut self.function, value.clone(), ScalarType::Variable);
+                .free_if_string(&mut self.function, value.clone(), ScalarType::Var);
         }
 
         // All global scalars that this function used need to flushed from function locals back to the heap
@@ -167,6 +168,26 @@ impl<'a> FunctionCodegen<'a> {
         Ok(())
     }
 
+    // Every scalar the function body reads or writes, in the order they appear
+    fn globals_used(&mut self, func: &TypedUserFunction) -> Vec<Symbol> {
+        let mut used = vec![];
+        walk_stmt(&mut func.function().body, &mut |expr| match &expr.expr {
+            Expr::Variable(var) | Expr::ScalarAssign(var, _) => used.push(var.clone()),
+            Expr::IncDec { lvalue: LValue::Variable(var), .. } => used.push(var.clone()),
+            Expr::CallSub { string: LValue::Variable(var), .. } => used.push(var.clone()),
+            _ => {}
+        });
+        used.iter().map(|var| self.var_symbol(var)).collect()
+    }
+
+    // jawk doesn't implement awk's special variables, NR, FS etc. are plain globals
+    fn var_symbol(&mut self, var: &Variable) -> Symbol {
+        match var {
+            Variable::User(sym) => sym.clone(),
+            Variable::Special(special) => self.symbolizer.get(&special.to_string()),
+        }
+    }
+
     fn compile_stmt(&mut self, stmt: &Stmt) -> Result<(), PrintableError> {
         match stmt {
             Stmt::Return(ret) => {
@@ -225,14 +246,14 @@ impl<'a> FunctionCodegen<'a> {
                 let val = self.compile_expr(expr, false)?;
                 // Optimize print based on static knowledge of type
                 match expr.typ {
-                    ScalarType::String => {
+                    ScalarType::Str => {
                         self.runtime
                             .print_string(&mut self.function, val.pointer.clone());
                     }
-                    ScalarType::Float => {
+                    ScalarType::Num => {
                         self.runtime.print_float(&mut self.function, val.float);
                     }
-                    ScalarType::Variable => {
+                    ScalarType::Var => {
                         let float_tag = self.float_tag();
                         let mut float_lbl = Label::new();
                         let mut done_lbl = Label::new();
@@ -289,6 +310,12 @@ impl<'a> FunctionCodegen<'a> {
                 self.function.insn_label(&mut done_label);
                 self.break_lbl.pop().unwrap();
             }
+            Stmt::ForIn { .. } => {
+                return Err(PrintableError::new("for (var in array) loops are not supported by the jit"));
+            }
+            Stmt::Files { .. } | Stmt::NextFile => {
+                return Err(PrintableError::new("BEGINFILE, ENDFILE and nextfile are not supported by the jit"));
+            }
         }
         Ok(())
     }
@@ -319,7 +346,7 @@ impl<'a> FunctionCodegen<'a> {
                             call_args.push(compiled.pointer);
                         }
                         ArgT::Array => {
-                            if let Expr::Variable(sym) = &ast_arg.expr {
+                            if let Expr::Variable(Variable::User(sym)) = &ast_arg.expr {
                                 let array = self.function_scope.get_array(&mut self.function, &sym)?;
                                 call_args.push(array)
                             } else {
@@ -338,13 +365,14 @@ impl<'a> FunctionCodegen<'a> {
                 self.function.insn_call(&target.jit_function(), call_args);
                 let ret_value = self.function_scope.get_returned_value(&mut self.function);
                 if side_effect_only {
-                    self.drop_if_str(ret_value.clone(), ScalarType::Variable);
+                    self.drop_if_str(ret_value.clone(), ScalarType::Var);
                     self.no_op_value()
                 } else {
                     ret_value
                 }
             }
             Expr::ScalarAssign(var, value) => {
+                let var = &self.var_symbol(var);
                 // BEGIN: Optimization
                 // Optimization to allow reusing the string being assigned to by a string concat operation
                 // a = "init"
@@ -361,14 +389,14 @@ impl<'a> FunctionCodegen<'a> {
                         .get_scalar(&mut self.function, var)?
                         .clone();
                     let strings_to_concat = self.compile_expressions_to_str(vars)?;
-                    self.drop_if_str(old_value, ScalarType::Variable);
+                    self.drop_if_str(old_value, ScalarType::Var);
                     let new_value = self.concat_values(&strings_to_concat);
                     self.function_scope
                         .set_scalar(&mut self.function, var, &new_value);
                     return Ok(if side_effect_only {
                         self.no_op_value()
                     } else {
-                        self.copy_if_string(new_value, ScalarType::Variable)
+                        self.copy_if_string(new_value, ScalarType::Var)
                     });
                 }
                 let new_value = self.compile_expr(value, false)?;
@@ -380,16 +408,16 @@ impl<'a> FunctionCodegen<'a> {
                 self.zero_ptr(),
             ),
             Expr::String(str) => {
-                let ptr = Rc::into_raw(str.clone()) as *mut c_void;
+                let ptr = Rc::into_raw(AwkStr::new_rc(str.bytes().to_vec())) as *mut c_void;
                 let ptr = self.function.create_void_ptr_constant(ptr);
                 let val = ValueT::new(self.string_tag(), self.zero_f(), ptr);
-                self.runtime.copy_if_string(&mut self.function, val, ScalarType::String)
+                self.runtime.copy_if_string(&mut self.function, val, ScalarType::Str)
             }
             Expr::Regex(reg) => {
-                let ptr = Rc::into_raw(reg.clone()) as *mut c_void;
+                let ptr = Rc::into_raw(AwkStr::new_rc(reg.bytes().to_vec())) as *mut c_void;
                 let ptr = self.function.create_void_ptr_constant(ptr);
                 let val = ValueT::new(self.string_tag(), self.zero_f(), ptr);
-                self.runtime.copy_if_string(&mut self.function, val, ScalarType::String)
+                self.runtime.copy_if_string(&mut self.function, val, ScalarType::Str)
             }
             Expr::MathOp(left_expr, op, right_expr) => {
                 // Convert left and right to floats if needed and perform the MathOp
@@ -416,7 +444,7 @@ impl<'a> FunctionCodegen<'a> {
                 let tag = self.float_tag();
 
                 // Optimize the case where we know both are floats
-                if left_expr.typ == ScalarType::Float && right_expr.typ == ScalarType::Float {
+                if left_expr.typ == ScalarType::Num && right_expr.typ == ScalarType::Num {
                     return Ok(ValueT::float(
                         tag,
                         self.float_binop(&left, &right, *op),
@@ -508,9 +536,10 @@ impl<'a> FunctionCodegen<'a> {
                 res
             }
             Expr::Variable(var) => {
+                let var = self.var_symbol(var);
                 let var = self
                     .function_scope
-                    .get_scalar(&mut self.function, var)?
+                    .get_scalar(&mut self.function, &var)?
                     .clone();
                 self.runtime
                     .copy_if_string(&mut self.function, var, expr.typ)
@@ -633,7 +662,38 @@ impl<'a> FunctionCodegen<'a> {
                 let rhs = self.compile_expr(value, false)?;
                 self.assign_to_array(name, indices_arr, rhs, value.typ, side_effect_only)?
             }
-            Expr::CallSub { arg1, arg2, arg3, global } => {
+            Expr::ColumnAssign { .. } => {
+                return Err(PrintableError::new("jawk does not support assigning to a column"));
+            }
+            Expr::IncDec { lvalue, increment, pre } => {
+                // Indices of an array lvalue are evaluated twice, once to read and once to assign
+                let old = self.compile_expr(&TypedExpr::new(lvalue.clone().into()), false)?;
+                let old_num = self.val_to_float(&old, ScalarType::Var);
+                self.drop_if_str(old, ScalarType::Var);
+                let delta = self.function.create_float64_constant(if *increment { 1.0 } else { -1.0 });
+                let new_num = self.function.insn_add(&old_num, &delta);
+                let new = self.mk_float(new_num.clone());
+                match lvalue {
+                    LValue::Variable(var) => {
+                        let var = self.var_symbol(var);
+                        self.assign_to_variable(&var, new, ScalarType::Num, true)?;
+                    }
+                    LValue::ArrayIndex { name, indices } => {
+                        self.assign_to_array(name, indices, new, ScalarType::Num, true)?;
+                    }
+                    LValue::Column(_) => {
+                        return Err(PrintableError::new("jawk does not support assigning to a column"));
+                    }
+                }
+                if side_effect_only {
+                    self.no_op_value()
+                } else if *pre {
+                    self.mk_float(new_num)
+                } else {
+                    self.mk_float(old_num)
+                }
+            }
+            Expr::CallSub { ere: arg1, replacement: arg2, string: arg3, global } => {
                 let ere_val = self.compile_expr(arg1, false)?;
                 let repl_val = self.compile_expr(arg2, false)?;
                 let ere_str = self.val_to_string(&ere_val, arg1.typ);
@@ -641,7 +701,7 @@ impl<'a> FunctionCodegen<'a> {
 
                 let texpr = TypedExpr::new(arg3.clone().into()); // TODO: remove clone
                 let arg3_value = self.compile_expr(&texpr, false)?;
-                let input_ptr = self.val_to_string(&arg3_value, ScalarType::Variable);
+                let input_ptr = self.val_to_string(&arg3_value, ScalarType::Var);
 
                 let is_global = self.function.create_int_constant(if *global { 1 } else { 0 });
                 let new_str_ptr = self.runtime.sub(
@@ -657,10 +717,11 @@ impl<'a> FunctionCodegen<'a> {
 
                 match arg3 {
                     LValue::Variable(name) => {
-                        self.assign_to_variable(name, new_str_ptr, ScalarType::String, true)?;
+                        let name = self.var_symbol(name);
+                        self.assign_to_variable(&name, new_str_ptr, ScalarType::Str, true)?;
                     }
                     LValue::ArrayIndex { name, indices } => {
-                        self.assign_to_array(name, indices, new_str_ptr, ScalarType::String, true)?;
+                        self.assign_to_array(name, indices, new_str_ptr, ScalarType::Str, true)?;
                     }
                     LValue::Column(_col) => {
                         todo!("sub with col")
@@ -668,6 +729,9 @@ impl<'a> FunctionCodegen<'a> {
                 }
                 self.mk_float(num_replacements)
             }
+            Expr::Getline { .. } => {
+                return Err(PrintableError::new("getline is not supported by the jit"));
+            }
         })
     }
 }
@@ -1,6 +1,7 @@
 use crate::codegen::{ValuePtrT, ValueT, Tag};
 use crate::global_scalars::SymbolMapping;
 use crate::runtime::Runtime;
+use crate::specials::{ArrSpecial, SclSpecial};
 use crate::symbolizer::Symbol;
 use crate::{AnalysisResults, PrintableError, Symbolizer};
 use gnu_libjit::{Context, Function, Value};
@@ -19,17 +20,33 @@ impl Globals {
         analysis: AnalysisResults,
         runtime: &mut RuntimeT,
         function: &mut Function,
-        _symbolizer: &mut Symbolizer,
+        symbolizer: &mut Symbolizer,
     ) -> Self {
-        let scalar_memory = 3 * analysis.global_scalars.len();
+        // jawk doesn't implement awk's special variables, NR, FS etc. are plain globals
+        let mut mapping = SymbolMapping::new();
+        for (name, _) in analysis.global_scalars.mapping() {
+            mapping.insert(name);
+        }
+        for (name, _) in SclSpecial::variants() {
+            mapping.insert(&symbolizer.get(name));
+        }
+        let mut arrays = SymbolMapping::new();
+        for (name, _) in analysis.global_arrays.mapping() {
+            arrays.insert(name);
+        }
+        for (name, _) in ArrSpecial::variants() {
+            arrays.insert(&symbolizer.get(name));
+        }
+
+        let scalar_memory = 3 * mapping.len();
 
         let global_scalar_allocation: Vec<i64> = Vec::with_capacity(scalar_memory);
         let global_return_value: Vec<i64> = Vec::with_capacity(3);
 
         let init = Self {
             global_scalar_allocation,
-            mapping: analysis.global_scalars,
-            arrays: analysis.global_arrays,
+            mapping,
+            arrays,
             global_return_value,
         };
 
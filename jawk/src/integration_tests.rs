@@ -122,7 +122,7 @@ mod integration_tests {
         println!("Program:\n{}", prog);
         let mut symbolizer = Symbolizer::new();
         let program =
-            analyze(parse(lex(&prog, &mut symbolizer).unwrap(), &mut symbolizer).unwrap()).unwrap();
+            analyze(parse(lex(&prog, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
         println!("Ast:\n{}", &program);
 
         let temp_dir = tempdir().unwrap();
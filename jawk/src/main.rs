@@ -19,22 +19,15 @@ use crate::printable_error::PrintableError;
 use crate::typing::AnalysisResults;
 
 pub use crate::codegen::{compile_and_capture, compile_and_run};
-pub use crate::lexer::lex;
-pub use crate::parser::parse;
-pub use crate::symbolizer::Symbolizer;
-pub use crate::typing::analyze;
+pub use awk_frontend::{analyze, lex, parse, Symbolizer};
+use awk_frontend::{lexer, parser, printable_error, specials, symbolizer, typing};
 
 mod args;
 mod codegen;
 mod columns;
 mod global_scalars;
 mod integration_tests;
-mod lexer;
-mod parser;
-mod printable_error;
 mod runtime;
-mod symbolizer;
-mod typing;
 mod awk_str;
 mod util;
 
@@ -47,7 +40,7 @@ pub fn runner(args: Vec<String>) -> Result<(), PrintableError> {
     let ast = analyze(parse(
         lex(&args.program, &mut symbolizer)?,
         &mut symbolizer,
-    )?)?;
+    )?, &mut symbolizer)?;
     if args.debug {
         println!("{}", ast);
     }
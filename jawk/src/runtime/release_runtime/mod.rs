@@ -141,7 +141,7 @@ impl Runtime for ReleaseRuntime {
     fn free_if_string(&mut self, func: &mut Function, value: ValueT, typ: ScalarType) {
         let data_ptr = self.data_ptr(func);
         match typ {
-            ScalarType::String => {
+            ScalarType::Str => {
                 func.insn_call_native(
                     free_string as *mut c_void,
                     &[data_ptr, value.pointer],
@@ -149,8 +149,8 @@ impl Runtime for ReleaseRuntime {
                     Abi::Cdecl,
                 );
             }
-            ScalarType::Float => {}
-            ScalarType::Variable => {
+            ScalarType::Num => {}
+            ScalarType::Var => {
                 func.insn_call_native(
                     free_if_string as *mut c_void,
                     &[data_ptr, value.tag, value.pointer],
@@ -163,14 +163,14 @@ impl Runtime for ReleaseRuntime {
     fn copy_if_string(&mut self, func: &mut Function, value: ValueT, typ: ScalarType) -> ValueT {
         let data_ptr = self.data_ptr(func);
         let ptr = match typ {
-            ScalarType::String => func.insn_call_native(
+            ScalarType::Str => func.insn_call_native(
                 copy_string as *mut c_void,
                 &[data_ptr, value.pointer],
                 Some(Context::void_ptr_type()),
                 Abi::Cdecl,
             ),
-            ScalarType::Float => value.pointer,
-            ScalarType::Variable => func.insn_call_native(
+            ScalarType::Num => value.pointer,
+            ScalarType::Var => func.insn_call_native(
                 copy_if_string as *mut c_void,
                 &[data_ptr, value.tag.clone(), value.pointer],
                 Some(Context::void_ptr_type()),
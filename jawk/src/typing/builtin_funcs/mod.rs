@@ -1,6 +0,0 @@
-mod builtin_factory;
-mod builtin_func;
-mod typed_builtin;
-
-pub use builtin_factory::BuiltinFactory;
-pub use builtin_func::BuiltinFunc;
@@ -1,5 +0,0 @@
-mod function_pass;
-mod inference_pass;
-
-pub use function_pass::function_pass;
-pub use inference_pass::inference_pass;
@@ -9,13 +9,13 @@ fn main() {
     let out_dir_path = Path::new(&out_dir);
     let path = out_dir_path.join("mawk-1.3.4-20200120");
 
-    Command::new("cp").args(&["-R", "./mawk-1.3.4-20200120", &out_dir]).output().expect("unable to copy mawk to outdir");
+    Command::new("cp").args(["-R", "./mawk-1.3.4-20200120", &out_dir]).output().expect("unable to copy mawk to outdir");
 
     env::set_current_dir(path.to_str().expect("expect mawk to exist in cargo outdir")).expect("expected mawk to exist in out dir");
 
     Command::new("./configure")
         .output().expect("Failed to run `./configure while installing mawk");
-    Command::new("make").args(&["regexp.o"])
+    Command::new("make").args(["regexp.o"])
         .output().expect("Failed to run `make` while installing mawk");
 
     let regexpo_path = path.join("regexp.o");
@@ -1,8 +1,11 @@
 #[cfg(test)]
 mod tests;
+mod submatch;
 
 extern crate core;
 
+use std::cell::OnceCell;
+use std::fmt::{Display, Formatter};
 use std::os::raw::c_char;
 
 #[cfg(feature="thread_safe")]
@@ -11,17 +14,72 @@ use std::sync::Mutex;
 use once_cell::sync::Lazy;
 
 use mawk_regex_sys::{REtest, REcompile, PTR, REdestroy, size_t, REmatch};
+use submatch::Submatcher;
 
 pub struct Regex {
     ptr: PTR,
+    source: Vec<u8>,
+    // Built the first time captures are asked for. None if the regex has no groups.
+    submatcher: OnceCell<Result<Option<Submatcher>, CapturesError>>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Match {
     pub start: usize,
     pub len: usize,
 }
 
+impl Match {
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+// A match and where each parenthesized group matched inside it. Group 0 is the whole match,
+// the rest are numbered by their open paren. Groups that didn't take part in the match are None.
+#[derive(Debug, PartialEq)]
+pub struct Captures {
+    groups: Vec<Option<Match>>,
+}
+
+impl Captures {
+    pub fn whole(&self) -> Match {
+        self.groups[0].unwrap()
+    }
+
+    pub fn get(&self, group: usize) -> Option<Match> {
+        self.groups.get(group).copied().flatten()
+    }
+
+    // Number of groups including the whole match
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=Option<Match>> + '_ {
+        self.groups.iter().copied()
+    }
+}
+
+// mawk matched but where the groups matched couldn't be worked out. Either the regex uses syntax the
+// group matcher doesn't parse or it found no way to divide up mawk's match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturesError {
+    regex: Vec<u8>,
+}
+
+impl CapturesError {
+    fn new(regex: &[u8]) -> Self {
+        CapturesError { regex: regex.to_vec() }
+    }
+}
+
+impl Display for CapturesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unable to find the groups of regex {}", String::from_utf8_lossy(&self.regex))
+    }
+}
+
 // Mawk is not thread safe.... But it really slows down my rust tests to have to
 // run them single threaded. In thread_safe mode use a global mutex to prevent crashes and allow concurrent
 // tests
@@ -30,10 +88,19 @@ static GLOBAL_MUTEX: Lazy<Mutex<i32>> = Lazy::new(|| Mutex::new(0));
 
 impl Regex {
     pub fn new(regex: &[u8]) -> Self {
+        // Mawk's lexer peeks one byte past the end of the regex for a trailing $ so it's
+        // handed a nul terminated copy. Otherwise whatever follows regex in memory can anchor it.
+        let mut terminated = Vec::with_capacity(regex.len() + 1);
+        terminated.extend_from_slice(regex);
+        terminated.push(0);
         #[cfg(feature="thread_safe")]
         let lck = GLOBAL_MUTEX.lock().unwrap();
         let reg = unsafe {
-            Regex { ptr: REcompile(regex.as_ptr() as *mut c_char, regex.len() as ::std::os::raw::c_ulong) }
+            Regex {
+                ptr: REcompile(terminated.as_ptr() as *mut c_char, regex.len() as ::std::os::raw::c_ulong),
+                source: regex.to_vec(),
+                submatcher: OnceCell::new(),
+            }
         };
         #[cfg(feature="thread_safe")]
         drop(lck);
@@ -52,6 +119,41 @@ impl Regex {
     }
 
     pub fn match_idx(&self, str: &[u8]) -> Option<Match> {
+        self.find(str, false)
+    }
+
+    // First match in str[start..], positioned like captures_from but without finding the groups
+    pub fn match_idx_from(&self, str: &[u8], start: usize) -> Option<Match> {
+        let mut whole = self.find(&str[start..], start != 0)?;
+        whole.start += start;
+        Some(whole)
+    }
+
+    pub fn captures(&self, str: &[u8]) -> Result<Option<Captures>, CapturesError> {
+        self.captures_from(str, 0)
+    }
+
+    // Captures of the first match in str[start..]. Positions are relative to str not the
+    // slice and ^ only matches when start is 0.
+    pub fn captures_from(&self, str: &[u8], start: usize) -> Result<Option<Captures>, CapturesError> {
+        let mut whole = match self.find(&str[start..], start != 0) {
+            Some(whole) => whole,
+            None => return Ok(None),
+        };
+        whole.start += start;
+        let mut groups = vec![Some(whole)];
+        let submatcher = self.submatcher.get_or_init(|| Submatcher::new(&self.source)).as_ref().map_err(Clone::clone)?;
+        if let Some(submatcher) = submatcher {
+            let submatches = submatcher.submatches(str, whole.start, whole.end(), start != 0)
+                .ok_or_else(|| CapturesError::new(&self.source))?;
+            groups.extend(submatches.into_iter()
+                .map(|group| group.map(|(start, end)| Match { start, len: end - start })));
+        }
+        Ok(Some(Captures { groups }))
+    }
+
+    // no_bol stops ^ from matching at the start of str
+    fn find(&self, str: &[u8], no_bol: bool) -> Option<Match> {
         #[cfg(feature="thread_safe")]
         let lck = GLOBAL_MUTEX.lock().unwrap();
         let mut match_len: Box<size_t> = Box::new(0);
@@ -60,10 +162,10 @@ impl Regex {
                     str.len() as ::std::os::raw::c_ulong,
                     self.ptr,
                     &mut *match_len as *mut size_t,
-                    0,
+                    no_bol as ::std::os::raw::c_int,
             )
         };
-        let res = if result_ptr == 0 as *mut c_char {
+        let res = if result_ptr.is_null() {
             return None;
         } else {
             let idx = unsafe { result_ptr.offset_from(str.as_ptr() as *const c_char) };
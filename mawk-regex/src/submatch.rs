@@ -0,0 +1,377 @@
+// mawk's matcher only reports where the whole match is. To find where each parenthesized group
+// matched we parse the regex again and run a small backtracking matcher over that span.
+// mawk picks the span (leftmost longest), this only decides how the groups divide it up.
+// Groups are numbered by their open paren. Alternatives are tried left to right and repetitions
+// take as much as they can, the last iteration of a repeated group is the one reported.
+
+use crate::CapturesError;
+
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Byte(u8),
+    Any,
+    Class(Box<[bool; 256]>),
+    Bol,
+    Eol,
+    Group(usize, Box<Node>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Star(Box<Node>),
+    Plus(Box<Node>),
+    Quest(Box<Node>),
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    idx: usize,
+    groups: usize,
+    // Open parens around idx, a ) with none open is a literal like it is to mawk
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.idx).copied()
+    }
+
+    fn alt(&mut self) -> Option<Node> {
+        let mut alts = vec![self.concat()?];
+        while self.peek() == Some(b'|') {
+            self.idx += 1;
+            alts.push(self.concat()?);
+        }
+        Some(if alts.len() == 1 { alts.pop().unwrap() } else { Node::Alt(alts) })
+    }
+
+    fn concat(&mut self) -> Option<Node> {
+        let mut items = vec![];
+        let start = self.idx;
+        while let Some(byte) = self.peek() {
+            if byte == b'|' || (byte == b')' && self.depth > 0) {
+                break;
+            }
+            self.idx += 1;
+            let atom = match byte {
+                b'(' => {
+                    self.groups += 1;
+                    let group = self.groups;
+                    self.depth += 1;
+                    let inner = self.alt()?;
+                    if self.peek() != Some(b')') {
+                        return None;
+                    }
+                    self.depth -= 1;
+                    self.idx += 1;
+                    Node::Group(group, Box::new(inner))
+                }
+                b'.' => Node::Any,
+                b'[' => self.class()?,
+                // Anchors only at the start or end of an alternative, otherwise they're literal
+                b'^' if self.idx - 1 == start => Node::Bol,
+                b'$' if self.peek().is_none() || self.peek() == Some(b'|') || (self.peek() == Some(b')') && self.depth > 0) => Node::Eol,
+                b'\\' => Node::Byte(self.escape()),
+                _ => Node::Byte(byte),
+            };
+            items.push(self.repetitions(atom));
+        }
+        Some(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap(),
+            _ => Node::Concat(items),
+        })
+    }
+
+    fn repetitions(&mut self, mut atom: Node) -> Node {
+        while let Some(byte) = self.peek() {
+            atom = match byte {
+                b'*' => Node::Star(Box::new(atom)),
+                b'+' => Node::Plus(Box::new(atom)),
+                b'?' => Node::Quest(Box::new(atom)),
+                _ => break,
+            };
+            self.idx += 1;
+        }
+        atom
+    }
+
+    // After a backslash
+    fn escape(&mut self) -> u8 {
+        let byte = match self.peek() {
+            None => return b'\\',
+            Some(byte) => byte,
+        };
+        self.idx += 1;
+        match byte {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'0'..=b'7' => {
+                let mut value = (byte - b'0') as u32;
+                for _ in 0..2 {
+                    match self.peek() {
+                        Some(digit @ b'0'..=b'7') => {
+                            value = value * 8 + (digit - b'0') as u32;
+                            self.idx += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                value as u8
+            }
+            _ => byte,
+        }
+    }
+
+    // After the [
+    fn class(&mut self) -> Option<Node> {
+        let mut set = Box::new([false; 256]);
+        let negated = self.peek() == Some(b'^');
+        if negated {
+            self.idx += 1;
+        }
+        let mut first = true;
+        loop {
+            let byte = self.peek()?;
+            self.idx += 1;
+            if byte == b']' && !first {
+                break;
+            }
+            first = false;
+            if byte == b'[' && self.peek() == Some(b':') {
+                let rest = &self.src[self.idx + 1..];
+                let end = rest.windows(2).position(|w| w == b":]")?;
+                for b in 0..=255u8 {
+                    if named_class(&rest[..end], b)? {
+                        set[b as usize] = true;
+                    }
+                }
+                self.idx += end + 3;
+                continue;
+            }
+            let low = if byte == b'\\' { self.escape() } else { byte };
+            let is_range = self.peek() == Some(b'-') && self.src.get(self.idx + 1).is_some_and(|b| *b != b']');
+            if is_range {
+                self.idx += 1;
+                let high = self.peek()?;
+                self.idx += 1;
+                let high = if high == b'\\' { self.escape() } else { high };
+                for b in low..=high {
+                    set[b as usize] = true;
+                }
+            } else {
+                set[low as usize] = true;
+            }
+        }
+        if negated {
+            for member in set.iter_mut() {
+                *member = !*member;
+            }
+        }
+        Some(Node::Class(set))
+    }
+}
+
+fn named_class(name: &[u8], b: u8) -> Option<bool> {
+    Some(match name {
+        b"alpha" => b.is_ascii_alphabetic(),
+        b"digit" => b.is_ascii_digit(),
+        b"alnum" => b.is_ascii_alphanumeric(),
+        b"upper" => b.is_ascii_uppercase(),
+        b"lower" => b.is_ascii_lowercase(),
+        b"space" => b.is_ascii_whitespace() || b == 0x0b,
+        b"blank" => b == b' ' || b == b'\t',
+        b"punct" => b.is_ascii_punctuation(),
+        b"print" => (0x20..0x7f).contains(&b),
+        b"graph" => b.is_ascii_graphic(),
+        b"cntrl" => b.is_ascii_control(),
+        b"xdigit" => b.is_ascii_hexdigit(),
+        _ => return None,
+    })
+}
+
+#[derive(Debug)]
+enum Inst {
+    Byte(u8),
+    Any,
+    Class(Box<[bool; 256]>),
+    Bol,
+    Eol,
+    // Try the first target then the second
+    Split(usize, usize),
+    Jmp(usize),
+    Save(usize),
+    Match,
+}
+
+#[derive(Debug)]
+pub struct Submatcher {
+    prog: Vec<Inst>,
+    // Not counting the whole match
+    groups: usize,
+}
+
+impl Submatcher {
+    // None if the regex has no groups, an error if it's syntax this parser doesn't understand
+    pub fn new(regex: &[u8]) -> Result<Option<Self>, CapturesError> {
+        if !regex.contains(&b'(') {
+            return Ok(None);
+        }
+        let mut parser = Parser { src: regex, idx: 0, groups: 0, depth: 0 };
+        let node = match parser.alt() {
+            Some(node) if parser.idx == regex.len() => node,
+            _ => return Err(CapturesError::new(regex)),
+        };
+        if parser.groups == 0 {
+            return Ok(None);
+        }
+        let mut prog = vec![];
+        compile(&node, &mut prog);
+        prog.push(Inst::Match);
+        Ok(Some(Submatcher { prog, groups: parser.groups }))
+    }
+
+    // Where each group matched given the whole regex matched str[start..end]. Index i is group i + 1.
+    // None if no way of matching exactly that span was found.
+    pub fn submatches(&self, str: &[u8], start: usize, end: usize, no_bol: bool) -> Option<Vec<Option<(usize, usize)>>> {
+        let width = end - start + 1;
+        let mut visited = vec![false; self.prog.len() * width];
+        let mut slots: Vec<Option<usize>> = vec![None; self.groups * 2];
+        let mut jobs = vec![Job::Explore(0, start)];
+        while let Some(job) = jobs.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Restore(slot, old) => {
+                    slots[slot] = old;
+                    continue;
+                }
+                Job::Explore(pc, pos) => (pc, pos),
+            };
+            // Whether the rest of the program matches only depends on pc and pos so never try one twice
+            loop {
+                let seen = &mut visited[pc * width + pos - start];
+                if *seen {
+                    break;
+                }
+                *seen = true;
+                match &self.prog[pc] {
+                    Inst::Byte(byte) => {
+                        if pos == end || str[pos] != *byte {
+                            break;
+                        }
+                        pos += 1;
+                    }
+                    Inst::Any => {
+                        if pos == end {
+                            break;
+                        }
+                        pos += 1;
+                    }
+                    Inst::Class(set) => {
+                        if pos == end || !set[str[pos] as usize] {
+                            break;
+                        }
+                        pos += 1;
+                    }
+                    Inst::Bol => {
+                        if pos != 0 || no_bol {
+                            break;
+                        }
+                    }
+                    Inst::Eol => {
+                        if pos != str.len() {
+                            break;
+                        }
+                    }
+                    Inst::Split(first, second) => {
+                        jobs.push(Job::Explore(*second, pos));
+                        pc = *first;
+                        continue;
+                    }
+                    Inst::Jmp(target) => {
+                        pc = *target;
+                        continue;
+                    }
+                    Inst::Save(slot) => {
+                        jobs.push(Job::Restore(*slot, slots[*slot]));
+                        slots[*slot] = Some(pos);
+                    }
+                    Inst::Match => {
+                        if pos != end {
+                            break;
+                        }
+                        let groups = slots.chunks(2).map(|pair| match pair {
+                            [Some(start), Some(end)] => Some((*start, *end)),
+                            _ => None,
+                        }).collect();
+                        return Some(groups);
+                    }
+                }
+                pc += 1;
+            }
+        }
+        None
+    }
+}
+
+enum Job {
+    Explore(usize, usize),
+    Restore(usize, Option<usize>),
+}
+
+fn compile(node: &Node, prog: &mut Vec<Inst>) {
+    match node {
+        Node::Empty => {}
+        Node::Byte(byte) => prog.push(Inst::Byte(*byte)),
+        Node::Any => prog.push(Inst::Any),
+        Node::Class(set) => prog.push(Inst::Class(set.clone())),
+        Node::Bol => prog.push(Inst::Bol),
+        Node::Eol => prog.push(Inst::Eol),
+        Node::Group(group, inner) => {
+            prog.push(Inst::Save((group - 1) * 2));
+            compile(inner, prog);
+            prog.push(Inst::Save((group - 1) * 2 + 1));
+        }
+        Node::Concat(items) => items.iter().for_each(|item| compile(item, prog)),
+        Node::Alt(alts) => {
+            let mut jumps = vec![];
+            for (idx, alt) in alts.iter().enumerate() {
+                if idx == alts.len() - 1 {
+                    compile(alt, prog);
+                    break;
+                }
+                let split = prog.len();
+                prog.push(Inst::Split(split + 1, 0));
+                compile(alt, prog);
+                jumps.push(prog.len());
+                prog.push(Inst::Jmp(0));
+                prog[split] = Inst::Split(split + 1, prog.len());
+            }
+            let end = prog.len();
+            for jump in jumps {
+                prog[jump] = Inst::Jmp(end);
+            }
+        }
+        Node::Star(inner) => {
+            let split = prog.len();
+            prog.push(Inst::Split(split + 1, 0));
+            compile(inner, prog);
+            prog.push(Inst::Jmp(split));
+            prog[split] = Inst::Split(split + 1, prog.len());
+        }
+        Node::Plus(inner) => {
+            let start = prog.len();
+            compile(inner, prog);
+            prog.push(Inst::Split(start, prog.len() + 1));
+        }
+        Node::Quest(inner) => {
+            let split = prog.len();
+            prog.push(Inst::Split(split + 1, 0));
+            compile(inner, prog);
+            prog[split] = Inst::Split(split + 1, prog.len());
+        }
+    }
+}
@@ -1,5 +1,5 @@
 mod tests {
-    use crate::Regex;
+    use crate::{Match, Regex};
 
     #[test]
     fn simple_test_eol() {
@@ -15,6 +15,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn regex_followed_by_dollar() {
+        // Only the first byte is the regex, the $ after it must not anchor it
+        let source = "a$".as_bytes();
+        let regex = Regex::new(&source[..1]);
+        assert!(regex.matches("ab".as_bytes()));
+        assert_eq!(regex.match_idx("xaab".as_bytes()), Some(Match { start: 1, len: 1 }));
+    }
+
     #[test]
     fn indices() {
         for _ in 0..10000 {
@@ -26,4 +35,72 @@ mod tests {
             assert_eq!(Regex::new("z+".as_bytes()).match_idx("AAAzzzzzzABC".as_bytes()).unwrap().len, 6);
         }
     }
-}
\ No newline at end of file
+
+    fn groups(regex: &str, str: &str, start: usize) -> Option<Vec<Option<(usize, usize)>>> {
+        Regex::new(regex.as_bytes()).captures_from(str.as_bytes(), start).unwrap()
+            .map(|captures| captures.iter().map(|g| g.map(|g| (g.start, g.len))).collect())
+    }
+
+    #[test]
+    fn captures() {
+        assert_eq!(groups("z+", "abzz", 0), Some(vec![Some((2, 2))]));
+        assert_eq!(groups("(a+)(b+)", "xaabbby", 0), Some(vec![Some((1, 5)), Some((1, 2)), Some((3, 3))]));
+        assert_eq!(groups("(a|b)+", "abba", 0), Some(vec![Some((0, 4)), Some((3, 1))]));
+        assert_eq!(groups("x(y)?z", "xz", 0), Some(vec![Some((0, 2)), None]));
+        assert_eq!(groups("((a)|b)c", "bc", 0), Some(vec![Some((0, 2)), Some((0, 1)), None]));
+        assert_eq!(groups("([[:digit:]]+)-([^-]*)$", "tel 555-1234", 0), Some(vec![Some((4, 8)), Some((4, 3)), Some((8, 4))]));
+        assert_eq!(groups("(a*)(a*)", "aaa", 0), Some(vec![Some((0, 3)), Some((0, 3)), Some((3, 0))]));
+        assert_eq!(groups("(\\.)", "a.b", 0), Some(vec![Some((1, 1)), Some((1, 1))]));
+        assert_eq!(groups("(b)", "abab", 2), Some(vec![Some((3, 1)), Some((3, 1))]));
+        assert_eq!(groups("^(a)", "aa", 1), None);
+        assert_eq!(groups("(c)", "abab", 0), None);
+    }
+
+    #[test]
+    fn match_idx_from() {
+        let regex = Regex::new("b+".as_bytes());
+        assert_eq!(regex.match_idx_from("abab".as_bytes(), 2), Some(Match { start: 3, len: 1 }));
+        assert_eq!(regex.match_idx_from("abab".as_bytes(), 4), None);
+        assert_eq!(Regex::new("^a".as_bytes()).match_idx_from("aa".as_bytes(), 1), None);
+        assert_eq!(Regex::new("x*".as_bytes()).match_idx_from("ab".as_bytes(), 2), Some(Match { start: 2, len: 0 }));
+    }
+
+    #[test]
+    fn captures_api() {
+        let captures = Regex::new("([a-z]+)@([a-z]+)".as_bytes()).captures("mail bob@example now".as_bytes()).unwrap().unwrap();
+        assert_eq!(captures.group_count(), 3);
+        assert_eq!(captures.whole(), Match { start: 5, len: 11 });
+        assert_eq!(captures.get(1), Some(Match { start: 5, len: 3 }));
+        assert_eq!(captures.get(2).map(|m| m.end()), Some(16));
+        assert_eq!(captures.get(3), None);
+    }
+
+    #[test]
+    fn captures_bracket_classes() {
+        assert_eq!(groups("([]a]+)(b)", "x]a]b", 0), Some(vec![Some((1, 4)), Some((1, 3)), Some((4, 1))]));
+        assert_eq!(groups("\\(([^]()]+)\\)", "f(a]b) (x)", 0), Some(vec![Some((7, 3)), Some((8, 1))]));
+        assert_eq!(groups("([a-c-]+)([[:upper:][:digit:]]*)", "zb-aX9y", 0), Some(vec![Some((1, 5)), Some((1, 3)), Some((4, 2))]));
+        assert_eq!(groups("([|)(])", "a)b", 0), Some(vec![Some((1, 1)), Some((1, 1))]));
+    }
+
+    #[test]
+    fn captures_intervals() {
+        // mawk's matcher has no intervals, braces are literal inside and outside groups
+        assert_eq!(groups("(a|b){2}c", "abc", 0), None);
+        assert_eq!(groups("(a|b){2}c", "ab{2}c", 0), Some(vec![Some((1, 5)), Some((1, 1))]));
+        assert_eq!(groups("(a{1,2})", "aa{1,2}", 0), Some(vec![Some((1, 6)), Some((1, 6))]));
+    }
+
+    #[test]
+    fn captures_unbalanced_paren() {
+        // mawk reads a ) with no ( open as a literal
+        assert_eq!(groups("(a)b)", "ab)", 0), Some(vec![Some((0, 3)), Some((0, 1))]));
+    }
+
+    #[test]
+    fn captures_error() {
+        // mawk reads 2$? as (2$)? so it matches x alone, the group matcher can't divide that up
+        let err = Regex::new("(.)2$?".as_bytes()).captures("xy".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "unable to find the groups of regex (.)2$?");
+    }
+}
@@ -1,5 +1,4 @@
 use std::{cmp, io, ptr, slice};
-use std::fs::File;
 use std::io::Read;
 use std::mem::MaybeUninit;
 use std::ops::Index;
@@ -36,6 +35,12 @@ impl Index<usize> for QuickDropDeque {
 
 const DEFAULT_READ_SIZE: usize = 1024 * 8;
 
+impl Default for QuickDropDeque {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl QuickDropDeque {
     pub fn new() -> Self {
         let cap = 4;
@@ -65,6 +70,10 @@ impl QuickDropDeque {
         count(self.tail, self.head, self.cap())
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.tail == self.head
+    }
+
     #[allow(dead_code)]
     pub fn is_full(&self) -> bool {
         self.cap() - self.len() == 1
@@ -80,7 +89,7 @@ impl QuickDropDeque {
     }
 
     #[inline(never)]
-    pub fn read(&mut self, file: &mut File) -> io::Result<usize> {
+    pub fn read<R: Read>(&mut self, file: &mut R) -> io::Result<usize> {
         let free_bytes = self.cap() - self.len();
         if free_bytes <= self.io_size {
             self.reserve(self.io_size);
@@ -92,11 +101,11 @@ impl QuickDropDeque {
             let dest_slice = unsafe { std::slice::from_raw_parts_mut(self.ptr().add(self.head), self.io_size) };
             file.read(dest_slice)?
         } else {
-            let mut slice_one = unsafe { std::slice::from_raw_parts_mut(self.ptr().add(self.head), head_room) };
-            let mut bytes_read = file.read(&mut slice_one)?;
+            let slice_one = unsafe { std::slice::from_raw_parts_mut(self.ptr().add(self.head), head_room) };
+            let mut bytes_read = file.read(slice_one)?;
             if bytes_read == head_room {
-                let mut slice_two = unsafe { std::slice::from_raw_parts_mut(self.ptr(), self.io_size - head_room) };
-                bytes_read += file.read(&mut slice_two)?;
+                let slice_two = unsafe { std::slice::from_raw_parts_mut(self.ptr(), self.io_size - head_room) };
+                bytes_read += file.read(slice_two)?;
             }
             bytes_read
         };
@@ -285,6 +294,8 @@ fn count(tail: usize, head: usize, size: usize) -> usize {
     (head.wrapping_sub(tail)) & (size - 1)
 }
 
+/// # Safety
+/// Every byte of slice must be initialized
 pub const unsafe fn slice_assume_init_ref(slice: &[MaybeUninit<u8>]) -> &[u8] {
     unsafe { &*(slice as *const [MaybeUninit<u8>] as *const [u8]) }
 }
@@ -31,6 +31,7 @@ pub enum TryReserveErrorKind {
     AllocError {
         /// The layout of allocation request that failed
         layout: Layout,
+        #[allow(dead_code)]
         non_exhaustive: (),
     },
 }
@@ -114,7 +115,7 @@ impl RawVec {
     /// the returned `RawVec`.
     pub const fn new_in() -> Self {
         // `cap: 0` means "unallocated". zero-sized types are ignored.
-        Self { ptr: 0 as *mut u8, cap: 0 }
+        Self { ptr: std::ptr::null_mut(), cap: 0 }
     }
 
     /// Like `with_capacity`, but parameterized over the choice of
@@ -159,7 +160,7 @@ impl RawVec {
         // matches the size requested. If that ever changes, the capacity
         // here should change to `ptr.len() / mem::size_of::<T>()`.
         Self {
-            ptr: ptr as *mut u8,
+            ptr,
             cap: capacity,
         }
     }
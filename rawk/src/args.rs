@@ -1,183 +1,292 @@
-use crate::{lex, PrintableError, Symbolizer};
-use std::path::PathBuf;
-use std::ptr::eq;
+use crate::PrintableError;
 use mawk_regex::Regex;
 use crate::awk_str::AwkStr;
-use crate::compiler::validate_program;
-use crate::lexer::escaped_string_reader;
+use crate::lexer::escaped_cli_reader;
+use crate::runtime::Input;
+use crate::compiler::DEFAULT_SPECIALIZATION_LIMIT;
 
-// TODO: Find a small library to do this
+const ASSIGNMENT_REGEX: &str = "^[_a-zA-Z][_a-zA-Z0-9]*=";
 
+// --jit is only listed when rawk was built with the jit feature
+macro_rules! usage {
+    ($jit:literal) => { concat!("\
+Usage: rawk [--debug] [-F sepstring] [-v assignment] ... [--] program [argument...]
+Usage: rawk [--debug] [-F sepstring] -f progfile [-f progfile | -e program] ... [-v assignment] ... [--] [argument...]
+Usage: rawk --compile progfile -o compiledfile
+Usage: rawk [--debug] [-F sepstring] [-v assignment] ... -c compiledfile [--] [argument...]
+--debug       : Dump the AST, bytecode/metadata, and more.
+--version     : Print the version and exit
+--specialize=n: Compile up to n copies of each function typed for its args, 0 disables. Default 4
+", $jit, "\
+--csv         : Read input as csv where quoted fields may contain commas, newlines, and doubled quotes
+--help        : Print this message and exit
+-F sepstring  : Set the field separator eg. -F , (-F t is a tab)
+-v assignment : Set a variable eg. -v X=4
+-f progfile   : Load program text from a file eg. -f prog.awk. May be repeated.
+-e program    : Inline program text, may be mixed with -f. Also --source program
+--compile file: Compile the program in file and save the bytecode to the file given by -o instead of running it
+-c file       : Run a program saved by --compile
+--            : Stop processing flags
+argument      : Either a file, - for stdin, or an assignment. Eg. input_file.data or X=4
+program       : Body of the awk program
+") };
+}
 
-const ASSIGNMENT_REGEX: &'static str = "[_a-Z][_a-Z0-9]=";
+#[cfg(feature = "jit")]
+pub const USAGE: &str = usage!("--jit         : Compile the bytecode to native code with libjit\n");
+#[cfg(not(feature = "jit"))]
+pub const USAGE: &str = usage!("");
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct AwkArgs {
     pub debug: bool,
+    pub help: bool,
+    pub version: bool,
+    pub specialization_limit: usize,
+    pub jit: bool,
+    pub csv: bool,
     pub program: String,
+    // --compile saves the compiled program to this path instead of running it
+    pub compile_to: Option<String>,
+    // -c runs this program saved by --compile, program is empty
+    pub precompiled: Option<String>,
+    // Files and name=value assignments in the order they were given, ARGV[1..]
     pub files: Vec<String>,
+    // -F and -v assignments in the order they were given, these happen before BEGIN
+    pub assignments: Vec<(String, AwkStr)>,
 }
 
+// Program text may be split up over any number of -f and -e flags. Each is loaded in order
+// and joined with a newline.
 #[derive(Debug, PartialEq)]
 pub enum ProgramType {
-    CLI(String),
-    File(Vec<String>),
+    Cli(String),
+    File(String),
 }
 
 impl ProgramType {
-    // pub fn load(self) -> Result<String, PrintableError> {
-    //     match self {
-    //         ProgramType::CLI(s) => Ok(s),
-    //         ProgramType::File(s) => match std::fs::read_to_string(&s) {
-    //             Ok(s) => Ok(s),
-    //             Err(e) => Err(PrintableError::new(format!("Unable to load source program '{}'\nGot error: {}", s, e))),
-    //         },
-    //     }
-    // }
+    pub fn load(self) -> Result<String, PrintableError> {
+        match self {
+            ProgramType::Cli(s) => Ok(s),
+            ProgramType::File(s) => match std::fs::read_to_string(&s) {
+                Ok(s) => Ok(s),
+                Err(e) => Err(PrintableError::new(format!("rawk: can't open source file `{}` for reading: {}", s, e))),
+            },
+        }
+    }
 }
 
-fn print_help() {
-    eprintln!(
-        "\
-Usage: rawk [--debug] [-F sepstring] [-v assignment] ... program [argument...]
-Usage: rawk [--debug] [-F sepstring] -f progfile [-f progfile] ... [-v assignment] ... [argument...]
---debug: Dump the AST, bytecode/metadata, and more.
--F       : Set the field separator
--v       : Set a variable eg. -v X=4
--f       : Specify an program file eg. -f prog.awk
-argument : Either a file or an assignment. Eg. input_file.data or X=4
-program  : Body of the awk program
-"
-    );
+fn usage_error<S: Into<String>>(msg: S) -> PrintableError {
+    PrintableError::new(format!("rawk: {}\n{}", msg.into(), USAGE.trim_end()))
 }
 
 struct AwkArgBuilder {
-    argv: Vec<String>,
-    fieldsep: Option<String>,
+    debug: bool,
+    help: bool,
+    version: bool,
+    specialization_limit: usize,
+    jit: bool,
+    csv: bool,
+    compile: bool,
+    compile_to: Option<String>,
+    precompiled: Option<String>,
+    files: Vec<String>,
     assignments: Vec<(String, AwkStr)>,
-    program: Option<ProgramType>,
+    program: Vec<ProgramType>,
     assignment_regex: Regex,
 }
 
 impl AwkArgBuilder {
     pub fn new() -> Self {
         Self {
-            argv: vec![],
-            fieldsep: None,
+            debug: false,
+            help: false,
+            version: false,
+            specialization_limit: DEFAULT_SPECIALIZATION_LIMIT,
+            jit: false,
+            csv: false,
+            compile: false,
+            compile_to: None,
+            precompiled: None,
+            files: vec![],
             assignments: vec![],
-            program: None,
+            program: vec![],
             assignment_regex: Regex::new(ASSIGNMENT_REGEX.as_bytes()),
         }
     }
-    pub fn drop(self) -> Result<AwkArgs, PrintableError> {
-        todo!()
-    }
-    pub fn add_inline_program(&mut self, program: String) -> Result<(), PrintableError> {
-        if self.program.is_none() {
-            self.program = Some(ProgramType::CLI(program));
-            Ok(())
-        } else {
-            Err(PrintableError::new("Awk does not allow mixing inline programs and -f programs loaded from files"))
+    pub fn done(self) -> Result<AwkArgs, PrintableError> {
+        let mut program = String::new();
+        if self.compile && self.compile_to.is_none() {
+            return Err(usage_error("--compile needs an output file eg. -o prog.rawkc"));
         }
-    }
-    pub fn add_program_source_file(&mut self, file: String) -> Result<(), PrintableError> {
-        match &mut self.program {
-            None => {
-                self.program = Some(ProgramType::File(vec![file]));
+        if !self.compile && self.compile_to.is_some() {
+            return Err(usage_error("-o is only used with --compile"));
+        }
+        if self.precompiled.is_some() && (self.compile || !self.program.is_empty()) {
+            return Err(usage_error("-c can't be combined with program text"));
+        }
+        if !self.help && !self.version && self.precompiled.is_none() {
+            if self.program.is_empty() {
+                return Err(usage_error("no program text"));
             }
-            Some(existing) => {
-                match existing {
-                    ProgramType::CLI(_) => {
-                        return Err(PrintableError::new("Awk does not allow mixing inline programs and -f programs loaded from files"));
-                    }
-                    ProgramType::File(files) => {
-                        files.push(file)
-                    }
+            for (idx, source) in self.program.into_iter().enumerate() {
+                if idx != 0 {
+                    program.push('\n');
                 }
+                program.push_str(&source.load()?);
             }
         }
-        Ok(())
+        Ok(AwkArgs {
+            debug: self.debug,
+            help: self.help,
+            version: self.version,
+            specialization_limit: self.specialization_limit,
+            jit: self.jit,
+            csv: self.csv,
+            program,
+            compile_to: self.compile_to,
+            precompiled: self.precompiled,
+            files: self.files,
+            assignments: self.assignments,
+        })
+    }
+    pub fn add_inline_program(&mut self, program: String) {
+        self.program.push(ProgramType::Cli(program));
+    }
+    pub fn add_program_source_file(&mut self, file: String) {
+        self.program.push(ProgramType::File(file));
     }
     pub fn add_fieldsep(&mut self, fs: String) -> Result<(), PrintableError> {
-        if self.fieldsep.is_some() {
-            return Err(PrintableError::new("Cannot supply multiple -F arguments.".to_string()));
-        }
-        self.fieldsep = Some(fs);
+        // Like other awks -F t is a tab not a t
+        let fs = if fs == "t" { AwkStr::new_string("\t".to_string()) } else { Self::escape(&fs)? };
+        self.assignments.push(("FS".to_string(), fs));
         Ok(())
     }
     pub fn add_assignment(&mut self, assignment: String) -> Result<(), PrintableError> {
         if self.assignment_regex.matches(assignment.as_bytes()) {
-            self.assignment(assignment);
-            Ok(())
+            self.assignment(assignment)
         } else {
-            Err(PrintableError::new(format!("`{}` does not match the required format of an assignment. It must begin with _ or a-Z and then be followed by zero or more of _, a-Z, or 0-9 and then an equals sign.", assignment)))
+            Err(usage_error(format!("`{}` does not match the required format of an assignment. It must begin with _ or a-Z and then be followed by zero or more of _, a-Z, or 0-9 and then an equals sign.", assignment)))
         }
     }
 
-    fn assignment(&mut self, assignment: String) {
-        let equals = assignment.find("=").unwrap();
-        let name = &assignment[..equals];
-        let mut value = assignment[equals..].to_string();
-        if value.ends_with("\\") {
-            value.push('\\');
-        }
-        // Add a trailing quote of starting and trailing quotes are not present. Do not add a leading quote as the escaped string reader expects
-        // that to be already consumed.
-        if value.starts_with("\"") && value.ends_with("\"") {
-            value.push('\"');
-        };
-        let mut value_iterator = value.chars().peekable();
-        let string = escaped_string_reader(&mut value_iterator)?;
-        let value = AwkStr::new_from_vec(string);
-        self.assignments.push((name.to_string(), value));
+    fn escape(value: &str) -> Result<AwkStr, PrintableError> {
+        Ok(AwkStr::new_from_vec(escaped_cli_reader(value)?))
+    }
+
+    fn assignment(&mut self, assignment: String) -> Result<(), PrintableError> {
+        self.assignments.push(split_assignment(&assignment)?);
+        Ok(())
     }
 
     pub fn add_argument(&mut self, arg: String) -> Result<(), PrintableError> {
-        self.argv.push(arg.clone());
-        if self.assignment_regex.matches(arg.as_bytes()) {
-            self.assignment(arg);
+        // The first operand is the program unless one was given via -f, -e or -c
+        if self.program.is_empty() && self.precompiled.is_none() && !self.help && !self.version {
+            self.add_inline_program(arg);
+        } else {
+            // Check the escapes in name=value operands now, they're assigned when reading reaches them
+            if self.assignment_regex.matches(arg.as_bytes()) {
+                split_assignment(&arg)?;
+            }
+            self.files.push(arg);
         }
         Ok(())
     }
 }
 
+// name=value into the name and the value with escapes processed
+fn split_assignment(assignment: &str) -> Result<(String, AwkStr), PrintableError> {
+    let equals = assignment.find('=').unwrap();
+    let value = AwkArgBuilder::escape(&assignment[equals + 1..])?;
+    Ok((assignment[..equals].to_string(), value))
+}
+
+// Value for a flag that takes one. Either the rest of the current arg `-fprog.awk` or the next arg `-f prog.awk`
+fn flag_value<I: Iterator<Item=String>>(arg: &str, flag: &str, iter: &mut I) -> Result<String, PrintableError> {
+    if arg.len() > flag.len() {
+        return Ok(arg[flag.len()..].to_string());
+    }
+    match iter.next() {
+        Some(value) => Ok(value),
+        None => Err(usage_error(format!("option requires an argument -- {}", flag.trim_start_matches('-')))),
+    }
+}
+
 impl AwkArgs {
+    // What the program reads from, name=value operands become assignments
+    pub fn inputs(&self) -> Result<Vec<Input>, PrintableError> {
+        let assignment_regex = Regex::new(ASSIGNMENT_REGEX.as_bytes());
+        self.files.iter().map(|arg| {
+            if !assignment_regex.matches(arg.as_bytes()) {
+                return Ok(Input::Path(arg.clone()));
+            }
+            let (name, value) = split_assignment(arg)?;
+            Ok(Input::Assignment { arg: arg.clone(), name, value })
+        }).collect()
+    }
+
+    // args does not include the executable name
     pub fn new(args: Vec<String>) -> Result<Self, PrintableError> {
         let mut builder = AwkArgBuilder::new();
-        let mut iter = args.into_iter().peekable();
+        let mut iter = args.into_iter();
         while let Some(next) = iter.next() {
-            if next == " - f" {
-                if let Some(filepath) = iter.next() {
-                    builder.add_program_source_file(filepath)?;
-                } else {
-                    return Err(PrintableError::new("The -f flag must be followed by a file path to an awk program.Eg. `rawk -f program.awk`"));
-                }
-            } else if next == " - F" {
-                if let Some(fieldsep) = iter.next() {
-                    builder.add_fieldsep(fieldsep)?;
-                } else {
-                    return Err(PrintableError::new("The -F flag must be followed by a field separator.Eg. `rawk -F.` to use period."));
-                }
-            } else if next == " - v" {
-                if let Some(assignment) = iter.next() {
-                    builder.add_assignment(assignment)?;
-                } else {
-                    return Err(PrintableError::new("The -v flag must be followed by an assignment.Eg. `rawk -v a = 3` will initialize a to 3 in your program."));
+            if next == "--" {
+                break;
+            } else if next == "--debug" {
+                builder.debug = true;
+            } else if next == "--help" {
+                builder.help = true;
+            } else if next == "--version" {
+                builder.version = true;
+            } else if let Some(limit) = next.strip_prefix("--specialize=") {
+                builder.specialization_limit = match limit.parse() {
+                    Ok(limit) => limit,
+                    Err(_) => return Err(usage_error(format!("--specialize expects a number not `{}`", limit))),
+                };
+            } else if next == "--jit" {
+                if !cfg!(feature = "jit") {
+                    return Err(usage_error("--jit is not available, rawk was built without the jit feature"));
                 }
-            } else if next.starts_with(" - f") {
-                builder.add_program_source_file((&next[2..]).to_string())?;
-            } else if next.starts_with(" - F") {
-                builder.add_fieldsep((&next[2..]).to_string())?;
-            } else if next.starts_with(" - v") {
-                builder.add_assignment((&next[2..]).to_string())?;
+                builder.jit = true;
+            } else if next == "--csv" {
+                builder.csv = true;
+            } else if next == "--source" {
+                let program = flag_value(&next, "--source", &mut iter)?;
+                builder.add_inline_program(program);
+            } else if let Some(program) = next.strip_prefix("--source=") {
+                builder.add_inline_program(program.to_string());
+            } else if next == "--compile" {
+                let file = flag_value(&next, "--compile", &mut iter)?;
+                builder.add_program_source_file(file);
+                builder.compile = true;
+            } else if next.starts_with("-o") {
+                builder.compile_to = Some(flag_value(&next, "-o", &mut iter)?);
+            } else if next.starts_with("-c") {
+                builder.precompiled = Some(flag_value(&next, "-c", &mut iter)?);
+            } else if next.starts_with("-f") {
+                let file = flag_value(&next, "-f", &mut iter)?;
+                builder.add_program_source_file(file);
+            } else if next.starts_with("-e") {
+                let program = flag_value(&next, "-e", &mut iter)?;
+                builder.add_inline_program(program);
+            } else if next.starts_with("-F") {
+                let fieldsep = flag_value(&next, "-F", &mut iter)?;
+                builder.add_fieldsep(fieldsep)?;
+            } else if next.starts_with("-v") {
+                let assignment = flag_value(&next, "-v", &mut iter)?;
+                builder.add_assignment(assignment)?;
+            } else if next.starts_with("--") {
+                return Err(usage_error(format!("unrecognized option '{}'", next)));
+            } else if next.starts_with('-') && next != "-" {
+                return Err(usage_error(format!("invalid option -- '{}'", &next[1..])));
             } else {
-                // After we reach our first argument stop parsing any more -f lags
+                // After we reach our first argument stop parsing any more flags
                 builder.add_argument(next)?;
                 break;
             }
         }
 
-        while let Some(arg) = iter.next() {
+        for arg in iter {
             builder.add_argument(arg)?;
         }
 
@@ -188,7 +297,9 @@ impl AwkArgs {
 #[cfg(test)]
 mod test {
     use mawk_regex::Regex;
-    use crate::args::ASSIGNMENT_REGEX;
+    use crate::args::{ASSIGNMENT_REGEX, AwkArgs, USAGE};
+    use crate::runtime::Input;
+    use crate::compiler::DEFAULT_SPECIALIZATION_LIMIT;
 
     #[test]
     fn test_assignment_regex() {
@@ -197,10 +308,143 @@ mod test {
         assert!(regex.matches("a=".as_bytes()));
         assert!(regex.matches("_=1".as_bytes()));
         assert!(regex.matches("_a0=132332\"".as_bytes()));
+        assert!(regex.matches("ABC=abc=".as_bytes()));
         assert!(!regex.matches("\t".as_bytes()));
         assert!(!regex.matches("".as_bytes()));
         assert!(!regex.matches("=".as_bytes()));
         assert!(!regex.matches("=1".as_bytes()));
         assert!(!regex.matches("2=1".as_bytes()));
+        assert!(!regex.matches("dir/a=1".as_bytes()));
+    }
+
+    fn args(args: &[&str]) -> AwkArgs {
+        AwkArgs::new(args.iter().map(|s| s.to_string()).collect()).unwrap()
+    }
+
+    fn assignment(name: &str, value: &str) -> (String, Vec<u8>) {
+        (name.to_string(), value.as_bytes().to_vec())
     }
-}
\ No newline at end of file
+
+    fn assignments(args: &AwkArgs) -> Vec<(String, Vec<u8>)> {
+        args.assignments.iter().map(|(name, value)| (name.clone(), value.bytes().to_vec())).collect()
+    }
+
+    #[test]
+    fn test_inline_program_and_files() {
+        let parsed = args(&["--debug", "{ print $1 }", "a.txt", "-", "b.txt"]);
+        assert!(parsed.debug);
+        assert_eq!(parsed.program, "{ print $1 }");
+        assert_eq!(parsed.files, vec!["a.txt", "-", "b.txt"]);
+    }
+
+    #[test]
+    fn test_flags_after_program_are_files() {
+        let parsed = args(&["{ print $1 }", "-f", "x"]);
+        assert_eq!(parsed.files, vec!["-f", "x"]);
+    }
+
+    #[test]
+    fn test_double_dash() {
+        let parsed = args(&["--", "-v"]);
+        assert_eq!(parsed.program, "-v");
+        assert!(parsed.files.is_empty());
+    }
+
+    #[test]
+    fn test_fs() {
+        assert_eq!(assignments(&args(&["-F", ",", "1"])), vec![assignment("FS", ",")]);
+        assert_eq!(assignments(&args(&["-F,", "1"])), vec![assignment("FS", ",")]);
+        assert_eq!(assignments(&args(&["-Ft", "1"])), vec![assignment("FS", "\t")]);
+        assert_eq!(assignments(&args(&["-F", "\\t", "1"])), vec![assignment("FS", "\t")]);
+    }
+
+    #[test]
+    fn test_assignments() {
+        let parsed = args(&["-v", "a=1", "-vb=x\\ty", "{}", "c=3", "file"]);
+        assert_eq!(assignments(&parsed), vec![assignment("a", "1"), assignment("b", "x\ty")]);
+        // Operand assignments stay in order with the files
+        assert_eq!(parsed.files, vec!["c=3", "file"]);
+        let inputs = parsed.inputs().unwrap();
+        assert!(matches!(&inputs[0], Input::Assignment { arg, name, value } if arg == "c=3" && name == "c" && value.bytes() == b"3"));
+        assert!(matches!(&inputs[1], Input::Path(path) if path == "file"));
+    }
+
+    #[test]
+    fn test_mixed_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prog.awk");
+        std::fs::write(&path, "BEGIN { a = 1 }").unwrap();
+        let path = path.to_str().unwrap();
+        let parsed = args(&["-e", "BEGIN { b = 1 }", "-f", path, "--source=END { }", "file"]);
+        assert_eq!(parsed.program, "BEGIN { b = 1 }\nBEGIN { a = 1 }\nEND { }");
+        assert_eq!(parsed.files, vec!["file"]);
+    }
+
+    #[test]
+    fn test_help_version() {
+        assert!(args(&["--help"]).help);
+        assert!(args(&["--version"]).version);
+    }
+
+    #[test]
+    fn test_specialize() {
+        assert_eq!(args(&["{}"]).specialization_limit, DEFAULT_SPECIALIZATION_LIMIT);
+        assert_eq!(args(&["--specialize=0", "{}"]).specialization_limit, 0);
+        assert_eq!(args(&["--specialize=12", "{}"]).specialization_limit, 12);
+    }
+
+    #[test]
+    fn test_precompiled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prog.awk");
+        std::fs::write(&path, "BEGIN { a = 1 }").unwrap();
+        let path = path.to_str().unwrap();
+        let parsed = args(&["--compile", path, "-o", "prog.rawkc"]);
+        assert_eq!(parsed.program, "BEGIN { a = 1 }");
+        assert_eq!(parsed.compile_to, Some("prog.rawkc".to_string()));
+        assert_eq!(parsed.precompiled, None);
+
+        let parsed = args(&["-v", "a=1", "-cprog.rawkc", "file", "b=2"]);
+        assert_eq!(parsed.precompiled, Some("prog.rawkc".to_string()));
+        assert_eq!(parsed.program, "");
+        assert_eq!(parsed.files, vec!["file", "b=2"]);
+        assert_eq!(assignments(&parsed), vec![assignment("a", "1")]);
+    }
+
+    #[test]
+    fn test_jit() {
+        assert!(!args(&["{}"]).jit);
+        if cfg!(feature = "jit") {
+            assert!(args(&["--jit", "{}"]).jit);
+        } else {
+            let err = AwkArgs::new(vec!["--jit".to_string(), "{}".to_string()]).unwrap_err().msg;
+            assert!(err.starts_with("rawk: --jit is not available"));
+        }
+        assert_eq!(USAGE.contains("--jit"), cfg!(feature = "jit"));
+    }
+
+    #[test]
+    fn test_csv() {
+        assert!(!args(&["{}"]).csv);
+        let parsed = args(&["--csv", "-v", "a=1", "{}", "data.csv"]);
+        assert!(parsed.csv);
+        assert_eq!(parsed.files, vec!["data.csv"]);
+        // Flags stop at the program
+        assert!(!args(&["{}", "--csv"]).csv);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |a: &[&str]| AwkArgs::new(a.iter().map(|s| s.to_string()).collect()).unwrap_err().msg;
+        assert!(err(&[]).starts_with("rawk: no program text\nUsage: rawk"));
+        assert!(err(&["-f"]).starts_with("rawk: option requires an argument -- f\n"));
+        assert!(err(&["-x", "{}"]).starts_with("rawk: invalid option -- 'x'\n"));
+        assert!(err(&["--nope", "{}"]).starts_with("rawk: unrecognized option '--nope'\n"));
+        assert!(err(&["-v", "1=a", "{}"]).contains("does not match the required format"));
+        assert!(err(&["-f", "/does/not/exist.awk"]).starts_with("rawk: can't open source file"));
+        assert!(err(&["--specialize=x", "{}"]).starts_with("rawk: --specialize expects a number not `x`\n"));
+        assert!(err(&["--compile", "p.awk"]).starts_with("rawk: --compile needs an output file"));
+        assert!(err(&["-o", "p.rawkc", "{}"]).starts_with("rawk: -o is only used with --compile\n"));
+        assert!(err(&["-c", "p.rawkc", "-e", "{}"]).starts_with("rawk: -c can't be combined with program text\n"));
+    }
+}
@@ -1,9 +1,4 @@
-mod awk_byte_str;
-mod awk_str;
-mod rc_awk_str;
 mod sub_repl_str;
 
-pub use crate::awk_str::awk_str::AwkStr;
-pub use crate::awk_str::rc_awk_str::RcAwkStr;
-pub use crate::awk_str::awk_byte_str::AwkByteStr;
-pub use crate::awk_str::sub_repl_str::SubReplStr;
\ No newline at end of file
+pub use awk_frontend::awk_str::{AwkByteStr, AwkStr, RcAwkStr};
+pub use crate::awk_str::sub_repl_str::{GensubReplStr, SubReplStr};
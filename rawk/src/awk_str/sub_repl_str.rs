@@ -1,5 +1,4 @@
 use crate::awk_str::{AwkStr};
-use crate::compiler::compile;
 use crate::util::memchr_libc;
 
 // Struct for efficiently implementing the REPL
@@ -31,8 +30,8 @@ impl PartialEq for ReplComponent {
 impl SubReplStr {
     pub fn new(bytes: Vec<u8>) -> Self {
         // If there are no special characters skip the escaping routine as it's not super quick
-        if memchr_libc(&bytes, '&' as u8) == None
-            && memchr_libc(&bytes, '\\' as u8) == None {
+        if memchr_libc(&bytes, b'&').is_none()
+            && memchr_libc(&bytes, b'\\').is_none() {
             return Self {
                 components: vec![ReplComponent::EscapedBytes(bytes)]
             };
@@ -71,7 +70,7 @@ impl EscapeBuilder {
     }
 
     pub fn add_amp(&mut self) {
-        if self.current_str.len() != 0 {
+        if !self.current_str.is_empty() {
             let mut new_str = vec![];
             std::mem::swap(&mut new_str, &mut self.current_str);
             self.components.push(ReplComponent::EscapedBytes(new_str));
@@ -80,7 +79,7 @@ impl EscapeBuilder {
     }
 
     pub fn done(mut self) -> Vec<ReplComponent> {
-        if self.current_str.len() != 0 {
+        if !self.current_str.is_empty() {
             self.components.push(ReplComponent::EscapedBytes(self.current_str));
         }
         self.components
@@ -92,18 +91,18 @@ fn escape_reader(str: &[u8]) -> Vec<ReplComponent> {
     let mut iter = str.iter().peekable();
     while let Some(char) = iter.next() {
         let next = iter.peek();
-        if *char == '\\' as u8 && next == Some(&&('\\' as u8)) {
+        if *char == b'\\' && next == Some(&&b'\\') {
             // Escaped \
-            builder.add_byte('\\' as u8);
+            builder.add_byte(b'\\');
             iter.next();
-        } else if *char == '\\' as u8 && next == Some(&&('&' as u8)) {
+        } else if *char == b'\\' && next == Some(&&b'&') {
             // Escaped &
-            builder.add_byte('&' as u8);
+            builder.add_byte(b'&');
             iter.next();
-        } else if *char == '\\' as u8 {
+        } else if *char == b'\\' {
             // Just a \
-            builder.add_byte('\\' as u8)
-        } else if *char == '&' as u8 {
+            builder.add_byte(b'\\')
+        } else if *char == b'&' {
             // Logical &
             builder.add_amp();
         } else {
@@ -114,10 +113,71 @@ fn escape_reader(str: &[u8]) -> Vec<ReplComponent> {
     builder.done()
 }
 
+// The replacement arg of gensub. Unlike sub \0-\9 are groups of the match, & is all of it, and a
+// backslash before anything else is dropped.
+pub struct GensubReplStr {
+    components: Vec<GensubComponent>,
+}
+
+#[derive(Debug, PartialEq)]
+enum GensubComponent {
+    Bytes(Vec<u8>),
+    // 0 is the whole match
+    Group(usize),
+}
+
+impl GensubReplStr {
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut components = vec![];
+        let mut current = vec![];
+        let mut iter = bytes.iter().peekable();
+        while let Some(byte) = iter.next() {
+            let group = match (*byte, iter.peek()) {
+                (b'\\', Some(digit)) if digit.is_ascii_digit() => (**digit - b'0') as usize,
+                (b'\\', Some(escaped)) => {
+                    current.push(**escaped);
+                    iter.next();
+                    continue;
+                }
+                (b'&', _) => 0,
+                (byte, _) => {
+                    current.push(byte);
+                    continue;
+                }
+            };
+            if *byte == b'\\' {
+                iter.next();
+            }
+            if !current.is_empty() {
+                components.push(GensubComponent::Bytes(std::mem::take(&mut current)));
+            }
+            components.push(GensubComponent::Group(group));
+        }
+        if !current.is_empty() {
+            components.push(GensubComponent::Bytes(current));
+        }
+        Self { components }
+    }
+
+    // groups[0] is the whole match, groups that didn't match and ones past the end are empty
+    pub fn push_replacement(&self, str: &mut AwkStr, groups: &[Option<&[u8]>]) {
+        for component in &self.components {
+            match component {
+                GensubComponent::Bytes(bytes) => str.push_str(bytes),
+                GensubComponent::Group(group) => {
+                    if let Some(Some(bytes)) = groups.get(*group) {
+                        str.push_str(bytes);
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::awk_str::AwkStr;
-    use crate::awk_str::sub_repl_str::{escape_reader, ReplComponent, SubReplStr};
+    use crate::awk_str::sub_repl_str::{escape_reader, GensubComponent, GensubReplStr, ReplComponent, SubReplStr};
 
     fn s(bytes: &str) -> ReplComponent {
         ReplComponent::EscapedBytes(bytes.as_bytes().to_vec())
@@ -160,4 +220,17 @@ mod test {
         repl.push_replacement(&mut str, "xyz".as_bytes());
         assert_eq!(str.bytes(), "a&xyz&bxyzb\\\\".as_bytes())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_gensub_repl() {
+        let repl = GensubReplStr::new("<\\2-\\1|&\\0\\\\\\&\\q\\7>\\".as_bytes());
+        assert_eq!(repl.components, vec![
+            GensubComponent::Bytes(b"<".to_vec()), GensubComponent::Group(2), GensubComponent::Bytes(b"-".to_vec()),
+            GensubComponent::Group(1), GensubComponent::Bytes(b"|".to_vec()), GensubComponent::Group(0), GensubComponent::Group(0),
+            GensubComponent::Bytes(b"\\&q".to_vec()), GensubComponent::Group(7), GensubComponent::Bytes(b">\\".to_vec()),
+        ]);
+        let mut str = AwkStr::new_empty();
+        repl.push_replacement(&mut str, &[Some(b"ab"), Some(b"a"), None]);
+        assert_eq!(str.bytes(), "<-a|abab\\&q>\\".as_bytes())
+    }
+}
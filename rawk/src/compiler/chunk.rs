@@ -1,10 +1,8 @@
 use std::ops::{Deref, DerefMut};
 use hashbrown::HashMap;
-use crate::awk_str::RcAwkStr;
-#[cfg(test)]
+use crate::compiler::{optimizer, superinstructions};
 use crate::vm::{VmProgram, VmFunc};
 use crate::vm::{Code};
-use crate::vm::StringScalar;
 
 pub struct Chunk {
     bytecode: Vec<Code>,
@@ -31,46 +29,32 @@ impl Chunk {
         self.bytecode.push(code);
     }
 
+    // Labels are removed and jumps to them become jumps to the op that followed them
     pub fn resolve_labels(&mut self) {
         let mut label_indices = HashMap::new();
-        for (idx, byte) in self.bytecode.iter_mut().enumerate() {
+        let mut bytecode = Vec::with_capacity(self.bytecode.len());
+        for byte in self.bytecode.drain(..) {
             match byte {
                 Code::Label(lbl) => {
-                    label_indices.insert(*lbl, idx);
-                    let mut nop = Code::NoOp;
-                    std::mem::swap(byte, &mut nop);
+                    label_indices.insert(lbl, bytecode.len());
                 }
-                _ => {}
+                _ => bytecode.push(byte),
             }
         }
-        let chunk_len = self.bytecode.len();
+        self.bytecode = bytecode;
         for (idx, byte) in self.bytecode.iter_mut().enumerate() {
-            let lbl = match byte {
-                Code::JumpLbl(lbl) => lbl,
-                Code::JumpIfFalseNumLbl(lbl) => lbl,
-                Code::JumpIfFalseStrLbl(lbl) => lbl,
-                Code::JumpIfFalseVarLbl(lbl) => lbl,
-                Code::JumpIfTrueNumLbl(lbl) => lbl,
-                Code::JumpIfTrueStrLbl(lbl) => lbl,
-                Code::JumpIfTrueVarLbl(lbl) => lbl,
-                Code::JumpIfTrueNextLineLbl(lbl) => lbl,
-                Code::JumpIfFalseNextLineLbl(lbl) => lbl,
-                _ => continue,
+            let lbl = match byte.jump_label() {
+                Some(lbl) => lbl,
+                None => continue,
             };
-            let mut label_idx = *label_indices.get(lbl).unwrap();
-            // TODO: Restore this
-            if label_idx + 1 < chunk_len {
-                // As long as the jump isn't to the end of the program there's no
-                // need to jump to the no-op itself, instead jump to the next actual op.
-                label_idx += 1;
-            }
-            let label_idx = label_idx as isize;
+            let label_idx = *label_indices.get(&lbl).unwrap() as isize;
             let offset = label_idx - (idx as isize);
             byte.resolve_label_to_offset(offset)
         }
     }
 
     pub fn optimize(&mut self) {
+        optimizer::optimize(&mut self.bytecode);
 
         // Optimize concat to clear the destination scalar before concat'ing.
         // This allows RcAwkStr's to be downgraded for efficient extension
@@ -88,20 +72,23 @@ impl Chunk {
         }
         new_code.push(self.bytecode.last().unwrap().clone());
         self.bytecode = new_code;
+
+        if superinstructions::enabled() {
+            superinstructions::fuse(&mut self.bytecode);
+        }
     }
 
-    #[cfg(test)]
     pub fn pretty_print(&self, _func: &VmFunc, prog: &VmProgram, output: &mut Vec<u8>) {
         output.extend_from_slice("Bytecode:\n".as_bytes());
         for (idx, byte) in self.bytecode.iter().enumerate() {
             let ip = format!("\t{:2} ", idx);
-            output.extend_from_slice(&ip.as_bytes());
+            output.extend_from_slice(ip.as_bytes());
 
             byte.pretty_print(output);
 
             let meta = byte.meta(&prog.func_map);
             let side_effect = format!("{:?}\n", meta);
-            output.extend_from_slice(&side_effect.as_bytes());
+            output.extend_from_slice(side_effect.as_bytes());
         }
     }
 }
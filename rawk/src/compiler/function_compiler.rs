@@ -1,13 +1,14 @@
 use std::rc::Rc;
 use crate::awk_str::RcAwkStr;
 use crate::lexer::{BinOp, LogicalOp, MathOp};
-use crate::parser::{ArgT, Expr, LValue, ScalarType, Stmt, TypedExpr, Variable};
+use crate::parser::{ArgT, Expr, GetlineSource, LValue, ScalarType, Stmt, TypedExpr, Variable};
 use crate::printable_error::PrintableError;
 use crate::symbolizer::Symbol;
-use crate::typing::{AnalysisResults, BuiltinFunc, FunctionMap, ITypedFunction, TypedProgram, TypedUserFunction};
-use crate::vm::{Code, Label, VmFunc};
+use crate::typing::{BuiltinFunc, ITypedFunction, NativeArgT, TypedProgram, TypedUserFunction};
+use crate::vm::{Code, Incr, Indices, Label, VmFunc};
 use crate::compiler::chunk::Chunk;
 use crate::stackt::StackT;
+use crate::runtime::time::DEFAULT_STRFTIME_FORMAT;
 
 
 pub struct FunctionCompiler<'a> {
@@ -15,6 +16,10 @@ pub struct FunctionCompiler<'a> {
     label_count: usize,
     typed_program: &'a mut TypedProgram,
     break_labels: Vec<Label>,
+    // for (k in arr) loops around the current statement, each has keys saved by the VM
+    for_in_depth: usize,
+    // Where nextfile jumps and whether it skips the rest of the file first, None where it's not allowed
+    next_file_target: Option<(Label, bool)>,
     parser_func: Rc<TypedUserFunction>,
 }
 
@@ -25,6 +30,8 @@ impl<'a> FunctionCompiler<'a> {
             chunk: Chunk::new(),
             label_count: 0,
             break_labels: vec![],
+            for_in_depth: 0,
+            next_file_target: None,
             parser_func,
         }
     }
@@ -37,12 +44,9 @@ impl<'a> FunctionCompiler<'a> {
         let func = cpy.function();
         self.stmt(&func.body)?;
 
-        // If function doesn't end with a user provided return return the empty string
-        if !self.chunk.ends_with(&[Code::Ret]) {
-            let strnum = RcAwkStr::new_bytes("".as_bytes().to_vec());
-            self.add(Code::ConstStrNum { strnum });
-            self.add(Code::StrToVar);
-            self.add(Code::Ret);
+        // If function doesn't end with a user provided return return the uninitialized value
+        if !self.chunk.last().is_some_and(|code| code.is_ret()) {
+            self.uninitialized_return();
         }
 
         self.chunk.optimize();
@@ -50,14 +54,24 @@ impl<'a> FunctionCompiler<'a> {
         Ok(VmFunc::new(self.chunk, id, self.parser_func.clone()))
     }
 
-    fn add(&mut self, code: Code) {
-        self.chunk.push(code)
+    // Falling off the end of a function and a bare `return` return the uninitialized value. When the
+    // return type is Num or Str this is unreachable (the typing pass saw every path return).
+    fn uninitialized_return(&mut self) {
+        let return_type = self.parser_func.return_type();
+        match return_type {
+            ScalarType::Num => self.add(Code::ConstNum { num: 0.0 }),
+            ScalarType::Str => self.add(Code::ConstStr { str: RcAwkStr::new_bytes(vec![]) }),
+            ScalarType::Var => {
+                let strnum = RcAwkStr::new_bytes(vec![]);
+                self.add(Code::ConstStrNum { strnum });
+                self.add(Code::StrToVar);
+            }
+        }
+        self.add(Code::ret(return_type));
     }
 
-    fn create_and_insert_lbl(&mut self) -> Label {
-        let lbl = self.create_lbl();
-        self.insert_lbl(lbl);
-        lbl
+    fn add(&mut self, code: Code) {
+        self.chunk.push(code)
     }
 
     fn create_lbl(&mut self) -> Label {
@@ -75,6 +89,10 @@ impl<'a> FunctionCompiler<'a> {
             Stmt::Expr(expr) => {
                 self.expr_opt(expr, None)?;
             }
+            Stmt::Print(expr) if expr.typ == ScalarType::Num => {
+                self.expr(expr, StackT::Num)?;
+                self.add(Code::PrintNum);
+            }
             Stmt::Print(expr) => {
                 self.expr(expr, StackT::Str)?;
                 self.add(Code::Print);
@@ -134,12 +152,14 @@ impl<'a> FunctionCompiler<'a> {
                         :done
                     */
                     let body_lbl = self.create_lbl();
+                    let next_lbl = self.create_lbl();
                     let done_lbl = self.create_lbl();
 
                     self.add(Code::JumpIfFalseNextLineLbl(done_lbl));
                     self.break_labels.push(done_lbl);
                     self.insert_lbl(body_lbl);
-                    self.stmt(body)?;
+                    self.with_next_file_target(Some((next_lbl, true)), |comp| comp.stmt(body))?;
+                    self.insert_lbl(next_lbl);
                     self.add(Code::JumpIfTrueNextLineLbl(body_lbl));
                     self.insert_lbl(done_lbl);
                     self.break_labels.pop().unwrap();
@@ -177,6 +197,99 @@ impl<'a> FunctionCompiler<'a> {
                     self.break_labels.pop().unwrap();
                 }
             }
+            Stmt::ForIn { var, array, body } => {
+                /*
+                    [Array]
+                    ForInStart
+                :next
+                    ForInNext
+                    JumpIfFalseNum :done
+                    ForInKey
+                    [Assign to var]
+                    [Body]
+                    Jump :next
+                :done
+                    ForInEnd
+                */
+                let next_lbl = self.create_lbl();
+                let done_lbl = self.create_lbl();
+
+                self.push_array(array);
+                self.add(Code::ForInStart);
+                self.insert_lbl(next_lbl);
+                self.add(Code::ForInNext);
+                self.add(Code::JumpIfFalseNumLbl(done_lbl));
+                self.add(Code::ForInKey);
+                let key_typ = match var {
+                    Variable::User(_) => ScalarType::Str,
+                    Variable::Special(_) => {
+                        self.add(Code::StrToVar);
+                        ScalarType::Var
+                    }
+                };
+                self.assign_to_scalar(var, key_typ, true);
+                self.break_labels.push(done_lbl);
+                self.for_in_depth += 1;
+                self.stmt(body)?;
+                self.for_in_depth -= 1;
+                self.break_labels.pop().unwrap();
+                self.add(Code::JumpLbl(next_lbl));
+                self.insert_lbl(done_lbl);
+                self.add(Code::ForInEnd);
+            }
+            Stmt::Files { begin_file, records, end_file } => {
+                /*
+                :next_file
+                    NextFile
+                    JumpIfFalseNum :done
+                    [BeginFile]
+                    CheckFileOpened
+                :records
+                    NextRecordInFile
+                    JumpIfFalseNum :end_file
+                    [Records]
+                    Jump :records
+                :end_file
+                    [EndFile]
+                    Jump :next_file
+                :done
+                */
+                let next_file_lbl = self.create_lbl();
+                let records_lbl = self.create_lbl();
+                let end_file_lbl = self.create_lbl();
+                let done_lbl = self.create_lbl();
+
+                self.break_labels.push(done_lbl);
+                self.insert_lbl(next_file_lbl);
+                self.add(Code::NextFile);
+                self.add(Code::JumpIfFalseNumLbl(done_lbl));
+                // nextfile in BEGINFILE skips the file's ENDFILE too
+                self.with_next_file_target(Some((next_file_lbl, false)), |comp| comp.stmt(begin_file))?;
+                self.add(Code::CheckFileOpened);
+                self.insert_lbl(records_lbl);
+                self.add(Code::NextRecordInFile);
+                self.add(Code::JumpIfFalseNumLbl(end_file_lbl));
+                self.with_next_file_target(Some((records_lbl, true)), |comp| comp.stmt(records))?;
+                self.add(Code::JumpLbl(records_lbl));
+                self.insert_lbl(end_file_lbl);
+                self.with_next_file_target(None, |comp| comp.stmt(end_file))?;
+                self.add(Code::JumpLbl(next_file_lbl));
+                self.insert_lbl(done_lbl);
+                self.break_labels.pop().unwrap();
+            }
+            Stmt::NextFile => {
+                let (target, skip_file) = match self.next_file_target {
+                    Some(target) => target,
+                    None => return Err(PrintableError::new("nextfile can only be used in BEGINFILE and pattern action rules".to_string())),
+                };
+                for _ in 0..self.for_in_depth {
+                    self.add(Code::ForInEnd);
+                }
+                if skip_file {
+                    self.add(Code::SkipFile);
+                }
+                self.add(Code::JumpLbl(target));
+            }
             Stmt::Printf { args, fstring } => {
                 for arg in args {
                     self.expr(arg, StackT::Str)?;
@@ -192,18 +305,29 @@ impl<'a> FunctionCompiler<'a> {
                 }
             }
             Stmt::Return(ret) => {
+                // Returning from inside for (k in arr) loops skips their ForInEnd
+                for _ in 0..self.for_in_depth {
+                    self.add(Code::ForInEnd);
+                }
                 if let Some(ret) = ret {
-                    self.expr(ret, StackT::Var)?;
+                    let return_type = self.parser_func.return_type();
+                    self.expr(ret, return_type.into())?;
+                    self.add(Code::ret(return_type));
                 } else {
-                    self.add(Code::ConstNum { num: 0.0 });
-                    self.add(Code::NumToVar);
+                    self.uninitialized_return();
                 }
-                self.add(Code::Ret);
             }
         }
         Ok(())
     }
 
+    fn with_next_file_target(&mut self, target: Option<(Label, bool)>, compile: impl FnOnce(&mut Self) -> Result<(), PrintableError>) -> Result<(), PrintableError> {
+        let outer = std::mem::replace(&mut self.next_file_target, target);
+        let result = compile(self);
+        self.next_file_target = outer;
+        result
+    }
+
     fn expr(&mut self, expr: &TypedExpr, desired_stack: StackT) -> Result<Option<StackT>, PrintableError> {
         self.expr_opt(expr, Some(desired_stack))
     }
@@ -221,7 +345,7 @@ impl<'a> FunctionCompiler<'a> {
                     Variable::Special(_) => ScalarType::Var,
                 };
                 self.expr(value, rhs_typ.into())?;
-                let side_effect_only = desired_stack == None;
+                let side_effect_only = desired_stack.is_none();
                 self.assign_to_scalar(scalar_name, rhs_typ, side_effect_only)
             }
             Expr::NumberF64(num) => {
@@ -347,10 +471,9 @@ impl<'a> FunctionCompiler<'a> {
             Expr::Variable(scalar) => {
                 match scalar {
                     Variable::User(scalar) => {
-                        if let Some(arg_idx) = self.parser_func.scalar_arg_idx(scalar) {
-                            // TODO: function args should be on non-unknown stacks
-                            self.add(Code::arg_scl(ScalarType::Var, arg_idx));
-                            Some(StackT::Var)
+                        if let Some((typ, arg_idx)) = self.parser_func.scalar_arg(scalar) {
+                            self.add(Code::arg_scl(typ, arg_idx));
+                            Some(typ.into())
                         } else if let Some(id) = self.typed_program.global_analysis.global_scalars.get(scalar) {
                             self.add(Code::gscl(*id, expr.typ));
                             Some(expr.typ.into())
@@ -401,40 +524,83 @@ impl<'a> FunctionCompiler<'a> {
                 Some(expr.typ.into())
             }
             Expr::ArrayAssign { name, indices, value } => {
-                self.expr(value, value.typ.into())?;
-                let side_effect_only = desired_stack == None;
-                self.assign_to_array(name, indices, value.typ, side_effect_only)?;
-                if side_effect_only { None } else { Some(value.typ.into()) }
+                // Typing ensures only numbers are stored in num arrays
+                let typ = if self.is_num_array(name) { ScalarType::Num } else { value.typ };
+                self.expr(value, typ.into())?;
+                let side_effect_only = desired_stack.is_none();
+                self.assign_to_array(name, indices, typ, side_effect_only)?;
+                if side_effect_only { None } else { Some(typ.into()) }
+            }
+            Expr::ColumnAssign { column, value } => {
+                self.expr(value, StackT::Str)?;
+                self.expr(column, StackT::Num)?;
+                if desired_stack.is_none() {
+                    self.add(Code::AssignColumn);
+                    None
+                } else {
+                    self.add(Code::AssignRetColumn);
+                    Some(StackT::Str)
+                }
+            }
+            Expr::IncDec { lvalue, increment, pre } => {
+                let side_effect_only = desired_stack.is_none();
+                let incr = Incr::new(*increment, *pre, side_effect_only);
+                match lvalue {
+                    LValue::Variable(Variable::User(name)) => {
+                        if let Some(arg_idx) = self.parser_func.scalar_arg_idx(name) {
+                            self.add(Code::IncArg { arg_idx, incr });
+                        } else {
+                            let id = self.typed_program.global_analysis.global_scalars.get(name).expect("compiler bug in typing pass global scalar not found");
+                            self.add(Code::IncGscl { id: *id, incr });
+                        }
+                    }
+                    LValue::Variable(Variable::Special(special)) => {
+                        self.add(Code::IncSclSpecial { special: *special, incr });
+                    }
+                    LValue::ArrayIndex { name, indices } => {
+                        self.push_array(name);
+                        let indices = self.indices(indices)?;
+                        if self.is_num_array(name) {
+                            self.add(Code::IncNumArray { indices, incr });
+                        } else {
+                            self.add(Code::IncArray { indices, incr });
+                        }
+                    }
+                    LValue::Column(col) => {
+                        self.expr(col, StackT::Num)?;
+                        self.add(Code::IncColumn { incr });
+                    }
+                }
+                if side_effect_only { None } else { Some(StackT::Num) }
             }
             Expr::ArrayIndex { name, indices } => {
                 self.push_array(name);
-                for idx in indices {
-                    self.expr(idx, StackT::Str)?;
-                };
-                self.add(Code::ArrayIndex { indices: indices.len() }); // TODO: u16max
-                Some(StackT::Var)
+                let indices = self.indices(indices)?;
+                if desired_stack == Some(StackT::Num) && self.is_num_array(name) {
+                    self.add(Code::NumArrayIndex { indices });
+                    Some(StackT::Num)
+                } else {
+                    self.add(Code::ArrayIndex { indices });
+                    Some(StackT::Var)
+                }
             }
             Expr::InArray { name, indices } => {
                 self.push_array(name);
-                for idx in indices {
-                    self.expr(idx, StackT::Str)?;
-                };
-                self.add(Code::ArrayMember { indices: indices.len() }); // TODO: u16max
+                let indices = self.indices(indices)?;
+                self.add(Code::ArrayMember { indices });
                 Some(StackT::Num)
             }
             Expr::Call { target, args } => {
-                // TODO: Arg # mismatch and implicit array creation
-
                 if let Some(builtin) = BuiltinFunc::get(target.to_str()) {
                     let t = self.builtin(builtin, args)?;
                     Some(t.into())
-                } else if let Some(target_func) = self.typed_program.functions.get(target) {
+                } else if let Some(target_func) = self.typed_program.functions.get_user_function(target) {
                     let id = self.typed_program.functions.get_id(&target_func.name()).unwrap();
                     let target_name = target_func.name();
                     for (idx, (function_arg, call_arg)) in target_func.args().iter().zip(args).enumerate() {
                         match function_arg.typ {
                             ArgT::Scalar => {
-                                self.expr(call_arg, StackT::Var)?;
+                                self.expr(call_arg, target_func.arg_types()[idx].into())?;
                             }
                             ArgT::Array => {
                                 if let Expr::Variable(var) = &call_arg.expr {
@@ -456,8 +622,27 @@ impl<'a> FunctionCompiler<'a> {
                             }
                         }
                     }
-                    self.add(Code::Call { target: id });
-                    Some(StackT::Var)
+                    self.add(Code::Call { target: id, args: args.len() });
+                    Some(target_func.return_type().into())
+                } else if let Some((id, native)) = self.typed_program.functions.native(target).map(|(id, native)| (id, native.clone())) {
+                    if args.len() != native.args.len() {
+                        return Err(PrintableError::new(format!("Function {} accepts {} args but was called with {}", target, native.args.len(), args.len())));
+                    }
+                    for (idx, (typ, call_arg)) in native.args.iter().zip(args).enumerate() {
+                        match typ {
+                            NativeArgT::Num => { self.expr(call_arg, StackT::Num)?; }
+                            NativeArgT::Str => { self.expr(call_arg, StackT::Str)?; }
+                            NativeArgT::Array => {
+                                if let Expr::Variable(Variable::User(var)) = &call_arg.expr {
+                                    self.push_array(var);
+                                } else {
+                                    return Err(PrintableError::new(format!("Tried to use scalar as arg #{} to function {} which accepts an array", idx + 1, target)));
+                                }
+                            }
+                        }
+                    }
+                    self.add(Code::CallNative { id });
+                    Some(native.returns.into())
                 } else {
                     return Err(PrintableError::new(format!("Attempted to call unknown function: `{}`", target)));
                 }
@@ -476,7 +661,7 @@ impl<'a> FunctionCompiler<'a> {
                 self.expr(&typed_str_expr, StackT::Str)?;
 
                 // Stack: [ere, repl, string]
-                self.add(Code::Sub3 { global: if *global { true } else { false } });
+                self.add(Code::Sub3 { global: *global });
                 // Pushes String with subs and the number of subs
 
                 // Stack: [result]
@@ -487,10 +672,61 @@ impl<'a> FunctionCompiler<'a> {
                     LValue::ArrayIndex { name, indices } => {
                         self.assign_to_array(name, indices, ScalarType::Str, true)?;
                     }
-                    LValue::Column(_col) => todo!("column assignment"),
+                    LValue::Column(col) => {
+                        self.expr(col, StackT::Num)?;
+                        self.add(Code::AssignColumn);
+                    }
                 }
                 Some(StackT::Num)
             }
+            Expr::Getline { source, var } => {
+                /*
+                [File or command]
+                GetlineInput|GetlineInputVar|GetlineFile|GetlineCommand
+                JumpIfFalseNum :done
+                GetlineRecord
+                [Assign to var or $0]
+                :done
+                GetlineResult
+                 */
+                let done = self.create_lbl();
+                match source {
+                    GetlineSource::Input if var.is_none() => self.add(Code::GetlineInput),
+                    GetlineSource::Input => self.add(Code::GetlineInputVar),
+                    GetlineSource::File(file) => {
+                        self.expr(file, StackT::Str)?;
+                        self.add(Code::GetlineFile);
+                    }
+                    GetlineSource::Command(command) => {
+                        self.expr(command, StackT::Str)?;
+                        self.add(Code::GetlineCommand);
+                    }
+                }
+                self.add(Code::JumpIfFalseNumLbl(done));
+                // GetlineInput already read into $0
+                if !matches!((source, var), (GetlineSource::Input, None)) {
+                    self.add(Code::GetlineRecord);
+                    match var {
+                        Some(LValue::Variable(name)) => {
+                            self.assign_to_scalar(name, ScalarType::Str, true);
+                        }
+                        Some(LValue::ArrayIndex { name, indices }) => {
+                            self.assign_to_array(name, indices, ScalarType::Str, true)?;
+                        }
+                        Some(LValue::Column(col)) => {
+                            self.expr(col, StackT::Num)?;
+                            self.add(Code::AssignColumn);
+                        }
+                        None => {
+                            self.add(Code::ConstNum { num: 0.0 });
+                            self.add(Code::AssignColumn);
+                        }
+                    }
+                }
+                self.insert_lbl(done);
+                self.add(Code::GetlineResult);
+                Some(StackT::Num)
+            }
         };
 
         match (stack, desired_stack) {
@@ -544,15 +780,48 @@ impl<'a> FunctionCompiler<'a> {
                        result_type: ScalarType,
                        side_effect_only: bool) -> Result<(), PrintableError> {
         self.push_array(name);
+        let indices = self.indices(indices)?;
+        if self.is_num_array(name) {
+            self.add(Code::num_array_assign(indices, side_effect_only));
+        } else {
+            self.add(Code::array_assign(indices, result_type, side_effect_only));
+        }
+        Ok(())
+    }
+
+    // Push the indices of an array element
+    fn indices(&mut self, indices: &[TypedExpr]) -> Result<Indices, PrintableError> {
+        if let [idx] = indices {
+            match idx.typ {
+                ScalarType::Num => {
+                    self.expr(idx, StackT::Num)?;
+                    return Ok(Indices::Num);
+                }
+                ScalarType::Var => {
+                    self.expr(idx, StackT::Var)?;
+                    return Ok(Indices::Var);
+                }
+                ScalarType::Str => {}
+            }
+        }
         for idx in indices {
             self.expr(idx, StackT::Str)?;
         };
-        self.add(Code::array_assign(indices.len(), result_type, side_effect_only));
-        Ok(())
+        Ok(Indices::Str(indices.len() as u32)) // TODO: u32max
     }
 
-    fn builtin(&mut self, builtin: BuiltinFunc, args: &Vec<TypedExpr>) -> Result<ScalarType, PrintableError> {
+    fn builtin(&mut self, builtin: BuiltinFunc, args: &[TypedExpr]) -> Result<ScalarType, PrintableError> {
         let code = match builtin {
+            BuiltinFunc::Asort => match args.len() {
+                1 => Code::BuiltinAsort1,
+                2 => Code::BuiltinAsort2,
+                _ => Code::BuiltinAsort3,
+            },
+            BuiltinFunc::Asorti => match args.len() {
+                1 => Code::BuiltinAsorti1,
+                2 => Code::BuiltinAsorti2,
+                _ => Code::BuiltinAsorti3,
+            },
             BuiltinFunc::Atan2 => Code::BuiltinAtan2,
             BuiltinFunc::Cos => Code::BuiltinCos,
             BuiltinFunc::Exp => Code::BuiltinExp,
@@ -566,12 +835,41 @@ impl<'a> FunctionCompiler<'a> {
             BuiltinFunc::Index => Code::BuiltinIndex,
             BuiltinFunc::Int => Code::BuiltinInt,
             BuiltinFunc::Length => {
-                if args.len() == 0 {
-                    Code::BuiltinLength0
-                } else {
-                    Code::BuiltinLength1
+                match args.first().map(|arg| self.scalar_or_array(arg)) {
+                    None => Code::BuiltinLength0,
+                    Some(ArgT::Scalar) => Code::BuiltinLength1,
+                    Some(ArgT::Array) => Code::BuiltinLengthArr,
+                    Some(ArgT::Unknown) => {
+                        self.add(Code::ConstNum { num: 0.0 });
+                        return Ok(ScalarType::Num);
+                    }
                 }
             }
+            BuiltinFunc::Isarray => {
+                let is_array = match self.scalar_or_array(&args[0]) {
+                    ArgT::Array => true,
+                    ArgT::Unknown => false,
+                    ArgT::Scalar => {
+                        // Still evaluated for its side effects
+                        self.expr(&args[0], StackT::Var)?;
+                        self.add(Code::Pop);
+                        false
+                    }
+                };
+                self.add(Code::ConstNum { num: if is_array { 1.0 } else { 0.0 } });
+                return Ok(ScalarType::Num);
+            }
+            BuiltinFunc::Typeof => {
+                match self.scalar_or_array(&args[0]) {
+                    ArgT::Scalar => {
+                        self.expr(&args[0], StackT::Var)?;
+                        self.add(Code::BuiltinTypeof);
+                    }
+                    ArgT::Array => self.add(Code::ConstStr { str: RcAwkStr::new_bytes(b"array".to_vec()) }),
+                    ArgT::Unknown => self.add(Code::ConstStr { str: RcAwkStr::new_bytes(b"untyped".to_vec()) }),
+                }
+                return Ok(ScalarType::Str);
+            }
             BuiltinFunc::Log => Code::BuiltinLog,
             BuiltinFunc::Rand => Code::BuiltinRand,
             BuiltinFunc::Sin => Code::BuiltinSin,
@@ -584,7 +882,7 @@ impl<'a> FunctionCompiler<'a> {
             }
             BuiltinFunc::Sqrt => Code::BuiltinSqrt,
             BuiltinFunc::Srand => {
-                if args.len() == 0 {
+                if args.is_empty() {
                     Code::BuiltinSrand0
                 } else {
                     Code::BuiltinSrand1
@@ -593,18 +891,83 @@ impl<'a> FunctionCompiler<'a> {
             BuiltinFunc::Tolower => Code::BuiltinTolower,
             BuiltinFunc::Toupper => Code::BuiltinToupper,
             BuiltinFunc::Matches => Code::BuiltinMatches,
+            BuiltinFunc::Gensub => {
+                if args.len() == 3 {
+                    Code::BuiltinGensub3
+                } else {
+                    Code::BuiltinGensub4
+                }
+            }
+            BuiltinFunc::Systime => Code::BuiltinSystime,
+            BuiltinFunc::Mktime => Code::BuiltinMktime,
+            BuiltinFunc::Strftime => Code::BuiltinStrftime,
+            BuiltinFunc::And => Code::BuiltinAnd,
+            BuiltinFunc::Or => Code::BuiltinOr,
+            BuiltinFunc::Xor => Code::BuiltinXor,
+            BuiltinFunc::Lshift => Code::BuiltinLshift,
+            BuiltinFunc::Rshift => Code::BuiltinRshift,
+            BuiltinFunc::Compl => Code::BuiltinCompl,
+            BuiltinFunc::Strtonum => Code::BuiltinStrtonum,
             BuiltinFunc::System => todo!("builtin System"),
             BuiltinFunc::Sprintf => todo!("builtin Sprintf"),
             BuiltinFunc::Close => todo!("builtin Close"),
         };
         let meta = code.meta(&self.typed_program.functions);
         for (idx, arg) in meta.args().iter().enumerate() {
-            self.expr(&args[idx], *arg)?;
+            match args.get(idx) {
+                Some(expr) => { self.expr(expr, *arg)?; }
+                None => self.default_builtin_arg(builtin, idx),
+            }
         }
         self.add(code);
         Ok(meta.returns().single_scalar_return_value())
     }
 
+    // Optional args that one code handles whether or not they're passed
+    fn default_builtin_arg(&mut self, builtin: BuiltinFunc, idx: usize) {
+        match (builtin, idx) {
+            (BuiltinFunc::Strftime, 0) => self.add(Code::ConstStr { str: RcAwkStr::new_bytes(DEFAULT_STRFTIME_FORMAT.as_bytes().to_vec()) }),
+            (BuiltinFunc::Strftime, 1) => self.add(Code::BuiltinSystime),
+            // Not UTC
+            (BuiltinFunc::Strftime, 2) | (BuiltinFunc::Mktime, 1) => {
+                self.add(Code::ConstNum { num: 0.0 });
+                self.add(Code::NumToVar);
+            }
+            _ => panic!("compiler bug: {} has no default for arg {}", builtin, idx),
+        }
+    }
+
+    // Whether an arg to a builtin like length(x) that takes a scalar or an array is an array.
+    // Unknown when it's a variable that's never used as either.
+    fn scalar_or_array(&self, expr: &TypedExpr) -> ArgT {
+        let name = match &expr.expr {
+            Expr::Variable(Variable::User(name)) => name,
+            _ => return ArgT::Scalar,
+        };
+        if let Some(arg) = self.parser_func.args().iter().find(|arg| arg.name == *name) {
+            return arg.typ;
+        }
+        let globals = &self.typed_program.global_analysis;
+        if globals.global_scalars.contains_key(name) {
+            ArgT::Scalar
+        } else if globals.global_arrays.contains_key(name) {
+            ArgT::Array
+        } else {
+            ArgT::Unknown
+        }
+    }
+
+    fn is_num_array(&self, name: &Symbol) -> bool {
+        let typ = match self.parser_func.elem_type(name) {
+            Some(typ) => typ,
+            None => {
+                let arr = self.typed_program.global_analysis.global_arrays.get(name).expect("compiler bug in typing pass global array not found");
+                self.typed_program.global_analysis.array_types[arr.id]
+            }
+        };
+        typ == ScalarType::Num
+    }
+
     fn push_array(&mut self, name: &Symbol) {
         if let Some(arg_idx) = self.parser_func.array_arg_idx(name) {
             self.add(Code::ArgArray { arg_idx }); // TODO: u16max
@@ -1,33 +1,32 @@
-use std::rc::Rc;
-use hashbrown::HashMap;
 use crate::compiler::function_compiler::FunctionCompiler;
 use crate::printable_error::PrintableError;
-use crate::symbolizer::Symbol;
-use crate::typing::{TypedProgram, TypedUserFunction};
+use crate::typing::TypedProgram;
 use crate::vm::VmProgram;
+use crate::Symbolizer;
 
 mod function_compiler;
 mod chunk;
+mod optimizer;
+mod superinstructions;
+mod specialize;
 
 pub use chunk::Chunk;
 
 
-#[cfg(test)]
 mod program_validator;
 
-#[cfg(test)]
 pub use crate::compiler::program_validator::validate_program;
+pub use specialize::DEFAULT_SPECIALIZATION_LIMIT;
 
-pub fn compile(mut program: TypedProgram) -> Result<VmProgram, PrintableError> {
-    let mut functions = vec![];
+// specialization_limit is the max number of clones of each user function, 0 disables specialization
+pub fn compile(mut program: TypedProgram, symbolizer: &mut Symbolizer, specialization_limit: usize) -> Result<VmProgram, PrintableError> {
+    specialize::specialize(&mut program, symbolizer, specialization_limit);
 
-    // TODO avoid this clone
-    let funcs: Vec<(Symbol, Rc<TypedUserFunction>)> = program.functions
-        .user_functions_iter()
-        .map(|(name,func)| (name.clone(), func.clone())).
-        collect();
-    for (_name, function) in funcs {
-        let compiler = FunctionCompiler::new(&mut program, function.clone());
+    // Compiled in order of id so each VmFunc's index is its id
+    let mut functions = vec![];
+    for id in 0..program.functions.len() {
+        let function = program.functions.get_by_id(id).unwrap().clone();
+        let compiler = FunctionCompiler::new(&mut program, function);
         functions.push(compiler.compile()?);
     }
     let prog = VmProgram::new(functions, program.global_analysis, program.functions);
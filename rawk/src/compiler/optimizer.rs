@@ -0,0 +1,249 @@
+use hashbrown::HashSet;
+use crate::awk_str::RcAwkStr;
+use crate::runtime::converter::Converter;
+use crate::vm::{op_helpers, Code, Label};
+
+// Peephole optimizations over a function's bytecode. Runs before labels are resolved so ops can
+// be removed without fixing up jump offsets. Every rewrite removes at least one op so passes
+// are repeated until the code stops shrinking.
+pub fn optimize(code: &mut Vec<Code>) {
+    let mut converter = Converter::new();
+    loop {
+        let len = code.len();
+        fold(code, &mut converter);
+        remove_dead_code(code);
+        remove_jumps_to_next(code);
+        if code.len() == len {
+            break;
+        }
+    }
+}
+
+// Each op is pushed onto the output and then the tail of the output is reduced for as long as
+// it matches a pattern. Labels are never part of a pattern so nothing is folded across a
+// point another jump could land on.
+fn fold(code: &mut Vec<Code>, converter: &mut Converter) {
+    let mut out: Vec<Code> = Vec::with_capacity(code.len());
+    for op in code.drain(..) {
+        out.push(op);
+        while reduce(&mut out, converter) {}
+    }
+    *code = out;
+}
+
+fn reduce(out: &mut Vec<Code>, converter: &mut Converter) -> bool {
+    let len = out.len();
+    let replacement: Vec<Code> = match &out[len.saturating_sub(3)..] {
+        [Code::ConstNum { num: lhs }, Code::ConstNum { num: rhs }, op] => {
+            if let Some(num) = math(op, *lhs, *rhs) {
+                vec![Code::ConstNum { num }]
+            } else if let Some(res) = compare(op, *lhs, *rhs) {
+                vec![Code::ConstNum { num: if res { 1.0 } else { 0.0 } }]
+            } else {
+                return reduce_pair(out, converter);
+            }
+        }
+        [.., Code::Concat { count }] => return fold_concat(out, *count),
+        _ => return reduce_pair(out, converter),
+    };
+    out.truncate(len - 3);
+    out.extend(replacement);
+    true
+}
+
+fn reduce_pair(out: &mut Vec<Code>, converter: &mut Converter) -> bool {
+    let len = out.len();
+    if len < 2 {
+        return false;
+    }
+    let replacement: Vec<Code> = match &out[len - 2..] {
+        // Only integers convert to the same string no matter what CONVFMT is
+        [Code::ConstNum { num }, Code::NumToStr] if num.fract() == 0.0 => {
+            let str = RcAwkStr::new_bytes(converter.num_to_str_internal(*num).to_vec());
+            vec![Code::ConstStr { str }]
+        }
+        [Code::ConstStr { str }, Code::StrToNum] | [Code::ConstStrNum { strnum: str }, Code::StrToNum] => {
+            vec![Code::ConstNum { num: converter.str_to_num(str).unwrap_or(0.0) }]
+        }
+        [Code::ConstNum { .. }, Code::PopNum] => vec![],
+        [Code::ConstStr { .. }, Code::PopStr] => vec![],
+        [Code::ConstNum { num }, Code::JumpIfFalseNumLbl(lbl)] => {
+            if *num == 0.0 { vec![Code::JumpLbl(*lbl)] } else { vec![] }
+        }
+        [Code::ConstNum { num }, Code::JumpIfTrueNumLbl(lbl)] => {
+            if *num != 0.0 { vec![Code::JumpLbl(*lbl)] } else { vec![] }
+        }
+        // Round trips through the Var stack. NumToStr; StrToNum is not one of these, the number
+        // is formatted with CONVFMT so the result can differ from the original.
+        [Code::NumToVar, Code::VarToNum] => vec![],
+        [Code::StrToVar, Code::VarToStr] => vec![],
+        [Code::NumToVar, Code::VarToStr] => vec![Code::NumToStr],
+        [Code::StrToVar, Code::VarToNum] => vec![Code::StrToNum],
+        _ => return false,
+    };
+    out.truncate(len - 2);
+    out.extend(replacement);
+    true
+}
+
+// Concat pops its first operand first so the constants right before it are its leading
+// operands, in reverse. Merge them into one.
+fn fold_concat(out: &mut Vec<Code>, count: usize) -> bool {
+    let concat_idx = out.len() - 1;
+    let consts = out[..concat_idx].iter().rev()
+        .take(count)
+        .take_while(|op| matches!(op, Code::ConstStr { .. }))
+        .count();
+    if consts < 2 {
+        return false;
+    }
+    let mut merged = vec![];
+    for op in out[concat_idx - consts..concat_idx].iter().rev() {
+        if let Code::ConstStr { str } = op {
+            merged.extend_from_slice(str);
+        }
+    }
+    out.truncate(concat_idx - consts);
+    out.push(Code::ConstStr { str: RcAwkStr::new_bytes(merged) });
+    let count = count - consts + 1;
+    if count > 1 {
+        out.push(Code::Concat { count });
+    }
+    true
+}
+
+fn math(op: &Code, lhs: f64, rhs: f64) -> Option<f64> {
+    Some(match op {
+        Code::Add => op_helpers::add(lhs, rhs),
+        Code::Minus => op_helpers::minus(lhs, rhs),
+        Code::Mult => op_helpers::mult(lhs, rhs),
+        Code::Div => op_helpers::div(lhs, rhs),
+        Code::Mod => op_helpers::modulo(lhs, rhs),
+        Code::Exp => op_helpers::exp(lhs, rhs),
+        _ => return None,
+    })
+}
+
+fn compare(op: &Code, lhs: f64, rhs: f64) -> Option<bool> {
+    Some(match op {
+        Code::LtNum => op_helpers::lt(lhs, rhs),
+        Code::GtNum => op_helpers::gt(lhs, rhs),
+        Code::LtEqNum => op_helpers::lteq(lhs, rhs),
+        Code::GtEqNum => op_helpers::gteq(lhs, rhs),
+        Code::EqEqNum => op_helpers::eq(lhs, rhs),
+        Code::NeqNum => op_helpers::neq(lhs, rhs),
+        _ => return None,
+    })
+}
+
+// Drops labels nothing jumps to and ops after a jump or return that no label makes reachable
+// again. This is the code after break and return statements and the branches constant
+// conditions never take.
+fn remove_dead_code(code: &mut Vec<Code>) {
+    let targets: HashSet<Label> = code.iter().filter_map(Code::jump_label).collect();
+    let mut reachable = true;
+    code.retain(|op| {
+        if let Code::Label(lbl) = op {
+            if targets.contains(lbl) {
+                reachable = true;
+                return true;
+            }
+            return false;
+        }
+        if !reachable {
+            return false;
+        }
+        if matches!(op, Code::JumpLbl(_)) || op.is_ret() {
+            reachable = false;
+        }
+        true
+    });
+}
+
+// `if` without an else jumps over nothing, a jump to the labels right after it can be dropped
+fn remove_jumps_to_next(code: &mut Vec<Code>) {
+    let mut out: Vec<Code> = Vec::with_capacity(code.len());
+    for (idx, op) in code.iter().enumerate() {
+        if let Code::JumpLbl(target) = op {
+            let mut next_labels = code[idx + 1..].iter().take_while(|op| matches!(op, Code::Label(_)));
+            if next_labels.any(|op| *op == Code::Label(*target)) {
+                continue;
+            }
+        }
+        out.push(op.clone());
+    }
+    *code = out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::{compile, validate_program};
+    use crate::vm::{Code, VmProgram};
+    use crate::{analyze, lex, parse, Symbolizer};
+
+    fn compile_prog(program: &str) -> VmProgram {
+        let mut symbolizer = Symbolizer::new();
+        let ast = analyze(parse(lex(program, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
+        let prog = compile(ast, &mut symbolizer, 0).unwrap();
+        validate_program(&prog).unwrap();
+        prog
+    }
+
+    fn main_chunk(program: &str) -> Vec<Code> {
+        let prog = compile_prog(program);
+        let main = prog.functions.iter().find(|f| f.name().to_str() == "main function").unwrap();
+        main.chunk().to_vec()
+    }
+
+    fn has_str(chunk: &[Code], expected: &str) -> bool {
+        chunk.iter().any(|c| matches!(c, Code::ConstStr { str } if str.bytes() == expected.as_bytes()))
+    }
+
+    #[test]
+    fn test_fold_math() {
+        let chunk = main_chunk("BEGIN { x = (1 + 2) * 3 - 4 / 2 ^ 2 % 5 }");
+        assert!(chunk.contains(&Code::ConstNum { num: 8.0 }));
+        assert!(!chunk.iter().any(|c| matches!(c, Code::Add | Code::Mult | Code::Minus | Code::Div | Code::Exp | Code::Mod)));
+    }
+
+    #[test]
+    fn test_fold_compare_and_branch() {
+        let chunk = main_chunk("BEGIN { if (1 < 2) { x = \"yes\" } else { x = \"no\" } }");
+        assert!(!chunk.iter().any(|c| matches!(c, Code::LtNum | Code::RelJumpIfFalseNum { .. } | Code::RelJump { .. })));
+        assert!(!has_str(&chunk, "no"));
+    }
+
+    #[test]
+    fn test_fold_concat() {
+        let chunk = main_chunk("BEGIN { x = \"a\" \"b\" 1; y = \"c\" \"d\" $1 }");
+        assert!(has_str(&chunk, "ab1"));
+        assert!(has_str(&chunk, "cd"));
+        assert_eq!(chunk.iter().filter(|c| matches!(c, Code::Concat { count: 2 })).count(), 1);
+    }
+
+    #[test]
+    fn test_no_fold_non_integer_to_str() {
+        let chunk = main_chunk("BEGIN { x = 0.1234567 \"\" }");
+        assert!(chunk.contains(&Code::NumToStr));
+    }
+
+    #[test]
+    fn test_remove_after_break_and_return() {
+        let prog = compile_prog("function f() { return 1; print \"dead\" } BEGIN { while (1) { break; print \"dead\" } print f() }");
+        for func in prog.functions.iter() {
+            assert!(!has_str(func.chunk(), "dead"));
+        }
+    }
+
+    #[test]
+    fn test_validates() {
+        for prog in [
+            "BEGIN { while (x < 10) { x++; if (x == 5) { break; } } print x }",
+            "BEGIN { while (1) { if (x++ > 3) { break } } print (0 && x) (1 || x) (x ? 1 : 2) }",
+            "function f(a) { if (a) { return 1 } else { return 2 } } BEGIN { print f(0) f(1) }",
+            "{ print $1 } END { print 1 + 2 \"x\" }",
+        ] {
+            compile_prog(prog);
+        }
+    }
+}
@@ -1,41 +1,25 @@
-use std::fmt::{Debug, Formatter};
+use crate::printable_error::PrintableError;
 use crate::stack_counter::StackCounter;
-use crate::vm::{VmFunc, VmProgram};
+use crate::stackt::StackT;
+use crate::vm::{Code, VmFunc, VmProgram};
 
-// Validates that each function has a net +1 (return value) effect on the scalar stack
-// and a net 0 effect on the array stack. Also validates stack heights are consistent
-// regardless of how you reach a given ip (instruction pointer).
-// Panics if program is invalid.
-pub fn validate_program(prog: &VmProgram) {
+// Validates that every op's operands exist (global ids, args, call targets), that no op pops
+// from an empty stack, that each function returns its return type, and that stack heights are
+// consistent regardless of how you reach a given ip (instruction pointer).
+// Run on every precompiled program when it's loaded and on compiled programs with --debug.
+pub fn validate_program(prog: &VmProgram) -> Result<(), PrintableError> {
     for func in &prog.functions {
-        let mut validator = FunctionValidator::new(&func, &prog);
-        validator.validate()
-    }
-}
-
-// Records the scalar stack and array stack height at a given
-// ip. This should not change based on how you reach an ip.
-#[derive(Copy, Clone)]
-struct StackHeights {
-    ip: usize,
-    stacks: StackCounter,
-}
-
-impl Debug for StackHeights {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\nip:{:2} {}", self.ip, self.stacks)
-    }
-}
-
-impl PartialEq for StackHeights {
-    fn eq(&self, other: &Self) -> bool {
-        self.stacks == other.stacks
+        let mut validator = FunctionValidator::new(func, prog);
+        if let Err(msg) = validator.validate() {
+            return Err(PrintableError::new(format!("invalid bytecode in function `{}` {}", func.name(), msg)));
+        }
     }
+    Ok(())
 }
 
 struct FunctionValidator<'a> {
     // Stack height when a given ip is reached but before it executes
-    stack_heights: Vec<Option<StackHeights>>,
+    stack_heights: Vec<Option<StackCounter>>,
     func: &'a VmFunc,
     prog: &'a VmProgram,
 }
@@ -43,44 +27,95 @@ struct FunctionValidator<'a> {
 impl<'a> FunctionValidator<'a> {
     pub fn new(func: &'a VmFunc, prog: &'a VmProgram) -> Self {
         Self {
-            stack_heights: (0..func.chunk().len()).map(|_| None).collect(),
+            stack_heights: vec![None; func.chunk().len()],
             func,
             prog,
         }
     }
-    pub fn validate(&mut self) {
-        let init = vec![StackHeights { ip: 0, stacks: StackCounter::new() }];
-        self.validate_rec(0, &init);
-    }
 
-    fn validate_rec(&mut self, ip: usize, history: &Vec<StackHeights>) {
-
-        println!("ip: {}", ip);
-        // If we've been at this ip before make sure height match
-        let stack_heights = history.last().unwrap();
-        if let Some(existing) = self.stack_heights[ip] {
-            assert_eq!(existing, *stack_heights, "Stack height do not match at ip {} in func {}. \nExpected: {:?} Found {:?}\nHistory: {:?}", ip, self.func.name(), existing, stack_heights, history);
-            return;
-        } else {
-            self.stack_heights[ip] = Some(*stack_heights);
-        }
-        let side_effect = self.func.chunk()[ip].meta(&self.prog.func_map);
-        if side_effect.is_ret() {
-            return;
+    pub fn validate(&mut self) -> Result<(), String> {
+        let chunk = self.func.chunk();
+        if chunk.is_empty() {
+            return Err("has no bytecode".to_string());
         }
+        // Iterative so long functions don't overflow the stack
+        let mut pending = vec![(0, StackCounter::new())];
+        while let Some((ip, stacks)) = pending.pop() {
+            // If we've been at this ip before make sure heights match
+            if let Some(existing) = self.stack_heights[ip] {
+                if existing != stacks {
+                    return Err(format!("stack heights do not match at ip {}. Expected: {} Found: {}", ip, existing, stacks));
+                }
+                continue;
+            }
+            self.stack_heights[ip] = Some(stacks);
 
-        // Add this element to the history
-        let mut history = history.clone();
-        let mut next = stack_heights.clone();
-        next.stacks.sub(&side_effect.args());
-        next.stacks.add(&side_effect.returns());
-        next.ip = ip;
-        history.push(next);
+            let code = &chunk[ip];
+            self.check_operands(code).map_err(|msg| format!("at ip {}: {}", ip, msg))?;
+            let meta = code.meta(&self.prog.func_map);
+            let mut next = stacks;
+            for stack in meta.args() {
+                if next.count(*stack) == 0 {
+                    return Err(format!("at ip {}: {:?} pops from an empty {:?} stack", ip, code, stack));
+                }
+                next.sub(&[*stack]);
+            }
+            if meta.is_ret() {
+                if *code != Code::ret(self.func.return_type()) {
+                    return Err(format!("at ip {}: {:?} does not match the return type {:?}", ip, code, self.func.return_type()));
+                }
+                continue;
+            }
+            next.add(meta.returns());
+
+            for descendant in meta.descendants() {
+                let new_ip = (ip as isize) + descendant;
+                if !(0..chunk.len() as isize).contains(&new_ip) {
+                    return Err(format!("at ip {}: jump outside of chunk", ip));
+                }
+                pending.push((new_ip as usize, next));
+            }
+        }
+        Ok(())
+    }
 
-        for descendant in side_effect.descendants() {
-            let new_ip = (ip as isize) + descendant;
-            assert!((0..self.func.chunk().len() as isize).contains(&new_ip), "jump outside of chunk at ip {}", ip);
-            self.validate_rec(new_ip as usize, &history);
+    // Ids and arg indices must exist before the vm indexes with them
+    fn check_operands(&self, code: &Code) -> Result<(), String> {
+        let analysis = &self.prog.analysis;
+        let arg_stacks = self.func.arg_stacks();
+        let (id, count, what) = match code {
+            Code::ClearGscl(id)
+            | Code::AssignGsclVar(id) | Code::AssignGsclNum(id) | Code::AssignGsclStr(id)
+            | Code::AssignRetGsclVar(id) | Code::AssignRetGsclNum(id) | Code::AssignRetGsclStr(id)
+            | Code::GsclVar(id) | Code::GsclNum(id) | Code::GsclStr(id)
+            | Code::IncGscl { id, .. } | Code::AddGsclNum { id, .. } => (id.id, analysis.global_scalars.len(), "global scalar"),
+            Code::GlobalArr(id) => (id.id, analysis.global_arrays.len(), "global array"),
+            // Assignments are to Var args, the suffix is the type of the value assigned
+            Code::ClearArgScl(arg_idx)
+            | Code::AssignArgVar { arg_idx } | Code::AssignArgStr { arg_idx } | Code::AssignArgNum { arg_idx }
+            | Code::AssignRetArgVar { arg_idx } | Code::AssignRetArgStr { arg_idx } | Code::AssignRetArgNum { arg_idx }
+            | Code::ArgVar { arg_idx } | Code::IncArg { arg_idx, .. } => (*arg_idx, arg_stacks.count(StackT::Var), "var arg"),
+            Code::ArgStr { arg_idx } => (*arg_idx, arg_stacks.count(StackT::Str), "str arg"),
+            Code::ArgNum { arg_idx } => (*arg_idx, arg_stacks.count(StackT::Num), "num arg"),
+            Code::ArgArray { arg_idx } => (*arg_idx, arg_stacks.count(StackT::Array), "array arg"),
+            Code::Call { target, args } => {
+                return match self.prog.functions.get(*target) {
+                    None => Err(format!("call to function {} which does not exist", target)),
+                    Some(func) if *args > func.args().len() => Err(format!("call passes {} args to `{}` which takes {}", args, func.name(), func.args().len())),
+                    Some(_) => Ok(()),
+                };
+            }
+            Code::CallNative { id } => {
+                return match self.prog.func_map.native_by_id(*id) {
+                    None => Err(format!("call to native function {} which is not registered", id)),
+                    Some(_) => Ok(()),
+                };
+            }
+            _ => return Ok(()),
+        };
+        if id >= count {
+            return Err(format!("{:?} uses {} {} but there are only {}", code, what, id, count));
         }
+        Ok(())
     }
 }
@@ -0,0 +1,197 @@
+use std::rc::Rc;
+use hashbrown::HashMap;
+use crate::parser::{walk_stmt, ArgT, Expr, LValue, ScalarType, TypedExpr, Variable};
+use crate::symbolizer::Symbol;
+use crate::typing::{return_type_pass, ITypedFunction, TypedProgram, TypedUserFunction};
+use crate::Symbolizer;
+
+// Clone user functions once per distinct signature of scalar arg types they are called with.
+//
+// Typing widens a scalar arg to Var as soon as it is used as both a string and a number so every
+// call converts its args to Var and every read converts them back. A clone instead takes each arg
+// on the stack of the type it's called with and compiles to its own fully typed VmFunc. Only args
+// the function never assigns to are specialized, others remain Var. At most `limit` clones are
+// made per function, calls with any other signature use the original.
+
+pub const DEFAULT_SPECIALIZATION_LIMIT: usize = 4;
+
+struct Specializations {
+    // For each arg, true if it's a scalar the function never assigns to
+    read_only: Vec<bool>,
+    // Signature of each clone and its name
+    clones: Vec<(Vec<ScalarType>, Symbol)>,
+}
+
+impl Specializations {
+    fn signature(&self, args: &[TypedExpr]) -> Vec<ScalarType> {
+        self.read_only.iter().enumerate().map(|(idx, read_only)| {
+            match args.get(idx) {
+                Some(arg) if *read_only => arg.typ,
+                _ => ScalarType::Var,
+            }
+        }).collect()
+    }
+    fn clone_for(&self, args: &[TypedExpr]) -> Option<Symbol> {
+        let signature = self.signature(args);
+        self.clones.iter().find(|(sig, _name)| *sig == signature).map(|(_sig, name)| name.clone())
+    }
+}
+
+pub fn specialize(program: &mut TypedProgram, symbolizer: &mut Symbolizer, limit: usize) {
+    if limit == 0 {
+        return;
+    }
+    let originals: Vec<Rc<TypedUserFunction>> = (0..program.functions.len())
+        .map(|id| program.functions.get_by_id(id).unwrap().clone())
+        .collect();
+
+    let mut specializations: HashMap<Symbol, Specializations> = HashMap::new();
+    for func in originals.iter().filter(|func| !func.is_main()) {
+        let read_only = read_only_args(func);
+        if read_only.contains(&true) {
+            specializations.insert(func.name(), Specializations { read_only, clones: vec![] });
+        }
+    }
+    if specializations.is_empty() {
+        return;
+    }
+
+    // Collect signatures in the order calls appear
+    for func in originals.iter() {
+        walk_stmt(&mut func.function().body, &mut |expr| {
+            if let Expr::Call { target, args } = &expr.expr {
+                if let Some(spec) = specializations.get_mut(target) {
+                    let signature = spec.signature(args);
+                    if spec.clones.len() < limit
+                        && signature.iter().any(|typ| *typ != ScalarType::Var)
+                        && !spec.clones.iter().any(|(sig, _name)| *sig == signature) {
+                        let name = symbolizer.get(&format!("{}<{}>", target, signature_str(&signature)));
+                        spec.clones.push((signature, name));
+                    }
+                }
+            }
+        });
+    }
+
+    // Clone before any calls are rewritten so each clone starts from the original body
+    let mut clones = vec![];
+    for func in originals.iter() {
+        if let Some(spec) = specializations.get(&func.name()) {
+            for (signature, name) in spec.clones.iter() {
+                let clone = Rc::new(func.specialize(name.clone(), signature.clone()));
+                retype(&clone);
+                program.functions.insert(clone.clone());
+                clones.push(clone);
+            }
+        }
+    }
+
+    for func in originals.iter().chain(clones.iter()) {
+        walk_stmt(&mut func.function().body, &mut |expr| {
+            if let Expr::Call { target, args } = &mut expr.expr {
+                if let Some(clone) = specializations.get(target).and_then(|spec| spec.clone_for(args)) {
+                    *target = clone;
+                }
+            }
+        });
+    }
+
+    // Clones may return narrower types than the functions they were cloned from
+    return_type_pass(&program.functions);
+}
+
+fn signature_str(signature: &[ScalarType]) -> String {
+    let types: Vec<&str> = signature.iter().map(|typ| match typ {
+        ScalarType::Str => "s",
+        ScalarType::Num => "n",
+        ScalarType::Var => "v",
+    }).collect();
+    types.join(",")
+}
+
+fn read_only_args(func: &TypedUserFunction) -> Vec<bool> {
+    let names: Vec<Symbol> = func.user_func_args().iter().map(|arg| arg.name.clone()).collect();
+    let mut read_only: Vec<bool> = func.user_func_args().iter().map(|arg| arg.typ == ArgT::Scalar).collect();
+    walk_stmt(&mut func.function().body, &mut |expr| {
+        let assigned = match &expr.expr {
+            Expr::ScalarAssign(Variable::User(name), _value) => name,
+            Expr::IncDec { lvalue: LValue::Variable(Variable::User(name)), .. } => name,
+            Expr::CallSub { string: LValue::Variable(Variable::User(name)), .. } => name,
+            Expr::Getline { var: Some(LValue::Variable(Variable::User(name))), .. } => name,
+            _ => return,
+        };
+        if let Some(idx) = names.iter().position(|arg| arg == assigned) {
+            read_only[idx] = false;
+        }
+    });
+    read_only
+}
+
+// Re-type a clone's body now that its args have types. Only expressions typed by their
+// children can change, everything else was typed without looking at args.
+fn retype(func: &TypedUserFunction) {
+    let args: Vec<(Symbol, ScalarType)> = func.user_func_args().iter()
+        .zip(func.arg_types().iter())
+        .filter(|(arg, _typ)| arg.typ == ArgT::Scalar)
+        .map(|(arg, typ)| (arg.name.clone(), *typ))
+        .collect();
+    walk_stmt(&mut func.function().body, &mut |expr| {
+        expr.typ = match &expr.expr {
+            Expr::Variable(Variable::User(name)) => {
+                match args.iter().find(|(arg, _typ)| arg == name) {
+                    Some((_arg, typ)) => *typ,
+                    None => return,
+                }
+            }
+            Expr::Ternary(_cond, if_so, if_not) => {
+                if if_so.typ == if_not.typ { if_so.typ } else { ScalarType::Var }
+            }
+            Expr::ScalarAssign(_var, value) => value.typ,
+            Expr::ArrayAssign { value, .. } => value.typ,
+            _ => return,
+        };
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::compile;
+    use crate::vm::{Cmp, Code, VmProgram};
+    use crate::{analyze, lex, parse, Symbolizer};
+
+    fn compile_prog(program: &str, limit: usize) -> VmProgram {
+        let mut symbolizer = Symbolizer::new();
+        let ast = analyze(parse(lex(program, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
+        compile(ast, &mut symbolizer, limit).unwrap()
+    }
+
+    fn names(prog: &VmProgram) -> Vec<String> {
+        let mut names: Vec<String> = prog.functions.iter().map(|f| f.name().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    const MAX: &str = "function max(a, b) { return a > b ? a : b } BEGIN { print max(1, 2); print max(\"a\", \"b\"); print max(1, 2) }";
+
+    #[test]
+    fn test_clone_per_signature() {
+        let prog = compile_prog(MAX, 4);
+        assert_eq!(names(&prog), vec!["main function", "max", "max<n,n>", "max<s,s>"]);
+        let num = prog.functions.iter().find(|f| f.name().to_str() == "max<n,n>").unwrap();
+        assert!(num.chunk().iter().any(|c| matches!(c, Code::ArgNum { .. })));
+        assert!(num.chunk().iter().any(|c| matches!(c, Code::RelJumpIfCmpNum { cmp: Cmp::Gt, .. })));
+        assert!(num.chunk().iter().any(|c| matches!(c, Code::RetNum)));
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(names(&compile_prog(MAX, 1)), vec!["main function", "max", "max<n,n>"]);
+        assert_eq!(names(&compile_prog(MAX, 0)), vec!["main function", "max"]);
+    }
+
+    #[test]
+    fn test_assigned_args_stay_var() {
+        let prog = compile_prog("function f(a, b) { a = 1; return a b } BEGIN { print f(1, 2); print f(\"x\", 2) }", 4);
+        assert_eq!(names(&prog), vec!["f", "f<v,n>", "main function"]);
+    }
+}
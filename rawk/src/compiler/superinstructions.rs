@@ -0,0 +1,177 @@
+use crate::vm::{Cmp, Code};
+
+// Fuse common sequences of ops into superinstructions so they cost one dispatch instead of
+// several. The set comes from counting adjacent pairs of ops executed by the integration tests:
+//   cargo test -p rawk --features op_profile integration -- --test-threads 1 --nocapture
+// prints the running totals after each program (ops are left unfused in that build). Besides the
+// string pairs from one concat heavy loop test the most frequent pairs were
+//   GsclNum ConstNum, ConstNum Add, Add AssignGsclNum      x = x + 1 and x += 1
+//   LtNum RelJumpIfTrueNum, Gt RelJumpIfFalseNum           loop and if conditions
+//   ConstNum Column, Column StrToVar                       $1
+// Column StrToNum ($1 + 0 or any field used as a number) is fused as well, it's rare in the tests
+// but common in real programs and skips allocating a string for the field.
+//
+// Runs on labeled code after the optimizer. A label between two ops stops them from being fused
+// so no jump can land in the middle of a superinstruction.
+
+// Tests turn fusing off for their thread to check superinstructions don't change any output
+#[cfg(test)]
+thread_local! {
+    static DISABLED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Left unfused when profiling so the counts show which pairs are worth fusing
+pub fn enabled() -> bool {
+    #[cfg(test)]
+    if DISABLED.with(|disabled| disabled.get()) {
+        return false;
+    }
+    !cfg!(feature = "op_profile")
+}
+
+pub fn fuse(code: &mut Vec<Code>) {
+    let mut out: Vec<Code> = Vec::with_capacity(code.len());
+    for op in code.drain(..) {
+        out.push(op);
+        while fuse_tail(&mut out) {}
+    }
+    *code = out;
+}
+
+fn fuse_tail(out: &mut Vec<Code>) -> bool {
+    let len = out.len();
+    if len >= 3 {
+        if let [Code::GsclNum(id), Code::AddConst { num }, Code::AssignGsclNum(assign_id)] = &out[len - 3..] {
+            let delta = *num as f32;
+            if id == assign_id && delta as f64 == *num && u32::try_from(id.id).is_ok() {
+                let fused = Code::AddGsclNum { id: *id, delta };
+                out.truncate(len - 3);
+                out.push(fused);
+                return true;
+            }
+        }
+    }
+    if len < 2 {
+        return false;
+    }
+    let fused = match &out[len - 2..] {
+        [Code::Column, Code::StrToNum] => Code::ColumnNum,
+        [Code::ConstNum { num }, Code::Column] => Code::ConstColumn { idx: num.round() as usize },
+        [Code::ConstColumn { idx }, Code::StrToNum] => Code::ConstColumnNum { idx: *idx },
+        [Code::ConstNum { num }, Code::Add] => Code::AddConst { num: *num },
+        [Code::ConstNum { num }, Code::Minus] => Code::AddConst { num: -*num },
+        [cmp, jump] => {
+            let (jump_if, label) = match jump {
+                Code::JumpIfTrueNumLbl(label) => (true, *label),
+                Code::JumpIfFalseNumLbl(label) => (false, *label),
+                _ => return false,
+            };
+            if let Some(cmp) = num_cmp(cmp) {
+                Code::JumpIfCmpNumLbl { cmp, jump_if, label }
+            } else if let Some(cmp) = var_cmp(cmp) {
+                Code::JumpIfCmpVarLbl { cmp, jump_if, label }
+            } else {
+                return false;
+            }
+        }
+        _ => return false,
+    };
+    out.truncate(len - 2);
+    out.push(fused);
+    true
+}
+
+fn num_cmp(op: &Code) -> Option<Cmp> {
+    Some(match op {
+        Code::LtNum => Cmp::Lt,
+        Code::GtNum => Cmp::Gt,
+        Code::LtEqNum => Cmp::LtEq,
+        Code::GtEqNum => Cmp::GtEq,
+        Code::EqEqNum => Cmp::EqEq,
+        Code::NeqNum => Cmp::Neq,
+        _ => return None,
+    })
+}
+
+fn var_cmp(op: &Code) -> Option<Cmp> {
+    Some(match op {
+        Code::Lt => Cmp::Lt,
+        Code::Gt => Cmp::Gt,
+        Code::LtEq => Cmp::LtEq,
+        Code::GtEq => Cmp::GtEq,
+        Code::EqEq => Cmp::EqEq,
+        Code::Neq => Cmp::Neq,
+        _ => return None,
+    })
+}
+
+// Fusing is off in op_profile builds
+#[cfg(all(test, not(feature = "op_profile")))]
+mod tests {
+    use crate::compiler::{compile, validate_program};
+    use crate::compiler::superinstructions::DISABLED;
+    use crate::test::run_rawk;
+    use crate::vm::{Cmp, Code};
+    use crate::{analyze, lex, parse, Symbolizer};
+
+    fn main_chunk(program: &str) -> Vec<Code> {
+        let mut symbolizer = Symbolizer::new();
+        let ast = analyze(parse(lex(program, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
+        let prog = compile(ast, &mut symbolizer, 0).unwrap();
+        validate_program(&prog).unwrap();
+        let main = prog.functions.iter().find(|f| f.name().to_str() == "main function").unwrap();
+        main.chunk().to_vec()
+    }
+
+    #[test]
+    fn test_add_gscl() {
+        let chunk = main_chunk("BEGIN { x = y = z = 0; while (x < 10) { x = x + 1; y += 0.5; z -= 2 } print x y z }");
+        assert_eq!(chunk.iter().filter(|c| matches!(c, Code::AddGsclNum { .. })).count(), 3);
+        assert!(chunk.iter().any(|c| matches!(c, Code::AddGsclNum { delta, .. } if *delta == -2.0)));
+    }
+
+    #[test]
+    fn test_add_gscl_inexact() {
+        let chunk = main_chunk("BEGIN { x = 0; x = x + 0.1; print x }");
+        assert!(!chunk.iter().any(|c| matches!(c, Code::AddGsclNum { .. })));
+        assert!(chunk.contains(&Code::AddConst { num: 0.1 }));
+    }
+
+    #[test]
+    fn test_compare_and_branch() {
+        let chunk = main_chunk("BEGIN { x = 0; while (x < 10) { x++ } if ($1 > \"a\") { print 1 } }");
+        assert!(chunk.iter().any(|c| matches!(c, Code::RelJumpIfCmpNum { cmp: Cmp::Lt, jump_if: true, .. })));
+        assert!(chunk.iter().any(|c| matches!(c, Code::RelJumpIfCmpVar { cmp: Cmp::Gt, jump_if: false, .. })));
+    }
+
+    #[test]
+    fn test_columns() {
+        let chunk = main_chunk("{ x = $2 + 1; y = $n + 1; z = $3; print z }");
+        assert!(chunk.contains(&Code::ConstColumnNum { idx: 2 }));
+        assert!(chunk.contains(&Code::ColumnNum));
+        assert!(chunk.contains(&Code::ConstColumn { idx: 3 }));
+        assert!(!chunk.contains(&Code::Column));
+    }
+
+    #[test]
+    fn test_fused_matches_unfused() {
+        let prog = "BEGIN { n = 0 } { n = n + 1; s += $2 + $3; if ($1 < $2) c++; if ($3 > \"5\") d++ } END { print n \" \" s \" \" c \" \" d }";
+        let chunk = main_chunk(prog);
+        assert!(chunk.iter().any(|c| matches!(c, Code::AddGsclNum { .. })));
+        assert!(chunk.iter().any(|c| matches!(c, Code::ConstColumnNum { .. })));
+        assert!(chunk.iter().any(|c| matches!(c, Code::RelJumpIfCmpVar { .. })));
+
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data");
+        std::fs::write(&data, "1 2 3\n5 4 6\n7 8 x\n10 9 10\n").unwrap();
+        let args = [prog, data.to_str().unwrap()];
+        let fused = run_rawk(&args).unwrap();
+        DISABLED.with(|disabled| disabled.set(true));
+        let unfused = run_rawk(&args);
+        let unfused_chunk = main_chunk(prog);
+        DISABLED.with(|disabled| disabled.set(false));
+        assert!(!unfused_chunk.iter().any(|c| matches!(c, Code::AddGsclNum { .. } | Code::ConstColumnNum { .. } | Code::RelJumpIfCmpVar { .. })));
+        assert_eq!(fused, unfused.unwrap());
+        assert_eq!(fused.0, b"4 42 2 2\n");
+    }
+}
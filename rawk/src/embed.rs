@@ -0,0 +1,495 @@
+// Running awk programs from rust without shelling out to rawk.
+//
+//     let program = Program::compile("{ total += $2 } END { print total }", Options::new())?;
+//     let mut finished = program.run(vec![Input::bytes("sales", "a 1\nb 2\n")], vec![])?;
+//     assert_eq!(finished.output, b"3\n");
+//     assert_eq!(finished.scalar("total"), Some(Value::Num(3.0)));
+//
+// Rust functions can be made callable from awk with Options::native
+//
+//     let options = Options::new().native("hash", &[NativeArgT::Str], ScalarType::Num, |args| {
+//         Value::Num(my_hash(args.str(0)) as f64)
+//     });
+
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::rc::Rc;
+use crate::awk_str::{AwkStr, RcAwkStr};
+use crate::compiler::{compile, DEFAULT_SPECIALIZATION_LIMIT};
+use crate::lexer::lex_spanned;
+use crate::parser::{parse_spanned, ScalarType, SclSpecial};
+use crate::printable_error::{PrintableError, Span};
+use crate::runtime::arrays::{ArrayKey, Arrays};
+use crate::runtime::Input;
+use crate::symbolizer::Symbolizer;
+use crate::typing::{analyze_with_natives, BuiltinFunc, GlobalArrayId, NativeArgT, NativeFunc};
+use crate::vm::{RuntimeScalar, StringScalar, VirtualMachine, VmProgram};
+use crate::assign_variables;
+
+pub struct Options {
+    specialization_limit: usize,
+    variables: Vec<(String, AwkStr)>,
+    natives: Vec<(NativeFunc, NativeFn)>,
+    csv: bool,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Options { specialization_limit: DEFAULT_SPECIALIZATION_LIMIT, variables: vec![], natives: vec![], csv: false }
+    }
+
+    // Same as --csv
+    pub fn csv(mut self) -> Self {
+        self.csv = true;
+        self
+    }
+
+    // Same as --specialize=limit
+    pub fn specialization_limit(mut self, limit: usize) -> Self {
+        self.specialization_limit = limit;
+        self
+    }
+
+    // Set before BEGIN on every run like -v name=value. The value is used as is, escapes are not processed.
+    pub fn variable<N: Into<String>, V: Into<Vec<u8>>>(mut self, name: N, value: V) -> Self {
+        self.variables.push((name.into(), AwkStr::new_from_vec(value.into())));
+        self
+    }
+
+    // Makes func callable from awk as name(...). Scalar args are converted to the declared type
+    // before the call, array args are passed by reference. The returned value is converted to returns.
+    pub fn native<N, F>(mut self, name: N, args: &[NativeArgT], returns: ScalarType, func: F) -> Self
+        where N: Into<String>, F: Fn(&mut NativeArgs) -> Value + 'static {
+        self.natives.push((NativeFunc::new(name, args.to_vec(), returns), Rc::new(func)));
+        self
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    // Lexing, parsing, or type checking failed
+    Compile,
+    // An input couldn't be read or the output couldn't be written
+    Runtime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    // Where in the program source, only set for some compile errors
+    pub span: Option<Span>,
+}
+
+impl Error {
+    fn new(kind: ErrorKind, err: PrintableError) -> Self {
+        Error { kind, message: err.msg, span: err.span }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.message, span),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// The value of a variable or array element after a run
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Num(f64),
+    Str(Vec<u8>),
+}
+
+impl From<RuntimeScalar> for Value {
+    fn from(scalar: RuntimeScalar) -> Self {
+        match scalar {
+            RuntimeScalar::Num(num) => Value::Num(num),
+            RuntimeScalar::Str(str) | RuntimeScalar::StrNum(str) => Value::Str(str.bytes().to_vec()),
+        }
+    }
+}
+
+impl Value {
+    pub(crate) fn into_scalar(self) -> RuntimeScalar {
+        match self {
+            Value::Num(num) => RuntimeScalar::Num(num),
+            Value::Str(str) => RuntimeScalar::Str(RcAwkStr::new_bytes(str)),
+        }
+    }
+}
+
+pub type NativeFn = Rc<dyn Fn(&mut NativeArgs) -> Value>;
+
+pub(crate) enum NativeArg {
+    Num(f64),
+    Str(StringScalar),
+    Array(GlobalArrayId),
+}
+
+// The args to one call of a native function, in the order they were declared
+pub struct NativeArgs<'a> {
+    args: Vec<NativeArg>,
+    arrays: &'a mut Arrays,
+}
+
+impl<'a> NativeArgs<'a> {
+    pub(crate) fn new(args: Vec<NativeArg>, arrays: &'a mut Arrays) -> Self {
+        NativeArgs { args, arrays }
+    }
+
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    // Panics if arg idx wasn't declared as NativeArgT::Num
+    pub fn num(&self, idx: usize) -> f64 {
+        match &self.args[idx] {
+            NativeArg::Num(num) => *num,
+            _ => panic!("native arg {} is not a number", idx),
+        }
+    }
+
+    // Panics if arg idx wasn't declared as NativeArgT::Str
+    pub fn str(&self, idx: usize) -> &[u8] {
+        match &self.args[idx] {
+            NativeArg::Str(str) => str.bytes(),
+            _ => panic!("native arg {} is not a string", idx),
+        }
+    }
+
+    // Every element of array arg idx keyed by its string form, in no particular order
+    pub fn array(&self, idx: usize) -> Vec<(Vec<u8>, Value)> {
+        self.arrays.entries(self.array_id(idx)).into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect()
+    }
+
+    // Strings stored this way compare as numbers when they look like one, like elements from split()
+    pub fn set_element<K: Into<Vec<u8>>>(&mut self, idx: usize, key: K, value: Value) {
+        let arr = self.array_id(idx);
+        let key = ArrayKey::Str(RcAwkStr::new_bytes(key.into()));
+        let value = match value {
+            Value::Num(num) => RuntimeScalar::Num(num),
+            Value::Str(str) => StringScalar::input(AwkStr::new_from_vec(str)).into(),
+        };
+        self.arrays.assign(arr, key, value);
+    }
+
+    pub fn clear_array(&mut self, idx: usize) {
+        let arr = self.array_id(idx);
+        self.arrays.clear(arr).for_each(drop);
+    }
+
+    // Panics if arg idx wasn't declared as NativeArgT::Array
+    fn array_id(&self, idx: usize) -> GlobalArrayId {
+        match &self.args[idx] {
+            NativeArg::Array(arr) => *arr,
+            _ => panic!("native arg {} is not an array", idx),
+        }
+    }
+}
+
+pub struct Program {
+    // Shared with each run's vm. Compile a program once and run it many times.
+    vm_program: Rc<VmProgram>,
+    symbolizer: Symbolizer,
+    variables: Vec<(String, AwkStr)>,
+    natives: Vec<NativeFn>,
+    csv: bool,
+}
+
+impl Program {
+    pub fn compile(source: &str, options: Options) -> Result<Program, Error> {
+        let compile_err = |err| Error::new(ErrorKind::Compile, err);
+        let (natives, native_fns): (Vec<NativeFunc>, Vec<NativeFn>) = options.natives.into_iter().unzip();
+        check_natives(&natives).map_err(compile_err)?;
+        let mut symbolizer = Symbolizer::new();
+        let (tokens, spans) = lex_spanned(source, &mut symbolizer).map_err(compile_err)?;
+        let program = parse_spanned(tokens, &spans, &mut symbolizer).map_err(compile_err)?;
+        let ast = analyze_with_natives(program, &mut symbolizer, natives).map_err(compile_err)?;
+        let vm_program = compile(ast, &mut symbolizer, options.specialization_limit).map_err(compile_err)?;
+        Ok(Program {
+            vm_program: Rc::new(vm_program),
+            symbolizer,
+            variables: options.variables,
+            natives: native_fns,
+            csv: options.csv,
+        })
+    }
+
+    // Runs the program over each input in order, with no inputs it reads stdin like the cli.
+    // Output from print and printf goes to output which is handed back in Finished.
+    pub fn run<W: Write + 'static>(&self, inputs: Vec<Input>, output: W) -> Result<Finished<W>, Error> {
+        let output = SharedWriter(Rc::new(RefCell::new(output)));
+        let mut vm = VirtualMachine::with_program(self.vm_program.clone(), inputs, Box::new(output.clone()), Box::new(std::io::sink()));
+        vm.natives = self.natives.clone();
+        if self.csv {
+            vm.enable_csv();
+        }
+        let symbolizer = self.symbolizer.clone();
+        let variables = self.variables.iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+        assign_variables(&mut vm, variables);
+        vm.run_main();
+        if let Some(err) = vm.error.take() {
+            return Err(Error::new(ErrorKind::Runtime, err));
+        }
+        if let Err(err) = vm.stdout.flush() {
+            return Err(Error::new(ErrorKind::Runtime, PrintableError::new(format!("Failed to write to output. Message: {}", err))));
+        }
+        // Drop the vm's handle so output is the only one left
+        vm.stdout = Box::new(std::io::sink());
+        let output = match Rc::try_unwrap(output.0) {
+            Ok(cell) => cell.into_inner(),
+            Err(_) => unreachable!("vm kept a handle to the output"),
+        };
+        Ok(Finished { output, vm, symbolizer })
+    }
+}
+
+// Natives can't replace a builtin or each other. Awk functions of the same name replace the native.
+fn check_natives(natives: &[NativeFunc]) -> Result<(), PrintableError> {
+    for (idx, native) in natives.iter().enumerate() {
+        if BuiltinFunc::get(&native.name).is_some() {
+            return Err(PrintableError::new(format!("native function {} has the same name as a builtin", native.name)));
+        }
+        if natives[..idx].iter().any(|prior| prior.name == native.name) {
+            return Err(PrintableError::new(format!("native function {} is registered twice", native.name)));
+        }
+    }
+    Ok(())
+}
+
+// A finished run. Holds onto the vm so globals can be read back.
+pub struct Finished<W> {
+    pub output: W,
+    vm: VirtualMachine,
+    symbolizer: Symbolizer,
+}
+
+impl<W> Finished<W> {
+    // A global or special variable (NR, FS, ...). None if the program never uses name as a scalar.
+    pub fn scalar(&mut self, name: &str) -> Option<Value> {
+        if let Ok(special) = SclSpecial::try_from(name) {
+            return Some(self.vm.special(special).into());
+        }
+        let id = *self.vm.vm_program.analysis.global_scalars.get(&self.symbolizer.get(name))?;
+        Some(self.vm.gscl(id).clone().into())
+    }
+
+    // Every element of a global array keyed by its string form, sorted by key.
+    // None if the program never uses name as an array.
+    pub fn array(&mut self, name: &str) -> Option<Vec<(Vec<u8>, Value)>> {
+        let id = *self.vm.vm_program.analysis.global_arrays.get(&self.symbolizer.get(name))?;
+        let mut entries: Vec<(Vec<u8>, Value)> = self.vm.rt.arrays.entries(id).into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Some(entries)
+    }
+}
+
+// The vm needs a Box<dyn Write> that it owns, this lets the caller get their writer back after
+struct SharedWriter<W>(Rc<RefCell<W>>);
+
+impl<W> Clone for SharedWriter<W> {
+    fn clone(&self) -> Self {
+        SharedWriter(self.0.clone())
+    }
+}
+
+impl<W: Write> Write for SharedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::embed::{ErrorKind, Options, Program, Value};
+    use crate::parser::ScalarType;
+    use crate::printable_error::Span;
+    use crate::runtime::Input;
+    use crate::typing::NativeArgT;
+
+    #[test]
+    fn test_run_bytes() {
+        let program = Program::compile("{ total += $2; seen[$1]++ } END { print total }", Options::new()).unwrap();
+        let mut finished = program.run(vec![Input::bytes("sales", "a 1\nb 2\na 4\n")], vec![]).unwrap();
+        assert_eq!(finished.output, b"7\n");
+        assert_eq!(finished.scalar("total"), Some(Value::Num(7.0)));
+        assert_eq!(finished.scalar("NR"), Some(Value::Num(3.0)));
+        assert_eq!(finished.scalar("unused"), None);
+        assert_eq!(
+            finished.array("seen"),
+            Some(vec![(b"a".to_vec(), Value::Num(2.0)), (b"b".to_vec(), Value::Num(1.0))])
+        );
+        assert_eq!(finished.array("total"), None);
+    }
+
+    #[test]
+    fn test_run_twice() {
+        let program = Program::compile("{ print prefix $0 }", Options::new().variable("prefix", "> ")).unwrap();
+        let first = program.run(vec![Input::bytes("one", "a\n")], vec![]).unwrap();
+        let second = program.run(vec![Input::reader("two", Box::new("b\nc\n".as_bytes())), Input::bytes("three", "d")], vec![]).unwrap();
+        assert_eq!(first.output, b"> a\n");
+        assert_eq!(second.output, b"> b\n> c\n> d\n");
+
+        // Runs share the compiled program and let it go once they're dropped
+        assert_eq!(Rc::strong_count(&program.vm_program), 3);
+        drop((first, second));
+        assert_eq!(Rc::strong_count(&program.vm_program), 1);
+    }
+
+    #[test]
+    fn test_variables_are_input() {
+        let program = Program::compile("BEGIN { x = x; print (x == 10) }", Options::new().variable("x", "10.0").variable("FS", ",")).unwrap();
+        let mut finished = program.run(vec![], vec![]).unwrap();
+        assert_eq!(finished.output, b"1\n");
+        assert_eq!(finished.scalar("x"), Some(Value::Str(b"10.0".to_vec())));
+        assert_eq!(finished.scalar("FS"), Some(Value::Str(b",".to_vec())));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = Program::compile("BEGIN {\n  x = }", Options::new()).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Compile);
+        assert_eq!(err.span, Some(Span::new(2, 7)));
+
+        let program = Program::compile("{ print $0 }", Options::new()).unwrap();
+        let err = program.run(vec![Input::Path("/does/not/exist".to_string())], vec![]).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Runtime);
+        assert_eq!(err.span, None);
+        assert!(err.message.contains("/does/not/exist"));
+    }
+
+    #[test]
+    fn test_beginfile_unreadable() {
+        let skips = "BEGINFILE { if (ERRNO != \"\") { print FILENAME \": \" ERRNO; nextfile } } { print FILENAME \" \" $0 }";
+        let program = Program::compile(skips, Options::new()).unwrap();
+        let inputs = vec![Input::bytes("one", "a\n"), Input::Path("/does/not/exist".to_string()), Input::bytes("two", "b\n")];
+        let finished = program.run(inputs, vec![]).unwrap();
+        assert_eq!(finished.output, b"one a\n/does/not/exist: No such file or directory\ntwo b\n");
+
+        // Without nextfile the file is still fatal
+        let program = Program::compile("BEGINFILE { print FILENAME } { print $0 }", Options::new()).unwrap();
+        let err = program.run(vec![Input::Path("/does/not/exist".to_string())], vec![]).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Runtime);
+        assert!(err.message.contains("/does/not/exist"));
+    }
+
+    #[test]
+    fn test_nextfile_outside_rules() {
+        let err = Program::compile("BEGIN { nextfile }", Options::new()).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Compile);
+        let err = Program::compile("ENDFILE { nextfile }", Options::new()).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Compile);
+    }
+
+    #[test]
+    fn test_csv() {
+        let data = "name,note\r\nann,\"likes \"\"awk\"\", a lot\"\r\nbob,\"two\nlines\"\n";
+        let program = Program::compile("{ print NR \" \" NF \" [\" $2 \"]\" }", Options::new().csv()).unwrap();
+        let finished = program.run(vec![Input::bytes("notes", data)], vec![]).unwrap();
+        assert_eq!(finished.output, b"1 2 [note]\n2 2 [likes \"awk\", a lot]\n3 2 [two\nlines]\n");
+
+        // Changing a field quotes the fields that need it when $0 is rebuilt
+        let program = Program::compile("BEGIN { OFS = \",\" } { $1 = $1 \"!\"; print $0 }", Options::new().csv()).unwrap();
+        let finished = program.run(vec![Input::bytes("notes", data)], vec![]).unwrap();
+        assert_eq!(finished.output, b"name!,note\nann!,\"likes \"\"awk\"\", a lot\"\nbob!,\"two\nlines\"\n");
+
+        // FS is ignored
+        let program = Program::compile("BEGIN { FS = \" \" } { print $1 }", Options::new().csv()).unwrap();
+        let finished = program.run(vec![Input::bytes("spaces", "a b,c\n")], vec![]).unwrap();
+        assert_eq!(finished.output, b"a b\n");
+    }
+
+    #[test]
+    fn test_native_functions() {
+        let options = Options::new()
+            .native("hash", &[NativeArgT::Str], ScalarType::Num, |args| {
+                Value::Num(args.str(0).iter().map(|b| *b as f64).sum())
+            })
+            .native("shout", &[NativeArgT::Str, NativeArgT::Num], ScalarType::Str, |args| {
+                let mut shouted = args.str(0).to_ascii_uppercase();
+                shouted.extend(std::iter::repeat_n(b'!', args.num(1) as usize));
+                Value::Str(shouted)
+            })
+            .native("lookup", &[NativeArgT::Str], ScalarType::Var, |args| {
+                match args.str(0) {
+                    b"one" => Value::Num(1.0),
+                    other => Value::Str(other.to_vec()),
+                }
+            });
+        let program = Program::compile(
+            "{ print hash($1) \" \" shout($1, 2) \" \" lookup($1) \" \" hash(12) }",
+            options,
+        ).unwrap();
+        let finished = program.run(vec![Input::bytes("words", "ab\none\n")], vec![]).unwrap();
+        assert_eq!(finished.output, b"195 AB!! ab 99\n322 ONE!! 1 99\n");
+    }
+
+    #[test]
+    fn test_native_arrays() {
+        let options = Options::new()
+            .native("fill", &[NativeArgT::Array, NativeArgT::Str], ScalarType::Num, |args| {
+                args.clear_array(0);
+                let words: Vec<Vec<u8>> = args.str(1).split(|b| *b == b',').map(|w| w.to_vec()).collect();
+                for (idx, word) in words.iter().enumerate() {
+                    args.set_element(0, (idx + 1).to_string(), Value::Str(word.clone()));
+                }
+                Value::Num(words.len() as f64)
+            })
+            .native("total", &[NativeArgT::Array], ScalarType::Num, |args| {
+                Value::Num(args.array(0).iter().map(|(_key, value)| match value {
+                    Value::Num(num) => *num,
+                    Value::Str(str) => String::from_utf8_lossy(str).parse().unwrap_or(0.0),
+                }).sum())
+            });
+        let program = Program::compile(
+            "function f(arr) { return fill(arr, \"5,x,10\") } BEGIN { a[\"old\"] = 1; n = f(a); print n \" \" a[1] \" \" a[2] \" \" (\"old\" in a) \" \" (a[3] > 9) \" \" total(a) }",
+            options,
+        ).unwrap();
+        let finished = program.run(vec![], vec![]).unwrap();
+        assert_eq!(finished.output, b"3 5 x 0 1 15\n");
+    }
+
+    #[test]
+    fn test_native_errors() {
+        let builtin = Options::new().native("length", &[NativeArgT::Str], ScalarType::Num, |_| Value::Num(0.0));
+        let err = Program::compile("BEGIN { }", builtin).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::Compile);
+
+        let twice = Options::new()
+            .native("f", &[], ScalarType::Num, |_| Value::Num(0.0))
+            .native("f", &[], ScalarType::Num, |_| Value::Num(1.0));
+        assert!(Program::compile("BEGIN { }", twice).is_err());
+
+        let array = || Options::new().native("f", &[NativeArgT::Array], ScalarType::Num, |_| Value::Num(0.0));
+        assert!(Program::compile("BEGIN { f(1) }", array()).is_err());
+        assert!(Program::compile("BEGIN { f(a, b) }", array()).is_err());
+
+        // An awk function of the same name replaces the native one
+        let program = Program::compile("function f(a) { return a \"awk\" } BEGIN { print f(\"an \") }", array()).unwrap();
+        assert_eq!(program.run(vec![], vec![]).unwrap().output, b"an awk\n");
+    }
+}
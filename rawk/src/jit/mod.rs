@@ -0,0 +1,329 @@
+use std::os::raw::{c_ulong, c_void};
+use gnu_libjit::{Abi, Context, Function, Label, Value};
+use std::rc::Rc;
+use hashbrown::HashMap;
+use crate::stack_counter::StackCounter;
+use crate::stackt::StackT;
+use crate::typing::GlobalScalarId;
+use crate::vm::{Cmp, Code, RuntimeScalar, VirtualMachine, VmFunc, VmProgram};
+
+// Translates each VmFunc into a libjit function taking the vm. The typed stack heights before
+// each op (see stack_counter) give every slot of a function's num stack a fixed libjit variable,
+// so math, comparisons and numeric branches happen in registers and jumps need no bookkeeping.
+// Ops that aren't translated call their subroutine through run_op exactly like the interpreter
+// would, their num args are pushed to the vm's num stack first and the nums they push are popped
+// back into their slots.
+
+pub type JitFn = extern "C" fn(*mut VirtualMachine) -> i32;
+
+pub struct JitProgram {
+    // Owns the compiled code
+    _context: Context,
+    // The compiled code points at the bytecode it falls back to so it's kept alive here
+    _prog: Rc<VmProgram>,
+    // Indexed by function id
+    functions: Vec<JitFn>,
+}
+
+impl JitProgram {
+    pub fn new(prog: Rc<VmProgram>) -> Self {
+        let context = Context::new();
+        context.build_start();
+        let mut compiled: Vec<Option<Function>> = vec![None; prog.functions.len()];
+        for func in prog.functions.iter() {
+            compiled[func.id()] = Some(FunctionJit::new(&context, &prog, func).compile());
+        }
+        context.build_end();
+        let functions = compiled.into_iter()
+            .map(|function| function.expect("compiler bug function ids are not dense").to_closure())
+            .collect();
+        Self { _context: context, _prog: prog, functions }
+    }
+
+    pub fn entry(&self, function_id: usize) -> JitFn {
+        self.functions[function_id]
+    }
+}
+
+extern "C" fn jit_pop_num(vm: *mut VirtualMachine) -> f64 {
+    unsafe { (*vm).pop_num() }
+}
+
+extern "C" fn jit_push_num(vm: *mut VirtualMachine, num: f64) {
+    unsafe { (*vm).push_num(num) }
+}
+
+extern "C" fn jit_gscl_num(vm: *mut VirtualMachine, id: usize) -> f64 {
+    match unsafe { (*vm).gscl(GlobalScalarId { id }) } {
+        RuntimeScalar::Num(num) => *num,
+        _ => unreachable!("compiler bug global {} is read as a num but doesn't hold one", id),
+    }
+}
+
+extern "C" fn jit_assign_gscl_num(vm: *mut VirtualMachine, id: usize, num: f64) {
+    unsafe { (*vm).assign_gscl(GlobalScalarId { id }, RuntimeScalar::Num(num)) }
+}
+
+// Runs one op with its subroutine, returns the next ip
+extern "C" fn jit_run_op(vm: *mut VirtualMachine, func: *const VmFunc, ip: usize) -> usize {
+    let func = unsafe { &*func };
+    (func[ip].code)(unsafe { &mut *vm }, ip, func[ip].imm)
+}
+
+fn target(ip: usize, offset: isize) -> usize {
+    (ip as isize + offset) as usize
+}
+
+// Typed stack heights before each op, None for ops that are never reached. The program validator
+// checks that every path to an op agrees on them so the first path found is used.
+fn stack_heights(prog: &VmProgram, func: &VmFunc) -> Vec<Option<StackCounter>> {
+    let chunk = func.chunk();
+    let mut heights = vec![None; chunk.len()];
+    let mut pending = vec![(0, StackCounter::new())];
+    while let Some((ip, stacks)) = pending.pop() {
+        if heights[ip].is_some() {
+            continue;
+        }
+        heights[ip] = Some(stacks);
+        let meta = chunk[ip].meta(&prog.func_map);
+        if meta.is_ret() {
+            continue;
+        }
+        let mut next = stacks;
+        next.sub(meta.args());
+        next.add(meta.returns());
+        for offset in meta.descendants() {
+            pending.push((target(ip, *offset), next));
+        }
+    }
+    heights
+}
+
+struct FunctionJit<'a> {
+    prog: &'a VmProgram,
+    func: &'a VmFunc,
+    function: Function,
+    vm: Value,
+    // Typed stack heights before each op
+    heights: Vec<Option<StackCounter>>,
+    // A variable for each slot of this function's num stack, the first is the bottom
+    nums: Vec<Value>,
+    // Ops that are jumped to by ip
+    labels: HashMap<usize, Label>,
+    // Placed after the last op, returns to the caller
+    exit: Label,
+}
+
+impl<'a> FunctionJit<'a> {
+    fn new(context: &Context, prog: &'a VmProgram, func: &'a VmFunc) -> Self {
+        let function = context.function(Abi::Cdecl, &Context::int_type(), vec![Context::void_ptr_type()])
+            .expect("libjit failed to create a function");
+        let vm = function.arg(0).expect("libjit function has no vm arg");
+        let mut labels = HashMap::new();
+        for (ip, code) in func.chunk().iter().enumerate() {
+            if code.is_ret() {
+                continue;
+            }
+            for offset in code.meta(&prog.func_map).descendants() {
+                if *offset != 1 {
+                    labels.insert(target(ip, *offset), Label::new());
+                }
+            }
+        }
+        let heights = stack_heights(prog, func);
+        Self { prog, func, function, vm, heights, nums: vec![], labels, exit: Label::new() }
+    }
+
+    fn compile(mut self) -> Function {
+        let func = self.func;
+        for (ip, code) in func.chunk().iter().enumerate() {
+            if let Some(label) = self.labels.get_mut(&ip) {
+                self.function.insn_label(label);
+            }
+            // Unreachable ops aren't translated
+            if let Some(stacks) = self.heights[ip] {
+                self.op(ip, code, stacks.count(StackT::Num));
+            }
+        }
+        self.function.insn_label(&mut self.exit);
+        let zero = self.function.create_int_constant(0);
+        self.function.insn_return(&zero);
+        self.function.compile();
+        self.function
+    }
+
+    // height is the number of nums on this function's num stack before the op runs
+    fn op(&mut self, ip: usize, code: &Code, height: usize) {
+        match code {
+            Code::ConstNum { num } => {
+                let num = self.function.create_float64_constant(*num);
+                self.store(height, &num);
+            }
+            Code::Add => self.math(height, Function::insn_add),
+            Code::Minus => self.math(height, Function::insn_sub),
+            Code::Mult => self.math(height, Function::insn_mult),
+            Code::Div => self.math(height, Function::insn_div),
+            Code::Mod => self.math(height, Function::insn_rem),
+            Code::Exp => self.math(height, Function::insn_pow),
+            Code::AddConst { num } => {
+                let lhs = self.slot(height - 1);
+                let rhs = self.function.create_float64_constant(*num);
+                let sum = self.function.insn_add(&lhs, &rhs);
+                self.store(height - 1, &sum);
+            }
+            Code::LtNum => self.compare(height, Cmp::Lt),
+            Code::GtNum => self.compare(height, Cmp::Gt),
+            Code::LtEqNum => self.compare(height, Cmp::LtEq),
+            Code::GtEqNum => self.compare(height, Cmp::GtEq),
+            Code::EqEqNum => self.compare(height, Cmp::EqEq),
+            Code::NeqNum => self.compare(height, Cmp::Neq),
+            Code::GsclNum(id) => {
+                let num = self.gscl_num(id.id);
+                self.store(height, &num);
+            }
+            Code::AssignGsclNum(id) | Code::AssignRetGsclNum(id) => {
+                // The Ret variant leaves the value in its slot
+                let num = self.slot(height - 1);
+                self.assign_gscl_num(id.id, &num);
+            }
+            Code::AddGsclNum { id, delta } => {
+                let num = self.gscl_num(id.id);
+                let delta = self.function.create_float64_constant(*delta as f64);
+                let sum = self.function.insn_add(&num, &delta);
+                self.assign_gscl_num(id.id, &sum);
+            }
+            Code::RelJump { offset } => self.branch(None, true, target(ip, *offset)),
+            Code::RelJumpIfFalseNum { offset } | Code::RelJumpIfTrueNum { offset } => {
+                let num = self.slot(height - 1);
+                let zero = self.function.create_float64_constant(0.0);
+                let cond = self.function.insn_eq(&num, &zero);
+                let jump_if_zero = matches!(code, Code::RelJumpIfFalseNum { .. });
+                self.branch(Some(&cond), jump_if_zero, target(ip, *offset));
+            }
+            Code::RelJumpIfCmpNum { cmp, jump_if, offset } => {
+                let lhs = self.slot(height - 2);
+                let rhs = self.slot(height - 1);
+                let cond = self.cmp(*cmp, &lhs, &rhs);
+                self.branch(Some(&cond), *jump_if, target(ip, *offset));
+            }
+            Code::Ret | Code::RetNum | Code::RetStr => {
+                // The return value is left on the vm's stack for the caller
+                if let Code::RetNum = code {
+                    self.push(height - 1);
+                }
+                self.function.insn_branch(&mut self.exit);
+            }
+            _ => self.fallback(ip, code, height),
+        }
+    }
+
+    fn fallback(&mut self, ip: usize, code: &Code, height: usize) {
+        let meta = code.meta(&self.prog.func_map);
+        let num_args = meta.args().iter().filter(|arg| **arg == StackT::Num).count();
+        let base = height - num_args;
+        for slot in base..height {
+            self.push(slot);
+        }
+        let func = self.function.create_void_ptr_constant(self.func as *const VmFunc as *mut c_void);
+        let ip_value = self.function.create_ulong_constant(ip as c_ulong);
+        let next_ip = self.function.insn_call_native(
+            jit_run_op as *mut c_void,
+            [self.vm.clone(), func, ip_value],
+            Some(Context::ulong_type()),
+            Abi::Cdecl);
+        // Subroutines that print an error stop the function by returning usize::MAX
+        let stop = self.function.create_ulong_constant(usize::MAX as c_ulong);
+        let stopped = self.function.insn_eq(&next_ip, &stop);
+        self.function.insn_branch_if(&stopped, &mut self.exit);
+        // Top of the stack first
+        for slot in (base..base + meta.returns().count(StackT::Num)).rev() {
+            let num = self.function.insn_call_native(
+                jit_pop_num as *mut c_void,
+                [self.vm.clone()],
+                Some(Context::float64_type()),
+                Abi::Cdecl);
+            self.store(slot, &num);
+        }
+        for offset in meta.descendants().iter().filter(|offset| **offset != 1) {
+            let target = target(ip, *offset);
+            let target_value = self.function.create_ulong_constant(target as c_ulong);
+            let cond = self.function.insn_eq(&next_ip, &target_value);
+            self.branch(Some(&cond), true, target);
+        }
+    }
+
+    fn math(&mut self, height: usize, op: fn(&mut Function, &Value, &Value) -> Value) {
+        let lhs = self.slot(height - 2);
+        let rhs = self.slot(height - 1);
+        let res = op(&mut self.function, &lhs, &rhs);
+        self.store(height - 2, &res);
+    }
+
+    fn compare(&mut self, height: usize, cmp: Cmp) {
+        let lhs = self.slot(height - 2);
+        let rhs = self.slot(height - 1);
+        // cond is an int 1 or 0, awk wants 1.0 or 0.0
+        let cond = self.cmp(cmp, &lhs, &rhs);
+        let res = self.function.insn_convert(&cond, &Context::float64_type());
+        self.store(height - 2, &res);
+    }
+
+    fn cmp(&mut self, cmp: Cmp, lhs: &Value, rhs: &Value) -> Value {
+        match cmp {
+            Cmp::Lt => self.function.insn_lt(lhs, rhs),
+            Cmp::Gt => self.function.insn_gt(lhs, rhs),
+            Cmp::LtEq => self.function.insn_le(lhs, rhs),
+            Cmp::GtEq => self.function.insn_ge(lhs, rhs),
+            Cmp::EqEq => self.function.insn_eq(lhs, rhs),
+            Cmp::Neq => self.function.insn_ne(lhs, rhs),
+        }
+    }
+
+    // Branch to the op at target unconditionally (cond is None), or when cond is jump_if
+    fn branch(&mut self, cond: Option<&Value>, jump_if: bool, target: usize) {
+        let label = self.labels.get_mut(&target).expect("compiler bug jump target has no label");
+        match cond {
+            None => self.function.insn_branch(label),
+            Some(cond) if jump_if => self.function.insn_branch_if(cond, label),
+            Some(cond) => self.function.insn_branch_if_not(cond, label),
+        }
+    }
+
+    // The variable for a slot of the num stack, created the first time it's used
+    fn slot(&mut self, slot: usize) -> Value {
+        while self.nums.len() <= slot {
+            let num = self.function.create_value_float64();
+            self.nums.push(num);
+        }
+        self.nums[slot].clone()
+    }
+
+    fn store(&mut self, slot: usize, num: &Value) {
+        let dest = self.slot(slot);
+        self.function.insn_store(&dest, num);
+    }
+
+    // Copy a slot to the vm's num stack for a subroutine or the caller
+    fn push(&mut self, slot: usize) {
+        let num = self.slot(slot);
+        self.function.insn_call_native(jit_push_num as *mut c_void, [self.vm.clone(), num], None, Abi::Cdecl);
+    }
+
+    fn gscl_num(&mut self, id: usize) -> Value {
+        let id = self.function.create_ulong_constant(id as c_ulong);
+        self.function.insn_call_native(
+            jit_gscl_num as *mut c_void,
+            [self.vm.clone(), id],
+            Some(Context::float64_type()),
+            Abi::Cdecl)
+    }
+
+    fn assign_gscl_num(&mut self, id: usize, num: &Value) {
+        let id = self.function.create_ulong_constant(id as c_ulong);
+        self.function.insn_call_native(
+            jit_assign_gscl_num as *mut c_void,
+            [self.vm.clone(), id, num.clone()],
+            None,
+            Abi::Cdecl);
+    }
+}
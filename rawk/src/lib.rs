@@ -0,0 +1,104 @@
+use std::io::{Write};
+use crate::args::{AwkArgs, USAGE};
+use crate::awk_str::AwkStr;
+use crate::compiler::{compile, validate_program};
+use crate::printable_error::PrintableError;
+
+pub use crate::lexer::lex;
+pub use crate::parser::parse;
+pub use crate::symbolizer::Symbolizer;
+pub use crate::typing::analyze;
+use crate::vm::{VirtualMachine, VmProgram};
+
+use awk_frontend::{global_scalars, lexer, parser, printable_error, specials, stack_counter, stackt, symbolizer, typing};
+
+mod args;
+mod vm;
+mod compiler;
+mod util;
+mod awk_str;
+#[cfg(test)]
+mod test;
+mod runtime;
+#[cfg(feature = "jit")]
+mod jit;
+mod embed;
+
+pub use crate::embed::{Error, ErrorKind, Finished, NativeArgs, NativeFn, Options, Program, Value};
+pub use crate::parser::ScalarType;
+pub use crate::typing::NativeArgT;
+pub use crate::printable_error::Span;
+pub use crate::runtime::Input;
+
+pub type IO = Box<dyn Write>;
+
+pub fn runner(args: Vec<String>, mut out: IO, err: IO) -> Result<(IO, IO), PrintableError> {
+    let mut symbolizer = Symbolizer::new();
+    let args = AwkArgs::new(args)?;
+    if args.help || args.version {
+        let msg = if args.help { USAGE.to_string() } else { format!("rawk {}\n", env!("CARGO_PKG_VERSION")) };
+        if let Err(err) = out.write_all(msg.as_bytes()).and_then(|_| out.flush()) {
+            return Err(PrintableError::new(format!("Failed to write to stdout. Message: {}", err)))
+        }
+        return Ok((out, err));
+    }
+
+    let prog = if let Some(path) = &args.precompiled {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(PrintableError::new(format!("rawk: can't open compiled program `{}` for reading: {}", path, e))),
+        };
+        VmProgram::load(&bytes, &mut symbolizer)?
+    } else {
+        let ast = analyze(parse(lex(&args.program, &mut symbolizer)?, &mut symbolizer, )?, &mut symbolizer)?;
+        if args.debug {
+            // AST display is not utf-8 safe so it's only available in debug builds
+            #[cfg(debug_assertions)]
+            println!("{}", ast);
+        }
+        compile(ast, &mut symbolizer, args.specialization_limit)?
+    };
+    if let Some(path) = &args.compile_to {
+        if let Err(e) = std::fs::write(path, prog.save()) {
+            return Err(PrintableError::new(format!("rawk: can't write compiled program to `{}`: {}", path, e)));
+        }
+        return Ok((out, err));
+    }
+    if args.debug {
+        let prog_pretty = prog.pretty_print();
+        let prog_pretty = unsafe { String::from_utf8_unchecked(prog_pretty) };
+        println!("{}", prog_pretty);
+        validate_program(&prog)?;
+    }
+    let files = args.inputs()?;
+    let mut vm = VirtualMachine::new(prog, files, out, err);
+    if args.jit {
+        #[cfg(feature = "jit")]
+        vm.enable_jit();
+    }
+    if args.csv {
+        vm.enable_csv();
+    }
+    assign_variables(&mut vm, args.assignments);
+    let (mut out, mut err, error) = vm.run();
+    #[cfg(feature = "op_profile")]
+    eprint!("{}", vm::op_profile::report(20));
+    if let Err(err) = out.flush() {
+        return Err(PrintableError::new(format!("Failed to write to stdout. Message: {}", err)))
+    }
+    if let Err(err) = err.flush() {
+        return Err(PrintableError::new(format!("Failed to write to stderr. Message: {}", err)))
+    }
+    // Output printed before the error is kept, the error then fails the run
+    if let Some(error) = error {
+        return Err(error);
+    }
+    Ok((out, err))
+}
+
+// -F and -v assignments which happen before BEGIN
+fn assign_variables(vm: &mut VirtualMachine, assignments: Vec<(String, AwkStr)>) {
+    for (name, value) in assignments {
+        vm.assign_input(&name, value);
+    }
+}
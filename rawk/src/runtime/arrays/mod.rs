@@ -1,13 +1,17 @@
+mod sort;
 mod split;
+mod storage;
 
-use hashbrown::HashMap;
-use hashbrown::hash_map::Drain;
 use crate::awk_str::{RcAwkStr};
+use crate::parser::ScalarType;
 use crate::vm::RuntimeScalar;
 
 pub use split::{split_on_string, split_on_regex};
+pub use sort::{merge_sort_by, SortOrder};
+use crate::runtime::converter::Converter;
 use crate::typing::GlobalArrayId;
 use crate::util::unwrap;
+use storage::Storage;
 
 #[derive(Hash, Clone, Eq, PartialEq)]
 pub struct MapKey {
@@ -20,66 +24,271 @@ impl MapKey {
     }
 }
 
-struct AwkMap {
-    map: HashMap<MapKey, RuntimeScalar>,
+// The key of an array element. Int is a key that is a positive integer, either a number or
+// a string holding one with no sign, leading zeros, etc. Both name the same element as the
+// string the number converts to, so "3" and 3 are the same key but "03" is not.
+pub enum ArrayKey {
+    Int(usize),
+    Str(RcAwkStr),
+}
+
+// Larger numbers convert to strings in exponent form (1e10) so they are always keyed by that string
+const MAX_INT_KEY: usize = 9_999_999_999;
+
+impl ArrayKey {
+    pub fn from_num(num: f64) -> Option<Self> {
+        if num >= 1.0 && num <= MAX_INT_KEY as f64 && num.fract() == 0.0 {
+            Some(ArrayKey::Int(num as usize))
+        } else {
+            None
+        }
+    }
+
+    // The key as a 1 based index if it's a positive integer
+    fn dense_idx(&self) -> Option<usize> {
+        match self {
+            ArrayKey::Int(idx) => Some(*idx),
+            ArrayKey::Str(str) => {
+                let bytes = str.bytes();
+                if bytes.is_empty() || bytes.len() > 15 || bytes[0] == b'0' || !bytes.iter().all(u8::is_ascii_digit) {
+                    return None;
+                }
+                Some(bytes.iter().fold(0, |idx, digit| idx * 10 + (digit - b'0') as usize))
+            }
+        }
+    }
+
+    fn map_key(&self) -> MapKey {
+        match self {
+            ArrayKey::Int(idx) => MapKey::new(RcAwkStr::new_bytes(idx.to_string().into_bytes())),
+            ArrayKey::Str(str) => MapKey::new(str.clone()),
+        }
+    }
+}
+
+// Num array elements that were referenced but never assigned. Reads turn it into 0 or an
+// uninitialized value so this NaN never leaves the array.
+const UNINIT_NUM: u64 = 0x7ff8_0000_dead_beef;
+
+fn uninit_num() -> f64 {
+    f64::from_bits(UNINIT_NUM)
+}
+
+fn is_uninit_num(num: f64) -> bool {
+    num.to_bits() == UNINIT_NUM
+}
+
+fn uninit_scalar() -> RuntimeScalar {
+    RuntimeScalar::StrNum(RcAwkStr::new_bytes(vec![]))
+}
+
+// Arrays only ever assigned numbers (see typing::array_type_pass) store them unboxed
+enum AwkMap {
+    Var(Storage<RuntimeScalar>),
+    Num(Storage<f64>),
+}
+
+fn num_scalar(num: f64) -> RuntimeScalar {
+    if is_uninit_num(num) { uninit_scalar() } else { RuntimeScalar::Num(num) }
 }
 
 impl AwkMap {
-    fn access(&self, key: &MapKey) -> Option<&RuntimeScalar> {
-        self.map.get(key)
+    fn new(typ: ScalarType) -> Self {
+        match typ {
+            ScalarType::Num => AwkMap::Num(Storage::new()),
+            ScalarType::Str | ScalarType::Var => AwkMap::Var(Storage::new()),
+        }
+    }
+    fn is_num(&self) -> bool {
+        matches!(self, AwkMap::Num(_))
+    }
+    fn access(&self, key: &ArrayKey) -> Option<RuntimeScalar> {
+        match self {
+            AwkMap::Var(storage) => storage.get(key).cloned(),
+            AwkMap::Num(storage) => storage.get(key).map(|num| num_scalar(*num)),
+        }
+    }
+    // Referencing an element creates it, uninitialized
+    fn access_or_create(&mut self, key: ArrayKey, uninit: impl FnOnce() -> RuntimeScalar) -> RuntimeScalar {
+        match self {
+            AwkMap::Var(storage) => storage.get_or_insert(key, uninit).clone(),
+            AwkMap::Num(storage) => {
+                let num = *storage.get_or_insert(key, uninit_num);
+                if is_uninit_num(num) { uninit() } else { RuntimeScalar::Num(num) }
+            }
+        }
+    }
+    fn assign(&mut self, key: ArrayKey, val: RuntimeScalar) -> Option<RuntimeScalar> {
+        match self {
+            AwkMap::Var(storage) => storage.insert(key, val),
+            AwkMap::Num(_) => panic!("compiler bug: scalar assigned to a num array"),
+        }
+    }
+    fn access_or_insert(&mut self, key: ArrayKey) -> &mut RuntimeScalar {
+        match self {
+            AwkMap::Var(storage) => storage.get_or_insert(key, || RuntimeScalar::Num(0.0)),
+            AwkMap::Num(_) => panic!("compiler bug: scalar access to a num array"),
+        }
+    }
+    fn nums(&mut self) -> &mut Storage<f64> {
+        match self {
+            AwkMap::Num(storage) => storage,
+            AwkMap::Var(_) => panic!("compiler bug: num access to a scalar array"),
+        }
+    }
+    fn len(&self) -> usize {
+        match self {
+            AwkMap::Var(storage) => storage.len(),
+            AwkMap::Num(storage) => storage.len(),
+        }
     }
-    fn assign(&mut self, key: &MapKey, val: RuntimeScalar) -> Option<RuntimeScalar> {
-        self.map.insert(key.clone(), val)
+    fn in_array(&self, key: &ArrayKey) -> bool {
+        match self {
+            AwkMap::Var(storage) => storage.contains(key),
+            AwkMap::Num(storage) => storage.contains(key),
+        }
     }
-    fn new() -> Self {
-        Self {
-            map: HashMap::new(),
+
+    fn entries(&self) -> Vec<(Vec<u8>, RuntimeScalar)> {
+        match self {
+            AwkMap::Var(storage) => storage.entries().into_iter().map(|(key, value)| (key, value.clone())).collect(),
+            AwkMap::Num(storage) => storage.entries().into_iter().map(|(key, num)| (key, num_scalar(*num))).collect(),
         }
     }
-    fn in_array(&mut self, key: &MapKey) -> bool {
-        self.map.contains_key(key)
+
+    fn sorted_entries(&self, order: SortOrder, converter: &mut Converter) -> Vec<(Vec<u8>, RuntimeScalar)> {
+        sort::sort_entries(self.entries(), order, converter)
     }
 
-    fn drain(&mut self) -> Drain<'_, MapKey, RuntimeScalar> {
-        self.map.drain()
+    // Replaces every element with values keyed 1..=n
+    fn assign_list(&mut self, values: Vec<RuntimeScalar>) {
+        let _ = self.drain();
+        for (idx, value) in values.into_iter().enumerate() {
+            let key = ArrayKey::Int(idx + 1);
+            match (&mut *self, value) {
+                (AwkMap::Num(storage), RuntimeScalar::Num(num)) => { storage.insert(key, num); }
+                (AwkMap::Num(_), _) => panic!("compiler bug: scalar assigned to a num array"),
+                (AwkMap::Var(storage), value) => { storage.insert(key, value); }
+            }
+        }
+    }
+
+    // Yields the removed values so the caller can reuse their strings
+    fn drain(&mut self) -> impl Iterator<Item=RuntimeScalar> + '_ {
+        let drain = match self {
+            AwkMap::Var(storage) => Some(storage.drain()),
+            AwkMap::Num(storage) => {
+                let _ = storage.drain();
+                None
+            }
+        };
+        drain.into_iter().flatten()
     }
 }
 
 pub struct Arrays {
     arrays: Vec<AwkMap>,
+    // Local arrays from function calls that have returned, reused by later calls
+    free: Vec<GlobalArrayId>,
 }
 
 impl Arrays {
-    pub fn new(count: usize) -> Self {
-        let mut arrays = Vec::with_capacity(count);
-        for _ in 0..count {
-            arrays.push(AwkMap::new())
+    // types is the element type of each global array
+    pub fn new(types: &[ScalarType]) -> Self {
+        let arrays = types.iter().map(|typ| AwkMap::new(*typ)).collect();
+        Self { arrays, free: vec![] }
+    }
+
+    // A fresh empty array for an array param the caller didn't pass. Lives until free_local.
+    pub fn allocate_local(&mut self, typ: ScalarType) -> GlobalArrayId {
+        let num = typ == ScalarType::Num;
+        if let Some(idx) = self.free.iter().rposition(|arr| self.arrays[arr.id].is_num() == num) {
+            return self.free.swap_remove(idx);
         }
-        Self { arrays }
+        self.arrays.push(AwkMap::new(typ));
+        GlobalArrayId { id: self.arrays.len() - 1 }
     }
 
-    pub fn clear(&mut self, arr: GlobalArrayId) -> Drain<'_, MapKey, RuntimeScalar> {
+    pub fn free_local(&mut self, arr: GlobalArrayId) -> impl Iterator<Item=RuntimeScalar> + '_ {
+        self.free.push(arr);
+        self.clear(arr)
+    }
+
+    pub fn clear(&mut self, arr: GlobalArrayId) -> impl Iterator<Item=RuntimeScalar> + '_ {
         let array = self.arrays.get_mut(arr.id).expect("array to exist based on id");
         array.drain()
     }
 
-    pub fn access(&mut self, arr: GlobalArrayId, key: RcAwkStr) -> Option<&RuntimeScalar> {
+    // a[key] in the program. Missing elements are created with uninit's value.
+    pub fn access_or_create(&mut self, arr: GlobalArrayId, key: ArrayKey, uninit: impl FnOnce() -> RuntimeScalar) -> RuntimeScalar {
+        let array = unwrap(self.arrays.get_mut(arr.id));
+        array.access_or_create(key, uninit)
+    }
+
+    pub fn entries(&self, arr: GlobalArrayId) -> Vec<(Vec<u8>, RuntimeScalar)> {
+        unwrap(self.arrays.get(arr.id)).entries()
+    }
+
+    // Entries ordered like PROCINFO["sorted_in"] orders for (k in arr)
+    pub fn sorted_entries(&self, arr: GlobalArrayId, order: SortOrder, converter: &mut Converter) -> Vec<(Vec<u8>, RuntimeScalar)> {
+        unwrap(self.arrays.get(arr.id)).sorted_entries(order, converter)
+    }
+
+    pub fn assign_list(&mut self, arr: GlobalArrayId, values: Vec<RuntimeScalar>) {
+        unwrap(self.arrays.get_mut(arr.id)).assign_list(values)
+    }
+
+    // Doesn't create the element, for lookups the program didn't ask for
+    pub fn access(&mut self, arr: GlobalArrayId, key: ArrayKey) -> Option<RuntimeScalar> {
         let array = self.arrays.get_mut(arr.id).expect("array to exist based on id");
-        array.access(&MapKey::new(key))
+        array.access(&key)
     }
 
     pub fn assign(
         &mut self,
         arr: GlobalArrayId,
-        indices: RcAwkStr,
+        key: ArrayKey,
         value: RuntimeScalar,
     ) -> Option<RuntimeScalar> {
         let array = unwrap(self.arrays.get_mut(arr.id));
-        array.assign(&MapKey::new(indices), value)
+        array.assign(key, value)
     }
 
-    pub fn in_array(&mut self, arr: GlobalArrayId, indices: RcAwkStr) -> bool {
+    // Elements that don't exist yet are created as 0
+    pub fn access_or_insert(&mut self, arr: GlobalArrayId, key: ArrayKey) -> &mut RuntimeScalar {
         let array = unwrap(self.arrays.get_mut(arr.id));
-        array.in_array(&MapKey::new(indices))
+        array.access_or_insert(key)
+    }
+
+    // Number of elements
+    pub fn len(&self, arr: GlobalArrayId) -> usize {
+        unwrap(self.arrays.get(arr.id)).len()
+    }
+
+    pub fn in_array(&mut self, arr: GlobalArrayId, key: ArrayKey) -> bool {
+        let array = unwrap(self.arrays.get_mut(arr.id));
+        array.in_array(&key)
+    }
+
+    // Elements of num arrays. Missing elements are created uninitialized and read as 0 which is
+    // only correct when the caller wants a number, otherwise use access_or_create.
+    pub fn access_num(&mut self, arr: GlobalArrayId, key: ArrayKey) -> f64 {
+        let array = unwrap(self.arrays.get_mut(arr.id));
+        let num = *array.nums().get_or_insert(key, uninit_num);
+        if is_uninit_num(num) { 0.0 } else { num }
+    }
+
+    pub fn assign_num(&mut self, arr: GlobalArrayId, key: ArrayKey, value: f64) {
+        let array = unwrap(self.arrays.get_mut(arr.id));
+        array.nums().insert(key, value);
+    }
+
+    pub fn access_or_insert_num(&mut self, arr: GlobalArrayId, key: ArrayKey) -> &mut f64 {
+        let array = unwrap(self.arrays.get_mut(arr.id));
+        let num = array.nums().get_or_insert(key, || 0.0);
+        if is_uninit_num(*num) {
+            *num = 0.0;
+        }
+        num
     }
 }
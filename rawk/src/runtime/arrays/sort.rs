@@ -0,0 +1,197 @@
+use std::cmp::Ordering;
+use crate::runtime::converter::Converter;
+use crate::vm::RuntimeScalar;
+
+// The orderings gawk predefines for PROCINFO["sorted_in"], asort and asorti
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortBy {
+    Unsorted,
+    IndStr,
+    IndNum,
+    ValType,
+    ValStr,
+    ValNum,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortOrder {
+    pub by: SortBy,
+    pub desc: bool,
+}
+
+impl SortOrder {
+    pub const UNSORTED: SortOrder = SortOrder { by: SortBy::Unsorted, desc: false };
+    pub const IND_STR_ASC: SortOrder = SortOrder { by: SortBy::IndStr, desc: false };
+    pub const VAL_TYPE_ASC: SortOrder = SortOrder { by: SortBy::ValType, desc: false };
+
+    // None if name isn't one of the predefined orderings, it's then the name of a user function
+    pub fn parse(name: &[u8]) -> Option<Self> {
+        if name == b"@unsorted" {
+            return Some(SortOrder::UNSORTED);
+        }
+        let name = name.strip_prefix(b"@")?;
+        let split = name.iter().rposition(|b| *b == b'_')?;
+        let by = match &name[..split] {
+            b"ind_str" => SortBy::IndStr,
+            b"ind_num" => SortBy::IndNum,
+            b"val_type" => SortBy::ValType,
+            b"val_str" => SortBy::ValStr,
+            b"val_num" => SortBy::ValNum,
+            _ => return None,
+        };
+        let desc = match &name[split + 1..] {
+            b"asc" => false,
+            b"desc" => true,
+            _ => return None,
+        };
+        Some(SortOrder { by, desc })
+    }
+}
+
+// What an element is compared by, in order: rank (numbers before strings), number, string.
+// Elements that tie are ordered by their index as a string.
+struct SortKey {
+    rank: u8,
+    num: f64,
+    str: Vec<u8>,
+}
+
+impl SortKey {
+    fn new(by: SortBy, index: &[u8], value: &RuntimeScalar, converter: &mut Converter) -> Self {
+        let mut key = SortKey { rank: 0, num: 0.0, str: vec![] };
+        match by {
+            SortBy::Unsorted | SortBy::IndStr => {}
+            SortBy::IndNum => key.num = converter.str_to_num(index).unwrap_or(0.0),
+            SortBy::ValType => {
+                if is_numeric(value, converter) {
+                    key.num = to_num(value, converter);
+                } else {
+                    key.rank = 1;
+                    key.str = to_bytes(value, converter);
+                }
+            }
+            SortBy::ValStr => key.str = to_bytes(value, converter),
+            SortBy::ValNum => {
+                key.num = to_num(value, converter);
+                key.str = to_bytes(value, converter);
+            }
+        }
+        key
+    }
+
+    fn cmp(&self, other: &SortKey) -> Ordering {
+        self.rank.cmp(&other.rank)
+            .then_with(|| self.num.total_cmp(&other.num))
+            .then_with(|| self.str.cmp(&other.str))
+    }
+}
+
+fn is_numeric(value: &RuntimeScalar, converter: &mut Converter) -> bool {
+    match value {
+        RuntimeScalar::Num(_) => true,
+        RuntimeScalar::Str(_) => false,
+        RuntimeScalar::StrNum(str) => str.is_empty() || converter.looks_numeric(str),
+    }
+}
+
+fn to_num(value: &RuntimeScalar, converter: &mut Converter) -> f64 {
+    match value {
+        RuntimeScalar::Num(num) => *num,
+        RuntimeScalar::Str(str) | RuntimeScalar::StrNum(str) => converter.str_to_num(str).unwrap_or(0.0),
+    }
+}
+
+fn to_bytes(value: &RuntimeScalar, converter: &mut Converter) -> Vec<u8> {
+    match value {
+        RuntimeScalar::Num(num) => converter.num_to_str_internal(*num).to_vec(),
+        RuntimeScalar::Str(str) | RuntimeScalar::StrNum(str) => str.bytes().to_vec(),
+    }
+}
+
+pub fn sort_entries(entries: Vec<(Vec<u8>, RuntimeScalar)>, order: SortOrder, converter: &mut Converter) -> Vec<(Vec<u8>, RuntimeScalar)> {
+    if order.by == SortBy::Unsorted {
+        return entries;
+    }
+    let mut keyed: Vec<(SortKey, (Vec<u8>, RuntimeScalar))> = entries.into_iter()
+        .map(|entry| (SortKey::new(order.by, &entry.0, &entry.1, converter), entry))
+        .collect();
+    keyed.sort_by(|(key_a, (idx_a, _)), (key_b, (idx_b, _))| {
+        let ordering = key_a.cmp(key_b).then_with(|| idx_a.cmp(idx_b));
+        if order.desc { ordering.reverse() } else { ordering }
+    });
+    keyed.into_iter().map(|(_key, entry)| entry).collect()
+}
+
+// Stable merge sort for user comparison functions. Unlike slice::sort_by it's fine with
+// comparisons that aren't a total order, it just produces some order.
+pub fn merge_sort_by<T>(mut items: Vec<T>, cmp: &mut dyn FnMut(&T, &T) -> Ordering) -> Vec<T> {
+    if items.len() <= 1 {
+        return items;
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort_by(items, cmp);
+    let right = merge_sort_by(right, cmp);
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        if cmp(r, l) == Ordering::Less {
+            merged.push(right.next().unwrap());
+        } else {
+            merged.push(left.next().unwrap());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::awk_str::RcAwkStr;
+    use crate::runtime::arrays::sort::{merge_sort_by, sort_entries, SortBy, SortOrder};
+    use crate::runtime::converter::Converter;
+    use crate::vm::RuntimeScalar;
+
+    fn str(s: &str) -> RuntimeScalar {
+        RuntimeScalar::Str(RcAwkStr::new_bytes(s.as_bytes().to_vec()))
+    }
+
+    fn indices(entries: &[(Vec<u8>, RuntimeScalar)]) -> Vec<String> {
+        entries.iter().map(|(idx, _)| String::from_utf8(idx.clone()).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(SortOrder::parse(b"@ind_str_asc"), Some(SortOrder::IND_STR_ASC));
+        assert_eq!(SortOrder::parse(b"@val_num_desc"), Some(SortOrder { by: SortBy::ValNum, desc: true }));
+        assert_eq!(SortOrder::parse(b"@unsorted"), Some(SortOrder::UNSORTED));
+        assert_eq!(SortOrder::parse(b"@val_num_up"), None);
+        assert_eq!(SortOrder::parse(b"cmp_func"), None);
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let entries = vec![
+            (b"10".to_vec(), str("b")),
+            (b"9".to_vec(), RuntimeScalar::Num(3.0)),
+            (b"x".to_vec(), str("a")),
+            (b"1".to_vec(), RuntimeScalar::Num(20.0)),
+        ];
+        let mut conv = Converter::new();
+        let sorted = |by, desc, conv: &mut Converter| indices(&sort_entries(entries.clone(), SortOrder { by, desc }, conv));
+        assert_eq!(sorted(SortBy::IndStr, false, &mut conv), vec!["1", "10", "9", "x"]);
+        assert_eq!(sorted(SortBy::IndNum, false, &mut conv), vec!["x", "1", "9", "10"]);
+        assert_eq!(sorted(SortBy::IndNum, true, &mut conv), vec!["10", "9", "1", "x"]);
+        assert_eq!(sorted(SortBy::ValType, false, &mut conv), vec!["9", "1", "x", "10"]);
+        assert_eq!(sorted(SortBy::ValStr, false, &mut conv), vec!["1", "9", "x", "10"]);
+        assert_eq!(sorted(SortBy::ValNum, false, &mut conv), vec!["x", "10", "9", "1"]);
+    }
+
+    #[test]
+    fn test_merge_sort_is_stable() {
+        let items = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd'), (1, 'e')];
+        let sorted = merge_sort_by(items, &mut |a, b| a.0.cmp(&b.0));
+        assert_eq!(sorted, vec![(1, 'b'), (1, 'e'), (2, 'd'), (3, 'a'), (3, 'c')]);
+    }
+}
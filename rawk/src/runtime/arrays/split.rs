@@ -1,4 +1,5 @@
 use mawk_regex::Regex;
+use crate::runtime::columns::is_blank;
 use crate::util::index_in_slice;
 
 
@@ -7,6 +8,7 @@ pub struct RegexSplit<'a> {
     string: &'a [u8],
     regex: &'a Regex,
     start: usize,
+    done: bool,
 }
 
 pub fn split_on_regex<'a>(regex: &'a Regex, string: &'a [u8], ) -> RegexSplit<'a> {
@@ -14,22 +16,25 @@ pub fn split_on_regex<'a>(regex: &'a Regex, string: &'a [u8], ) -> RegexSplit<'a
         string,
         regex,
         start: 0,
+        done: string.is_empty(),
     }
 }
 
-// Used for the builtin split() function when splitting on FS
+// Used for the builtin split() function when splitting on FS. A single space splits on
+// runs of blanks ignoring leading and trailing blanks like field splitting does.
 pub struct StringSplit<'a> {
     string: &'a [u8],
     sep: &'a [u8],
     start: usize,
-
+    done: bool,
 }
 
 pub fn split_on_string<'a>(sep: &'a [u8], string: &'a [u8]) -> StringSplit<'a> {
     StringSplit {
         string,
         sep,
-        start: 0
+        start: 0,
+        done: string.is_empty(),
     }
 }
 
@@ -37,21 +42,19 @@ impl<'a> Iterator for RegexSplit<'a> {
     type Item = &'a [u8];
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
         let slice = &self.string[self.start..];
-        if slice.len() > 0 {
-            if let Some(idx) = self.regex.match_idx(slice) {
-                let res = &self.string[self.start..self.start+idx.start];
-                self.start += idx.len + idx.start;
-                Some(res)
-            } else {
-                let res = &self.string[self.start..];
-                self.start = self.string.len();
-                Some(res)
-            }
+        if let Some(idx) = self.regex.match_idx(slice) {
+            let res = &self.string[self.start..self.start + idx.start];
+            self.start += idx.len + idx.start;
+            Some(res)
         } else {
-            None
+            // Last field may be empty if the string ends with a separator
+            self.done = true;
+            Some(slice)
         }
-
     }
 }
 
@@ -59,21 +62,32 @@ impl<'a> Iterator for StringSplit<'a> {
     type Item = &'a [u8];
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.sep == b" " {
+            let slice = &self.string[self.start..];
+            let start = match slice.iter().position(|b| !is_blank(*b)) {
+                Some(start) => start,
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            };
+            let len = slice[start..].iter().position(|b| is_blank(*b)).unwrap_or(slice.len() - start);
+            self.start += start + len;
+            return Some(&slice[start..start + len]);
+        }
         let slice = &self.string[self.start..];
-        if slice.len() > 0 {
-            if let Some(idx) = index_in_slice(self.sep, slice) {
-                let res = &self.string[self.start..self.start+idx];
-                self.start += idx + self.sep.len();
-                Some(res)
-            } else {
-                let res = &self.string[self.start..];
-                self.start = self.string.len();
-                Some(res)
-            }
+        if let Some(idx) = index_in_slice(self.sep, slice) {
+            let res = &self.string[self.start..self.start + idx];
+            self.start += idx + self.sep.len();
+            Some(res)
         } else {
-            None
+            // Last field may be empty if the string ends with a separator
+            self.done = true;
+            Some(slice)
         }
-
     }
 }
 
@@ -101,6 +115,19 @@ mod tests {
         assert_eq!(split_on_string(&split, "aBBBcBBBd".as_bytes()).collect::<Vec<&[u8]>>(), vec!["a".as_bytes(), "c".as_bytes(), "d".as_bytes()]);
     }
 
+    #[test]
+    fn test_split_trailing_sep() {
+        let split = ",".as_bytes();
+        assert_eq!(split_on_string(split, "a,,b,".as_bytes()).collect::<Vec<&[u8]>>(), vec!["a".as_bytes(), "".as_bytes(), "b".as_bytes(), "".as_bytes()]);
+    }
+
+    #[test]
+    fn test_split_on_space() {
+        let split = " ".as_bytes();
+        assert_eq!(split_on_string(split, " \ta  b\n c ".as_bytes()).collect::<Vec<&[u8]>>(), vec!["a".as_bytes(), "b".as_bytes(), "c".as_bytes()]);
+        assert_eq!(split_on_string(split, "   ".as_bytes()).count(), 0);
+    }
+
     #[test]
     fn test_split_nothing_str() {
         let split = "AKASDFJASLKDFJLA".as_bytes();
@@ -0,0 +1,165 @@
+use hashbrown::HashMap;
+use crate::runtime::arrays::{ArrayKey, MapKey};
+
+// Elements of one array. Arrays keyed by 1..=n, like the output of split() or a[NR] = $0, are
+// kept in a Vec indexed by key - 1. The first key that isn't one of those or the next one moves
+// every element into a map keyed by string.
+pub enum Storage<V> {
+    Dense(Vec<V>),
+    Hashed(HashMap<MapKey, V>),
+}
+
+impl<V> Storage<V> {
+    pub fn new() -> Self {
+        Storage::Dense(vec![])
+    }
+
+    pub fn get(&self, key: &ArrayKey) -> Option<&V> {
+        match self {
+            Storage::Dense(vec) => key.dense_idx().and_then(|idx| vec.get(idx - 1)),
+            Storage::Hashed(map) => map.get(&key.map_key()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Storage::Dense(vec) => vec.len(),
+            Storage::Hashed(map) => map.len(),
+        }
+    }
+
+    pub fn contains(&self, key: &ArrayKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: ArrayKey, value: V) -> Option<V> {
+        match self.slot(&key) {
+            Some(idx) => {
+                let vec = self.dense();
+                if idx == vec.len() + 1 {
+                    vec.push(value);
+                    None
+                } else {
+                    Some(std::mem::replace(&mut vec[idx - 1], value))
+                }
+            }
+            None => self.hashed().insert(key.map_key(), value),
+        }
+    }
+
+    pub fn get_or_insert(&mut self, key: ArrayKey, default: impl FnOnce() -> V) -> &mut V {
+        match self.slot(&key) {
+            Some(idx) => {
+                let vec = self.dense();
+                if idx == vec.len() + 1 {
+                    vec.push(default());
+                }
+                &mut vec[idx - 1]
+            }
+            None => self.hashed().entry(key.map_key()).or_insert_with(default),
+        }
+    }
+
+    // Removes every element. The array starts out dense again.
+    pub fn drain(&mut self) -> impl Iterator<Item=V> + '_ {
+        let old = std::mem::replace(self, Storage::new());
+        let (dense, hashed) = match old {
+            Storage::Dense(vec) => (Some(vec), None),
+            Storage::Hashed(map) => (None, Some(map)),
+        };
+        dense.into_iter().flatten().chain(hashed.into_iter().flat_map(|map| map.into_values()))
+    }
+
+    // Every element with its key in string form. Hashed arrays are in no particular order.
+    pub fn entries(&self) -> Vec<(Vec<u8>, &V)> {
+        match self {
+            Storage::Dense(vec) => vec.iter().enumerate().map(|(idx, value)| ((idx + 1).to_string().into_bytes(), value)).collect(),
+            Storage::Hashed(map) => map.iter().map(|(key, value)| (key.key.bytes().to_vec(), value)).collect(),
+        }
+    }
+
+    // Index into the Vec where key is or would be pushed. None if the array is or must become hashed.
+    fn slot(&self, key: &ArrayKey) -> Option<usize> {
+        match self {
+            Storage::Dense(vec) => key.dense_idx().filter(|idx| *idx <= vec.len() + 1),
+            Storage::Hashed(_) => None,
+        }
+    }
+
+    fn dense(&mut self) -> &mut Vec<V> {
+        match self {
+            Storage::Dense(vec) => vec,
+            Storage::Hashed(_) => unreachable!("array is not dense"),
+        }
+    }
+
+    fn hashed(&mut self) -> &mut HashMap<MapKey, V> {
+        if let Storage::Dense(vec) = self {
+            let mut map = HashMap::with_capacity(vec.len());
+            for (idx, value) in vec.drain(..).enumerate() {
+                map.insert(ArrayKey::Int(idx + 1).map_key(), value);
+            }
+            *self = Storage::Hashed(map);
+        }
+        match self {
+            Storage::Hashed(map) => map,
+            Storage::Dense(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::awk_str::RcAwkStr;
+    use crate::runtime::arrays::ArrayKey;
+    use crate::runtime::arrays::storage::Storage;
+
+    fn str_key(key: &str) -> ArrayKey {
+        ArrayKey::Str(RcAwkStr::new_bytes(key.as_bytes().to_vec()))
+    }
+
+    #[test]
+    fn test_dense_until_gap() {
+        let mut storage: Storage<usize> = Storage::new();
+        storage.insert(ArrayKey::Int(1), 1);
+        storage.insert(str_key("2"), 2);
+        storage.insert(ArrayKey::Int(2), 3);
+        assert!(matches!(storage, Storage::Dense(ref vec) if vec == &vec![1, 3]));
+        storage.insert(ArrayKey::Int(4), 4);
+        assert!(matches!(storage, Storage::Hashed(_)));
+        assert_eq!(storage.get(&str_key("1")), Some(&1));
+        assert_eq!(storage.get(&ArrayKey::Int(2)), Some(&3));
+        assert_eq!(storage.get(&str_key("4")), Some(&4));
+        assert_eq!(storage.get(&ArrayKey::Int(3)), None);
+        assert_eq!(storage.len(), 3);
+        let mut entries = storage.entries();
+        entries.sort();
+        assert_eq!(entries, vec![(b"1".to_vec(), &1), (b"2".to_vec(), &3), (b"4".to_vec(), &4)]);
+    }
+
+    #[test]
+    fn test_non_canonical_keys_are_hashed() {
+        for key in ["01", "0", "", "1.0", "-1", " 1"] {
+            let mut storage: Storage<usize> = Storage::new();
+            storage.insert(ArrayKey::Int(1), 1);
+            storage.insert(str_key(key), 2);
+            assert!(matches!(storage, Storage::Hashed(_)));
+            assert_eq!(storage.get(&ArrayKey::Int(1)), Some(&1));
+            assert_eq!(storage.get(&str_key(key)), Some(&2));
+        }
+    }
+
+    #[test]
+    fn test_drain_resets_to_dense() {
+        let mut storage: Storage<usize> = Storage::new();
+        storage.insert(str_key("x"), 1);
+        storage.insert(ArrayKey::Int(1), 2);
+        let mut values: Vec<usize> = storage.drain().collect();
+        values.sort();
+        assert_eq!(values, vec![1, 2]);
+        assert!(matches!(storage, Storage::Dense(ref vec) if vec.is_empty()));
+        assert!(!storage.contains(&ArrayKey::Int(1)));
+        *storage.get_or_insert(ArrayKey::Int(1), || 5) += 1;
+        assert_eq!(storage.get(&str_key("1")), Some(&6));
+    }
+}
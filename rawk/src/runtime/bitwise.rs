@@ -0,0 +1,114 @@
+use crate::printable_error::PrintableError;
+
+// Results are kept to the integers an f64 holds exactly, like gawk
+const MAX_EXACT_INT: u64 = (1 << f64::MANTISSA_DIGITS) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    Lshift,
+    Rshift,
+}
+
+impl BitOp {
+    fn name(&self) -> &'static str {
+        match self {
+            BitOp::And => "and",
+            BitOp::Or => "or",
+            BitOp::Xor => "xor",
+            BitOp::Lshift => "lshift",
+            BitOp::Rshift => "rshift",
+        }
+    }
+}
+
+// Fractions are truncated. Negative values are fatal in gawk, there's no sensible unsigned value for them.
+fn to_uint(func: &str, arg: usize, value: f64) -> Result<u64, PrintableError> {
+    if value < 0.0 {
+        return Err(PrintableError::new(format!("fatal: {}: argument {} negative value {} is not allowed", func, arg, value)));
+    }
+    Ok(value as u64)
+}
+
+pub fn bit_op(op: BitOp, left: f64, right: f64) -> Result<f64, PrintableError> {
+    let left = to_uint(op.name(), 1, left)?;
+    let right = to_uint(op.name(), 2, right)?;
+    let shift = u32::try_from(right).unwrap_or(u32::MAX);
+    let result = match op {
+        BitOp::And => left & right,
+        BitOp::Or => left | right,
+        BitOp::Xor => left ^ right,
+        BitOp::Lshift => left.checked_shl(shift).unwrap_or(0),
+        BitOp::Rshift => left.checked_shr(shift).unwrap_or(0),
+    };
+    Ok((result & MAX_EXACT_INT) as f64)
+}
+
+pub fn compl(value: f64) -> Result<f64, PrintableError> {
+    let value = to_uint("compl", 1, value)?;
+    Ok((!value & MAX_EXACT_INT) as f64)
+}
+
+// The value of a "0x1F" hex or "017" octal string, None if it's neither and is parsed as a
+// decimal number. Like gawk, parsing stops at the first digit that's not valid for the base.
+pub fn non_decimal_to_num(str: &[u8]) -> Option<f64> {
+    if str.first() != Some(&b'0') {
+        return None;
+    }
+    let (radix, digits) = match str.get(1) {
+        Some(b'x' | b'X') if str.get(2).is_some_and(|b| b.is_ascii_hexdigit()) => (16, &str[2..]),
+        Some(b'0'..=b'7') => {
+            // "019", "01.5" and "01e2" are decimal
+            let run = str.iter().take_while(|b| b.is_ascii_digit()).count();
+            if str[..run].iter().any(|b| *b == b'8' || *b == b'9') || matches!(str.get(run), Some(b'.' | b'e' | b'E')) {
+                return None;
+            }
+            (8, &str[1..])
+        }
+        _ => return None,
+    };
+    let mut num = 0.0;
+    for digit in digits.iter().map_while(|b| (*b as char).to_digit(radix)) {
+        num = num * radix as f64 + digit as f64;
+    }
+    Some(num)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::bitwise::{bit_op, compl, non_decimal_to_num, BitOp};
+
+    #[test]
+    fn test_bit_ops() {
+        assert_eq!(bit_op(BitOp::And, 12.0, 10.0).unwrap(), 8.0);
+        assert_eq!(bit_op(BitOp::Or, 12.0, 10.0).unwrap(), 14.0);
+        assert_eq!(bit_op(BitOp::Xor, 12.0, 10.0).unwrap(), 6.0);
+        assert_eq!(bit_op(BitOp::And, 7.9, 3.2).unwrap(), 3.0);
+        assert_eq!(bit_op(BitOp::Lshift, 1.0, 10.0).unwrap(), 1024.0);
+        assert_eq!(bit_op(BitOp::Lshift, 1.0, 64.0).unwrap(), 0.0);
+        assert_eq!(bit_op(BitOp::Rshift, 1024.0, 3.0).unwrap(), 128.0);
+        assert_eq!(bit_op(BitOp::Rshift, 1024.0, 100.0).unwrap(), 0.0);
+        assert!(bit_op(BitOp::And, -1.0, 1.0).unwrap_err().msg.contains("argument 1 negative"));
+        assert!(bit_op(BitOp::Lshift, 1.0, -1.0).unwrap_err().msg.contains("argument 2 negative"));
+        assert_eq!(compl(0.0).unwrap(), 9007199254740991.0);
+        assert_eq!(compl(9007199254740990.0).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_non_decimal_to_num() {
+        assert_eq!(non_decimal_to_num(b"0x1F"), Some(31.0));
+        assert_eq!(non_decimal_to_num(b"0XffZ"), Some(255.0));
+        assert_eq!(non_decimal_to_num(b"017"), Some(15.0));
+        assert_eq!(non_decimal_to_num(b"0178"), None);
+        assert_eq!(non_decimal_to_num(b"017a"), Some(15.0));
+        assert_eq!(non_decimal_to_num(b"0x"), None);
+        assert_eq!(non_decimal_to_num(b"0.5"), None);
+        assert_eq!(non_decimal_to_num(b"01.5"), None);
+        assert_eq!(non_decimal_to_num(b"01e2"), None);
+        assert_eq!(non_decimal_to_num(b"0"), None);
+        assert_eq!(non_decimal_to_num(b" 0x10"), None);
+        assert_eq!(non_decimal_to_num(b"17"), None);
+    }
+}
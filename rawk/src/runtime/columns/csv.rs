@@ -0,0 +1,163 @@
+use quick_drop_deque::QuickDropDeque;
+
+const QUOTE: u8 = b'"';
+const COMMA: u8 = b',';
+const NEWLINE: u8 = b'\n';
+const CARRIAGE_RETURN: u8 = b'\r';
+
+// Where csv_record_end stopped scanning. Quotes only start a quoted field at the start of a field,
+// the same as csv_fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvScan {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    // Either the closing quote or the first half of ""
+    QuoteInQuoted,
+}
+
+// Finds the newline ending the first csv record in dq. Newlines inside quotes are part of the
+// record. Bytes before scanned were checked by an earlier call and state is where that left off
+// so each read only scans the new bytes.
+pub fn csv_record_end(dq: &QuickDropDeque, scanned: &mut usize, state: &mut CsvScan) -> Option<usize> {
+    let (left, right) = dq.as_slices();
+    while *scanned < dq.len() {
+        let idx = *scanned;
+        let byte = if idx < left.len() { left[idx] } else { right[idx - left.len()] };
+        *scanned += 1;
+        *state = match (*state, byte) {
+            (CsvScan::Quoted, QUOTE) => CsvScan::QuoteInQuoted,
+            (CsvScan::Quoted, _) => CsvScan::Quoted,
+            (CsvScan::QuoteInQuoted, QUOTE) => CsvScan::Quoted,
+            (CsvScan::FieldStart, QUOTE) => CsvScan::Quoted,
+            (_, NEWLINE) => {
+                *state = CsvScan::FieldStart;
+                return Some(idx);
+            }
+            (_, COMMA) => CsvScan::FieldStart,
+            (_, _) => CsvScan::Unquoted,
+        };
+    }
+    None
+}
+
+// Record ends in \r\n, the \r isn't part of the last field
+pub fn ends_with_carriage_return(dq: &QuickDropDeque, end: usize) -> bool {
+    end != 0 && dq.get(end - 1) == Some(&CARRIAGE_RETURN)
+}
+
+// Splits a record on commas outside of quotes. Quoted fields are unquoted and "" becomes ".
+// An empty record has no fields, a trailing comma adds an empty last field.
+pub fn csv_fields(record: &[u8], fields: &mut Vec<Vec<u8>>) {
+    fields.clear();
+    if record.is_empty() {
+        return;
+    }
+    let mut field = vec![];
+    let mut at_field_start = true;
+    let mut in_quotes = false;
+    let mut idx = 0;
+    while idx < record.len() {
+        let byte = record[idx];
+        idx += 1;
+        if in_quotes {
+            if byte != QUOTE {
+                field.push(byte);
+            } else if record.get(idx) == Some(&QUOTE) {
+                field.push(QUOTE);
+                idx += 1;
+            } else {
+                in_quotes = false;
+            }
+        } else if byte == COMMA {
+            fields.push(std::mem::take(&mut field));
+            at_field_start = true;
+            continue;
+        } else if byte == QUOTE && at_field_start {
+            in_quotes = true;
+        } else {
+            field.push(byte);
+        }
+        at_field_start = false;
+    }
+    fields.push(field);
+}
+
+// The opposite of csv_fields for one field. Quotes the field if it contains the separator, a quote,
+// or a newline so it reads back as one field.
+pub fn csv_quote(field: &[u8], separator: &[u8], out: &mut Vec<u8>) {
+    let contains_separator = !separator.is_empty() && field.windows(separator.len()).any(|w| w == separator);
+    if !contains_separator && !field.iter().any(|b| *b == QUOTE || *b == NEWLINE) {
+        out.extend_from_slice(field);
+        return;
+    }
+    out.push(QUOTE);
+    for byte in field {
+        if *byte == QUOTE {
+            out.push(QUOTE);
+        }
+        out.push(*byte);
+    }
+    out.push(QUOTE);
+}
+
+#[cfg(test)]
+mod tests {
+    use quick_drop_deque::QuickDropDeque;
+    use crate::runtime::columns::csv::{csv_fields, csv_quote, csv_record_end, CsvScan};
+
+    fn fields(record: &str) -> Vec<String> {
+        let mut fields = vec![];
+        csv_fields(record.as_bytes(), &mut fields);
+        fields.into_iter().map(|f| String::from_utf8(f).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_csv_fields() {
+        assert_eq!(fields(""), Vec::<String>::new());
+        assert_eq!(fields("a,b,c"), vec!["a", "b", "c"]);
+        assert_eq!(fields("a,,"), vec!["a", "", ""]);
+        assert_eq!(fields(" a , b"), vec![" a ", " b"]);
+        assert_eq!(fields("\"a,b\",c"), vec!["a,b", "c"]);
+        assert_eq!(fields("\"say \"\"hi\"\"\",x"), vec!["say \"hi\"", "x"]);
+        assert_eq!(fields("\"two\nlines\""), vec!["two\nlines"]);
+        assert_eq!(fields("a\"b\",c"), vec!["a\"b\"", "c"]);
+        assert_eq!(fields("\"a\"b,c"), vec!["ab", "c"]);
+        assert_eq!(fields("\"\""), vec![""]);
+    }
+
+    #[test]
+    fn test_csv_record_end() {
+        let mut dq = QuickDropDeque::new();
+        dq.extend_from_slice(b"a,\"b\n");
+        let (mut scanned, mut state) = (0, CsvScan::FieldStart);
+        assert_eq!(csv_record_end(&dq, &mut scanned, &mut state), None);
+        assert_eq!(scanned, 5);
+        assert_eq!(state, CsvScan::Quoted);
+        dq.extend_from_slice(b"c\"\"\"\nd\n");
+        assert_eq!(csv_record_end(&dq, &mut scanned, &mut state), Some(9));
+        assert_eq!(state, CsvScan::FieldStart);
+
+        // A quote in the middle of a field doesn't start a quoted field
+        let mut dq = QuickDropDeque::new();
+        dq.extend_from_slice(b"a\"b\nc\n");
+        let (mut scanned, mut state) = (0, CsvScan::FieldStart);
+        assert_eq!(csv_record_end(&dq, &mut scanned, &mut state), Some(3));
+    }
+
+    #[test]
+    fn test_csv_quote() {
+        let quote = |field: &str, separator: &str| {
+            let mut out = vec![];
+            csv_quote(field.as_bytes(), separator.as_bytes(), &mut out);
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(quote("abc", ","), "abc");
+        assert_eq!(quote("a,c", ","), "\"a,c\"");
+        assert_eq!(quote("a,c", " "), "a,c");
+        assert_eq!(quote("a c", " "), "\"a c\"");
+        assert_eq!(quote("say \"hi\"", ","), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("a\nb", ","), "\"a\nb\"");
+        assert_eq!(quote("", ","), "");
+    }
+}
@@ -1,15 +1,17 @@
 use std::cmp::min;
-use std::fs::File;
+use std::io::Read;
 use crate::printable_error::PrintableError;
 
+use mawk_regex::Regex;
 use quick_drop_deque::QuickDropDeque;
-use crate::runtime::columns::splitter::get_into;
-use crate::util::index_in_full_dq;
+use crate::runtime::columns::csv::{csv_fields, csv_record_end, ends_with_carriage_return, CsvScan};
+use crate::runtime::columns::splitter::{count, fixed_width_count, fixed_width_field, fpat_fields, get_into, parse_field_widths, split_slice, SplitMode};
+use crate::util::{index_in_full_dq, subslices};
 
 #[allow(dead_code)]
 struct FileWithPath {
     path: String,
-    file: File,
+    file: Box<dyn Read>,
 }
 
 pub struct FileReader {
@@ -19,6 +21,20 @@ pub struct FileReader {
     next_rs: Option<Vec<u8>>,
     fs: Vec<u8>,
     next_fs: Option<Vec<u8>>,
+    split: SplitMode,
+    next_split: Option<SplitMode>,
+    // An invalid FIELDWIDTHS, reported when the next record is read
+    split_error: Option<PrintableError>,
+    // Where each field of the current record is when splitting with FPAT. Matching the regex
+    // is slow so the record is only split once, the first time a field is needed.
+    field_positions: Option<Vec<(usize, usize)>>,
+    record: Vec<u8>,
+    // --csv, records and fields follow RFC 4180 and RS, FS, FPAT and FIELDWIDTHS are ignored
+    csv: bool,
+    // The unquoted fields of the current csv record, split the first time a field is needed
+    unquoted_fields: Option<Vec<Vec<u8>>>,
+    // The \n or \r\n after the current csv record, dropped with it
+    csv_newline_len: usize,
     end_of_current_record: usize,
 }
 
@@ -31,23 +47,57 @@ impl FileReader {
             fs: vec![32],  //space
             next_rs: None,
             next_fs: None,
+            split: SplitMode::Fs,
+            next_split: None,
+            split_error: None,
+            field_positions: None,
+            record: vec![],
+            csv: false,
+            unquoted_fields: None,
+            csv_newline_len: 0,
             end_of_current_record: 0,
         }
     }
 
-    pub fn next_file(&mut self, file: File, path: String) {
+    pub fn next_file(&mut self, file: Box<dyn Read>, path: String) {
         self.file = Some(FileWithPath { file, path })
     }
 
+    // nextfile, drops what's left of the current file so the next read reaches its end
+    pub fn skip_file(&mut self) {
+        self.file = None;
+        self.slop.drop_front(self.slop.len());
+        self.end_of_current_record = 0;
+        self.field_positions = None;
+        self.unquoted_fields = None;
+        self.csv_newline_len = 0;
+    }
+
+    pub fn enable_csv(&mut self) {
+        self.csv = true;
+    }
+
+    pub fn is_csv(&self) -> bool {
+        self.csv
+    }
+
     pub fn try_next_record(&mut self) -> Result<bool, PrintableError> {
+        if self.csv {
+            return self.try_next_csv_record();
+        }
         let file = if let Some(file) = &mut self.file {
             file
         } else {
             return Ok(false);
         };
 
+        if let Some(err) = self.split_error.take() {
+            return Err(err);
+        }
+
         // Drop last record if any
         self.slop.drop_front(self.end_of_current_record);
+        self.field_positions = None;
 
         let mut rs_idx = index_in_full_dq(&self.rs, &self.slop);
         if rs_idx == Some(0) {
@@ -63,10 +113,13 @@ impl FileReader {
             rs_idx = None;
         }
 
-        // Swap to the new fs if any
+        // Swap to the new fs and splitting mode if any
         if let Some(next_fs) = self.next_fs.take() {
             self.fs = next_fs;
         }
+        if let Some(next_split) = self.next_split.take() {
+            self.split = next_split;
+        }
 
         loop {
             // Check if our last read grabbed more than 1 record if so we're done
@@ -84,7 +137,7 @@ impl FileReader {
                 // No more data!
                 self.end_of_current_record = self.slop.len();
 
-                if self.slop.len() != 0 {
+                if !self.slop.is_empty() {
                     // Reached EOF but we have slop from last read without RS completing it
                     return Ok(true);
                 } else {
@@ -95,6 +148,38 @@ impl FileReader {
         }
     }
 
+    // A csv record ends at the first newline outside of quotes so it may span many lines
+    fn try_next_csv_record(&mut self) -> Result<bool, PrintableError> {
+        let file = if let Some(file) = &mut self.file {
+            file
+        } else {
+            return Ok(false);
+        };
+
+        self.slop.drop_front(self.end_of_current_record + self.csv_newline_len);
+        self.csv_newline_len = 0;
+        self.unquoted_fields = None;
+
+        let mut scanned = 0;
+        let mut state = CsvScan::FieldStart;
+        loop {
+            if let Some(idx) = csv_record_end(&self.slop, &mut scanned, &mut state) {
+                self.csv_newline_len = if ends_with_carriage_return(&self.slop, idx) { 2 } else { 1 };
+                self.end_of_current_record = idx + 1 - self.csv_newline_len;
+                return Ok(true);
+            }
+            let bytes_read = match self.slop.read(&mut file.file) {
+                Ok(b) => b,
+                Err(err) => return Err(PrintableError::new(format!("Something went wrong reading from file `{}`. Error: {}", &file.path, err))),
+            };
+            if bytes_read == 0 {
+                // Last record without a newline
+                self.end_of_current_record = self.slop.len();
+                return Ok(!self.slop.is_empty());
+            }
+        }
+    }
+
     pub fn get_into_buf(&mut self, idx: usize, result: &mut Vec<u8>) {
         if idx == 0 {
             let slices = self.slop.as_slices();
@@ -105,8 +190,89 @@ impl FileReader {
                 let remaining = bytes_to_move - elements_from_left;
                 result.extend_from_slice(&slices.1[0..remaining]);
             }
+        } else if self.csv {
+            if let Some(field) = self.unquoted_fields().get(idx - 1) {
+                result.extend_from_slice(field);
+            }
         } else {
-            get_into(&self.fs, &self.slop, idx, self.end_of_current_record, result);
+            match &self.split {
+                SplitMode::Fs => get_into(&self.fs, &self.slop, idx, self.end_of_current_record, result),
+                SplitMode::Fpat(_) => {
+                    if let Some((start, end)) = self.field_positions().get(idx - 1).copied() {
+                        result.extend_from_slice(&self.record[start..end]);
+                    }
+                }
+                SplitMode::FieldWidths(widths) => {
+                    if let Some((start, end)) = fixed_width_field(widths, idx, self.end_of_current_record) {
+                        let (left, right) = subslices(&self.slop, start, end);
+                        result.extend_from_slice(left);
+                        result.extend_from_slice(right);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn nf(&mut self) -> usize {
+        if self.csv {
+            return self.unquoted_fields().len();
+        }
+        match &self.split {
+            SplitMode::Fs => count(&self.fs, &self.slop, self.end_of_current_record),
+            SplitMode::Fpat(_) => self.field_positions().len(),
+            SplitMode::FieldWidths(widths) => fixed_width_count(widths, self.end_of_current_record),
+        }
+    }
+
+    fn field_positions(&mut self) -> &[(usize, usize)] {
+        if self.field_positions.is_none() {
+            let mut positions = vec![];
+            if let SplitMode::Fpat(fpat) = &self.split {
+                let (left, right) = subslices(&self.slop, 0, self.end_of_current_record);
+                self.record.clear();
+                self.record.extend_from_slice(left);
+                self.record.extend_from_slice(right);
+                fpat_fields(fpat, &self.record, &mut positions);
+            }
+            self.field_positions = Some(positions);
+        }
+        self.field_positions.as_ref().unwrap()
+    }
+
+    fn unquoted_fields(&mut self) -> &[Vec<u8>] {
+        if self.unquoted_fields.is_none() {
+            let (left, right) = subslices(&self.slop, 0, self.end_of_current_record);
+            self.record.clear();
+            self.record.extend_from_slice(left);
+            self.record.extend_from_slice(right);
+            let mut fields = vec![];
+            csv_fields(&self.record, &mut fields);
+            self.unquoted_fields = Some(fields);
+        }
+        self.unquoted_fields.as_ref().unwrap()
+    }
+
+    // Splits a record assigned by the program the same way records from the file are split
+    pub fn split_record(&self, record: &[u8], fields: &mut Vec<Vec<u8>>) {
+        if self.csv {
+            csv_fields(record, fields);
+            return;
+        }
+        match &self.split {
+            SplitMode::Fs => split_slice(&self.fs, record, fields),
+            SplitMode::Fpat(fpat) => {
+                let mut positions = vec![];
+                fpat_fields(fpat, record, &mut positions);
+                fields.clear();
+                fields.extend(positions.iter().map(|(start, end)| record[*start..*end].to_vec()));
+            }
+            SplitMode::FieldWidths(widths) => {
+                fields.clear();
+                let count = fixed_width_count(widths, record.len());
+                fields.extend((1..=count)
+                    .filter_map(|idx| fixed_width_field(widths, idx, record.len()))
+                    .map(|(start, end)| record[start..end].to_vec()));
+            }
         }
     }
 
@@ -124,16 +290,37 @@ impl FileReader {
         }
         self.next_rs = Some(rs);
     }
-    pub fn get_rs(&mut self) -> &[u8] {
-        &self.rs
+    // RS as the program last set it, the reader only switches to it at the next record
+    pub fn newest_rs(&self) -> &[u8] {
+        self.next_rs.as_deref().unwrap_or(&self.rs)
     }
     pub fn set_fs(&mut self, bytes: Vec<u8>) {
+        self.next_split = Some(SplitMode::Fs);
         if self.fs == bytes {
             return
         }
         self.next_fs = Some(bytes);
     }
+    pub fn set_fpat(&mut self, bytes: Vec<u8>) {
+        self.next_split = Some(SplitMode::Fpat(Regex::new(&bytes)));
+    }
+    pub fn set_field_widths(&mut self, bytes: &[u8]) {
+        match parse_field_widths(bytes) {
+            Ok(widths) => self.next_split = Some(SplitMode::FieldWidths(widths)),
+            Err(err) => self.split_error = Some(err),
+        }
+    }
     pub fn get_fs(&mut self) -> &[u8] {
         &self.fs
     }
+    // Assigning to $0 splits it with the newest FS or FPAT instead of waiting for the next record
+    pub fn apply_next_split(&mut self) {
+        if let Some(next_fs) = self.next_fs.take() {
+            self.fs = next_fs;
+        }
+        if let Some(next_split) = self.next_split.take() {
+            self.split = next_split;
+            self.field_positions = None;
+        }
+    }
 }
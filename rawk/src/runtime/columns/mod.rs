@@ -1,88 +1,324 @@
 mod splitter;
+mod csv;
 mod file_reader;
+mod redirects;
 #[cfg(test)]
 mod tests;
 mod record_state;
 
+use std::ffi::CStr;
 use std::fs::File;
+use std::io::{Cursor, Read, stdin};
 use crate::awk_str::{AwkStr, RcAwkStr};
+use crate::runtime::columns::csv::csv_quote;
 use crate::runtime::columns::file_reader::FileReader;
+use crate::runtime::columns::redirects::Redirects;
 use crate::printable_error::PrintableError;
 
 pub use record_state::RecordState;
+pub use redirects::GetlineRead;
+pub use splitter::is_blank;
 use crate::runtime::columns::record_state::RecordStateOutput;
 
+// Once the program assigns to $0, a field, or NF the record no longer matches what was read.
+// It lives here until the next record is read.
+struct ModifiedRecord {
+    record: Vec<u8>,
+    fields: Vec<Vec<u8>>,
+}
+
+// Where records are read from. The command line only gives paths where "-" is stdin,
+// programs embedding rawk can hand over any reader.
+pub enum Input {
+    Path(String),
+    // name is only used in error messages
+    Reader { name: String, reader: Box<dyn Read> },
+    // A name=value operand. The variable is assigned when reading reaches it, arg is the
+    // operand as given for ARGV.
+    Assignment { arg: String, name: String, value: AwkStr },
+}
+
+impl Input {
+    pub fn reader<S: Into<String>>(name: S, reader: Box<dyn Read>) -> Self {
+        Input::Reader { name: name.into(), reader }
+    }
+    pub fn bytes<S: Into<String>, B: Into<Vec<u8>>>(name: S, bytes: B) -> Self {
+        Input::reader(name, Box::new(Cursor::new(bytes.into())))
+    }
+    // How the input appears in ARGV
+    pub fn arg(&self) -> &str {
+        match self {
+            Input::Path(path) => path,
+            Input::Reader { name, .. } => name,
+            Input::Assignment { arg, .. } => arg,
+        }
+    }
+    fn is_assignment(&self) -> bool {
+        matches!(self, Input::Assignment { .. })
+    }
+}
+
+// What's next in the file list when the program switches files itself
+pub enum NextInput {
+    // The file's name and the ERRNO message if it couldn't be opened
+    File(RcAwkStr, Option<String>),
+    Assignment(String, AwkStr),
+}
+
+impl From<String> for Input {
+    fn from(path: String) -> Self {
+        Input::Path(path)
+    }
+}
+
 pub struct Columns {
-    files: Vec<String>,
+    files: Vec<Input>,
     reader: FileReader,
+    // Why the current file couldn't be opened, fatal unless BEGINFILE skips it with nextfile
+    open_error: Option<PrintableError>,
+    modified: Option<ModifiedRecord>,
+    ofs: Vec<u8>,
+    nf: Option<usize>,
+    redirects: Redirects,
 }
 
 impl Columns {
-    pub fn new(mut files: Vec<String>) -> Self {
+    pub fn new(mut files: Vec<Input>) -> Self {
+        // With no files awk reads from stdin, after any assignments
+        if files.iter().all(Input::is_assignment) {
+            files.push(Input::Path("-".to_string()));
+        }
         files.reverse();
         Columns {
             files,
             reader: FileReader::new(),
+            open_error: None,
+            modified: None,
+            ofs: vec![32], // space
+            nf: None,
+            redirects: Redirects::new(),
         }
     }
 
     #[cfg(test)]
     pub fn get(&mut self, column: usize) -> Vec<u8> {
+        if self.modified.is_some() {
+            let mut buf = vec![];
+            self.get_into_buf(column, &mut buf);
+            return buf;
+        }
         self.reader.get(column)
     }
 
-    pub fn set(&mut self, _column: usize, _bytes: &[u8]) -> Vec<u8> {
-        todo!()
+    pub fn set(&mut self, column: usize, bytes: &[u8]) {
+        if column == 0 {
+            self.reader.apply_next_split();
+            let mut fields = vec![];
+            self.reader.split_record(bytes, &mut fields);
+            self.nf = Some(fields.len());
+            self.modified = Some(ModifiedRecord { record: bytes.to_vec(), fields });
+            return;
+        }
+        let modified = self.modified();
+        if modified.fields.len() < column {
+            modified.fields.resize(column, vec![]);
+        }
+        modified.fields[column - 1].clear();
+        modified.fields[column - 1].extend_from_slice(bytes);
+        self.rebuild_record();
     }
 
     pub fn get_into_buf(&mut self, column: usize, buf: &mut Vec<u8>) {
         buf.clear();
+        if let Some(modified) = &self.modified {
+            if column == 0 {
+                buf.extend_from_slice(&modified.record);
+            } else if let Some(field) = modified.fields.get(column - 1) {
+                buf.extend_from_slice(field);
+            }
+            return;
+        }
         self.reader.get_into_buf(column, buf);
     }
 
-    fn next_file(&mut self) -> Result<Option<RcAwkStr>, PrintableError> {
-        if let Some(file_path) = self.files.pop() {
-            let file = match File::open(&file_path) {
-                Ok(f) => f,
+    pub fn nf(&mut self) -> usize {
+        if let Some(nf) = self.nf {
+            return nf;
+        }
+        let nf = self.reader.nf();
+        self.nf = Some(nf);
+        nf
+    }
+
+    // Setting NF drops fields past NF or adds empty ones then rebuilds $0
+    pub fn set_nf(&mut self, nf: usize) {
+        let modified = self.modified();
+        modified.fields.resize(nf, vec![]);
+        self.rebuild_record();
+    }
+
+    pub fn set_ofs(&mut self, ofs: Vec<u8>) {
+        self.ofs = ofs;
+    }
+
+    // Copy the record out of the reader so it can be changed
+    fn modified(&mut self) -> &mut ModifiedRecord {
+        if self.modified.is_none() {
+            let mut record = vec![];
+            self.reader.get_into_buf(0, &mut record);
+            let mut fields = vec![];
+            self.reader.split_record(&record, &mut fields);
+            self.modified = Some(ModifiedRecord { record, fields });
+        }
+        self.modified.as_mut().unwrap()
+    }
+
+    // In csv mode fields are quoted so the new $0 splits back into the same fields
+    fn rebuild_record(&mut self) {
+        let modified = self.modified.as_mut().unwrap();
+        modified.record.clear();
+        for (idx, field) in modified.fields.iter().enumerate() {
+            if idx != 0 {
+                modified.record.extend_from_slice(&self.ofs);
+            }
+            if self.reader.is_csv() {
+                csv_quote(field, &self.ofs, &mut modified.record);
+            } else {
+                modified.record.extend_from_slice(field);
+            }
+        }
+        self.nf = Some(modified.fields.len());
+    }
+
+    fn open(input: Input) -> (String, std::io::Result<Box<dyn Read>>) {
+        match input {
+            Input::Path(file_path) if file_path == "-" => (file_path, Ok(Box::new(stdin()))),
+            Input::Path(file_path) => {
+                let file = File::open(&file_path).and_then(|file| {
+                    // Opening a directory works, reading it doesn't
+                    if file.metadata()?.is_dir() {
+                        return Err(std::io::Error::from_raw_os_error(libc::EISDIR));
+                    }
+                    Ok(Box::new(file) as Box<dyn Read>)
+                });
+                (file_path, file)
+            }
+            Input::Reader { name, reader } => (name, Ok(reader)),
+            Input::Assignment { .. } => unreachable!("assignments are not opened"),
+        }
+    }
+
+    fn next_file(&mut self) -> Result<Option<NextInput>, PrintableError> {
+        if let Some(input) = self.files.pop() {
+            if let Input::Assignment { name, value, .. } = input {
+                return Ok(Some(NextInput::Assignment(name, value)));
+            }
+            let (file_path, file) = Columns::open(input);
+            let file = match file {
+                Ok(file) => file,
                 Err(err) => return Err(PrintableError::new(format!("Failed to open file {}\n{}", file_path, err))),
             };
+            let name = RcAwkStr::new_str(&file_path);
             self.reader.next_file(file, file_path);
-            // TODO: real name
-            Ok(Some(RcAwkStr::new_str("")))
+            Ok(Some(NextInput::File(name, None)))
         } else {
             Ok(None)
         }
     }
 
     pub fn next_record(&mut self,
-                       mut state: RecordState,)
+                       state: RecordState,)
                        -> Result<RecordStateOutput, PrintableError> {
         let mut FNR = state.FNR;
         let mut NR = state.NR;
         let mut next_file: Option<RcAwkStr> = None;
+        self.modified = None;
+        self.nf = None;
         loop {
             if self.reader.try_next_record()? {
                 FNR += 1.0;
                 NR += 1.0;
                 return Ok(RecordStateOutput::new(NR, FNR, true, next_file));
             };
-            if let Some(next_file_name) = self.next_file()? {
-                FNR = 0.0;
-                next_file = Some(next_file_name);
-                continue;
-            } else {
-                // TODO: What should state be in the END block
-                return Ok(RecordStateOutput::new(NR, FNR, false, next_file));
+            match self.next_file()? {
+                Some(NextInput::File(next_file_name, _)) => {
+                    FNR = 0.0;
+                    next_file = Some(next_file_name);
+                }
+                // Stop so the assignment happens before the next file is read
+                Some(NextInput::Assignment(name, value)) => {
+                    return Ok(RecordStateOutput::assignment(NR, FNR, next_file, name, value));
+                }
+                None => {
+                    // TODO: What should state be in the END block
+                    return Ok(RecordStateOutput::new(NR, FNR, false, next_file));
+                }
             }
         }
     }
 
-    pub fn set_rs(&mut self, value: Vec<u8>) {
-        self.reader.set_rs(value)
+    // With BEGINFILE and ENDFILE rules files are switched by the program instead of next_record.
+    // Starts the next file, None if there are no more
+    pub fn start_next_file(&mut self) -> Option<NextInput> {
+        let input = self.files.pop()?;
+        if let Input::Assignment { name, value, .. } = input {
+            return Some(NextInput::Assignment(name, value));
+        }
+        self.reader.skip_file();
+        self.modified = None;
+        self.nf = None;
+        let (file_path, file) = Columns::open(input);
+        let name = RcAwkStr::new_str(&file_path);
+        match file {
+            Ok(file) => {
+                self.open_error = None;
+                self.reader.next_file(file, file_path);
+                Some(NextInput::File(name, None))
+            }
+            Err(err) => {
+                let errno = errno_message(&err);
+                self.open_error = Some(PrintableError::new(format!("Failed to open file {}\n{}", file_path, err)));
+                Some(NextInput::File(name, Some(errno)))
+            }
+        }
     }
 
-    pub fn get_rs(&mut self) -> &[u8] {
-        self.reader.get_rs()
+    // The error from start_next_file if the program didn't skip the file
+    pub fn take_open_error(&mut self) -> Option<PrintableError> {
+        self.open_error.take()
+    }
+
+    // Like next_record but stops at the end of the current file
+    pub fn next_record_in_file(&mut self, state: RecordState) -> Result<RecordStateOutput, PrintableError> {
+        self.modified = None;
+        self.nf = None;
+        if self.reader.try_next_record()? {
+            return Ok(RecordStateOutput::new(state.NR + 1.0, state.FNR + 1.0, true, None));
+        }
+        Ok(RecordStateOutput::new(state.NR, state.FNR, false, None))
+    }
+
+    pub fn skip_file(&mut self) {
+        self.reader.skip_file();
+        self.open_error = None;
+        self.modified = None;
+        self.nf = None;
+    }
+
+    pub fn enable_csv(&mut self) {
+        self.reader.enable_csv();
+    }
+
+    // getline < file and cmd | getline split records like the main input
+    pub fn getline_file(&mut self, path: &[u8]) -> GetlineRead {
+        self.redirects.read_file(path, self.reader.newest_rs(), self.reader.is_csv())
+    }
+    pub fn getline_command(&mut self, command: &[u8]) -> GetlineRead {
+        self.redirects.read_command(command, self.reader.newest_rs(), self.reader.is_csv())
+    }
+
+    pub fn set_rs(&mut self, value: Vec<u8>) {
+        self.reader.set_rs(value)
     }
 
     pub fn set_fs(&mut self, value: Vec<u8>) {
@@ -92,4 +328,20 @@ impl Columns {
     pub fn get_fs(&mut self) -> &[u8] {
         self.reader.get_fs()
     }
+
+    pub fn set_fpat(&mut self, value: Vec<u8>) {
+        self.reader.set_fpat(value);
+    }
+
+    pub fn set_field_widths(&mut self, value: Vec<u8>) {
+        self.reader.set_field_widths(&value);
+    }
+}
+
+// The message strerror gives, which is what awks put in ERRNO
+fn errno_message(err: &std::io::Error) -> String {
+    match err.raw_os_error() {
+        Some(code) => unsafe { CStr::from_ptr(libc::strerror(code)) }.to_string_lossy().to_string(),
+        None => err.to_string(),
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::awk_str::RcAwkStr;
+use crate::awk_str::{AwkStr, RcAwkStr};
 
 #[derive(Clone, Copy)]
 pub struct RecordState {
@@ -17,10 +17,15 @@ pub struct RecordStateOutput {
     pub FNR: f64,
     pub next_record: bool,
     pub new_file: Option<RcAwkStr>,
+    // A name=value operand to assign before reading on
+    pub assignment: Option<(String, AwkStr)>,
 }
 
 impl RecordStateOutput {
     pub fn new(NR: f64, FNR: f64, next_record: bool, new_file: Option<RcAwkStr>) -> Self {
-        Self { NR, FNR, next_record, new_file }
+        Self { NR, FNR, next_record, new_file, assignment: None }
+    }
+    pub fn assignment(NR: f64, FNR: f64, new_file: Option<RcAwkStr>, name: String, value: AwkStr) -> Self {
+        Self { NR, FNR, next_record: false, new_file, assignment: Some((name, value)) }
     }
 }
\ No newline at end of file
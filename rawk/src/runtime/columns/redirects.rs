@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{stdin, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::process::{Child, Command, Stdio};
+use crate::runtime::columns::file_reader::FileReader;
+
+// What one getline < file or cmd | getline read
+pub enum GetlineRead {
+    Record(Vec<u8>),
+    End,
+    // The file or command couldn't be opened or read, getline returns -1
+    Error,
+}
+
+struct Redirect {
+    reader: FileReader,
+    // Commands are waited on once the program is done with them
+    child: Option<Child>,
+}
+
+// Files read by getline < file and commands read by cmd | getline. Each stays open so the next
+// getline continues where the last one stopped.
+pub struct Redirects {
+    files: HashMap<Vec<u8>, Redirect>,
+    commands: HashMap<Vec<u8>, Redirect>,
+}
+
+impl Redirects {
+    pub fn new() -> Self {
+        Self { files: HashMap::new(), commands: HashMap::new() }
+    }
+
+    pub fn read_file(&mut self, path: &[u8], rs: &[u8], csv: bool) -> GetlineRead {
+        if !self.files.contains_key(path) {
+            match open_file(path) {
+                Ok(file) => { self.files.insert(path.to_vec(), Redirect::new(file, path, None)); }
+                Err(_) => return GetlineRead::Error,
+            }
+        }
+        self.files.get_mut(path).unwrap().read(rs, csv)
+    }
+
+    // The command runs with sh -c the first time it's read
+    pub fn read_command(&mut self, command: &[u8], rs: &[u8], csv: bool) -> GetlineRead {
+        if !self.commands.contains_key(command) {
+            let spawned = Command::new("sh")
+                .arg("-c")
+                .arg(OsStr::from_bytes(command))
+                .stdout(Stdio::piped())
+                .spawn();
+            match spawned {
+                Ok(mut child) => {
+                    let stdout = Box::new(child.stdout.take().unwrap());
+                    self.commands.insert(command.to_vec(), Redirect::new(stdout, command, Some(child)));
+                }
+                Err(_) => return GetlineRead::Error,
+            }
+        }
+        self.commands.get_mut(command).unwrap().read(rs, csv)
+    }
+}
+
+impl Drop for Redirects {
+    fn drop(&mut self) {
+        for (_command, redirect) in self.commands.drain() {
+            // Close the pipe first so a command with unread output isn't stuck writing it
+            let Redirect { reader, child } = redirect;
+            drop(reader);
+            if let Some(mut child) = child {
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+impl Redirect {
+    fn new(file: Box<dyn Read>, name: &[u8], child: Option<Child>) -> Self {
+        let mut reader = FileReader::new();
+        reader.next_file(file, String::from_utf8_lossy(name).to_string());
+        Self { reader, child }
+    }
+
+    // Records are split with the RS at the time of the read, like the main input
+    fn read(&mut self, rs: &[u8], csv: bool) -> GetlineRead {
+        self.reader.set_rs(rs.to_vec());
+        if csv && !self.reader.is_csv() {
+            self.reader.enable_csv();
+        }
+        match self.reader.try_next_record() {
+            Ok(true) => GetlineRead::Record(self.reader.get(0)),
+            Ok(false) => GetlineRead::End,
+            Err(_) => GetlineRead::Error,
+        }
+    }
+}
+
+fn open_file(path: &[u8]) -> std::io::Result<Box<dyn Read>> {
+    if path == b"-" || path == b"/dev/stdin" {
+        return Ok(Box::new(stdin()));
+    }
+    Ok(Box::new(File::open(OsStr::from_bytes(path))?))
+}
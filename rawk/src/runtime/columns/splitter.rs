@@ -1,40 +1,229 @@
 
+use std::cmp::min;
+use mawk_regex::Regex;
 use quick_drop_deque::QuickDropDeque;
-use crate::util::{index_in_dq, subslices};
+use crate::printable_error::PrintableError;
+use crate::util::{index_in_dq, index_of, subslices};
 
 const SPACE: u8 = 32;
 
-pub fn get(fs: &[u8], dq: &QuickDropDeque, field_idx: usize, end_of_record_idx: usize) -> Vec<u8> {
-    let mut vec = vec![];
-    get_into(fs, dq, field_idx, end_of_record_idx, &mut vec);
-    vec
+// How records are split into fields. Assigning FS or FPAT switches to that mode.
+pub enum SplitMode {
+    Fs,
+    // Fields are the text matching the regex instead of what's between separators
+    Fpat(Regex),
+    FieldWidths(Vec<FieldWidth>),
+}
+
+// One entry of FIELDWIDTHS, skip:width where width None is "*", the rest of the record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldWidth {
+    pub skip: usize,
+    pub width: Option<usize>,
+}
+
+// Parses "5 3 10 *" or "2:5 3". Only the last width can be "*".
+pub fn parse_field_widths(widths: &[u8]) -> Result<Vec<FieldWidth>, PrintableError> {
+    let entries: Vec<&[u8]> = widths.split(|b| is_blank(*b)).filter(|entry| !entry.is_empty()).collect();
+    let mut parsed = Vec::with_capacity(entries.len());
+    for (idx, entry) in entries.iter().enumerate() {
+        let is_last = idx + 1 == entries.len();
+        let (skip, width) = match entry.iter().position(|b| *b == b':') {
+            Some(colon) => (parse_width(&entry[..colon]), &entry[colon + 1..]),
+            None => (Some(0), *entry),
+        };
+        let width = if width == b"*" && is_last { Some(None) } else { parse_width(width).map(Some) };
+        match (skip, width) {
+            (Some(skip), Some(width)) => parsed.push(FieldWidth { skip, width }),
+            _ => return Err(PrintableError::new(format!(
+                "fatal: invalid FIELDWIDTHS value, for field {}, near `{}'", idx + 1, String::from_utf8_lossy(entry)))),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_width(width: &[u8]) -> Option<usize> {
+    if width.is_empty() || !width.iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    std::str::from_utf8(width).ok()?.parse().ok()
+}
+
+// Start and end of FIELDWIDTHS field field_idx (1 based) in a record of len bytes. A field
+// that starts past the end of the record isn't present, one that runs past it is cut short.
+pub fn fixed_width_field(widths: &[FieldWidth], field_idx: usize, len: usize) -> Option<(usize, usize)> {
+    let mut start = 0;
+    for (idx, field) in widths.iter().enumerate() {
+        start += field.skip;
+        if start >= len {
+            return None;
+        }
+        let end = match field.width {
+            Some(width) => min(start + width, len),
+            None => len,
+        };
+        if idx + 1 == field_idx {
+            return Some((start, end));
+        }
+        start = end;
+    }
+    None
+}
+
+// Number of FIELDWIDTHS fields present in a record of len bytes
+pub fn fixed_width_count(widths: &[FieldWidth], len: usize) -> usize {
+    let mut start = 0;
+    for (idx, field) in widths.iter().enumerate() {
+        start += field.skip;
+        if start >= len {
+            return idx;
+        }
+        start = match field.width {
+            Some(width) => start + width,
+            None => len,
+        };
+    }
+    widths.len()
+}
+
+// When FS is a single space fields are separated by runs of blanks and leading/trailing
+// blanks are ignored.
+pub fn is_blank(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t' || byte == b'\n'
+}
+
+// Calls field(start, end) for each blank separated field until it returns false
+fn blank_fields<'a, I: Iterator<Item=&'a u8>, F: FnMut(usize, usize) -> bool>(bytes: I, mut field: F) {
+    let mut start_of_field = None;
+    let mut len = 0;
+    for (idx, byte) in bytes.enumerate() {
+        len += 1;
+        match (start_of_field, is_blank(*byte)) {
+            (None, false) => start_of_field = Some(idx),
+            (Some(start), true) => {
+                start_of_field = None;
+                if !field(start, idx) {
+                    return;
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = start_of_field {
+        field(start, len);
+    }
 }
 
 fn move_into_buf(dq: &QuickDropDeque, result: &mut Vec<u8>, start: usize, end: usize) {
     let (left, right) = subslices(dq, start, end);
     result.extend_from_slice(left);
     result.extend_from_slice(right);
-    return;
 }
 
 pub fn get_into(fs: &[u8], dq: &QuickDropDeque, field_idx: usize, end_of_record_idx: usize, result: &mut Vec<u8>) {
     debug_assert!(field_idx != 0);
+    if fs == [SPACE] {
+        let (left, right) = subslices(dq, 0, end_of_record_idx);
+        let mut fields_found = 0;
+        blank_fields(left.iter().chain(right.iter()), |start, end| {
+            fields_found += 1;
+            if fields_found == field_idx {
+                move_into_buf(dq, result, start, end);
+                return false;
+            }
+            true
+        });
+        return;
+    }
     let mut start_of_field = 0;
     let mut fields_found = 0;
-    let fs_is_space = fs == &[SPACE]; // space
     while let Some(found_at) = index_in_dq(fs, dq, start_of_field, end_of_record_idx) {
         fields_found += 1;
         if fields_found == field_idx {
             move_into_buf(dq, result, start_of_field, found_at);
+            return;
         }
-        let mut spaces_after_record = 0;
-        while fs_is_space && dq.get(found_at + spaces_after_record + 1) == Some(&SPACE) {
-            spaces_after_record += 1;
-        }
-        start_of_field = found_at + fs.len() + spaces_after_record;
+        start_of_field = found_at + fs.len();
     }
     if fields_found + 1 == field_idx {
         // Trailing record
         move_into_buf(dq, result, start_of_field, end_of_record_idx);
     }
-}
\ No newline at end of file
+}
+
+// Number of fields in the record, same rules as get_into
+pub fn count(fs: &[u8], dq: &QuickDropDeque, end_of_record_idx: usize) -> usize {
+    if end_of_record_idx == 0 {
+        return 0;
+    }
+    let mut fields_found = 0;
+    if fs == [SPACE] {
+        let (left, right) = subslices(dq, 0, end_of_record_idx);
+        blank_fields(left.iter().chain(right.iter()), |_start, _end| {
+            fields_found += 1;
+            true
+        });
+        return fields_found;
+    }
+    let mut start_of_field = 0;
+    while let Some(found_at) = index_in_dq(fs, dq, start_of_field, end_of_record_idx) {
+        fields_found += 1;
+        start_of_field = found_at + fs.len();
+    }
+    fields_found + 1
+}
+
+// Split a record that has been assigned to by the program into fields, same rules as get_into
+pub fn split_slice(fs: &[u8], record: &[u8], fields: &mut Vec<Vec<u8>>) {
+    fields.clear();
+    if record.is_empty() {
+        return;
+    }
+    if fs == [SPACE] {
+        blank_fields(record.iter(), |start, end| {
+            fields.push(record[start..end].to_vec());
+            true
+        });
+        return;
+    }
+    let mut start_of_field = 0;
+    while let Some(found_at) = find_fs(fs, record, start_of_field) {
+        fields.push(record[start_of_field..found_at].to_vec());
+        start_of_field = found_at + fs.len();
+    }
+    fields.push(record[start_of_field..].to_vec());
+}
+
+fn find_fs(fs: &[u8], record: &[u8], start: usize) -> Option<usize> {
+    if fs.is_empty() {
+        return None;
+    }
+    index_of(fs, &record[start..]).map(|idx| idx + start)
+}
+
+// Start and end of each field matching FPAT. Like gawk an empty match right after a field is
+// where the separator is, not another field, so "a,,b" with FPAT=[^,]* is 3 fields.
+pub fn fpat_fields(fpat: &Regex, record: &[u8], fields: &mut Vec<(usize, usize)>) {
+    fields.clear();
+    if record.is_empty() {
+        return;
+    }
+    let mut start = 0;
+    let mut end_of_last_field = None;
+    while start <= record.len() {
+        let found = match fpat.match_idx_from(record, start) {
+            Some(found) => found,
+            None => return,
+        };
+        if found.len == 0 {
+            if end_of_last_field != Some(found.start) {
+                fields.push((found.start, found.start));
+            }
+            start = found.start + 1;
+        } else {
+            fields.push((found.start, found.end()));
+            start = found.end();
+            end_of_last_field = Some(found.end());
+        }
+    }
+}
@@ -9,7 +9,7 @@ fn setup(data: &str) -> (Columns, TempDir) {
     std::fs::write(file_path.clone(), data).unwrap();
 
     (Columns::new(vec![
-        file_path.to_str().unwrap().to_string(),
+        file_path.to_str().unwrap().to_string().into(),
     ]), temp_dir)
 }
 
@@ -22,8 +22,8 @@ fn test_files() {
     std::fs::write(file_path_2.clone(), "1 2 3\n4 5 6\n7 8 9\n").unwrap();
 
     let mut cols = Columns::new(vec![
-        file_path_1.to_str().unwrap().to_string(),
-        file_path_2.to_str().unwrap().to_string(),
+        file_path_1.to_str().unwrap().to_string().into(),
+        file_path_2.to_str().unwrap().to_string().into(),
     ]);
 
     let st = RecordState::new(0.0, 0.0);
@@ -113,7 +113,8 @@ fn test_setting_columns_and_fs_0() {
     assert_eq!(cols.get(1), "A ".as_bytes());
     assert_eq!(cols.get(0), "A B C".as_bytes());
     cols.set(2, "Z".as_bytes());
-    assert_eq!(cols.get(0), "A B Z".as_bytes());
+    // $0 is rebuilt with OFS not FS
+    assert_eq!(cols.get(0), "A  Z".as_bytes());
 }
 
 #[test]
@@ -199,3 +200,115 @@ fn test_setting_rs_3() {
     assert!(!cols.next_record(st).unwrap().next_record);
 }
 
+
+#[test]
+fn test_fpat() {
+    let st = RecordState::new(0.0, 0.0);
+    let (mut cols, _temp_dir) = setup("a b\nabc,\"d,e\",,f\nx,\n");
+    assert!(cols.next_record(st).unwrap().next_record);
+    cols.set_fpat("([^,]*)|(\"[^\"]+\")".as_bytes().to_vec());
+    // Takes effect on the next record like FS
+    assert_eq!(cols.nf(), 2);
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 4);
+    assert_eq!(cols.get(2), "\"d,e\"".as_bytes());
+    assert_eq!(cols.get(3), "".as_bytes());
+    assert_eq!(cols.get(4), "f".as_bytes());
+    assert_eq!(cols.get(5), "".as_bytes());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 2);
+    assert_eq!(cols.get(1), "x".as_bytes());
+    cols.set(0, "1,\"2,3\"".as_bytes());
+    assert_eq!(cols.nf(), 2);
+    assert_eq!(cols.get(2), "\"2,3\"".as_bytes());
+}
+
+#[test]
+fn test_fs_after_fpat() {
+    let st = RecordState::new(0.0, 0.0);
+    let (mut cols, _temp_dir) = setup("a b,c\na b,c\n");
+    cols.set_fpat("[^,]+".as_bytes().to_vec());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.get(1), "a b".as_bytes());
+    // Assigning FS switches back even when it's unchanged
+    cols.set_fs(" ".as_bytes().to_vec());
+    assert_eq!(cols.get(1), "a b".as_bytes());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.get(1), "a".as_bytes());
+    assert_eq!(cols.nf(), 2);
+}
+
+#[test]
+fn test_field_widths() {
+    let st = RecordState::new(0.0, 0.0);
+    let (mut cols, _temp_dir) = setup("AAAAABBBCCCC rest\nAAAAABB\n12345678\n");
+    cols.set_field_widths("5 3 4 *".as_bytes().to_vec());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 4);
+    assert_eq!(cols.get(2), "BBB".as_bytes());
+    assert_eq!(cols.get(4), " rest".as_bytes());
+    // Fields past the end of the record aren't present, the last one is cut short
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 2);
+    assert_eq!(cols.get(2), "BB".as_bytes());
+    assert_eq!(cols.get(3), "".as_bytes());
+    cols.set_field_widths("2:3 1:2".as_bytes().to_vec());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 2);
+    assert_eq!(cols.get(1), "345".as_bytes());
+    assert_eq!(cols.get(2), "78".as_bytes());
+}
+
+#[test]
+fn test_invalid_field_widths() {
+    let st = RecordState::new(0.0, 0.0);
+    let (mut cols, _temp_dir) = setup("abc\n");
+    cols.set_field_widths("2 * 3".as_bytes().to_vec());
+    let err = cols.next_record(st).err().unwrap();
+    assert_eq!(err.msg, "fatal: invalid FIELDWIDTHS value, for field 2, near `*'");
+}
+
+#[test]
+fn test_csv() {
+    let st = RecordState::new(0.0, 0.0);
+    let (mut cols, _temp_dir) = setup("a,\"b\nc\",d\r\n\r\n\"x \"\"y\"\"\",\nlast,\"line\"");
+    cols.enable_csv();
+    cols.set_rs("x".as_bytes().to_vec());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.get(0), "a,\"b\nc\",d".as_bytes());
+    assert_eq!(cols.nf(), 3);
+    assert_eq!(cols.get(2), "b\nc".as_bytes());
+    assert_eq!(cols.get(3), "d".as_bytes());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.get(0), "".as_bytes());
+    assert_eq!(cols.nf(), 0);
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 2);
+    assert_eq!(cols.get(1), "x \"y\"".as_bytes());
+    assert_eq!(cols.get(2), "".as_bytes());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.get(2), "line".as_bytes());
+    // Fields containing OFS are quoted
+    cols.set(1, "a b".as_bytes());
+    assert_eq!(cols.get(0), "\"a b\" line".as_bytes());
+    cols.set(0, "1,\"2,3\"".as_bytes());
+    assert_eq!(cols.nf(), 2);
+    assert_eq!(cols.get(2), "2,3".as_bytes());
+    assert!(!cols.next_record(st).unwrap().next_record);
+}
+
+#[test]
+fn test_csv_long_quoted_field() {
+    // The quoted field spans many reads
+    let long = "ab\n".repeat(20_000);
+    let st = RecordState::new(0.0, 0.0);
+    let (mut cols, _temp_dir) = setup(&format!("1,\"{}\",2\n3\n", long));
+    cols.enable_csv();
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.nf(), 3);
+    assert_eq!(cols.get(2), long.as_bytes());
+    assert_eq!(cols.get(3), "2".as_bytes());
+    assert!(cols.next_record(st).unwrap().next_record);
+    assert_eq!(cols.get(0), "3".as_bytes());
+    assert!(!cols.next_record(st).unwrap().next_record);
+}
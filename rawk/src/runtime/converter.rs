@@ -2,8 +2,8 @@ use std::ffi::{CStr, CString};
 use lexical_core::write_float_options::Options;
 use libc::{c_char};
 use mawk_regex::Regex;
+use crate::runtime::columns::is_blank;
 use crate::util::{memchr_libc_ptr};
-use crate::vm::RuntimeScalar;
 
 
 pub struct FloatWriter {
@@ -92,7 +92,8 @@ impl Converter {
             return fw.num_to_string(rounded)
         }
         if let Err(bytes_needed) = unsafe { snprintf(fmt_str, buffer, num) } {
-            buffer.reserve(bytes_needed);
+            // +1 for the nul snprintf writes after the number
+            buffer.reserve(bytes_needed + 1);
             if let Err(_idx) = unsafe { snprintf(fmt_str, buffer, num) } {
                 panic!("Compiler bug snprintf not behaving as expected")
             }
@@ -104,22 +105,82 @@ impl Converter {
         str_to_num(data, &self.float_regex)
     }
 
-    pub fn set_convfmt(&mut self,  _bytes: &[u8]) {
-        todo!()
+    // Whether a strnum (field, split() element, -v assignment) looks numeric.
+    // Unlike str_to_num the whole string must be the number, save for leading and trailing blanks
+    // and a sign. "1e2 " is numeric but "10abc" is not.
+    pub fn looks_numeric(&mut self, data: &[u8]) -> bool {
+        let start = if let Some(start) = data.iter().position(|b| !is_blank(*b)) { start } else { return false };
+        let end = data.iter().rposition(|b| !is_blank(*b)).unwrap() + 1;
+        let mut number = &data[start..end];
+        if number[0] == PLUS || number[0] == MINUS {
+            number = &number[1..];
+        }
+        match self.float_regex.match_idx(number) {
+            Some(reg_match) => reg_match.start == 0 && reg_match.len == number.len(),
+            None => false,
+        }
+    }
+
+    // A format that isn't a single floating point conversion would hand snprintf the wrong
+    // argument type so it's ignored and the prior format is kept
+    pub fn set_convfmt(&mut self, bytes: &[u8]) {
+        if let Some(fmt) = float_format(bytes) {
+            self.CONVFMT = fmt;
+        }
+    }
+    pub fn set_ofmt(&mut self, bytes: &[u8]) {
+        if let Some(fmt) = float_format(bytes) {
+            self.OFMT = fmt;
+        }
+    }
+}
+
+// Checks bytes is a printf format with exactly one conversion and it's one of aAeEfFgG.
+// Flags, a width and a precision are allowed but not * since there's no int argument for it.
+fn float_format(bytes: &[u8]) -> Option<CString> {
+    let mut conversions = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] != b'%' {
+            idx += 1;
+            continue;
+        }
+        idx += 1;
+        if bytes.get(idx) == Some(&b'%') {
+            idx += 1;
+            continue;
+        }
+        while idx < bytes.len() && b"-+ #0".contains(&bytes[idx]) {
+            idx += 1;
+        }
+        while idx < bytes.len() && bytes[idx].is_ascii_digit() {
+            idx += 1;
+        }
+        if bytes.get(idx) == Some(&b'.') {
+            idx += 1;
+            while idx < bytes.len() && bytes[idx].is_ascii_digit() {
+                idx += 1;
+            }
+        }
+        match bytes.get(idx) {
+            Some(conv) if b"aAeEfFgG".contains(conv) => conversions += 1,
+            _ => return None,
+        }
+        idx += 1;
     }
-    pub fn set_ofmt(&mut self,  _bytes: &[u8]) {
-        todo!()
+    if conversions != 1 {
+        return None;
     }
+    CString::new(bytes).ok()
 }
 
-const FLOAT_REGEX: &'static str =
-    "^(([0-9]+\\.([0-9]*)?)|(([0-9]*)?\\.?[0-9]+))(e[0-9]+)?";
-const SPACE: u8 = 32;
+const FLOAT_REGEX: &str =
+    "^(([0-9]+\\.([0-9]*)?)|(([0-9]*)?\\.?[0-9]+))([eE][-+]?[0-9]+)?";
 const PLUS: u8 = 43;
 const MINUS: u8 = 45;
 
 fn str_to_num(bytes: &[u8], float_regex: &Regex) -> Option<f64> {
-    if bytes.len() == 0 {
+    if bytes.is_empty() {
         None
     } else {
         let mut idx = 0;
@@ -127,7 +188,7 @@ fn str_to_num(bytes: &[u8], float_regex: &Regex) -> Option<f64> {
 
         // Skip front <blanks>
         while let Some(byte) = bytes.get(idx) {
-            if *byte == SPACE {
+            if is_blank(*byte) {
                 idx += 1;
                 continue;
             } else {
@@ -197,6 +258,29 @@ mod tests {
         assert_eq!(conv.num_to_str_output(1.1), "1.1".as_bytes());
     }
 
+    #[test]
+    fn test_set_formats() {
+        let mut conv = Converter::new();
+        conv.set_convfmt(b"%.2g");
+        conv.set_ofmt(b"<%08.3f>");
+        assert_eq!(conv.num_to_str_internal(0.123), "0.12".as_bytes());
+        assert_eq!(conv.num_to_str_output(1.5), "<0001.500>".as_bytes());
+        // Integers don't go through the format
+        assert_eq!(conv.num_to_str_internal(3.0), "3".as_bytes());
+
+        // Not a single float conversion, prior format is kept
+        for bad in ["%s", "%d", "%*g", "%g %g", "no conversion", "%"] {
+            conv.set_convfmt(bad.as_bytes());
+            assert_eq!(conv.num_to_str_internal(0.123), "0.12".as_bytes());
+        }
+        conv.set_convfmt(b"100%% %e");
+        assert_eq!(conv.num_to_str_internal(0.5), "100% 5.000000e-01".as_bytes());
+
+        // Longer than the buffer's first allocation
+        conv.set_ofmt(b"%.200f");
+        assert_eq!(conv.num_to_str_output(0.5).len(), 202);
+    }
+
     #[test]
     fn test_conv_large_integer() {
         let mut conv = Converter::new();
@@ -229,6 +313,25 @@ mod tests {
         assert_eq!(regex.match_idx(".0e1".as_bytes()), Some(Match { start: 0, len: 4 }));
         assert_eq!(regex.match_idx(".e1".as_bytes()), None);
         assert_eq!(regex.match_idx("1 2".as_bytes()), Some(Match { start: 0, len: 1 }));
+        assert_eq!(regex.match_idx("1E2".as_bytes()), Some(Match { start: 0, len: 3 }));
+        assert_eq!(regex.match_idx("1e-2".as_bytes()), Some(Match { start: 0, len: 4 }));
+        assert_eq!(regex.match_idx("1e+2".as_bytes()), Some(Match { start: 0, len: 4 }));
+    }
+
+    #[test]
+    fn test_looks_numeric() {
+        let mut conv = Converter::new();
+        assert!(conv.looks_numeric("1".as_bytes()));
+        assert!(conv.looks_numeric(" +3.14 ".as_bytes()));
+        assert!(conv.looks_numeric("\t-.5e-3\n".as_bytes()));
+        assert!(conv.looks_numeric("1E2".as_bytes()));
+        assert!(!conv.looks_numeric("".as_bytes()));
+        assert!(!conv.looks_numeric("   ".as_bytes()));
+        assert!(!conv.looks_numeric("10abc".as_bytes()));
+        assert!(!conv.looks_numeric("0x1A".as_bytes()));
+        assert!(!conv.looks_numeric("1 2".as_bytes()));
+        assert!(!conv.looks_numeric("+".as_bytes()));
+        assert!(!conv.looks_numeric(".".as_bytes()));
     }
 
 
@@ -1,20 +1,19 @@
 pub mod arrays;
+pub mod bitwise;
 #[allow(non_snake_case)]
 pub mod columns;
 pub mod converter;
 pub mod regex_cache;
 pub mod rc_manager;
 pub mod special_manager;
+pub mod time;
 
 pub use arrays::{*};
 pub use columns::{*};
-pub use rc_manager::{*};
-pub use regex_cache::{*};
-pub use converter::{*};
 
-use crate::runtime::columns::Columns;
 use crate::runtime::converter::Converter;
 use crate::runtime::regex_cache::RegexCache;
+use crate::parser::ScalarType;
 
 pub struct VmRuntime {
     pub arrays: Arrays,
@@ -22,16 +21,19 @@ pub struct VmRuntime {
     pub converter: Converter,
     pub regex_cache: RegexCache,
     pub srand_seed: f64,
+    // Written after each print
+    pub ors: Vec<u8>,
 }
 
 impl VmRuntime {
-    pub fn new(files: Vec<String>, array_count: usize) -> Self {
+    pub fn new(files: Vec<Input>, array_types: &[ScalarType]) -> Self {
         Self {
-            arrays: Arrays::new(array_count),
+            arrays: Arrays::new(array_types),
             columns: Columns::new(files),
             converter: Converter::new(),
             regex_cache: RegexCache::new(),
             srand_seed: 09171998.0,
+            ors: vec![b'\n'],
         }
     }
 }
\ No newline at end of file
@@ -1,4 +1,3 @@
-use std::rc::Rc;
 use crate::awk_str::{AwkStr, RcAwkStr};
 use crate::vm::{RuntimeScalar, StringScalar};
 
@@ -32,12 +31,7 @@ impl RcManager {
         }
     }
     pub fn get(&mut self) -> AwkStr {
-        self.cache.pop().unwrap_or_else(|| AwkStr::new_empty())
-    }
-    pub fn from_vec(&mut self, bytes: Vec<u8>) -> AwkStr {
-        let mut rc = self.get();
-        rc.overwrite_with(bytes);
-        rc
+        self.cache.pop().unwrap_or_else(AwkStr::new_empty)
     }
     pub fn copy_from_slice(&mut self, bytes: &[u8]) -> AwkStr {
         let mut rc = self.get();
@@ -10,12 +10,12 @@ impl RegexCache {
     pub fn new() -> Self { Self { cache: LruCache::new(32) } }
 
     pub fn get(&mut self, reg_str: &RcAwkStr) -> &mut Regex {
-        if self.cache.contains_key(&reg_str) {
-            self.cache.get_mut(&reg_str).unwrap()
+        if self.cache.contains_key(reg_str) {
+            self.cache.get_mut(reg_str).unwrap()
         } else {
-            let re = Regex::new(&reg_str);
+            let re = Regex::new(reg_str);
             self.cache.insert((reg_str).clone(), re);
-            self.cache.get_mut(&reg_str).unwrap()
+            self.cache.get_mut(reg_str).unwrap()
         }
     }
 }
\ No newline at end of file
@@ -1,9 +1,8 @@
 use crate::awk_str::RcAwkStr;
 use crate::parser::SclSpecial;
 use crate::runtime::VmRuntime;
-use crate::specials::NUM_SCL_SPECIALS;
 use crate::util::unwrap;
-use crate::vm::{RuntimeScalar, VirtualMachine};
+use crate::vm::RuntimeScalar;
 
 // Manages getting and setting awk specials
 pub struct SpecialManager {
@@ -14,7 +13,7 @@ impl SpecialManager {
     pub fn new(argc: usize) -> Self {
         // TODO: I could speed this up by hard coding the init values but it's
         // only run once so not vital
-        let storage = SclSpecial::variants().iter().map(|(name, special)| {
+        let storage = SclSpecial::variants().iter().map(|(_name, special)| {
             match special {
                 SclSpecial::FS => RuntimeScalar::Str(RcAwkStr::new_str(" ")),
                 SclSpecial::RS => RuntimeScalar::Str(RcAwkStr::new_str("\n")),
@@ -28,9 +27,11 @@ impl SpecialManager {
                 SclSpecial::RSTART => RuntimeScalar::Num(0.0),
                 SclSpecial::SUBSEP => RuntimeScalar::Str(RcAwkStr::new_str("-")),
                 SclSpecial::ARGC => RuntimeScalar::Num(argc as f64),
-                // TODO: OFS ORS
-                SclSpecial::OFS => RuntimeScalar::Str(RcAwkStr::new_str("PRINT OFS SEP NOT YET IMPLEMENTED")),
-                SclSpecial::ORS => RuntimeScalar::Str(RcAwkStr::new_str("PRINT ORS SEP NOT YET IMPLEMENTED")),
+                SclSpecial::ERRNO => RuntimeScalar::Str(RcAwkStr::new_str("")),
+                SclSpecial::FPAT => RuntimeScalar::Str(RcAwkStr::new_str("[^[:space:]]+")),
+                SclSpecial::FIELDWIDTHS => RuntimeScalar::Str(RcAwkStr::new_str("")),
+                SclSpecial::OFS => RuntimeScalar::Str(RcAwkStr::new_str(" ")),
+                SclSpecial::ORS => RuntimeScalar::Str(RcAwkStr::new_str("\n")),
             }
         }).collect();
         Self { storage }
@@ -58,23 +59,44 @@ impl SpecialManager {
                 let fs = SpecialManager::scalar_to_string_internal(rt, value);
                 rt.columns.set_fs(fs);
             }
+            SclSpecial::FPAT => {
+                let fpat = SpecialManager::scalar_to_string_internal(rt, value);
+                rt.columns.set_fpat(fpat);
+            }
+            SclSpecial::FIELDWIDTHS => {
+                let widths = SpecialManager::scalar_to_string_internal(rt, value);
+                rt.columns.set_field_widths(widths);
+            }
             SclSpecial::RS => {
                 let rs = SpecialManager::scalar_to_string_internal(rt, value);
                 rt.columns.set_rs(rs);
             }
 
             SclSpecial::NF => {
-                panic!("FN not done yet")
+                let nf = match value {
+                    RuntimeScalar::Num(num) => num,
+                    RuntimeScalar::Str(s) | RuntimeScalar::StrNum(s) => rt.converter.str_to_num(&s).unwrap_or(0.0),
+                };
+                rt.columns.set_nf(nf as usize);
+            }
+            SclSpecial::OFS => {
+                let ofs = SpecialManager::scalar_to_string_internal(rt, value);
+                rt.columns.set_ofs(ofs);
             }
 
             // No action needed
-            SclSpecial::FNR | SclSpecial::NR | SclSpecial::RSTART | SclSpecial::RLENGTH | SclSpecial::SUBSEP | SclSpecial::ARGC  => {}
-
-            SclSpecial::FILENAME => todo!("scl special manager"),
-            SclSpecial::CONVFMT => todo!("scl special manager"),
-            SclSpecial::OFMT => todo!("scl special manager"),
-            SclSpecial::OFS => todo!("scl special manager"),
-            SclSpecial::ORS => todo!("scl special manager"),
+            SclSpecial::FNR | SclSpecial::NR | SclSpecial::RSTART | SclSpecial::RLENGTH | SclSpecial::SUBSEP | SclSpecial::ARGC | SclSpecial::FILENAME | SclSpecial::ERRNO => {}
+            SclSpecial::CONVFMT => {
+                let convfmt = SpecialManager::scalar_to_string_internal(rt, value);
+                rt.converter.set_convfmt(&convfmt);
+            }
+            SclSpecial::OFMT => {
+                let ofmt = SpecialManager::scalar_to_string_internal(rt, value);
+                rt.converter.set_ofmt(&ofmt);
+            }
+            SclSpecial::ORS => {
+                rt.ors = SpecialManager::scalar_to_string_internal(rt, value);
+            }
         }
         prior_value
     }
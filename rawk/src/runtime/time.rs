@@ -0,0 +1,118 @@
+use std::ffi::CString;
+
+// gawk's strftime() format when none is given
+pub const DEFAULT_STRFTIME_FORMAT: &str = "%a %b %e %H:%M:%S %Z %Y";
+
+// tzset(3) from the C library, the libc crate only binds it on windows. Re-reads TZ so a
+// change made by the program takes effect.
+#[link(name = "c")]
+extern "C" {
+    fn tzset();
+}
+
+// Formatted times longer than this are truncated
+const MAX_STRFTIME_LEN: usize = 1 << 16;
+
+// Seconds since the epoch
+pub fn systime() -> f64 {
+    unsafe { libc::time(std::ptr::null_mut()) as f64 }
+}
+
+// Timestamp for "YYYY MM DD HH MM SS [DST]" in local time (or UTC). Values outside their usual
+// range are normalized so "2024 01 32 ..." is February 1st. -1 if the spec is malformed.
+pub fn mktime(spec: &[u8], utc: bool) -> f64 {
+    let fields: Option<Vec<i64>> = spec
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|field| !field.is_empty())
+        .map(|field| std::str::from_utf8(field).ok()?.parse().ok())
+        .collect();
+    let fields = match fields {
+        Some(fields) if fields.len() == 6 || fields.len() == 7 => fields,
+        _ => return -1.0,
+    };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = (fields[0] - 1900) as libc::c_int;
+    tm.tm_mon = (fields[1] - 1) as libc::c_int;
+    tm.tm_mday = fields[2] as libc::c_int;
+    tm.tm_hour = fields[3] as libc::c_int;
+    tm.tm_min = fields[4] as libc::c_int;
+    tm.tm_sec = fields[5] as libc::c_int;
+    // Negative lets libc work out whether DST is in effect
+    tm.tm_isdst = fields.get(6).map_or(-1, |dst| *dst as libc::c_int);
+    let time = unsafe {
+        if utc {
+            libc::timegm(&mut tm)
+        } else {
+            tzset();
+            libc::mktime(&mut tm)
+        }
+    };
+    time as f64
+}
+
+// Formats the timestamp like strftime(3). Local time honors TZ. None if the timestamp is
+// outside the years libc can represent.
+pub fn strftime(format: &[u8], timestamp: f64, utc: bool) -> Option<Vec<u8>> {
+    // A format can't contain a NUL for libc, gawk stops there too
+    let format = match format.iter().position(|b| *b == 0) {
+        Some(nul) => &format[..nul],
+        None => format,
+    };
+    if format.is_empty() {
+        return Some(vec![]);
+    }
+    let format = CString::new(format).unwrap();
+    let time = timestamp as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let converted = unsafe {
+        if utc {
+            libc::gmtime_r(&time, &mut tm)
+        } else {
+            tzset();
+            libc::localtime_r(&time, &mut tm)
+        }
+    };
+    if converted.is_null() {
+        return None;
+    }
+
+    // strftime returns 0 when the buffer is too small, which is also a valid length for formats
+    // like "%p" in some locales, so give up after a generous size
+    let mut buffer: Vec<u8> = vec![0; 128];
+    loop {
+        let len = unsafe { libc::strftime(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len(), format.as_ptr(), &tm) };
+        if len > 0 || buffer.len() >= MAX_STRFTIME_LEN {
+            buffer.truncate(len);
+            return Some(buffer);
+        }
+        buffer.resize(buffer.len() * 4, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::time::{mktime, strftime};
+
+    #[test]
+    fn test_mktime_utc() {
+        assert_eq!(mktime(b"1970 01 01 00 00 00", true), 0.0);
+        assert_eq!(mktime(b"2024 02 29 12 30 15", true), 1709209815.0);
+        assert_eq!(mktime(b"  2024 1 32 0 0 0  ", true), mktime(b"2024 02 01 00 00 00", true));
+        assert_eq!(mktime(b"2024 02 29 12 30", true), -1.0);
+        assert_eq!(mktime(b"2024 02 29 12 30 xx", true), -1.0);
+    }
+
+    #[test]
+    fn test_strftime_utc() {
+        assert_eq!(strftime(b"%Y-%m-%d %H:%M:%S", 1709209815.0, true).unwrap(), b"2024-02-29 12:30:15");
+        assert_eq!(strftime(b"%j %%", 0.0, true).unwrap(), b"001 %");
+        assert_eq!(strftime(b"", 0.0, true).unwrap(), b"");
+        assert_eq!(strftime(b"%Y\0%m", 0.0, true).unwrap(), b"1970");
+    }
+
+    #[test]
+    fn test_strftime_out_of_range() {
+        assert_eq!(strftime(b"%Y", -1e30, true), None);
+        assert_eq!(strftime(b"%Y", 1e30, false), None);
+    }
+}
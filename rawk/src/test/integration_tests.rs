@@ -1,5 +1,5 @@
 use crate::runner;
-use crate::test::{test_runner, long_number_file, ONE_LINE, SUB_RULES, SUB_ESCAPING, REDIRECT, NUMBERS, NUMBERS2, FLOAT_NUMBERS, NUMERIC_STRING, ABC, PERF_ARRAY_PROGRAM, EMPTY_INDEX_PROGRAM, TTX1, test_runner_multifile};
+use crate::test::{run_rawk, run_rawk_fatal, test_runner, long_number_file, ONE_LINE, SUB_RULES, SUB_ESCAPING, REDIRECT, NUMBERS, NUMBERS2, FLOAT_NUMBERS, NUMERIC_STRING, ABC, PERF_ARRAY_PROGRAM, EMPTY_INDEX_PROGRAM, TTX1, test_runner_multifile};
 use crate::test::awks::Awk;
 use crate::test::io_capture::IoCapture;
 #[macro_export]
@@ -25,8 +25,13 @@ macro_rules! test_except {
 
 #[test]
 fn prog_awk_test() {
-    let str = std::fs::read_to_string("/Users/n8ta/code/jawk/rawk/prog.awk").unwrap();
-    test_runner("run prog.awk", &str, "1 2 3\n4 5 6\n", "", 0);
+    let dir = tempfile::tempdir().unwrap();
+    let prog = dir.path().join("prog.awk");
+    let data = dir.path().join("data");
+    std::fs::write(&prog, "{ sum += $2 }\nEND { print sum }\n").unwrap();
+    std::fs::write(&data, "1 2 3\n4 5 6\n").unwrap();
+    let (out, _err) = run_rawk(&["-f", prog.to_str().unwrap(), data.to_str().unwrap()]).unwrap();
+    assert_eq!(out, b"7\n");
 }
 
 
@@ -38,6 +43,7 @@ test!(test_print_begin_int, "BEGIN {print 1;}", ONE_LINE, "1\n");
 test!(test_print_int, "{print 1;}", ONE_LINE, "1\n");
 test!(test_print_str, "BEGIN {print \"abc\";}", ONE_LINE, "abc\n");
 test!(test_print_str_loop, "{print \"abc\";}", ONE_LINE, "abc\n");
+test!(test_end_only_reads_input, "END { print NR }", NUMBERS, "3\n");
 test!(test_just_begin, "BEGIN { print 1; }", ONE_LINE, "1\n");
 
 test!(test_assign_undef_to_undef, "BEGIN { x = x; }", ONE_LINE, "");
@@ -721,6 +727,24 @@ test!(
         NUMERIC_STRING,
         "4\n1\n"
     );
+test!(test_inc_array_elem, "{ count[$1]++ } END { print count[1]; print count[4]; print count[7] }", "1 a\n4 b\n1 c\n1 d\n", "3\n1\n\n");
+test!(test_inc_array_elem_ret, "BEGIN { print a[1]++; print ++a[1]; print a[1]--; print --a[1]; print a[1] }", ONE_LINE, "0\n2\n2\n0\n0\n");
+test!(test_inc_array_multi_idx, "BEGIN { a[1,2]++; a[1,2]++; print a[1,2] }", ONE_LINE, "2\n");
+test!(test_inc_column, "{ $3++; print $0; print $3 }", NUMBERS, "1 2 4\n4\n4 5 7\n7\n7 8 10\n10\n");
+test!(test_inc_column_ret, "{ print $1++ + ++$2; print $1 $2 }", ONE_LINE, "4\n23\n");
+test!(test_inc_column_past_end, "{ ++$5; print $0; print NF }", ONE_LINE, "1 2 3  1\n5\n");
+test!(test_inc_special, "{ NR++; print NR }", NUMBERS, "2\n4\n6\n");
+test!(test_dec_special, "BEGIN { print --NR; print NR-- ; print NR }", ONE_LINE, "-1\n-1\n-2\n");
+test!(test_inc_uninit, "BEGIN { x++; y--; print x; print y; print z++; print ++w }", ONE_LINE, "1\n-1\n0\n1\n");
+test!(test_inc_str_var, "BEGIN { x = \"5\"; x++; print x }", ONE_LINE, "6\n");
+test!(test_inc_arg, "function f(a) { a++; return ++a } BEGIN { print f(1) }", ONE_LINE, "3\n");
+test!(test_assign_column, "{ $2 = \"x\"; print $0; print $2 }", NUMBERS, "1 x 3\nx\n4 x 6\nx\n7 x 9\nx\n");
+test!(test_assign_column_ret, "{ print $1 = \"z\"; print $0 }", ONE_LINE, "z\nz 2 3\n");
+test!(test_assign_column_0, "{ $0 = \"a b\"; print $2; print NF }", ONE_LINE, "b\n2\n");
+test!(test_assign_column_ofs, "{ OFS = \"-\"; $1 = $1; print $0 }", ONE_LINE, "1-2-3\n");
+test!(test_assign_nf, "{ NF = 2; print $0; NF = 4; print $0 }", ONE_LINE, "1 2\n1 2  \n");
+test!(test_nf, "{ print NF }", NUMBERS2, "4\n4\n4\n");
+test!(test_sub_column, "{ sub(\"2\", \"x\", $2); print $0 }", ONE_LINE, "1 x 3\n");
 test!(test_mod_2, "BEGIN { print (3 % 2) }", NUMERIC_STRING, "1\n");
 test!(
         test_ternary_false,
@@ -1162,6 +1186,9 @@ test!(test_length_1, "BEGIN { print length(1) + length(12); }", ONE_LINE, "3\n")
 test!(test_length_2, "{ a += length($2); } END { print a }", "1 22 333\n4444 55555 666666\n7777777 88888888 999999999", "15\n");
 test!(test_length_3, "{ print  length(); }", "123\n33345", "3\n5\n");
 test!(test_length_4, "BEGIN { print  length(45e2); }", ONE_LINE, "4\n");
+test!(test_length_array, "BEGIN { a[1] = 1; a[\"x\"] = 2; a[3] = 3; s = \"abcd\"; print length(a); print length(s) }", "", "3\n4\n");
+test!(test_length_split, "{ n = split($0, parts, \",\"); print length(parts) \" \" n }", "a,b,c\nd\n", "3 3\n1 1\n");
+test_except!(test_length_array_arg, "function count(arr) { return length(arr) } BEGIN { b[1] = 1; b[2] = 2; c = \"xyz\"; print count(b) \" \" length(c) }", "", "2 3\n", GAWK_EXT);
 
 test!(test_split_0, "BEGIN { print split(a,b); print b[0] }", ONE_LINE, "0\n\n");
 test!(test_split_1, "BEGIN { split(a,b,c); print b[0] }", ONE_LINE, "\n");
@@ -1248,6 +1275,50 @@ test!(test_no_ret_2, "function f() { } BEGIN { print (f()==\"\") }", ONE_LINE, "
 test!(test_no_ret_3, "function f() { } BEGIN { print (f()==1) }", ONE_LINE, "0\n");
 test!(test_no_ret_4, "function f() { } { print (f()==$1) }", "1\n", "0\n");
 test!(test_no_ret_5, "function f() { } { print (f()==$1) }", "0\n", "1\n");
+test!(test_func_local_scalar, "function f(x,   tmp) { if (tmp == 0 && tmp == \"\") { print \"uninit\" } tmp = x; return tmp } BEGIN { print f(1); print f(2) }", "", "uninit\n1\nuninit\n2\n");
+test!(test_func_local_array, "function f(x,   arr) { if (arr[1] == \"\") { print \"empty\" } arr[1] = x; return arr[1] } BEGIN { print f(1); print f(2) }", "", "empty\n1\nempty\n2\n");
+test!(test_func_local_array_passed_down, "function fill(a, v) { a[1] = v } function f(v,   loc) { fill(loc, v); return loc[1] } BEGIN { print f(\"x\"); print f(\"y\") }", "", "x\ny\n");
+test!(test_func_local_array_recursion, "function f(n,   arr) { arr[n] = n; if (n > 0) { f(n - 1) } return arr[n] \"-\" arr[n - 1] } BEGIN { print f(3) }", "", "3-\n");
+test!(test_func_local_array_split, "function count(s,   parts) { return split(s, parts, \",\") } { print count($0) }", "a,b,c\nd\n", "3\n1\n");
+test!(test_func_some_args, "function f(a, b, c) { return a \"-\" b \"-\" c } BEGIN { print f(1); print f(1, 2); print f(1, 2, 3) }", "", "1--\n1-2-\n1-2-3\n");
+test!(test_ret_num, "function sq(x) { return x * x } BEGIN { print sq(3) + 1; print sq(2) }", "", "10\n4\n");
+test!(test_ret_str, "function wrap(x) { return \"<\" x \">\" } BEGIN { print wrap(1) wrap(2); wrap(3) }", "", "<1><2>\n");
+test!(test_ret_mixed, "function f(x) { if (x) { return 1 } return \"a\" } BEGIN { print f(1); print f(0) }", "", "1\na\n");
+test!(test_ret_fall_off, "function f(x) { if (x) { return 5 } } BEGIN { print f(1); print f(0); print (f(0) == 0); print (f(0) == \"\") }", "", "5\n\n1\n1\n");
+test!(test_ret_bare, "function f() { return } BEGIN { print f(); print (f() == 0) }", "", "\n1\n");
+test!(test_ret_recursive, "function fact(n) { if (n <= 1) { return 1 } return n * fact(n - 1) } BEGIN { print fact(10) }", "", "3628800\n");
+test!(test_ret_through_call, "function a(x) { return b(x) } function b(x) { return x + 1 } BEGIN { print a(1) a(2) }", "", "23\n");
+test!(test_specialize_num_str, "function max(a, b) { return a > b ? a : b } BEGIN { print max(1, 2); print max(\"abc\", \"abd\"); x = \"10\"; print max(x, 9) } { print max($1, $2) }", NUMBERS, "2\nabd\n9\n2\n5\n8\n");
+test!(test_specialize_recursive, "function fact(n) { if (n <= 1) { return 1 } return n * fact(n - 1) } BEGIN { print fact(5); print fact(\"6\"); print fact($1) } { print fact($3) }", ONE_LINE, "120\n720\n1\n6\n");
+test!(test_specialize_assigned_arg, "function f(x, y) { x = x \"-\"; return x y } BEGIN { print f(1, 2); print f(\"a\", \"b\"); print f(1, \"b\") }", "", "1-2\na-b\n1-b\n");
+test!(test_specialize_array_arg, "function get(arr, k) { return arr[k] } BEGIN { a[1] = \"x\"; a[\"k\"] = 5; print get(a, 1); print get(a, \"k\") + 1 }", "", "x\n6\n");
+test!(test_specialize_many_signatures, "function f(a, b, c) { return a \"\" b \"\" c } BEGIN { print f(1, 1, 1); print f(\"a\", 1, 1); print f(1, \"b\", 1); print f(1, 1, \"c\"); print f(\"a\", \"b\", 1); print f(\"a\", \"b\", \"c\"); print f(1) }", "", "111\na11\n1b1\n11c\nab1\nabc\n1\n");
+test!(test_specialize_strnum_compare, "function eq(a, b) { return a == b } { print eq($1, $2); print eq($1, 1); print eq($1, \"1\"); print eq($1, \"1.0\") }", "1 1.0\n", "1\n1\n1\n0\n");
+test!(test_num_array_sum, "{ sum[$1] += $3; cnt[$1]++ } END { print sum[1] \" \" sum[4] \" \" cnt[7] \" \" (sum[1] * 2) }", NUMBERS, "3 6 1 6\n");
+test!(test_num_array_inplace_ops, "BEGIN { a[1] += 3; a[1] *= 4; a[1] -= 2; a[1] /= 5; a[1] %= 2; a[2] ^= 2; print a[1] \" \" a[2] }", "", "0 0\n");
+test!(test_num_array_missing_is_uninit, "BEGIN { a[1] = 1; print a[2] \"|\" (a[2] + 1) \"|\" length(a[2]) \"|\" (a[2] == \"\") \"|\" (a[2] == 0) }", "", "|1|0|1|1\n");
+test!(test_reference_creates_element, "BEGIN { a[1]; a[2]; print length(a); b[\"x\"]; b[\"y\"]; print length(b); if (c[3] == \"\") print length(c) (3 in c) }", "", "2\n2\n11\n");
+test!(test_num_array_reference_creates_element, "BEGIN { a[1] = 1; x = a[2] + a[\"k\"]; print length(a) \"[\" a[2] \"]\" (a[2] == 0) ((\"k\" in a)); a[2]++; print a[2] }", "", "3[]11\n1\n");
+test!(test_num_array_compare_as_num, "BEGIN { a[1] = 10; a[2] = 9; print (a[1] > a[2]); b = a[1]; print (b > \"9\") }", "", "1\n0\n");
+test!(test_num_array_param, "function add(arr, k, v) { arr[k] += v } function get(arr, k) { return arr[k] } BEGIN { add(t, \"x\", 2); add(t, \"x\", 3.5); print get(t, \"x\"); print get(t, \"y\") \"|\" }", "", "5.5\n|\n");
+test!(test_num_array_local, "function f(n,  loc) { loc[n] = n * 2; if (n > 0) { f(n - 1) } return loc[n] + loc[n + 1] } BEGIN { print f(3) }", "", "6\n");
+test!(test_num_array_in, "BEGIN { a[\"x\"] = 1; a[\"y\"]++; print ((\"x\" in a) (\"y\" in a) (\"z\" in a)) }", "", "110\n");
+test!(test_mixed_array_param, "function set(arr, k, v) { arr[k] = v } BEGIN { set(a, 1, 5); set(a, 2, \"x\"); a[3]++; print a[1] a[2] a[3] }", "", "5x1\n");
+test!(test_dense_array_gap, "BEGIN { a[1] = \"x\"; a[2] = \"y\"; a[5] = \"z\"; a[3] = \"w\"; print a[1] a[2] a[3] a[5] (4 in a) }", "", "xywz0\n");
+test!(test_dense_array_str_keys, "BEGIN { a[\"01\"] = 1; a[1] = 2; print a[\"01\"] \" \" a[1] \" \" a[\"1\"]; a[\"2\"] = 3; print a[2] }", "", "1 2 2\n3\n");
+test!(test_dense_array_zero_negative, "BEGIN { a[0] = 1; a[-1] = 2; a[1] = 3; k = \"-1\"; print a[0] a[-1] a[1] (k in a) }", "", "1231\n");
+test!(test_dense_array_fractional, "BEGIN { a[1] = 1; a[1.5] = 2; k = \"1.5\"; print a[1] a[1.5] a[k] (k in a) }", "", "1221\n");
+test!(test_dense_array_split_then_gap, "BEGIN { n = split(\"a b c\", a); a[5] = \"e\"; print n a[1] a[3] a[5] (4 in a); n = split(\"x y\", a); print n a[1] a[2] (3 in a) (5 in a) }", "", "3ace0\n2xy00\n");
+test!(test_dense_array_nr, "{ arr[NR] = $0 } END { print arr[3] arr[1] arr[2] }", "a\nb\nc\n", "cab\n");
+test!(test_dense_array_strnum_key, "{ a[$1] = $2 } END { print a[1] a[\"2\"] a[3] }", "1 a\n2 b\n03 c\n", "ab\n");
+test!(test_array_subsep_order, "BEGIN { a[1, 2] = 3; x = 1 SUBSEP 2; y = 2 SUBSEP 1; print a[1, 2] (x in a) (y in a) }", "", "310\n");
+test!(test_convfmt_array_key, "BEGIN { CONVFMT = \"%.2g\"; a[0.123] = 1; for (k in a) { print k }; print (\"0.12\" in a) a[0.1234] }", "", "0.12\n11\n");
+test!(test_convfmt_dense_array_key, "BEGIN { a[1] = 1; CONVFMT = \"%.1f\"; a[1.25] = 2; a[2] = 3; print a[\"1.2\"] a[2] length(a) }", "", "233\n");
+test!(test_ofmt_print, "BEGIN { OFMT = \"%.2f\"; x = 3.14159; print x; print x \"\"; print 2; CONVFMT = \"%.3f\"; print x \"\" }", "", "3.14\n3.14159\n2\n3.142\n");
+test!(test_ors, "BEGIN { ORS = \"|\"; print \"a\"; print \"b\\n\"; print 1.5; ORS = \"\\n\"; print \"c\" }", "", "a|b\n|1.5|c\n");
+test!(test_fold_constants, "BEGIN { print 1 + 2 * 3 \"a\" 2 ^ 3 % 5; print (1 < 2) (2 <= 1) (\"x\" \"y\" \"z\"); x = \"1\" + 0; print x + 1 }", "", "7a3\n10xyz\n2\n");
+test!(test_dead_code_after_break, "BEGIN { while (1) { x++; if (x > 2) { break; print \"no\" } } print x; if (0) { print \"no\" } else { print \"yes\" } }", "", "3\nyes\n");
+test!(test_dead_code_after_return, "function f(a) { if (a) { return \"t\" } else { return \"f\" } print \"no\" } BEGIN { print f(1) f(0) }", "", "tf\n");
 
 test!(test_logical_or_0, "\
     function f() { print 333; return 1; } \
@@ -1292,6 +1363,97 @@ const PI: &'static str = "    +3.14";
 
 test!(test_nr_basic, "{ print NR }", "11\n22\n33\n44\n55\nz\nz\n", "1\n2\n3\n4\n5\n6\n7\n");
 test!(test_fnr_basic, "{ print FNR }", "11\n22\n33\n44\n55\nz\nz\n", "1\n2\n3\n4\n5\n6\n7\n");
+test!(test_strnum_uninit, "BEGIN { if (x == 0) print \"a\"; if (x == \"\") print \"b\"; if (x < 1) print \"c\"; if (length(x) == 0) print \"d\" }", "", "a\nb\nc\nd\n");
+test!(test_strnum_uninit_array, "BEGIN { if (a[1] == 0) print \"a\"; if (a[1] == \"\") print \"b\" }", "", "a\nb\n");
+test!(test_strnum_field_leading_blanks, "{ if ($0 == 3) { print \"eq\" } else { print \"ne\" } }", "  3  \n+3\n3.0\n 1e0\n", "eq\neq\neq\nne\n");
+test!(test_strnum_field_exp, "{ if ($1 == 100) { print \"eq\" } else { print \"ne\" } }", "1e2\n1E2\n100.\n.1e3\n1e\n", "eq\neq\neq\neq\nne\n");
+test!(test_strnum_field_not_numeric, "{ if ($1 == 10) { print \"eq\" } else { print \"ne\" } }", "10abc\n0xA\n10 abc\n", "ne\nne\neq\n");
+test!(test_strnum_field_string_compare, "{ if ($1 < $2) { print \"lt\" } else { print \"ge\" } }", "10 9\nabc 9\n10 9x\n-1 -2\n", "ge\nge\nlt\nge\n");
+test!(test_strnum_empty_field, "BEGIN { FS = \",\" } { if ($2 == 0) print \"zero\"; if ($2 == \"\") print \"empty\" }", "a,,c\n", "empty\n");
+test!(test_strnum_split_elems, "BEGIN { n = split(\"10 9 x\", a, \" \"); if (a[1] > a[2]) print \"num\"; if (a[3] > a[1]) print \"str\"; print n }", "", "num\nstr\n3\n");
+test!(test_strnum_split_empty_elem, "BEGIN { split(\"1,,3\", a, \",\"); if (a[2] == 0) print \"zero\"; if (a[2] == \"\") print \"empty\" }", "", "empty\n");
+test!(test_strnum_str_constants, "BEGIN { if (\"10\" < \"9\") print \"str\"; a = \"10\"; b = 9; if (a < b) print \"str2\" }", "", "str\nstr2\n");
+test!(test_strnum_num_var, "{ x = $1 + 0; if (x == 1) print \"eq\" }", "1.0\n", "eq\n");
+test!(test_strnum_truthy, "{ if ($1) { print \"t\" } else { print \"f\" } }", "0\n0.0\n 0 \nx\n\n1\n", "f\nf\nf\nt\nf\nt\n");
+test!(test_strnum_environ, "BEGIN { v = ENVIRON[\"CARGO_PKG_VERSION_MAJOR\"]; if (v) print \"true\"; if (v == 0) print \"zero\"; if (v == \"0\") print \"str\"; if (v < \"00\") print \"lt\" }", "", "zero\nstr\nlt\n");
+test!(test_strnum_getline_var, "NR == 1 { getline x; if (x == 10) print \"eq\"; if (x < 9) print \"str\"; print $0 \" \" NR }", "1\n10\n3\n", "eq\n1 2\n");
+test!(test_strnum_getline_record, "{ r = getline; print $0 \" \" NR \" \" r; if ($1 > 9) print \"num\" }", "1\n10\n3\n", "10 2 1\nnum\n3 3 0\n");
+test!(test_strnum_getline_file, "NR == 1 { while ((getline line < FILENAME) > 0) { n++; if (line > 2) big++ } print n \" \" big \" \" NR }", "1\n10\n3\n", "3 2 1\n");
+test!(test_strnum_getline_command, "BEGIN { \"echo 10\" | getline v; if (v < 9) print \"str\"; if (v == 10.0) print \"eq\"; \"echo 3 20\" | getline; if ($1 < $2) print \"num\"; print NF }", "", "eq\nnum\n2\n");
+test!(test_getline_missing_file, "BEGIN { x = \"a\"; r = getline x < \"/nonexistent/file\"; print r \" \" x }", "", "-1 a\n");
+test!(test_getline_into_array_and_field, "BEGIN { getline a[1] < ARGV[1]; getline $2 < ARGV[1]; print a[1] \"-\" $0 \"-\" NF \"-\" NR }", "x y\nz\n", "x y- z-2-0\n");
+
+// gawk extensions. gawk is run with --posix so none of the other awks can check these.
+const GAWK_EXT: usize = Awk::Goawk as usize | Awk::Gawk as usize | Awk::Mawk as usize | Awk::Onetrueawk as usize;
+
+test_except!(test_gensub_backrefs, r#"BEGIN { print gensub("(a+)(b+)", "<\\2\\1>", "g", "xaabbyabz") }"#, "", "x<bbaa>y<ba>z\n", GAWK_EXT);
+test_except!(test_gensub_nth, r#"BEGIN { s = "a a a a"; print gensub("a", "b", 3, s); print s; print gensub("a", "b", "2", s) }"#, "", "a a b a\na a a a\na b a a\n", GAWK_EXT);
+test_except!(test_gensub_how_invalid, r#"BEGIN { print gensub("l", "L", 0, "hello") gensub("l", "L", "x", "hello") gensub("l", "L", 9, "hello") }"#, "", "heLloheLlohello\n", GAWK_EXT);
+test_except!(test_gensub_col_0, r#"{ print gensub("([0-9]+)", "(\\1)", "G"); print $0 }"#, "a 12 b 3\n", "a (12) b (3)\na 12 b 3\n", GAWK_EXT);
+test_except!(test_gensub_amp, r#"BEGIN { print gensub("o", "[&|\\0|\\\\&]", 2, "foo boo") }"#, "", "fo[o|o|\\o] boo\n", GAWK_EXT);
+test_except!(test_gensub_empty_matches, r#"{ print gensub("x*", "-", "g"); print gensub("b*", "-", "g") }"#, "abc\n", "-a-b-c-\n-a-c-\n", GAWK_EXT);
+test_except!(test_gensub_anchor, r#"BEGIN { print gensub("^a", "X", "g", "aaa"); print gensub("(.)(.)", "\\2\\1", "g", "abcde") }"#, "", "Xaa\nbadce\n", GAWK_EXT);
+test_except!(test_gensub_unmatched_group, r#"BEGIN { print gensub("x(y)?(z)", "[\\1|\\2|\\3]", "g", "xz xyz") }"#, "", "[|z|] [y|z|]\n", GAWK_EXT);
+test_except!(test_gensub_class_groups, r#"BEGIN { print gensub("([^]a]+)(]|[[:digit:]])", "<\\2\\1>", "g", "xy]b1a") }"#, "", "<]xy><1b>a\n", GAWK_EXT);
+// rawk's regexes have no intervals, braces are literal in groups too
+test_except!(test_gensub_interval_groups, r#"BEGIN { print gensub("(a|b){2}c", "<\\1>", 1, "abc"); print gensub("(a|b){2}c", "<\\1>", 1, "ab{2}c") }"#, "", "abc\na<b>\n", GAWK_EXT);
+#[test]
+fn test_gensub_unknown_groups_fails() {
+    // The group matcher can't follow mawk's reading of 2$? so the run fails instead of guessing
+    let (out, err) = run_rawk_fatal(&[r#"BEGIN { print "before"; print gensub("(.)2$?", "<\\1>", "g", "xy") }"#]);
+    assert_eq!(out, b"before\n");
+    assert_eq!(err.msg, "gensub: unable to find the groups of regex (.)2$?");
+}
+test!(test_for_in_sum, "BEGIN { a[1] = 1; a[2] = 2; a[3] = 3; for (k in a) { s += a[k]; n++ } print s \" \" n }", "", "6 3\n");
+test!(test_for_in_key_is_string, "BEGIN { a[9] = 1; for (k in a) { print (k < 10) } }", "", "0\n");
+test!(test_for_in_fields, "{ a[$1] = $2 } END { for (k in a) { if (k == \"b\") { print a[k] } } }", "a 1\nb 2\n", "2\n");
+test!(test_for_in_break, "BEGIN { a[1] = 1; a[2] = 2; for (k in a) { n++; break } print n }", "", "1\n");
+test_except!(test_sorted_in_ind, r#"BEGIN { a["b"] = 1; a["c"] = 2; a["a"] = 3; PROCINFO["sorted_in"] = "@ind_str_asc"; for (k in a) { print k }; PROCINFO["sorted_in"] = "@ind_str_desc"; for (k in a) { print k } }"#, "", "a\nb\nc\nc\nb\na\n", GAWK_EXT);
+test_except!(test_sorted_in_ind_num, r#"BEGIN { a[10] = 1; a[9] = 2; a[100] = 3; PROCINFO["sorted_in"] = "@ind_num_asc"; for (k in a) { print k }; PROCINFO["sorted_in"] = "@ind_str_asc"; for (k in a) { print k } }"#, "", "9\n10\n100\n10\n100\n9\n", GAWK_EXT);
+test_except!(test_sorted_in_val, r#"BEGIN { a["x"] = 10; a["y"] = 9; a["z"] = "abc"; PROCINFO["sorted_in"] = "@val_num_desc"; for (k in a) { print k }; PROCINFO["sorted_in"] = "@val_str_asc"; for (k in a) { print k }; PROCINFO["sorted_in"] = "@val_type_asc"; for (k in a) { print k } }"#, "", "x\ny\nz\nx\ny\nz\ny\nx\nz\n", GAWK_EXT);
+test_except!(test_sorted_in_user_func, r#"function by_len(i1, v1, i2, v2) { return length(v1) - length(v2) } BEGIN { a[1] = "ccc"; a[2] = "a"; a[3] = "bb"; PROCINFO["sorted_in"] = "by_len"; for (k in a) { print k " " a[k] } }"#, "", "2 a\n3 bb\n1 ccc\n", GAWK_EXT);
+test_except!(test_sorted_in_nested_return, r#"function first(arr) { for (k in arr) { for (j in arr) { return k j } } } BEGIN { a["b"] = 1; a["a"] = 1; PROCINFO["sorted_in"] = "@ind_str_asc"; print first(a); for (k in a) { print k } }"#, "", "aa\na\nb\n", GAWK_EXT);
+test_except!(test_asort, r#"BEGIN { a["x"] = 3; a["y"] = "b"; a["z"] = 1; a["w"] = "a"; n = asort(a, b); for (i = 1; i <= n; i++) { print b[i] }; print a["x"]; n = asort(a); print n " " a[1] " " a[4] }"#, "", "1\n3\na\nb\n3\n4 1 b\n", GAWK_EXT);
+test_except!(test_asort_how, r#"BEGIN { a[1] = 10; a[2] = 9; a[3] = 100; n = asort(a, b, "@val_str_asc"); for (i = 1; i <= n; i++) { print b[i] } }"#, "", "10\n100\n9\n", GAWK_EXT);
+test_except!(test_asorti, r#"BEGIN { a["b"] = 1; a["c"] = 3; a["a"] = 2; n = asorti(a, b); for (i = 1; i <= n; i++) { print b[i] }; n = asorti(a, c, "@val_num_desc"); for (i = 1; i <= n; i++) { print c[i] }; n = asorti(a); print n " " a[1] }"#, "", "a\nb\nc\nc\na\nb\n3 a\n", GAWK_EXT);
+test_except!(test_procinfo, r#"BEGIN { print (PROCINFO["pid"] > 0) (PROCINFO["ppid"] > 0) (PROCINFO["uid"] >= 0) (PROCINFO["egid"] >= 0) (PROCINFO["version"] != ""); print ("sorted_in" in PROCINFO) }"#, "", "11111\n0\n", GAWK_EXT);
+test_except!(test_systime, "BEGIN { print systime() > 1600000000 }", "", "1\n", GAWK_EXT);
+test_except!(test_mktime_utc, r#"BEGIN { print mktime("2024 02 29 12 30 15", 1); print mktime("1970 01 02 00 00 00", 1) }"#, "", "1709209815\n86400\n", GAWK_EXT);
+test_except!(test_mktime_bad_spec, r#"BEGIN { print mktime("2024 02 29"); print mktime("a b c d e f") }"#, "", "-1\n-1\n", GAWK_EXT);
+test_except!(test_strftime_utc, r#"BEGIN { print strftime("%Y-%m-%d %H:%M:%S", 1709209815, 1); print strftime("%j", 86400 * 40, 1) }"#, "", "2024-02-29 12:30:15\n041\n", GAWK_EXT);
+test_except!(test_strftime_round_trip, r#"BEGIN { t = mktime("2001 09 09 01 46 40"); print strftime("%Y %m %d %H %M %S", t) }"#, "", "2001 09 09 01 46 40\n", GAWK_EXT);
+test_except!(test_strftime_default_ts, r#"BEGIN { print length(strftime("%Y")) }"#, "", "4\n", GAWK_EXT);
+test_except!(test_bitwise, "BEGIN { print and(12, 10); print or(12, 10); print xor(12, 10); print compl(0) == 2^53 - 1 }", "", "8\n14\n6\n1\n", GAWK_EXT);
+test_except!(test_shifts, "BEGIN { print lshift(1, 10); print rshift(1024, 3); print rshift(255, 100); print and(7.9, 3.2) }", "", "1024\n128\n0\n3\n", GAWK_EXT);
+test_except!(test_bitwise_flags, "{ print and($1, 4) ? \"set\" : \"clear\" }", "5\n3\n12\n", "set\nclear\nset\n", GAWK_EXT);
+#[test]
+fn test_bitwise_negative() {
+    let (out, err) = run_rawk_fatal(&["BEGIN { print \"a\"; print and(-1, 1); print \"b\" }"]);
+    assert_eq!(out, b"a\n");
+    assert!(err.msg.contains("negative value -1 is not allowed"));
+}
+test_except!(test_strtonum, r#"BEGIN { print strtonum("0x1F"); print strtonum("017"); print strtonum("019"); print strtonum("12abc"); print strtonum(3.5) }"#, "", "31\n15\n19\n12\n3.5\n", GAWK_EXT);
+test_except!(test_strtonum_fields, "{ print strtonum($1) + 1 }", "0x10\n010\n", "17\n11\n", GAWK_EXT);
+test_except!(test_isarray, "BEGIN { a[1] = 1; s = 1; print isarray(a) \" \" isarray(s) \" \" isarray(1) \" \" isarray(never_used) }", "", "1 0 0 0\n", GAWK_EXT);
+test_except!(test_isarray_arg, "function f(x) { return isarray(x) } BEGIN { a[1] = 1; print f(a) }", "", "1\n", GAWK_EXT);
+test_except!(test_typeof, r#"BEGIN { a[1] = 1; n = 1; s = "s"; print typeof(a); print typeof(n); print typeof(s); print typeof(u + 0); print typeof(never_used) }"#, "", "array\nnumber\nstring\nnumber\nuntyped\n", GAWK_EXT);
+test_except!(test_typeof_input, "{ print typeof($1) \" \" typeof($2) }", "12 abc\n", "strnum string\n", GAWK_EXT);
+test_except!(test_typeof_unassigned, "BEGIN { x; print typeof(x) }", "", "unassigned\n", GAWK_EXT);
+test_except!(test_fpat_csv, r#"BEGIN { FPAT = "([^,]*)|(\"[^\"]+\")" } { print NF; for (i = 1; i <= NF; i++) { print "<" $i ">" } }"#, "abc,\"d,e\",,f\nx,\n", "4\n<abc>\n<\"d,e\">\n<>\n<f>\n2\n<x>\n<>\n", GAWK_EXT);
+test_except!(test_fpat_default, "BEGIN { FPAT = FPAT } { print NF \" \" $2 }", "  a  b\tc \n", "3 b\n", GAWK_EXT);
+test_except!(test_fpat_next_record, "{ print NF; FPAT = \"[0-9]+\" }", "a1 b22\na1 b22\n", "2\n2\n", GAWK_EXT);
+test_except!(test_fpat_then_fs, "NR == 1 { FPAT = \"[^,]+\" } { print $1; FS = \",\" }", "a b,c\na b,c\nd,e f\n", "a\na b\nd\n", GAWK_EXT);
+test_except!(test_fpat_assign_record, r#"BEGIN { FPAT = "[0-9]+" } { $0 = "x1y22"; print NF " " $2; $1 = "z"; print $0 }"#, "ignored\n", "2 22\nz 22\n", GAWK_EXT);
+test_except!(test_field_widths, r#"BEGIN { FIELDWIDTHS = "5 3 4 *" } { print NF; for (i = 1; i <= NF; i++) { print "<" $i ">" } }"#, "AAAAABBBCCCC rest\nAAAAABB\n\n", "4\n<AAAAA>\n<BBB>\n<CCCC>\n< rest>\n2\n<AAAAA>\n<BB>\n0\n", GAWK_EXT);
+test_except!(test_field_widths_skip, r#"BEGIN { FIELDWIDTHS = "2:3 1:2" } { print NF " " $1 " " $2; $2 = "zz"; print $0 }"#, "12345678\n", "2 345 78\n345 zz\n", GAWK_EXT);
+test_except!(test_field_widths_then_fs, "NR == 1 { FIELDWIDTHS = \"1 1\" } NR == 2 { FS = \" \" } { print $1 }", "ab cd\nab cd\nab cd\n", "ab\na\nab\n", GAWK_EXT);
+#[test]
+fn test_strftime_out_of_range() {
+    // Years libc can't represent give an empty string and a warning, like gawk
+    let (out, err) = run_rawk(&["BEGIN { print \"[\" strftime(\"%Y\", -1e30) \"]\"; print \"after\" }"]).unwrap();
+    assert_eq!(out, b"[]\nafter\n");
+    assert_eq!(err, b"rawk: warning: strftime: second argument out of range for time_t\n");
+}
 #[test]
 fn test_fnr_nr_two_files() {
     test_runner_multifile("test_fnr_nr_two_files", "{print NR; print FNR}",
@@ -1301,7 +1463,8 @@ fn test_fnr_nr_two_files() {
 
 #[test]
 fn test_filename_two_files() {
-    test_runner_multifile("test_fnr_nr_two_files", "{print FILENAME}",
+    // FILENAME is the path as given which is in a temp dir, only check the name
+    test_runner_multifile("test_fnr_nr_two_files", "{ n = split(FILENAME, parts, \"/\"); print parts[n] }",
                           vec![("1\n2\n3\n", "file1"), ("4\n5\n", "file2")],
                           "file1\nfile1\nfile1\nfile2\nfile2\n", 0);
 }
@@ -1309,18 +1472,124 @@ fn test_filename_two_files() {
 #[test]
 fn test_argv_rawk() {
     // only test rawk since argv[0] contains interpreter name it won't pass for all awks
-    test_runner_multifile("test_argv_argc", "BEGIN {print ARGC; for (i = 0; i < ARGC; i++) { print ARGV[i] } }",
+    test_runner_multifile("test_argv_argc", "BEGIN { print ARGC; print ARGV[0]; for (i = 1; i < ARGC; i++) { n = split(ARGV[i], parts, \"/\"); print parts[n] } }",
                           vec![("1\n", "file1ø"), ("2\n", "filΩ2"), ("2\n", "filΩ2   uh-oh-spaces")],
-                          "3\nrawk\nfile1ø\nfilΩ2\nfilΩ2   uh-oh-spaces\n", Awk::Onetrueawk  as usize| Awk::Goawk  as usize| Awk::Mawk  as usize| Awk::Gawk as usize );
+                          "4\nrawk\nfile1ø\nfilΩ2\nfilΩ2   uh-oh-spaces\n", Awk::Onetrueawk  as usize| Awk::Goawk  as usize| Awk::Mawk  as usize| Awk::Gawk as usize );
 }
 
 #[test]
 fn test_argv_all() {
     // don't print argv[0] so we can test against other awks
-    test_runner_multifile("test_argv_argc", "BEGIN {print ARGC; for (i = 1; i < ARGC; i++) { print ARGV[i] } }",
+    test_runner_multifile("test_argv_argc", "BEGIN { print ARGC; for (i = 1; i < ARGC; i++) { n = split(ARGV[i], parts, \"/\"); print parts[n] } }",
                           vec![("1\n", "file1ø"), ("2\n", "filΩ2"), ("2\n", "filΩ2   uh-oh-spaces")],
-                          "3\nfile1ø\nfilΩ2\nfilΩ2   uh-oh-spaces\n", 0);
+                          "4\nfile1ø\nfilΩ2\nfilΩ2   uh-oh-spaces\n", 0);
 }
+
+#[test]
+fn test_operand_assignments() {
+    let dir = tempfile::tempdir().unwrap();
+    let f1 = dir.path().join("f1");
+    let f2 = dir.path().join("f2");
+    std::fs::write(&f1, "1\n2\n").unwrap();
+    std::fs::write(&f2, "a,b\n").unwrap();
+    let (f1, f2) = (f1.to_str().unwrap(), f2.to_str().unwrap());
+
+    // Each assignment happens when reading reaches it, not before BEGIN
+    let (out, _err) = run_rawk(&["BEGIN { print \"[\" a \"]\" } { print a } END { print a }", "a=1", f1, "a=2", f2]).unwrap();
+    assert_eq!(out, b"[]\n1\n1\n2\n2\n");
+    // FS set between files splits the next file
+    let (out, _err) = run_rawk(&["{ print $1 }", f2, "FS=,", f2]).unwrap();
+    assert_eq!(out, b"a,b\na\n");
+    // Assignments after the last file happen before END
+    let (out, _err) = run_rawk(&["END { print x \"|\" (x == 41) }", f1, "x=41"]).unwrap();
+    assert_eq!(out, b"41|1\n");
+    // and show up in ARGV
+    let (out, _err) = run_rawk(&["BEGIN { print ARGC \" \" ARGV[1] }", "x=41", f1]).unwrap();
+    assert_eq!(out, b"3 x=41\n");
+}
+#[test]
+fn test_runtime_error_fails_run() {
+    // A fatal error inside a function stops the whole program and the run fails so main exits 2
+    let (out, err) = run_rawk_fatal(&["function f() { getline; print \"in f\" } BEGIN { print \"before\"; f(); print \"after\" }", "/nonexistent/file"]);
+    assert_eq!(out, b"before\n");
+    assert!(err.msg.contains("Failed to open file /nonexistent/file"));
+}
+#[test]
+fn test_sort_error_fails_run() {
+    // A missing comparison function stops the program right after the asort that named it
+    let (out, err) = run_rawk_fatal(&["function f() { a[1] = 1; asort(a, b, \"nope\"); print \"in f\" } BEGIN { print \"before\"; f(); print \"after\" }"]);
+    assert_eq!(out, b"before\n");
+    assert!(err.msg.contains("`nope` is not defined"));
+}
+#[test]
+fn test_missing_input_file() {
+    // Like mawk the run stops at a file that can't be opened, the files after it and END don't run
+    let dir = tempfile::tempdir().unwrap();
+    let f1 = dir.path().join("f1");
+    let f2 = dir.path().join("f2");
+    let missing = dir.path().join("missing");
+    std::fs::write(&f1, "1\n").unwrap();
+    std::fs::write(&f2, "2\n").unwrap();
+    let (f1, f2, missing) = (f1.to_str().unwrap(), f2.to_str().unwrap(), missing.to_str().unwrap());
+    let (out, err) = run_rawk_fatal(&["{ print $0 } END { print \"end\" }", f1, missing, f2]);
+    assert_eq!(out, b"1\n");
+    assert!(err.msg.contains(missing));
+}
+#[test]
+fn test_csv_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = dir.path().join("data.csv");
+    std::fs::write(&data, "a,\"b,c\",\"say \"\"hi\"\"\",\"multi\nline\"\nx,y\n").unwrap();
+    // Quoted fields keep their commas and newlines, doubled quotes become one
+    let prog = "{ print NF; for (i = 1; i <= NF; i++) { print \"<\" $i \">\" } }";
+    let (out, _err) = run_rawk(&["--csv", prog, data.to_str().unwrap()]).unwrap();
+    assert_eq!(out, b"4\n<a>\n<b,c>\n<say \"hi\">\n<multi\nline>\n2\n<x>\n<y>\n");
+}
+#[test]
+fn test_nextfile() {
+    test_runner_multifile("test_nextfile", "{ print $0; nextfile } END { print NR }",
+                          vec![("1\n2\n3\n", "file1"), ("4\n5\n", "file2")],
+                          "1\n4\n2\n", 0);
+}
+
+#[test]
+fn test_nextfile_in_for_in() {
+    test_runner_multifile("test_nextfile_in_for_in", "{ a[1] = 1; for (k in a) { print FNR; nextfile } }",
+                          vec![("1\n2\n", "file1"), ("3\n", "file2")],
+                          "1\n1\n", 0);
+}
+
+#[test]
+fn test_beginfile_endfile() {
+    test_runner_multifile("test_beginfile_endfile", "BEGINFILE { print \"start \" FNR } { print FNR \" \" NR } ENDFILE { print \"end \" FNR \" \" NR }",
+                          vec![("1\n2\n", "file1"), ("", "file2"), ("3\n", "file3")],
+                          "start 0\n1 1\n2 2\nend 2 2\nstart 0\nend 0 2\nstart 0\n1 3\nend 1 3\n", GAWK_EXT);
+}
+
+#[test]
+fn test_beginfile_filename() {
+    test_runner_multifile("test_beginfile_filename", "BEGINFILE { print index(FILENAME, \"file2\") > 0 } ENDFILE { print index(FILENAME, \"file2\") > 0 }",
+                          vec![("1\n", "file1"), ("2\n", "file2")],
+                          "0\n0\n1\n1\n", GAWK_EXT);
+}
+
+#[test]
+fn test_beginfile_nextfile() {
+    // nextfile in BEGINFILE skips the file's records and its ENDFILE
+    test_runner_multifile("test_beginfile_nextfile", "BEGINFILE { if (++files == 1) { nextfile } } { print $0 } ENDFILE { print \"end \" files } END { print NR }",
+                          vec![("1\n2\n", "file1"), ("3\n", "file2")],
+                          "3\nend 2\n1\n", GAWK_EXT);
+}
+
+#[test]
+fn test_endfile_after_nextfile() {
+    test_runner_multifile("test_endfile_after_nextfile", "{ print $0; nextfile } ENDFILE { print \"end \" FNR }",
+                          vec![("1\n2\n", "file1"), ("3\n4\n", "file2")],
+                          "1\nend 1\n3\nend 1\n", GAWK_EXT);
+}
+
+test_except!(test_beginfile_errno, "BEGINFILE { print \"[\" ERRNO \"]\" } END { print NR }", "1\n2\n", "[]\n2\n", GAWK_EXT);
+
 /*
     TODO: Things I have yet to impl
 
@@ -10,6 +10,7 @@ use std::fs;
 use std::io::{Write};
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Mutex;
 use std::str::from_utf8_unchecked;
 use std::time::{Duration, Instant};
 use tempfile::tempdir;
@@ -96,6 +97,37 @@ pub fn test_runner_multifile<S: AsRef<str>, StdoutT: Into<Vec<u8>>>(
         "LEFT jawk -- RIGHT oracle, did not match"
     );
 
+    // With the jit enabled the same program must match too
+    #[cfg(feature = "jit")]
+    {
+        let jit_stdout = Box::new(IoCapture::new());
+        let mut jit_args = vec!["--jit".to_string()];
+        jit_args.extend_from_slice(&args);
+        let _ = runner(jit_args, jit_stdout.clone(), Box::new(IoCapture::new())).unwrap();
+        let jit_output = jit_stdout.collect();
+        assert_eq!(
+            unsafe { from_utf8_unchecked(&jit_output) },
+            expected,
+            "LEFT jit rawk -- RIGHT oracle, did not match"
+        );
+    }
+    // The same program saved with --compile and run with -c must match too
+    let prog_path = temp_dir.path().join("prog.awk");
+    fs::write(&prog_path, prog).unwrap();
+    let compiled_path = temp_dir.path().join("prog.rawkc").to_str().unwrap().to_string();
+    let compile_args = vec!["--compile".to_string(), prog_path.to_str().unwrap().to_string(), "-o".to_string(), compiled_path.clone()];
+    let _ = runner(compile_args, Box::new(IoCapture::new()), Box::new(IoCapture::new())).unwrap();
+    let precompiled_stdout = Box::new(IoCapture::new());
+    let mut precompiled_args = vec!["-c".to_string(), compiled_path];
+    precompiled_args.extend_from_slice(&args[1..]);
+    let _ = runner(precompiled_args, precompiled_stdout.clone(), Box::new(IoCapture::new())).unwrap();
+    let precompiled_output = precompiled_stdout.collect();
+    assert_eq!(
+        unsafe { from_utf8_unchecked(&precompiled_output) },
+        expected,
+        "LEFT precompiled rawk -- RIGHT oracle, did not match"
+    );
+
     let run_perf_tests = std::env::vars().any(|f| f.0 == "jperf" && (f.1 == "true" || f.1 == "true\n"));
 
 
@@ -109,7 +141,30 @@ pub fn test_runner_multifile<S: AsRef<str>, StdoutT: Into<Vec<u8>>>(
     }
 }
 
+// Comparing against an awk that isn't installed fails unless this is set, then it's skipped
+const SKIP_MISSING_AWKS: &str = "RAWK_SKIP_MISSING_AWKS";
+
+fn installed(interpreter: &str) -> bool {
+    if std::process::Command::new(interpreter).output().is_ok() {
+        return true;
+    }
+    if std::env::var_os(SKIP_MISSING_AWKS).is_none() {
+        panic!("{} is not installed. Install it or set {}=1 to skip comparing against it", interpreter, SKIP_MISSING_AWKS);
+    }
+    // Test output is captured, write to stderr directly so the skip is seen. Once per awk.
+    static WARNED: Mutex<Vec<String>> = Mutex::new(vec![]);
+    let mut warned = WARNED.lock().unwrap();
+    if !warned.iter().any(|awk| awk == interpreter) {
+        warned.push(interpreter.to_string());
+        let _ = writeln!(std::io::stderr(), "warning: {} is not installed, skipping comparisons against it", interpreter);
+    }
+    false
+}
+
 fn test_against(interpreter: &str, oracle_output: &[u8], args: &[String]) {
+    if !installed(interpreter) {
+        return;
+    }
     let output = test_once(interpreter, args);
 
     assert_eq!(
@@ -125,9 +180,8 @@ fn test_perf(
     oracle_output: &[u8],
     args: &[String],
 ) {
-    match std::process::Command::new(interpreter).output() {
-        Ok(_) => {}
-        Err(_err) => return, // this interpreter doesn't exist
+    if !installed(interpreter) {
+        return;
     }
     let mut our_total = 0;
     let mut other_total = 0;
@@ -184,4 +238,24 @@ pub fn long_number_file() -> String {
         string.push('\n');
     }
     string
-}
\ No newline at end of file
+}
+// Runs rawk with args as they'd be given on the command line, for tests that need more than
+// a program and files. Returns stdout and stderr.
+pub fn run_rawk(args: &[&str]) -> Result<(Vec<u8>, Vec<u8>), crate::printable_error::PrintableError> {
+    let stdout = IoCapture::new();
+    let stderr = IoCapture::new();
+    let args = args.iter().map(|arg| arg.to_string()).collect();
+    runner(args, Box::new(stdout.clone()), Box::new(stderr.clone()))?;
+    Ok((stdout.collect(), stderr.collect()))
+}
+
+// Runs rawk like run_rawk for a program that hits a fatal error. Returns what was printed
+// before the error and the error.
+pub fn run_rawk_fatal(args: &[&str]) -> (Vec<u8>, crate::printable_error::PrintableError) {
+    let stdout = IoCapture::new();
+    let args = args.iter().map(|arg| arg.to_string()).collect();
+    match runner(args, Box::new(stdout.clone()), Box::new(IoCapture::new())) {
+        Ok(_) => panic!("expected a fatal error"),
+        Err(err) => (stdout.collect(), err),
+    }
+}
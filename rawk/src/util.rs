@@ -1,7 +1,7 @@
 use std::cmp::min;
-use std::fmt::Debug;
 use std::os::raw::c_void;
 use quick_drop_deque::QuickDropDeque;
+pub use awk_frontend::util::unwrap;
 
 pub fn clamp_to_slice_index(zero_indexed: f64, slice_len: usize) -> usize {
     if zero_indexed >= slice_len as f64 {
@@ -39,7 +39,7 @@ pub fn index_of<T: PartialEq>(needle: &[T], haystack: &[T]) -> Option<usize> {
 
 pub fn memchr_libc(buf: &[u8], needle: u8) -> Option<usize> {
     let len = buf.len();
-    let buf = buf.as_ptr() as *const u8;
+    let buf = buf.as_ptr();
     memchr_libc_ptr(buf as *const c_void, len, needle)
 }
 
@@ -50,7 +50,7 @@ pub fn memchr_libc_ptr(ptr: *const std::os::raw::c_void, len: usize, needle: u8)
             needle as i32,
             len)
     };
-    if res == 0 as *mut std::os::raw::c_void {
+    if res.is_null() {
         return None;
     }
     let res = res as *const u8;
@@ -90,10 +90,10 @@ fn index_in_slices_multibyte(needle: &[u8], left: &[u8], right: &[u8], offset: u
     }
     for idx in 0..(hlen - nlen) + 1 {
         let mut matches = true;
-        for needle_idx in 0..nlen {
+        for (needle_idx, needle_byte) in needle.iter().enumerate() {
             let sub_idx = idx + needle_idx;
             let haystack_at_idx = if sub_idx > llen { right[sub_idx - llen] } else { left[sub_idx] };
-            if needle[needle_idx] != haystack_at_idx {
+            if *needle_byte != haystack_at_idx {
                 matches = false;
                 break;
             }
@@ -112,33 +112,12 @@ pub fn pad(mut str: String, len: usize) -> String {
         }
     } else {
         while str.len() < len {
-            str.push_str(" ");
+            str.push(' ');
         }
     }
     str
 }
 
-#[cfg(debug_assertions)]
-pub fn unwrap_err<T,S: Debug>(value: Result<T,S>) -> T {
-    value.unwrap()
-}
-#[cfg(not(debug_assertions))]
-#[inline(always)]
-pub fn unwrap_err<T,S: Debug>(value: Result<T,S>) -> T {
-    unsafe { value.unwrap_unchecked() }
-}
-
-
-#[cfg(debug_assertions)]
-pub fn unwrap<T>(value: Option<T>) -> T {
-    value.unwrap()
-}
-#[cfg(not(debug_assertions))]
-#[inline(always)]
-pub fn unwrap<T>(value: Option<T>) -> T {
-    unsafe { value.unwrap_unchecked() }
-}
-
 // Search left and right for needle. Return index of result + offset.
 pub fn index_in_slices(needle: &[u8], slices: (&[u8], &[u8]), offset: usize) -> Option<usize> {
     let (left, right) = slices;
@@ -154,7 +133,7 @@ pub fn index_in_slices(needle: &[u8], slices: (&[u8], &[u8]), offset: usize) ->
         }
         return None;
     }
-    return index_in_slices_multibyte(needle, left, right, offset);
+    index_in_slices_multibyte(needle, left, right, offset)
 }
 
 pub fn index_in_slice(needle: &[u8], haystack: &[u8]) -> Option<usize> {
@@ -166,16 +145,16 @@ pub fn index_in_slice(needle: &[u8], haystack: &[u8]) -> Option<usize> {
         }
         return None;
     }
-    return index_in_slices_multibyte(needle, haystack, &[], 0);
+    index_in_slices_multibyte(needle, haystack, &[], 0)
 }
 
 pub fn index_in_full_dq(needle: &[u8], haystack: &QuickDropDeque) -> Option<usize> {
-    return index_in_slices(needle, haystack.as_slices(), 0)
+    index_in_slices(needle, haystack.as_slices(), 0)
 }
 
 
 pub fn index_in_dq(needle: &[u8], haystack: &QuickDropDeque, start: usize, end: usize) -> Option<usize> {
-    return index_in_slices(needle, subslices(haystack, start, end), start)
+    index_in_slices(needle, subslices(haystack, start, end), start)
 }
 
 #[cfg(test)]
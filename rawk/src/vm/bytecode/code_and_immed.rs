@@ -1,10 +1,33 @@
-use std::io::Write;
-use crate::awk_str::{AwkByteStr, RcAwkStr};
+use crate::awk_str::AwkByteStr;
 use crate::parser::SclSpecial;
 use crate::typing::{GlobalArrayId, GlobalScalarId};
 use crate::vm::VirtualMachine;
 
-type ByteFn = fn(&mut VirtualMachine, usize, Immed) -> usize;
+pub type ByteFn = fn(&mut VirtualMachine, usize, Immed) -> usize;
+
+// The function to call and how many of its args the caller passed. The rest are fresh locals.
+#[derive(Copy, Clone)]
+pub struct CallTarget {
+    pub target: u32,
+    pub args: u32,
+}
+
+// Where an array op finds its key. Str(n) is n strings joined by SUBSEP. A lone index that is a
+// number or Var stays on its own stack so integer keys are never converted to strings.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Indices {
+    Str(u32),
+    Num,
+    Var,
+}
+
+// A global num scalar and the constant added to it. Only constants exact as an f32 are fused into
+// AddGsclNum so both fit in 8 bytes.
+#[derive(Copy, Clone)]
+pub struct GsclAdd {
+    pub id: u32,
+    pub delta: f32,
+}
 
 #[derive(Copy, Clone)]
 pub union Immed {
@@ -14,12 +37,14 @@ pub union Immed {
     pub offset: isize,
     pub arg_idx: usize,
     pub concat_count: usize,
-    pub array_indices: usize,
-    pub sub3_isglobal: bool,
+    pub array_indices: Indices,
     pub printf_args: usize,
-    pub call_target: usize,
+    pub call: CallTarget,
+    pub native_id: usize,
     pub string: *const AwkByteStr,
     pub special: SclSpecial,
+    pub column: usize,
+    pub gscl_add: GsclAdd,
 }
 
 #[derive(Copy, Clone)]
@@ -1,15 +1,13 @@
-use crate::typing::{FunctionMap, GlobalArrayId, GlobalScalarId, ITypedFunction};
-use std::fmt::{Debug, Write};
-use std::rc::Rc;
+use crate::typing::{FunctionMap, GlobalArrayId, GlobalScalarId, NativeArgT};
+use std::fmt::Debug;
 use crate::awk_str::RcAwkStr;
-use crate::parser::{ArgT, ScalarType, SclSpecial};
+use crate::parser::{ScalarType, SclSpecial};
 use crate::stack_counter::{StackCounter as SC};
 use crate::stackt::StackT;
 use crate::util::pad;
-use crate::vm::bytecode::code_and_immed::{CodeAndImmed as CI};
-use crate::vm::bytecode::{Immed, Meta};
-use crate::vm::{VmProgram, StringScalar};
-use crate::vm::bytecode::subroutines::{num_to_var, builtin_atan2, builtin_cos, builtin_exp, builtin_substr2, builtin_substr3, builtin_index, builtin_int, builtin_length0, builtin_length1, builtin_log, builtin_rand, builtin_sin, builtin_split2, builtin_split3, builtin_sqrt, builtin_srand0, builtin_srand1, builtin_tolower, builtin_toupper, num_to_str, str_to_var, str_to_num, var_to_num, var_to_str, pop, pop_str, pop_num, column, assign_gscl_var, assign_gscl_num, assign_gscl_str, assign_gscl_ret_str, assign_gscl_ret_var, assign_gscl_ret_num, global_arr, gscl_var, gscl_num, gscl_str, assign_arg_var, assign_arg_str, assign_arg_num, assign_arg_ret_var, assign_arg_ret_str, assign_arg_ret_num, arg_var, arg_str, arg_num, arg_arr, exp, mult, div, modulo, add, minus, lt, gt, lteq, gteq, eqeq, neq, matches, nmatches, assign_array_var, assign_array_str, assign_array_num, assign_array_ret_var, assign_array_ret_str, assign_array_ret_num, array_index, array_member, concat, gsub3, sub3, rel_jump_if_false_var, rel_jump_if_false_str, rel_jump_if_false_num, rel_jump_if_true_var, rel_jump_if_true_str, rel_jump_if_true_num, rel_jump, print, printf, noop, ret, const_num, const_str, const_str_num, call, neq_num, gteq_num, eqeq_num, lteq_num, lt_num, gt_num, clear_gscl, clear_argscl, rel_jump_if_true_next_line, rel_jump_if_false_next_line, scl_special, assign_scl_special, assign_ret_scl_special, builtin_matches};
+use crate::vm::bytecode::code_and_immed::{ByteFn, CodeAndImmed as CI};
+use crate::vm::bytecode::{CallTarget, GsclAdd, Immed, Indices, Meta};
+use crate::vm::bytecode::subroutines::{num_to_var, builtin_atan2, builtin_cos, builtin_exp, builtin_substr2, builtin_substr3, builtin_index, builtin_int, builtin_length0, builtin_length1, builtin_log, builtin_rand, builtin_sin, builtin_split2, builtin_split3, builtin_sqrt, builtin_srand0, builtin_srand1, builtin_tolower, builtin_toupper, num_to_str, str_to_var, str_to_num, var_to_num, var_to_str, pop, pop_str, pop_num, column, assign_gscl_var, assign_gscl_num, assign_gscl_str, assign_gscl_ret_str, assign_gscl_ret_var, assign_gscl_ret_num, global_arr, gscl_var, gscl_num, gscl_str, assign_arg_var, assign_arg_str, assign_arg_num, assign_arg_ret_var, assign_arg_ret_str, assign_arg_ret_num, arg_var, arg_str, arg_num, arg_arr, exp, mult, div, modulo, add, minus, lt, gt, lteq, gteq, eqeq, neq, matches, nmatches, assign_array_var, assign_array_str, assign_array_num, assign_array_ret_var, assign_array_ret_str, assign_array_ret_num, array_index, array_member, assign_num_array, assign_ret_num_array, num_array_index, inc_num_array, concat, gsub3, sub3, rel_jump_if_false_var, rel_jump_if_false_str, rel_jump_if_false_num, rel_jump_if_true_var, rel_jump_if_true_str, rel_jump_if_true_num, rel_jump, print, print_num, printf, ret, const_num, const_str, const_str_num, call, call_native, neq_num, gteq_num, eqeq_num, lteq_num, lt_num, gt_num, clear_gscl, clear_argscl, rel_jump_if_true_next_line, rel_jump_if_false_next_line, scl_special, assign_scl_special, assign_ret_scl_special, builtin_matches, builtin_gensub3, builtin_gensub4, builtin_asort1, builtin_asort2, builtin_asort3, builtin_asorti1, builtin_asorti2, builtin_asorti3, for_in_start, for_in_next, for_in_key, for_in_end, builtin_systime, builtin_mktime, builtin_strftime, builtin_and, builtin_or, builtin_xor, builtin_lshift, builtin_rshift, builtin_compl, builtin_strtonum, builtin_length_arr, builtin_typeof, next_file, check_file_opened, next_record_in_file, skip_file, getline_input, getline_input_var, getline_file, getline_command, getline_record, getline_result, inc_gscl, inc_arg, inc_scl_special, inc_array, inc_column, assign_column, assign_ret_column, column_num, const_column, const_column_num, add_const, add_gscl_num, rel_jump_if_cmp_num, rel_jump_if_cmp_var, INCR_RET_NONE, INCR_RET_OLD, INCR_RET_NEW, CMP_LT, CMP_GT, CMP_LTEQ, CMP_GTEQ, CMP_EQEQ, CMP_NEQ};
 
 pub type LabelId = usize;
 
@@ -24,6 +22,82 @@ impl Label {
     }
 }
 
+// Direction of a ++/-- op and what it leaves on the num stack.
+// Inc/Dec push nothing, Pre* push the new value, Post* push the old value.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Incr {
+    Inc,
+    Dec,
+    PreInc,
+    PreDec,
+    PostInc,
+    PostDec,
+}
+
+impl Incr {
+    pub fn new(increment: bool, pre: bool, side_effect_only: bool) -> Self {
+        match (increment, pre, side_effect_only) {
+            (true, _, true) => Incr::Inc,
+            (false, _, true) => Incr::Dec,
+            (true, true, false) => Incr::PreInc,
+            (false, true, false) => Incr::PreDec,
+            (true, false, false) => Incr::PostInc,
+            (false, false, false) => Incr::PostDec,
+        }
+    }
+    fn returns(&self) -> SC {
+        match self {
+            Incr::Inc | Incr::Dec => SC::new(),
+            _ => SC::num(1),
+        }
+    }
+}
+
+// Comparison of a compare-and-branch superinstruction
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Cmp {
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    EqEq,
+    Neq,
+}
+
+// Each compare-and-branch subroutine is generic over the comparison and when it jumps
+macro_rules! cmp_fn {
+    ($cmp:expr, $jump_if:expr, $func:ident) => {
+        match ($cmp, $jump_if) {
+            (Cmp::Lt, true) => $func::<CMP_LT, true> as ByteFn,
+            (Cmp::Gt, true) => $func::<CMP_GT, true> as ByteFn,
+            (Cmp::LtEq, true) => $func::<CMP_LTEQ, true> as ByteFn,
+            (Cmp::GtEq, true) => $func::<CMP_GTEQ, true> as ByteFn,
+            (Cmp::EqEq, true) => $func::<CMP_EQEQ, true> as ByteFn,
+            (Cmp::Neq, true) => $func::<CMP_NEQ, true> as ByteFn,
+            (Cmp::Lt, false) => $func::<CMP_LT, false> as ByteFn,
+            (Cmp::Gt, false) => $func::<CMP_GT, false> as ByteFn,
+            (Cmp::LtEq, false) => $func::<CMP_LTEQ, false> as ByteFn,
+            (Cmp::GtEq, false) => $func::<CMP_GTEQ, false> as ByteFn,
+            (Cmp::EqEq, false) => $func::<CMP_EQEQ, false> as ByteFn,
+            (Cmp::Neq, false) => $func::<CMP_NEQ, false> as ByteFn,
+        }
+    };
+}
+
+// Each ++/-- subroutine is generic over the delta and what it returns, pick the right one
+macro_rules! incr_fn {
+    ($incr:expr, $func:ident) => {
+        match $incr {
+            Incr::Inc => $func::<1, INCR_RET_NONE> as ByteFn,
+            Incr::Dec => $func::<-1, INCR_RET_NONE> as ByteFn,
+            Incr::PreInc => $func::<1, INCR_RET_NEW> as ByteFn,
+            Incr::PreDec => $func::<-1, INCR_RET_NEW> as ByteFn,
+            Incr::PostInc => $func::<1, INCR_RET_OLD> as ByteFn,
+            Incr::PostDec => $func::<-1, INCR_RET_OLD> as ByteFn,
+        }
+    };
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Code {
     NumToVar,
@@ -72,6 +146,18 @@ pub enum Code {
     AssignRetSclSpecialVar(SclSpecial),
     SclSpecialVar(SclSpecial),
 
+    // Value to assign then the column index
+    AssignColumn,
+    AssignRetColumn,
+
+    // ++ and -- in place
+    IncGscl { id: GlobalScalarId, incr: Incr },
+    IncArg { arg_idx: usize, incr: Incr },
+    IncSclSpecial { special: SclSpecial, incr: Incr },
+    IncArray { indices: Indices, incr: Incr },
+    IncNumArray { indices: Indices, incr: Incr },
+    IncColumn { incr: Incr },
+
     Exp,
 
     Mult,
@@ -98,34 +184,74 @@ pub enum Code {
 
     Concat { count: usize },
 
-    ArrayMember { indices: usize },
+    ArrayMember { indices: Indices },
+
+    AssignArray { indices: Indices },
+    AssignArrayNum { indices: Indices },
+    AssignArrayStr { indices: Indices },
 
-    AssignArray { indices: usize },
-    AssignArrayNum { indices: usize },
-    AssignArrayStr { indices: usize },
+    AssignRetArray { indices: Indices },
+    AssignRetArrayNum { indices: Indices },
+    AssignRetArrayStr { indices: Indices }, // str stack
 
-    AssignRetArray { indices: usize },
-    AssignRetArrayNum { indices: usize },
-    AssignRetArrayStr { indices: usize }, // str stack
+    ArrayIndex { indices: Indices },
 
-    ArrayIndex { indices: usize },
+    // Arrays whose elements are all numbers. Values stay on the num stack.
+    AssignNumArray { indices: Indices },
+    AssignRetNumArray { indices: Indices },
+    NumArrayIndex { indices: Indices }, // Missing elements are 0 so only used when a number is wanted
 
-    Call { target: usize },
+    Call { target: usize, args: usize }, // args is how many args the caller passed
+    CallNative { id: usize }, // Args are on the stacks matching the native function's signature
 
     Print,
+    PrintNum,
 
     Printf { num_args: usize }, // excluding fstring
 
-    NoOp,
-
+    // Return value is left on the stack matching the function's return type
     Ret,
+    RetNum,
+    RetStr,
 
     // Index in constant table
     ConstStr { str: RcAwkStr },
     ConstStrNum { strnum: RcAwkStr },
     ConstNum { num: f64 },
 
+    // getline. Each read pushes 1 if it read a record, GetlineRecord then pushes the record and
+    // GetlineResult pushes what getline returns. GetlineInput reads into $0 and GetlineInputVar
+    // leaves $0 alone, GetlineFile and GetlineCommand pop the file or command.
+    GetlineInput,
+    GetlineInputVar,
+    GetlineFile,
+    GetlineCommand,
+    GetlineRecord,
+    GetlineResult,
+
+    // for (k in arr). ForInStart pops the array and saves its keys in PROCINFO["sorted_in"] order,
+    // ForInNext pushes 1 if there's another key, ForInKey pushes that key and ForInEnd drops the keys.
+    ForInStart,
+    ForInNext,
+    ForInKey,
+    ForInEnd,
+
+    // Reading input with BEGINFILE/ENDFILE rules. NextFile pushes 1 if it moved on to another file,
+    // CheckFileOpened is fatal if that file couldn't be opened and NextRecordInFile pushes 1 if
+    // the file has another record. SkipFile is nextfile.
+    NextFile,
+    CheckFileOpened,
+    NextRecordInFile,
+    SkipFile,
+
     // BEGIN BUILTINS FUNCS
+    // asort and asorti with src, src dest, and src dest how
+    BuiltinAsort1,
+    BuiltinAsort2,
+    BuiltinAsort3,
+    BuiltinAsorti1,
+    BuiltinAsorti2,
+    BuiltinAsorti3,
     BuiltinAtan2,
     BuiltinCos,
     BuiltinExp,
@@ -146,12 +272,35 @@ pub enum Code {
     BuiltinTolower,
     BuiltinToupper,
     BuiltinMatches,
+    // gensub(re, repl, how) on $0 and gensub(re, repl, how, target)
+    BuiltinGensub3,
+    BuiltinGensub4,
+    BuiltinSystime,
+    // mktime(spec, utc) and strftime(fmt, ts, utc), the compiler fills in missing args
+    BuiltinMktime,
+    BuiltinStrftime,
+    BuiltinAnd,
+    BuiltinOr,
+    BuiltinXor,
+    BuiltinLshift,
+    BuiltinRshift,
+    BuiltinCompl,
+    BuiltinStrtonum,
+    BuiltinLengthArr,
+    BuiltinTypeof,
     // END
 
     // Sub and gsub are paired with an assign code depending on what is being assigned to.
     // Pushes two scalars first the number of replacements, second the output string.
     Sub3 { global: bool },
 
+    // Superinstructions made by compiler::superinstructions from the ops in the comments
+    ColumnNum, // Column StrToNum
+    ConstColumn { idx: usize }, // ConstNum Column
+    ConstColumnNum { idx: usize }, // ConstColumn StrToNum
+    AddConst { num: f64 }, // ConstNum Add
+    AddGsclNum { id: GlobalScalarId, delta: f32 }, // GsclNum AddConst AssignGsclNum
+
     // These will be transformed before reaching VM
     JumpIfTrueNextLineLbl(Label),
     JumpIfFalseNextLineLbl(Label),
@@ -162,6 +311,8 @@ pub enum Code {
     JumpIfTrueVarLbl(Label),
     JumpIfTrueNumLbl(Label),
     JumpIfTrueStrLbl(Label),
+    JumpIfCmpNumLbl { cmp: Cmp, jump_if: bool, label: Label }, // LtNum.. JumpIf(True|False)NumLbl
+    JumpIfCmpVarLbl { cmp: Cmp, jump_if: bool, label: Label }, // Lt.. JumpIf(True|False)NumLbl
     Label(Label), // n/a
 
     // Transformed into these
@@ -173,6 +324,8 @@ pub enum Code {
     RelJumpIfTrueVar { offset: isize },
     RelJumpIfTrueNextLine { offset: isize },
     RelJumpIfFalseNextLine { offset: isize },
+    RelJumpIfCmpNum { cmp: Cmp, jump_if: bool, offset: isize },
+    RelJumpIfCmpVar { cmp: Cmp, jump_if: bool, offset: isize },
 
     RelJump { offset: isize },
 }
@@ -189,45 +342,64 @@ impl Code {
             Code::JumpIfTrueVarLbl(_) => { Self::RelJumpIfTrueVar { offset } }
             Code::JumpIfFalseNextLineLbl(_) => { Self::RelJumpIfFalseNextLine { offset } }
             Code::JumpIfTrueNextLineLbl(_) => { Self::RelJumpIfTrueNextLine { offset } }
+            Code::JumpIfCmpNumLbl { cmp, jump_if, .. } => { Self::RelJumpIfCmpNum { cmp: *cmp, jump_if: *jump_if, offset } }
+            Code::JumpIfCmpVarLbl { cmp, jump_if, .. } => { Self::RelJumpIfCmpVar { cmp: *cmp, jump_if: *jump_if, offset } }
             _ => return,
         };
         // Replace a jump to a label with a rel jump with an offset
         std::mem::swap(self, &mut replacement_jump);
     }
 
+    // The label a not yet resolved jump goes to
+    pub fn jump_label(&self) -> Option<Label> {
+        match self {
+            Code::JumpLbl(lbl)
+            | Code::JumpIfFalseNumLbl(lbl)
+            | Code::JumpIfFalseStrLbl(lbl)
+            | Code::JumpIfFalseVarLbl(lbl)
+            | Code::JumpIfTrueNumLbl(lbl)
+            | Code::JumpIfTrueStrLbl(lbl)
+            | Code::JumpIfTrueVarLbl(lbl)
+            | Code::JumpIfTrueNextLineLbl(lbl)
+            | Code::JumpIfFalseNextLineLbl(lbl)
+            | Code::JumpIfCmpNumLbl { label: lbl, .. }
+            | Code::JumpIfCmpVarLbl { label: lbl, .. } => Some(*lbl),
+            _ => None,
+        }
+    }
+
+    // Callers only move between different stacks
     pub fn move_stack_to_stack(src: ScalarType, dest: ScalarType) -> Self {
-        match src {
-            ScalarType::Str => {
-                match dest {
-                    ScalarType::Str => Code::NoOp,
-                    ScalarType::Num => Code::StrToNum,
-                    ScalarType::Var => Code::StrToVar,
-                }
-            }
-            ScalarType::Num => {
-                match dest {
-                    ScalarType::Str => Code::NumToStr,
-                    ScalarType::Num => Code::NoOp,
-                    ScalarType::Var => Code::NumToVar,
-                }
-            }
-            ScalarType::Var => {
-                match dest {
-                    ScalarType::Str => Code::VarToStr,
-                    ScalarType::Num => Code::VarToNum,
-                    ScalarType::Var => Code::NoOp,
-                }
-            }
+        match (src, dest) {
+            (ScalarType::Str, ScalarType::Num) => Code::StrToNum,
+            (ScalarType::Str, ScalarType::Var) => Code::StrToVar,
+            (ScalarType::Num, ScalarType::Str) => Code::NumToStr,
+            (ScalarType::Num, ScalarType::Var) => Code::NumToVar,
+            (ScalarType::Var, ScalarType::Str) => Code::VarToStr,
+            (ScalarType::Var, ScalarType::Num) => Code::VarToNum,
+            (src, dest) => panic!("compiler bug: no move from {:?} to {:?}", src, dest),
         }
     }
 
-    pub fn arg_scl(_typ: ScalarType, arg_idx: usize) -> Self {
-        return Code::ArgVar { arg_idx };
-        // match typ {
-        //     ScalarType::Variable => Code::ArgScl { arg_idx },
-        //     ScalarType::String => Code::ArgStrScl { arg_idx },
-        //     ScalarType::Float => Code::ArgNumScl { arg_idx },
-        // }
+    pub fn ret(typ: ScalarType) -> Self {
+        match typ {
+            ScalarType::Str => Code::RetStr,
+            ScalarType::Num => Code::RetNum,
+            ScalarType::Var => Code::Ret,
+        }
+    }
+
+    pub fn is_ret(&self) -> bool {
+        matches!(self, Code::Ret | Code::RetNum | Code::RetStr)
+    }
+
+    // arg_idx is the index among args of the same type
+    pub fn arg_scl(typ: ScalarType, arg_idx: usize) -> Self {
+        match typ {
+            ScalarType::Var => Code::ArgVar { arg_idx },
+            ScalarType::Str => Code::ArgStr { arg_idx },
+            ScalarType::Num => Code::ArgNum { arg_idx },
+        }
     }
 
     pub fn jump_if_false(typ: ScalarType, label: &Label) -> Code {
@@ -300,7 +472,7 @@ impl Code {
         }
     }
 
-    pub fn array_assign(indices: usize, typ: ScalarType, side_effect_only: bool) -> Self {
+    pub fn array_assign(indices: Indices, typ: ScalarType, side_effect_only: bool) -> Self {
         if side_effect_only {
             match typ {
                 ScalarType::Str => Code::AssignArrayStr { indices },
@@ -316,10 +488,17 @@ impl Code {
         }
     }
 
-    #[cfg(test)]
+    pub fn num_array_assign(indices: Indices, side_effect_only: bool) -> Self {
+        if side_effect_only {
+            Code::AssignNumArray { indices }
+        } else {
+            Code::AssignRetNumArray { indices }
+        }
+    }
+
     pub fn pretty_print(&self, output: &mut Vec<u8>) {
         let byte_padded = pad(format!("{:?}", self), 40);
-        output.extend_from_slice(&byte_padded.as_bytes());
+        output.extend_from_slice(byte_padded.as_bytes());
     }
 
     #[cfg(test)]
@@ -332,6 +511,9 @@ impl Code {
     pub fn meta(&self, functions: &FunctionMap) -> Meta {
         use StackT::{Num, Str, Var, Array};
         match self {
+            Code::BuiltinAsort1 | Code::BuiltinAsorti1 => Meta::new(vec![Array], SC::num(1)),
+            Code::BuiltinAsort2 | Code::BuiltinAsorti2 => Meta::new(vec![Array, Array], SC::num(1)),
+            Code::BuiltinAsort3 | Code::BuiltinAsorti3 => Meta::new(vec![Array, Array, Str], SC::num(1)),
             Code::BuiltinAtan2 => Meta::new(vec![Num, Num], SC::num(1)),
             Code::BuiltinCos => Meta::new(vec![Num], SC::num(1)),
             Code::BuiltinExp => Meta::new(vec![Num], SC::num(1)),
@@ -342,6 +524,16 @@ impl Code {
             Code::BuiltinLength0 => Meta::new(vec![], SC::num(1)),
             Code::BuiltinLength1 => Meta::new(vec![Str], SC::num(1)),
             Code::BuiltinMatches => Meta::new(vec![Str, Str], SC::num(1)),
+            Code::BuiltinGensub3 => Meta::new(vec![Str, Str, Var], SC::str(1)),
+            Code::BuiltinGensub4 => Meta::new(vec![Str, Str, Var, Str], SC::str(1)),
+            Code::BuiltinSystime => Meta::new(vec![], SC::num(1)),
+            Code::BuiltinMktime => Meta::new(vec![Str, Var], SC::num(1)),
+            Code::BuiltinStrftime => Meta::new(vec![Str, Num, Var], SC::str(1)),
+            Code::BuiltinAnd | Code::BuiltinOr | Code::BuiltinXor | Code::BuiltinLshift | Code::BuiltinRshift => Meta::new(vec![Num, Num], SC::num(1)),
+            Code::BuiltinCompl => Meta::new(vec![Num], SC::num(1)),
+            Code::BuiltinStrtonum => Meta::new(vec![Var], SC::num(1)),
+            Code::BuiltinLengthArr => Meta::new(vec![Array], SC::num(1)),
+            Code::BuiltinTypeof => Meta::new(vec![Var], SC::str(1)),
             Code::BuiltinLog => Meta::new(vec![Num], SC::num(1)),
             Code::BuiltinRand => Meta::new(vec![], SC::num(1)),
             Code::BuiltinSin => Meta::new(vec![Num], SC::num(1)),
@@ -423,21 +615,42 @@ impl Code {
             Code::AssignRetArrayNum { indices } => Meta::new(add_indices(vec![Num, Array], indices), SC::num(1)),
             Code::AssignRetArrayStr { indices } => Meta::new(add_indices(vec![Str, Array], indices), SC::str(1)),
             Code::ArrayIndex { indices } => Meta::new(add_indices(vec![StackT::Array], indices), SC::var(1)),
-
-            Code::Call { target } => {
-                let func = functions.get_by_id(*target as usize).unwrap();
-                let args = func.args();
-                let arg_stacks: Vec<StackT> = args.iter().map(|a| match a.typ {
-                    ArgT::Array => Some(Array),
-                    ArgT::Scalar => Some(Var),
-                    ArgT::Unknown => None,
-                }).flatten().collect();
-                Meta::new(arg_stacks, SC::var(1))
+            Code::AssignNumArray { indices } => Meta::new(add_indices(vec![Num, Array], indices), SC::new()),
+            Code::AssignRetNumArray { indices } => Meta::new(add_indices(vec![Num, Array], indices), SC::num(1)),
+            Code::NumArrayIndex { indices } => Meta::new(add_indices(vec![Array], indices), SC::num(1)),
+
+            Code::Call { target, args: passed } => {
+                let func = functions.get_by_id(*target ).unwrap();
+                let arg_stacks: Vec<StackT> = (0..*passed).filter_map(|idx| func.arg_stack(idx)).collect();
+                Meta::new(arg_stacks, SC::new().set(func.return_type().into(), 1))
+            }
+            Code::CallNative { id } => {
+                let native = functions.native_by_id(*id).expect("native function to exist based on id");
+                let arg_stacks: Vec<StackT> = native.args.iter().map(|typ| match typ {
+                    NativeArgT::Num => Num,
+                    NativeArgT::Str => Str,
+                    NativeArgT::Array => Array,
+                }).collect();
+                Meta::new(arg_stacks, SC::new().set(native.returns.into(), 1))
             }
+            Code::GetlineInput | Code::GetlineInputVar => Meta::new(vec![], SC::num(1)),
+            Code::GetlineFile | Code::GetlineCommand => Meta::new(vec![Str], SC::num(1)),
+            Code::GetlineRecord => Meta::new(vec![], SC::str(1)),
+            Code::GetlineResult => Meta::new(vec![], SC::num(1)),
+            Code::ForInStart => Meta::new(vec![Array], SC::new()),
+            Code::ForInNext => Meta::new(vec![], SC::num(1)),
+            Code::ForInKey => Meta::new(vec![], SC::str(1)),
+            Code::ForInEnd => Meta::new(vec![], SC::new()),
+            Code::NextFile => Meta::new(vec![], SC::num(1)),
+            Code::CheckFileOpened => Meta::new(vec![], SC::new()),
+            Code::NextRecordInFile => Meta::new(vec![], SC::num(1)),
+            Code::SkipFile => Meta::new(vec![], SC::new()),
             Code::Print => Meta::new(vec![Str], SC::new()),
+            Code::PrintNum => Meta::new(vec![Num], SC::new()),
             Code::Printf { num_args } => Meta::new((0..*num_args + 1).map(|_| Str).collect(), SC::new()),
-            Code::NoOp => Meta::new(vec![], SC::new()),
             Code::Ret => Meta::new(vec![Var], SC::var(1)).set_is_ret(),
+            Code::RetNum => Meta::new(vec![Num], SC::num(1)).set_is_ret(),
+            Code::RetStr => Meta::new(vec![Str], SC::str(1)).set_is_ret(),
             Code::ConstStr { .. } => Meta::new(vec![], SC::str(1)),
             Code::ConstStrNum { .. } => Meta::new(vec![], SC::str(1)),
             Code::ConstNum { .. } => Meta::new(vec![], SC::num(1)),
@@ -450,11 +663,19 @@ impl Code {
             Code::RelJumpIfTrueVar { offset } => Meta::new(vec![Var], SC::new()).jump(vec![*offset, 1]),
             Code::RelJumpIfFalseVar { offset } => Meta::new(vec![Var], SC::new()).jump(vec![*offset, 1]),
             Code::RelJump { offset } => Meta::new(vec![], SC::new()).jump(vec![*offset]),
-            Code::RelJumpIfTrueNextLine { offset } => Meta::new(vec![], SC::new()).jump(vec![*offset as isize, 1]),
-            Code::RelJumpIfFalseNextLine { offset } => Meta::new(vec![], SC::new()).jump(vec![*offset as isize, 1]),
+            Code::RelJumpIfCmpNum { offset, .. } => Meta::new(vec![Num, Num], SC::new()).jump(vec![*offset, 1]),
+            Code::RelJumpIfCmpVar { offset, .. } => Meta::new(vec![Var, Var], SC::new()).jump(vec![*offset, 1]),
+            Code::ColumnNum => Meta::new(vec![Num], SC::num(1)),
+            Code::ConstColumn { .. } => Meta::new(vec![], SC::str(1)),
+            Code::ConstColumnNum { .. } => Meta::new(vec![], SC::num(1)),
+            Code::AddConst { .. } => Meta::new(vec![Num], SC::num(1)),
+            Code::AddGsclNum { .. } => Meta::new(vec![], SC::new()),
+            Code::RelJumpIfTrueNextLine { offset } => Meta::new(vec![], SC::new()).jump(vec![(*offset), 1]),
+            Code::RelJumpIfFalseNextLine { offset } => Meta::new(vec![], SC::new()).jump(vec![(*offset), 1]),
             Code::JumpIfFalseNextLineLbl(_) | Code::JumpIfTrueNextLineLbl(_) | Code::JumpIfTrueVarLbl(_)
             | Code::JumpIfFalseVarLbl(_) | Code::JumpIfFalseNumLbl(_) | Code::JumpIfFalseStrLbl(_)
             | Code::JumpLbl(_) | Code::JumpIfTrueNumLbl(_) | Code::JumpIfTrueStrLbl(_)
+            | Code::JumpIfCmpNumLbl { .. } | Code::JumpIfCmpVarLbl { .. }
             | Code::Label(_) =>
                 panic!("labels should be removed before bytecode analysis"),
             Code::NumToVar => Meta::new(vec![Num], SC::var(1)),
@@ -467,6 +688,16 @@ impl Code {
             Code::AssignSclSpecialVar(_) => Meta::new(vec![Var], SC::new()),
             Code::AssignRetSclSpecialVar(_) => Meta::new(vec![Var], SC::var(1)),
             Code::SclSpecialVar(_) => Meta::new(vec![], SC::var(1)),
+
+            Code::AssignColumn => Meta::new(vec![Str, Num], SC::new()),
+            Code::AssignRetColumn => Meta::new(vec![Str, Num], SC::str(1)),
+
+            Code::IncGscl { incr, .. } => Meta::new(vec![], incr.returns()),
+            Code::IncArg { incr, .. } => Meta::new(vec![], incr.returns()),
+            Code::IncSclSpecial { incr, .. } => Meta::new(vec![], incr.returns()),
+            Code::IncArray { indices, incr } => Meta::new(add_indices(vec![Array], indices), incr.returns()),
+            Code::IncNumArray { indices, incr } => Meta::new(add_indices(vec![Array], indices), incr.returns()),
+            Code::IncColumn { incr } => Meta::new(vec![Num], incr.returns()),
         }
     }
 
@@ -531,7 +762,16 @@ impl Code {
             Code::AssignRetArray { indices } => CI::imm(assign_array_ret_var, Immed { array_indices: *indices }),
             Code::AssignRetArrayStr { indices } => CI::imm(assign_array_ret_str, Immed { array_indices: *indices }),
             Code::AssignRetArrayNum { indices } => CI::imm(assign_array_ret_num, Immed { array_indices: *indices }),
-
+            Code::AssignNumArray { indices } => CI::imm(assign_num_array, Immed { array_indices: *indices }),
+            Code::AssignRetNumArray { indices } => CI::imm(assign_ret_num_array, Immed { array_indices: *indices }),
+            Code::NumArrayIndex { indices } => CI::imm(num_array_index, Immed { array_indices: *indices }),
+
+            Code::BuiltinAsort1 => CI::new(builtin_asort1),
+            Code::BuiltinAsort2 => CI::new(builtin_asort2),
+            Code::BuiltinAsort3 => CI::new(builtin_asort3),
+            Code::BuiltinAsorti1 => CI::new(builtin_asorti1),
+            Code::BuiltinAsorti2 => CI::new(builtin_asorti2),
+            Code::BuiltinAsorti3 => CI::new(builtin_asorti3),
             Code::BuiltinAtan2 => CI::new(builtin_atan2),
             Code::BuiltinCos => CI::new(builtin_cos),
             Code::BuiltinExp => CI::new(builtin_exp),
@@ -542,6 +782,20 @@ impl Code {
             Code::BuiltinLength0 => CI::new(builtin_length0),
             Code::BuiltinLength1 => CI::new(builtin_length1),
             Code::BuiltinMatches => CI::new(builtin_matches),
+            Code::BuiltinGensub3 => CI::new(builtin_gensub3),
+            Code::BuiltinGensub4 => CI::new(builtin_gensub4),
+            Code::BuiltinSystime => CI::new(builtin_systime),
+            Code::BuiltinMktime => CI::new(builtin_mktime),
+            Code::BuiltinStrftime => CI::new(builtin_strftime),
+            Code::BuiltinAnd => CI::new(builtin_and),
+            Code::BuiltinOr => CI::new(builtin_or),
+            Code::BuiltinXor => CI::new(builtin_xor),
+            Code::BuiltinLshift => CI::new(builtin_lshift),
+            Code::BuiltinRshift => CI::new(builtin_rshift),
+            Code::BuiltinCompl => CI::new(builtin_compl),
+            Code::BuiltinStrtonum => CI::new(builtin_strtonum),
+            Code::BuiltinLengthArr => CI::new(builtin_length_arr),
+            Code::BuiltinTypeof => CI::new(builtin_typeof),
             Code::BuiltinLog => CI::new(builtin_log),
             Code::BuiltinRand => CI::new(builtin_rand),
             Code::BuiltinSin => CI::new(builtin_sin),
@@ -553,12 +807,27 @@ impl Code {
             Code::BuiltinTolower => CI::new(builtin_tolower),
             Code::BuiltinToupper => CI::new(builtin_toupper),
             Code::Sub3 { global } => CI::new(if *global { gsub3 } else { sub3 }),
+            Code::GetlineInput => CI::new(getline_input),
+            Code::GetlineInputVar => CI::new(getline_input_var),
+            Code::GetlineFile => CI::new(getline_file),
+            Code::GetlineCommand => CI::new(getline_command),
+            Code::GetlineRecord => CI::new(getline_record),
+            Code::GetlineResult => CI::new(getline_result),
+            Code::ForInStart => CI::new(for_in_start),
+            Code::ForInNext => CI::new(for_in_next),
+            Code::ForInKey => CI::new(for_in_key),
+            Code::ForInEnd => CI::new(for_in_end),
+            Code::NextFile => CI::new(next_file),
+            Code::CheckFileOpened => CI::new(check_file_opened),
+            Code::NextRecordInFile => CI::new(next_record_in_file),
+            Code::SkipFile => CI::new(skip_file),
             Code::Print => CI::new(print),
+            Code::PrintNum => CI::new(print_num),
             Code::Printf { num_args } => CI::imm(printf, Immed { printf_args: *num_args }),
-            Code::NoOp => CI::new(noop),
-            Code::Ret => CI::new(ret),
+            Code::Ret | Code::RetNum | Code::RetStr => CI::new(ret),
 
-            Code::Call { target } => CI::imm(call, Immed { call_target: *target }),
+            Code::Call { target, args } => CI::imm(call, Immed { call: CallTarget { target: *target as u32, args: *args as u32 } }),
+            Code::CallNative { id } => CI::imm(call_native, Immed { native_id: *id }),
 
             Code::RelJumpIfFalseVar { offset } => CI::imm(rel_jump_if_false_var, Immed { offset: *offset }),
             Code::RelJumpIfFalseStr { offset } => CI::imm(rel_jump_if_false_str, Immed { offset: *offset }),
@@ -589,7 +858,25 @@ impl Code {
             Code::AssignRetSclSpecialVar(special) => CI::imm(assign_ret_scl_special, Immed { special: *special }),
             Code::SclSpecialVar(special) => CI::imm(scl_special, Immed { special: *special }),
 
-            Code::Label(_) | Code::JumpIfTrueNextLineLbl(_) | Code::JumpIfFalseNextLineLbl(_) | Code::JumpIfFalseVarLbl(_) | Code::JumpIfFalseNumLbl(_) | Code::JumpIfFalseStrLbl(_) | Code::JumpLbl(_) | Code::JumpIfTrueVarLbl(_) | Code::JumpIfTrueNumLbl(_) | Code::JumpIfTrueStrLbl(_) => {
+            Code::AssignColumn => CI::new(assign_column),
+            Code::AssignRetColumn => CI::new(assign_ret_column),
+
+            Code::IncGscl { id, incr } => CI::imm(incr_fn!(incr, inc_gscl), Immed { global_scl_id: *id }),
+            Code::IncArg { arg_idx, incr } => CI::imm(incr_fn!(incr, inc_arg), Immed { arg_idx: *arg_idx }),
+            Code::IncSclSpecial { special, incr } => CI::imm(incr_fn!(incr, inc_scl_special), Immed { special: *special }),
+            Code::IncArray { indices, incr } => CI::imm(incr_fn!(incr, inc_array), Immed { array_indices: *indices }),
+            Code::IncNumArray { indices, incr } => CI::imm(incr_fn!(incr, inc_num_array), Immed { array_indices: *indices }),
+            Code::IncColumn { incr } => CI::new(incr_fn!(incr, inc_column)),
+
+            Code::RelJumpIfCmpNum { cmp, jump_if, offset } => CI::imm(cmp_fn!(cmp, jump_if, rel_jump_if_cmp_num), Immed { offset: *offset }),
+            Code::RelJumpIfCmpVar { cmp, jump_if, offset } => CI::imm(cmp_fn!(cmp, jump_if, rel_jump_if_cmp_var), Immed { offset: *offset }),
+            Code::ColumnNum => CI::new(column_num),
+            Code::ConstColumn { idx } => CI::imm(const_column, Immed { column: *idx }),
+            Code::ConstColumnNum { idx } => CI::imm(const_column_num, Immed { column: *idx }),
+            Code::AddConst { num } => CI::imm(add_const, Immed { num: *num }),
+            Code::AddGsclNum { id, delta } => CI::imm(add_gscl_num, Immed { gscl_add: GsclAdd { id: id.id as u32, delta: *delta } }),
+
+            Code::Label(_) | Code::JumpIfTrueNextLineLbl(_) | Code::JumpIfFalseNextLineLbl(_) | Code::JumpIfFalseVarLbl(_) | Code::JumpIfFalseNumLbl(_) | Code::JumpIfFalseStrLbl(_) | Code::JumpLbl(_) | Code::JumpIfTrueVarLbl(_) | Code::JumpIfTrueNumLbl(_) | Code::JumpIfTrueStrLbl(_) | Code::JumpIfCmpNumLbl { .. } | Code::JumpIfCmpVarLbl { .. } => {
                 panic!("labels should be removed before direct threading {:?}", self);
             }
         }
@@ -597,9 +884,15 @@ impl Code {
 }
 
 
-fn add_indices(mut v: Vec<StackT>, num_arr_indices: &usize) -> Vec<StackT> {
-    for _ in 0..*num_arr_indices {
-        v.push(StackT::Str)
+fn add_indices(mut v: Vec<StackT>, indices: &Indices) -> Vec<StackT> {
+    match indices {
+        Indices::Str(count) => {
+            for _ in 0..*count {
+                v.push(StackT::Str)
+            }
+        }
+        Indices::Num => v.push(StackT::Num),
+        Indices::Var => v.push(StackT::Var),
     }
     v
 }
\ No newline at end of file
@@ -26,7 +26,7 @@ impl Meta {
         &self.args
     }
     pub fn new(args: Vec<StackT>, returns: SC) -> Self {
-        Self { args, returns: returns, is_ret: false, descendant_offsets: vec![1] }
+        Self { args, returns, is_ret: false, descendant_offsets: vec![1] }
     }
     pub fn set_is_ret(mut self) -> Self {
         self.is_ret = true;
@@ -2,8 +2,8 @@ mod codes;
 mod subroutines;
 mod code_and_immed;
 mod meta;
-mod op_helpers;
+pub mod op_helpers;
 
-pub use codes::{Label, LabelId, Code};
+pub use codes::{Label, Cmp, Code, Incr};
 pub use meta::Meta;
-pub use code_and_immed::{CodeAndImmed, Immed};
\ No newline at end of file
+pub use code_and_immed::{CallTarget, CodeAndImmed, GsclAdd, Immed, Indices};
\ No newline at end of file
@@ -1,30 +1,44 @@
-use std::arch::x86_64::_mm_storel_pd;
+use std::cmp::Ordering;
 use std::io::Write;
 use std::time::{SystemTime, UNIX_EPOCH};
-use crate::{binop, binop_num_only, mathop, specials};
+use crate::{binop, binop_num_only, mathop};
 use crate::runtime::arrays::{split_on_regex, split_on_string};
-use crate::awk_str::{AwkStr, RcAwkStr, SubReplStr};
+use crate::awk_str::{AwkStr, GensubReplStr, RcAwkStr, SubReplStr};
 use crate::printable_error::PrintableError;
-use crate::specials::SclSpecial;
-use crate::typing::GlobalScalarId;
+use crate::parser::{ArgT, ScalarType};
+use crate::stackt::StackT;
+use crate::specials::{ArrSpecial, SclSpecial};
+use crate::runtime::arrays::{merge_sort_by, ArrayKey, Arrays, SortOrder};
+use crate::runtime::rc_manager::RcManager;
+use crate::runtime::time;
+use crate::runtime::bitwise::{self, BitOp};
+use crate::typing::{GlobalArrayId, GlobalScalarId};
 use crate::util::{clamp_to_max_len, clamp_to_slice_index, index_of, unwrap};
-use crate::vm::bytecode::code_and_immed::Immed;
+use crate::vm::bytecode::code_and_immed::{CallTarget, GsclAdd, Immed};
+use crate::vm::bytecode::op_helpers;
 use crate::vm::runtime_scalar::{RuntimeScalar, StringScalar};
-use crate::vm::machine::FunctionScope;
+use crate::vm::machine::{ForInKeys, FunctionScope};
 use crate::vm::VirtualMachine;
+use crate::embed::{NativeArg, NativeArgs};
+use crate::typing::NativeArgT;
 
 macro_rules! handle_err {
-    ($result:expr) => {
+    ($vm:expr, $result:expr) => {
         match $result {
             Ok(val) => val,
             Err(printable_err) => {
-                eprintln!("{}", printable_err);
+                $vm.runtime_error(printable_err);
                 return usize::MAX;
             }
         }
     }
 }
 
+// A fatal error raised by a helper that can't return usize::MAX itself stops the program here
+fn continue_unless_error(vm: &VirtualMachine, ip: usize) -> usize {
+    if vm.error.is_some() { usize::MAX } else { ip + 1 }
+}
+
 pub fn num_to_var(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let num = vm.pop_num();
     vm.push_unknown(RuntimeScalar::Num(num));
@@ -46,7 +60,7 @@ pub fn str_to_var(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
 
 pub fn str_to_num(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let str = vm.pop_string();
-    let num = vm.str_to_num(&*str);
+    let num = vm.str_to_num(&str);
     vm.push_num(num);
     ip + 1
 }
@@ -85,7 +99,52 @@ pub fn column(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let idx = index.round() as usize;
     let mut owned_str = vm.shitty_malloc.get();
     vm.rt.columns.get_into_buf(idx, owned_str.as_mut_vec());
-    vm.push_str(StringScalar::StrNum(owned_str.rc()));
+    vm.push_str(StringScalar::input(owned_str));
+    ip + 1
+}
+
+pub fn const_column(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let mut owned_str = vm.shitty_malloc.get();
+    vm.rt.columns.get_into_buf(unsafe { imm.column }, owned_str.as_mut_vec());
+    vm.push_str(StringScalar::input(owned_str));
+    ip + 1
+}
+
+// The field is only read to convert it, its buffer goes straight back to the cache
+fn column_to_num(vm: &mut VirtualMachine, idx: usize) -> f64 {
+    let mut owned_str = vm.shitty_malloc.get();
+    vm.rt.columns.get_into_buf(idx, owned_str.as_mut_vec());
+    let num = vm.rt.converter.str_to_num(&owned_str).unwrap_or(0.0);
+    vm.shitty_malloc.drop(owned_str.rc());
+    num
+}
+
+pub fn column_num(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let index = vm.pop_num();
+    let num = column_to_num(vm, index.round() as usize);
+    vm.push_num(num);
+    ip + 1
+}
+
+pub fn const_column_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let num = column_to_num(vm, unsafe { imm.column });
+    vm.push_num(num);
+    ip + 1
+}
+
+pub fn assign_column(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let index = vm.pop_num();
+    let value = vm.pop_string();
+    vm.rt.columns.set(index.round() as usize, &value);
+    vm.shitty_malloc.drop_str(value);
+    ip + 1
+}
+
+pub fn assign_ret_column(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let index = vm.pop_num();
+    let value = vm.pop_string();
+    vm.rt.columns.set(index.round() as usize, &value);
+    vm.push_str(value);
     ip + 1
 }
 
@@ -246,16 +305,14 @@ pub fn arg_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
 
 pub fn arg_str(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let arg_idx = unsafe { imm.arg_idx };
-    let arg = vm.get_scalar_arg(arg_idx);
-    let arg = vm.val_to_string_scalar(arg);
+    let arg = vm.get_str_arg(arg_idx);
     vm.push_str(arg);
     ip + 1
 }
 
 pub fn arg_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let arg_idx = unsafe { imm.arg_idx };
-    let arg = vm.get_scalar_arg(arg_idx);
-    let arg = vm.val_to_num(arg);
+    let arg = vm.get_num_arg(arg_idx);
     vm.push_num(arg);
     ip + 1
 }
@@ -267,6 +324,89 @@ pub fn arg_arr(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     ip + 1
 }
 
+// Const generic RET parameter for the ++/-- subroutines. Controls what is left on the num stack.
+pub const INCR_RET_NONE: u8 = 0;
+pub const INCR_RET_OLD: u8 = 1; // a++
+pub const INCR_RET_NEW: u8 = 2; // ++a
+
+#[inline(always)]
+fn push_incr_result<const RET: u8>(vm: &mut VirtualMachine, old: f64, new: f64) {
+    match RET {
+        INCR_RET_OLD => vm.push_num(old),
+        INCR_RET_NEW => vm.push_num(new),
+        _ => {}
+    }
+}
+
+pub fn inc_gscl<const DELTA: i8, const RET: u8>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let id = unsafe { imm.global_scl_id };
+    let old = vm.gscl(id).clone();
+    let old = vm.val_to_num(old);
+    let new = old + DELTA as f64;
+    vm.assign_gscl(id, RuntimeScalar::Num(new));
+    push_incr_result::<RET>(vm, old, new);
+    ip + 1
+}
+
+pub fn inc_arg<const DELTA: i8, const RET: u8>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let arg_idx = unsafe { imm.arg_idx };
+    let old = vm.get_scalar_arg(arg_idx);
+    let old = vm.val_to_num(old);
+    let new = old + DELTA as f64;
+    vm.set_scalar_arg(arg_idx, RuntimeScalar::Num(new));
+    push_incr_result::<RET>(vm, old, new);
+    ip + 1
+}
+
+pub fn inc_scl_special<const DELTA: i8, const RET: u8>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let special = unsafe { imm.special };
+    let old = vm.special(special);
+    let old = vm.val_to_num(old);
+    let new = old + DELTA as f64;
+    vm.assign_special(special, RuntimeScalar::Num(new));
+    push_incr_result::<RET>(vm, old, new);
+    ip + 1
+}
+
+pub fn inc_array<const DELTA: i8, const RET: u8>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let key = vm.array_key(unsafe { imm.array_indices });
+    let array = vm.pop_array();
+    // Update the element in place, no need to hash the indices twice
+    let element = vm.rt.arrays.access_or_insert(array, key);
+    let old = match element {
+        RuntimeScalar::Num(num) => *num,
+        RuntimeScalar::Str(s) | RuntimeScalar::StrNum(s) => vm.rt.converter.str_to_num(s).unwrap_or(0.0),
+    };
+    let new = old + DELTA as f64;
+    let prior_value = std::mem::replace(element, RuntimeScalar::Num(new));
+    vm.shitty_malloc.drop_scalar(prior_value);
+    push_incr_result::<RET>(vm, old, new);
+    ip + 1
+}
+
+pub fn inc_num_array<const DELTA: i8, const RET: u8>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let key = vm.array_key(unsafe { imm.array_indices });
+    let array = vm.pop_array();
+    let element = vm.rt.arrays.access_or_insert_num(array, key);
+    let old = *element;
+    let new = old + DELTA as f64;
+    *element = new;
+    push_incr_result::<RET>(vm, old, new);
+    ip + 1
+}
+
+pub fn inc_column<const DELTA: i8, const RET: u8>(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let idx = vm.pop_num().round() as usize;
+    let mut field = vm.shitty_malloc.get();
+    vm.rt.columns.get_into_buf(idx, field.as_mut_vec());
+    let old = vm.rt.converter.str_to_num(&field).unwrap_or(0.0);
+    let new = old + DELTA as f64;
+    vm.rt.columns.set(idx, vm.rt.converter.num_to_str_internal(new));
+    vm.shitty_malloc.drop(field.rc());
+    push_incr_result::<RET>(vm, old, new);
+    ip + 1
+}
+
 mathop!(exp, crate::vm::bytecode::op_helpers::exp);
 mathop!(mult, crate::vm::bytecode::op_helpers::mult);
 mathop!(div, crate::vm::bytecode::op_helpers::div);
@@ -292,7 +432,7 @@ binop_num_only!(neq_num, crate::vm::bytecode::op_helpers::neq);
 pub fn matches(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let regex_str = vm.pop_string(); // the regex
     let str = vm.pop_string(); // the string
-    let regex = vm.rt.regex_cache.get(&*regex_str);
+    let regex = vm.rt.regex_cache.get(&regex_str);
     let is_match = regex.matches(&str);
     vm.push_bool(is_match);
     ip + 1
@@ -301,88 +441,101 @@ pub fn matches(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
 pub fn nmatches(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let regex_str = vm.pop_string(); // the regex
     let str = vm.pop_string(); // the string
-    let regex = vm.rt.regex_cache.get(&*regex_str);
+    let regex = vm.rt.regex_cache.get(&regex_str);
     let is_match = regex.matches(&str);
     vm.push_bool(!is_match);
     ip + 1
 }
 
 pub fn assign_array_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
     let value = vm.pop_unknown();
-    let _ = vm.rt.arrays.assign(array, indices.rc(), value);
+    let _ = vm.rt.arrays.assign(array, key, value);
     ip + 1
 }
 
 pub fn assign_array_str(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
     let value = vm.pop_string();
-    let _ = vm.rt.arrays.assign(array, indices.rc(), value.clone().into());
+    let _ = vm.rt.arrays.assign(array, key, value.clone().into());
     ip + 1
 }
 
 pub fn assign_array_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
     let value = vm.pop_num();
-    let _ = vm.rt.arrays.assign(array, indices.rc(), RuntimeScalar::Num(value));
+    let _ = vm.rt.arrays.assign(array, key, RuntimeScalar::Num(value));
     ip + 1
 }
 
 pub fn assign_array_ret_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
     let value = vm.pop_unknown();
-    let _ = vm.rt.arrays.assign(array, indices.rc(), value.clone());
+    let _ = vm.rt.arrays.assign(array, key, value.clone());
     vm.push_unknown(value);
     ip + 1
 }
 
 pub fn assign_array_ret_str(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
     let value = vm.pop_string();
-    let _ = vm.rt.arrays.assign(array, indices.rc(), value.clone().into());
+    let _ = vm.rt.arrays.assign(array, key, value.clone().into());
     vm.push_str(value);
     ip + 1
 }
 
 pub fn assign_array_ret_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
+    let array = vm.pop_array();
+    let value = vm.pop_num();
+    let _ = vm.rt.arrays.assign(array, key, RuntimeScalar::Num(value));
+    vm.push_num(value);
+    ip + 1
+}
+
+pub fn assign_num_array(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
     let value = vm.pop_num();
-    let _ = vm.rt.arrays.assign(array, indices.rc(), RuntimeScalar::Num(value));
+    vm.rt.arrays.assign_num(array, key, value);
+    ip + 1
+}
+
+pub fn assign_ret_num_array(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let key = vm.array_key(unsafe { imm.array_indices });
+    let array = vm.pop_array();
+    let value = vm.pop_num();
+    vm.rt.arrays.assign_num(array, key, value);
+    vm.push_num(value);
+    ip + 1
+}
+
+pub fn num_array_index(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let key = vm.array_key(unsafe { imm.array_indices });
+    let array = vm.pop_array();
+    let value = vm.rt.arrays.access_num(array, key);
     vm.push_num(value);
     ip + 1
 }
 
 pub fn array_member(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
-    let contains = vm.rt.arrays.in_array(array, indices.rc());
+    let contains = vm.rt.arrays.in_array(array, key);
     vm.push_bool(contains);
     ip + 1
 }
 
 pub fn array_index(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let num_indices = unsafe { imm.array_indices };
-    let indices = vm.concat_array_indices(num_indices);
+    let key = vm.array_key(unsafe { imm.array_indices });
     let array = vm.pop_array();
-    let result = vm.rt.arrays.access(array, indices.rc()); // TODO: Skip this Rc::new() ?
-    let value = if let Some(result) = result {
-        result.clone()
-    } else {
-        RuntimeScalar::StrNum(vm.shitty_malloc.get().rc())
-    };
+    let shitty_malloc = &mut vm.shitty_malloc;
+    let value = vm.rt.arrays.access_or_create(array, key, || RuntimeScalar::StrNum(shitty_malloc.get().rc()));
     vm.push_unknown(value);
     ip + 1
 }
@@ -394,7 +547,7 @@ pub fn concat(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let mut string = vm.pop_string().downgrade_or_clone();
     for _ in 0..count - 1 {
         let additional = vm.pop_string();
-        string.push_str(&*additional);
+        string.push_str(&additional);
     }
     vm.push_str(StringScalar::Str(string.rc()));
     ip + 1
@@ -472,6 +625,27 @@ pub fn builtin_length1(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize
     ip + 1
 }
 
+pub fn builtin_length_arr(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let arr = vm.pop_array();
+    vm.push_num(vm.rt.arrays.len(arr) as f64);
+    ip + 1
+}
+
+// typeof() of a scalar, arrays and untyped variables are handled by the compiler
+pub fn builtin_typeof(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let typ: &[u8] = match vm.pop_unknown() {
+        RuntimeScalar::Num(_) => b"number",
+        RuntimeScalar::Str(_) => b"string",
+        // The uninitialized value
+        RuntimeScalar::StrNum(str) if str.is_empty() => b"unassigned",
+        RuntimeScalar::StrNum(str) if vm.rt.converter.looks_numeric(&str) => b"strnum",
+        RuntimeScalar::StrNum(_) => b"string",
+    };
+    let typ = vm.shitty_malloc.copy_from_slice(typ);
+    vm.push_str(StringScalar::Str(typ.rc()));
+    ip + 1
+}
+
 pub fn builtin_log(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let num = vm.pop_num();
     vm.push_num(num.ln());
@@ -491,19 +665,22 @@ pub fn builtin_sin(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     ip + 1
 }
 
-pub fn builtin_split2(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
-    let array = vm.pop_array();
-    let string = vm.pop_string();
+// Fills array with elements 1..n returning n
+fn assign_split<'a, I: Iterator<Item=&'a [u8]>>(shitty_malloc: &mut RcManager, arrays: &mut Arrays, array: GlobalArrayId, elements: I) -> f64 {
+    let _ = arrays.clear(array);
     let mut count: f64 = 0.0;
-    let _ = vm.rt.arrays.clear(array);
-    for (idx, elem) in split_on_string(vm.rt.columns.get_fs(), &string).enumerate()
-    {
+    for (idx, elem) in elements.enumerate() {
         count += 1.0;
-        let string = vm.shitty_malloc.copy_from_slice(elem);
-        let _ = vm.rt.arrays.assign(array,
-                                    RcAwkStr::new_bytes(format!("{}", idx + 1).into_bytes()),
-                                    RuntimeScalar::StrNum(string.rc()));
+        let string = shitty_malloc.copy_from_slice(elem);
+        let _ = arrays.assign(array, ArrayKey::Int(idx + 1), StringScalar::input(string).into());
     }
+    count
+}
+
+pub fn builtin_split2(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let array = vm.pop_array();
+    let string = vm.pop_string();
+    let count = assign_split(&mut vm.shitty_malloc, &mut vm.rt.arrays, array, split_on_string(vm.rt.columns.get_fs(), &string));
     vm.push_num(count);
     ip + 1
 }
@@ -511,18 +688,14 @@ pub fn builtin_split2(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize
 pub fn builtin_split3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let reg_str = vm.pop_string();
     let array = vm.pop_array();
-    let _ = vm.rt.arrays.clear(array);
     let string = vm.pop_string();
-    let reg = vm.rt.regex_cache.get(&reg_str);
-    let mut count: f64 = 0.0;
-    for (idx, elem) in split_on_regex(&reg, &string).enumerate()
-    {
-        count += 1.0;
-        let string = vm.shitty_malloc.copy_from_slice(elem);
-        let _ = vm.rt.arrays.assign(array,
-                                    vm.shitty_malloc.from_vec(format!("{}", idx + 1).into_bytes()).rc(),
-                                    RuntimeScalar::StrNum(string.rc()));
-    }
+    let count = if reg_str.bytes() == b" " {
+        // A single space splits on runs of blanks like the default FS
+        assign_split(&mut vm.shitty_malloc, &mut vm.rt.arrays, array, split_on_string(b" ", &string))
+    } else {
+        let reg = vm.rt.regex_cache.get(&reg_str);
+        assign_split(&mut vm.shitty_malloc, &mut vm.rt.arrays, array, split_on_regex(reg, &string))
+    };
     vm.push_num(count);
     ip + 1
 }
@@ -576,7 +749,7 @@ pub fn builtin_toupper(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize
 pub fn builtin_matches(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let regex_str = vm.pop_string(); // the regex
     let str = vm.pop_string(); // the string
-    let regex = vm.rt.regex_cache.get(&*regex_str);
+    let regex = vm.rt.regex_cache.get(&regex_str);
     let (start, len) =
         if let Some(match_idx) = regex.match_idx(&str) {
             ((match_idx.start + 1) as f64, (match_idx.len) as f64)
@@ -595,7 +768,7 @@ pub fn sub3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let regex_str = vm.pop_string();
     let regex = vm.rt.regex_cache.get(&regex_str);
 
-    let matched = regex.match_idx(&*input_str);
+    let matched = regex.match_idx(&input_str);
     if let Some(mtc) = matched {
         let mut new_string = vm.shitty_malloc.copy_from_slice(&input_str[0..mtc.start]);
         let matched_bytes = &input_str[0..mtc.start + mtc.len];
@@ -614,6 +787,406 @@ pub fn sub3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     ip + 1
 }
 
+pub fn getline_input(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let read = handle_err!(vm, vm.getline_input(true));
+    vm.push_num(if read { 1.0 } else { 0.0 });
+    ip + 1
+}
+
+pub fn getline_input_var(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let read = handle_err!(vm, vm.getline_input(false));
+    vm.push_num(if read { 1.0 } else { 0.0 });
+    ip + 1
+}
+
+pub fn getline_file(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let path = vm.pop_string();
+    let read = vm.rt.columns.getline_file(&path);
+    let read = vm.getline_redirect(read, false);
+    vm.shitty_malloc.drop_str(path);
+    vm.push_num(if read { 1.0 } else { 0.0 });
+    ip + 1
+}
+
+pub fn getline_command(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let command = vm.pop_string();
+    // Output printed before the command runs comes before anything it prints
+    let _ = vm.stdout.flush();
+    let read = vm.rt.columns.getline_command(&command);
+    let read = vm.getline_redirect(read, true);
+    vm.shitty_malloc.drop_str(command);
+    vm.push_num(if read { 1.0 } else { 0.0 });
+    ip + 1
+}
+
+pub fn getline_record(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let record = AwkStr::new_from_vec(std::mem::take(&mut vm.getline_record));
+    vm.push_str(StringScalar::input(record));
+    ip + 1
+}
+
+pub fn getline_result(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    vm.push_num(vm.getline_result);
+    ip + 1
+}
+
+pub fn builtin_gensub3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let mut target = vm.shitty_malloc.get();
+    vm.rt.columns.get_into_buf(0, target.as_mut_vec());
+    handle_err!(vm, gensub(vm, target.rc()));
+    ip + 1
+}
+
+pub fn builtin_gensub4(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let target = vm.pop_string();
+    let result = gensub(vm, (*target).clone());
+    vm.shitty_malloc.drop_str(target);
+    handle_err!(vm, result);
+    ip + 1
+}
+
+// gensub(regex, replacement, how, target) with target already popped
+fn gensub(vm: &mut VirtualMachine, target: RcAwkStr) -> Result<(), PrintableError> {
+    let how = vm.pop_unknown();
+    let replacement = vm.pop_string();
+    let regex_str = vm.pop_string();
+    // "g" or "G" replaces every match, otherwise only the nth. Anything less than 1 is 1.
+    let nth = match &how {
+        RuntimeScalar::Str(s) | RuntimeScalar::StrNum(s) if matches!(s.first(), Some(b'g') | Some(b'G')) => None,
+        _ => Some(vm.val_to_num(how).max(1.0) as usize),
+    };
+    let repl = GensubReplStr::new(&replacement);
+    let mut new_string = vm.shitty_malloc.get();
+    let regex = vm.rt.regex_cache.get(&regex_str);
+
+    let mut start_idx = 0;
+    let mut matches = 0;
+    // An empty match right after a match isn't replaced, same as gsub in other awks
+    let mut last_match_end = None;
+    while start_idx <= target.len() {
+        let captures = match regex.captures_from(&target, start_idx) {
+            Ok(Some(captures)) => captures,
+            Ok(None) => break,
+            Err(err) => return Err(PrintableError::new(format!("gensub: {}", err))),
+        };
+        let mtc = captures.whole();
+        let end = mtc.end();
+        if mtc.len == 0 && last_match_end == Some(mtc.start) {
+            if mtc.start == target.len() {
+                break;
+            }
+            new_string.push_str(&target[start_idx..mtc.start + 1]);
+            start_idx = mtc.start + 1;
+            continue;
+        }
+        matches += 1;
+        new_string.push_str(&target[start_idx..mtc.start]);
+        if nth.is_none_or(|nth| nth == matches) {
+            let groups: Vec<Option<&[u8]>> = captures.iter()
+                .map(|group| group.map(|group| &target[group.start..group.end()]))
+                .collect();
+            repl.push_replacement(&mut new_string, &groups);
+        } else {
+            new_string.push_str(&target[mtc.start..end]);
+        }
+        if mtc.len == 0 {
+            // Step over a byte so the next search starts after this empty match
+            if mtc.start < target.len() {
+                new_string.push_str(&target[mtc.start..mtc.start + 1]);
+            }
+            start_idx = mtc.start + 1;
+        } else {
+            start_idx = end;
+            last_match_end = Some(end);
+        }
+        if nth == Some(matches) {
+            break;
+        }
+    }
+    if start_idx < target.len() {
+        new_string.push_str(&target[start_idx..]);
+    }
+    vm.push_str(StringScalar::Str(new_string.rc()));
+    vm.shitty_malloc.drop_str(regex_str);
+    vm.shitty_malloc.drop_str(replacement);
+    Ok(())
+}
+
+pub fn builtin_systime(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    vm.push_num(time::systime());
+    ip + 1
+}
+
+pub fn builtin_mktime(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let utc = vm.pop_unknown().truthy();
+    let spec = vm.pop_string();
+    vm.push_num(time::mktime(&spec, utc));
+    vm.shitty_malloc.drop_str(spec);
+    ip + 1
+}
+
+pub fn builtin_strftime(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let utc = vm.pop_unknown().truthy();
+    let timestamp = vm.pop_num();
+    let format = vm.pop_string();
+    let formatted = time::strftime(&format, timestamp, utc).unwrap_or_else(|| {
+        // Like gawk the result is empty and the program carries on
+        vm.warning("strftime: second argument out of range for time_t");
+        vec![]
+    });
+    vm.shitty_malloc.drop_str(format);
+    let formatted = vm.shitty_malloc.copy_from_slice(&formatted);
+    vm.push_str(StringScalar::Str(formatted.rc()));
+    ip + 1
+}
+
+fn bit_op(vm: &mut VirtualMachine, ip: usize, op: BitOp) -> usize {
+    let right = vm.pop_num();
+    let left = vm.pop_num();
+    vm.push_num(handle_err!(vm, bitwise::bit_op(op, left, right)));
+    ip + 1
+}
+
+pub fn builtin_and(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    bit_op(vm, ip, BitOp::And)
+}
+
+pub fn builtin_or(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    bit_op(vm, ip, BitOp::Or)
+}
+
+pub fn builtin_xor(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    bit_op(vm, ip, BitOp::Xor)
+}
+
+pub fn builtin_lshift(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    bit_op(vm, ip, BitOp::Lshift)
+}
+
+pub fn builtin_rshift(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    bit_op(vm, ip, BitOp::Rshift)
+}
+
+pub fn builtin_compl(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let num = vm.pop_num();
+    vm.push_num(handle_err!(vm, bitwise::compl(num)));
+    ip + 1
+}
+
+pub fn builtin_strtonum(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let num = match vm.pop_unknown() {
+        RuntimeScalar::Num(num) => num,
+        // Input that looks numeric is already a decimal number
+        RuntimeScalar::StrNum(str) if vm.rt.converter.looks_numeric(&str) => vm.str_to_num(&str),
+        RuntimeScalar::Str(str) | RuntimeScalar::StrNum(str) => {
+            match bitwise::non_decimal_to_num(&str) {
+                Some(num) => num,
+                None => vm.str_to_num(&str),
+            }
+        }
+    };
+    vm.push_num(num);
+    ip + 1
+}
+
+pub fn builtin_asort1(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let src = vm.pop_array();
+    asort(vm, src, src, None, false);
+    continue_unless_error(vm, ip)
+}
+
+pub fn builtin_asort2(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let dest = vm.pop_array();
+    let src = vm.pop_array();
+    asort(vm, src, dest, None, false);
+    continue_unless_error(vm, ip)
+}
+
+pub fn builtin_asort3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let how = vm.pop_string();
+    let dest = vm.pop_array();
+    let src = vm.pop_array();
+    asort(vm, src, dest, Some(how), false);
+    continue_unless_error(vm, ip)
+}
+
+pub fn builtin_asorti1(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let src = vm.pop_array();
+    asort(vm, src, src, None, true);
+    continue_unless_error(vm, ip)
+}
+
+pub fn builtin_asorti2(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let dest = vm.pop_array();
+    let src = vm.pop_array();
+    asort(vm, src, dest, None, true);
+    continue_unless_error(vm, ip)
+}
+
+pub fn builtin_asorti3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let how = vm.pop_string();
+    let dest = vm.pop_array();
+    let src = vm.pop_array();
+    asort(vm, src, dest, Some(how), true);
+    continue_unless_error(vm, ip)
+}
+
+// Replaces dest with the sorted values (asort) or indices (asorti) of src keyed 1..=n.
+// Pushes n.
+fn asort(vm: &mut VirtualMachine, src: GlobalArrayId, dest: GlobalArrayId, how: Option<StringScalar>, indices: bool) {
+    let default = if indices { SortOrder::IND_STR_ASC } else { SortOrder::VAL_TYPE_ASC };
+    let entries = match how {
+        Some(how) => {
+            let entries = ordered_entries(vm, src, how.bytes(), default);
+            vm.shitty_malloc.drop_str(how);
+            entries
+        }
+        None => ordered_entries(vm, src, b"", default),
+    };
+    let count = entries.len();
+    let values = entries.into_iter().map(|(idx, value)| {
+        if indices {
+            RuntimeScalar::Str(vm.shitty_malloc.copy_from_slice(&idx).rc())
+        } else {
+            value
+        }
+    }).collect();
+    vm.rt.arrays.assign_list(dest, values);
+    vm.push_num(count as f64);
+}
+
+pub fn for_in_start(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let array = vm.pop_array();
+    let procinfo = GlobalArrayId { id: ArrSpecial::PROCINFO as usize };
+    let sorted_in = ArrayKey::Str(vm.shitty_malloc.copy_from_slice(b"sorted_in").rc());
+    let how = match vm.rt.arrays.access(procinfo, sorted_in) {
+        Some(how) => vm.val_to_string(how).bytes().to_vec(),
+        None => vec![],
+    };
+    let entries = ordered_entries(vm, array, &how, SortOrder::UNSORTED);
+    let keys = entries.into_iter().map(|(key, _value)| vm.shitty_malloc.copy_from_slice(&key).rc()).collect();
+    vm.for_in.push(ForInKeys { keys, next: 0 });
+    continue_unless_error(vm, ip)
+}
+
+pub fn for_in_next(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let loop_keys = unwrap(vm.for_in.last_mut());
+    let more = loop_keys.next < loop_keys.keys.len();
+    if more {
+        loop_keys.next += 1;
+    }
+    vm.push_bool(more);
+    ip + 1
+}
+
+pub fn for_in_key(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let loop_keys = unwrap(vm.for_in.last());
+    let key = loop_keys.keys[loop_keys.next - 1].clone();
+    vm.push_str(StringScalar::Str(key));
+    ip + 1
+}
+
+pub fn for_in_end(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    vm.for_in.pop();
+    ip + 1
+}
+
+pub fn next_file(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let more = vm.next_file();
+    vm.push_num(if more { 1.0 } else { 0.0 });
+    ip + 1
+}
+
+pub fn check_file_opened(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    if let Some(err) = vm.rt.columns.take_open_error() {
+        vm.runtime_error(err);
+        return usize::MAX;
+    }
+    ip + 1
+}
+
+pub fn next_record_in_file(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let more = handle_err!(vm, vm.next_line_in_file());
+    vm.push_num(if more { 1.0 } else { 0.0 });
+    ip + 1
+}
+
+pub fn skip_file(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    vm.rt.columns.skip_file();
+    ip + 1
+}
+
+// Elements of array in the order named by how: empty for the default, one of gawk's predefined
+// orderings like @val_num_desc, or a user function cmp(i1, v1, i2, v2) that returns less than 0
+// when the first element goes first.
+fn ordered_entries(vm: &mut VirtualMachine, array: GlobalArrayId, how: &[u8], default: SortOrder) -> Vec<(Vec<u8>, RuntimeScalar)> {
+    let order = if how.is_empty() { Some(default) } else { SortOrder::parse(how) };
+    if let Some(order) = order {
+        return vm.rt.arrays.sorted_entries(array, order, &mut vm.rt.converter);
+    }
+    let entries = vm.rt.arrays.entries(array);
+    let name = String::from_utf8_lossy(how);
+    let target = vm.vm_program.functions.iter().position(|func| !func.is_main() && func.name().to_str() == name);
+    let target = match target {
+        Some(target) => target,
+        None => {
+            vm.runtime_error(PrintableError::new(format!("fatal: sort comparison function `{}` is not defined", name)));
+            return entries;
+        }
+    };
+    if vm.vm_program.functions[target].args().iter().take(4).any(|arg| arg.typ == ArgT::Array) {
+        vm.runtime_error(PrintableError::new(format!("fatal: sort comparison function `{}` must take scalar args", name)));
+        return entries;
+    }
+    merge_sort_by(entries, &mut |a, b| user_compare(vm, target, a, b))
+}
+
+fn user_compare(vm: &mut VirtualMachine, target: usize, a: &(Vec<u8>, RuntimeScalar), b: &(Vec<u8>, RuntimeScalar)) -> Ordering {
+    let program = vm.vm_program.clone();
+    let func = &program.functions[target];
+    let idx_a = RuntimeScalar::Str(vm.shitty_malloc.copy_from_slice(&a.0).rc());
+    let idx_b = RuntimeScalar::Str(vm.shitty_malloc.copy_from_slice(&b.0).rc());
+    let values = [idx_a, a.1.clone(), idx_b, b.1.clone()];
+
+    // Push each arg on the stack for its type, same as a call from awk
+    let mut passed = 0;
+    for ((arg, typ), value) in func.args().iter().zip(func.arg_types().iter()).zip(values) {
+        passed += 1;
+        if arg.typ != ArgT::Scalar {
+            continue;
+        }
+        match typ {
+            ScalarType::Var => vm.push_unknown(value),
+            ScalarType::Str => {
+                let str = vm.val_to_string_scalar(value);
+                vm.push_str(str);
+            }
+            ScalarType::Num => {
+                let num = vm.val_to_num(value);
+                vm.push_num(num);
+            }
+        }
+    }
+    call(vm, 0, Immed { call: CallTarget { target: target as u32, args: passed } });
+    if vm.error.is_some() {
+        // Nothing was returned, the sort finishes and the caller stops
+        return Ordering::Equal;
+    }
+
+    let result = match func.return_type() {
+        ScalarType::Num => vm.pop_num(),
+        ScalarType::Str => {
+            let str = vm.pop_string();
+            vm.str_to_num(&str)
+        }
+        ScalarType::Var => {
+            let value = vm.pop_unknown();
+            vm.val_to_num(value)
+        }
+    };
+    result.partial_cmp(&0.0).unwrap_or(Ordering::Equal)
+}
+
 pub fn gsub3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let input_str = vm.pop_string();
     let replacement = vm.pop_string();
@@ -629,7 +1202,7 @@ pub fn gsub3(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
         matches += 1.0;
         repl.push_replacement(&mut new_string, matched_bytes);
         start_idx += mtc.start + mtc.len;
-        if input_str[start_idx..].len() == 0 {
+        if input_str[start_idx..].is_empty() {
             break;
         }
     }
@@ -648,7 +1221,8 @@ fn offset_ip(ip: usize, offset: isize) -> usize {
 
 pub fn rel_jump_if_false_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let offset = unsafe { imm.offset };
-    if vm.pop_unknown().truthy() {
+    let value = vm.pop_unknown();
+    if vm.val_truthy(&value) {
         ip + 1
     } else {
         offset_ip(ip, offset)
@@ -657,7 +1231,8 @@ pub fn rel_jump_if_false_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) ->
 
 pub fn rel_jump_if_false_str(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let offset = unsafe { imm.offset };
-    if vm.pop_string().truthy() {
+    let value = vm.pop_string();
+    if vm.str_truthy(&value) {
         ip + 1
     } else {
         offset_ip(ip, offset)
@@ -676,7 +1251,8 @@ pub fn rel_jump_if_false_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) ->
 
 pub fn rel_jump_if_true_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let offset = unsafe { imm.offset };
-    if vm.pop_unknown().truthy() {
+    let value = vm.pop_unknown();
+    if vm.val_truthy(&value) {
         offset_ip(ip, offset)
     } else {
         ip + 1
@@ -685,7 +1261,8 @@ pub fn rel_jump_if_true_var(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> u
 
 pub fn rel_jump_if_true_str(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let offset = unsafe { imm.offset };
-    if vm.pop_string().truthy() {
+    let value = vm.pop_string();
+    if vm.str_truthy(&value) {
         offset_ip(ip, offset)
     } else {
         ip + 1
@@ -703,7 +1280,7 @@ pub fn rel_jump_if_true_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> u
 
 pub fn rel_jump_if_true_next_line(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let offset = unsafe { imm.offset };
-    if handle_err!(vm.next_line()) {
+    if handle_err!(vm, vm.next_line()) {
         offset_ip(ip, offset)
     } else {
         ip + 1
@@ -712,7 +1289,7 @@ pub fn rel_jump_if_true_next_line(vm: &mut VirtualMachine, ip: usize, imm: Immed
 
 pub fn rel_jump_if_false_next_line(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     let offset = unsafe { imm.offset };
-    if handle_err!(vm.next_line()) {
+    if handle_err!(vm, vm.next_line()) {
         ip + 1
     } else {
         offset_ip(ip, offset)
@@ -724,12 +1301,86 @@ pub fn rel_jump(_vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     offset_ip(ip, offset)
 }
 
+// Const generic CMP parameter for the compare-and-branch subroutines
+pub const CMP_LT: u8 = 0;
+pub const CMP_GT: u8 = 1;
+pub const CMP_LTEQ: u8 = 2;
+pub const CMP_GTEQ: u8 = 3;
+pub const CMP_EQEQ: u8 = 4;
+pub const CMP_NEQ: u8 = 5;
+
+#[inline(always)]
+fn compare<const CMP: u8, T: PartialEq + PartialOrd>(lhs: T, rhs: T) -> bool {
+    match CMP {
+        CMP_LT => op_helpers::lt(lhs, rhs),
+        CMP_GT => op_helpers::gt(lhs, rhs),
+        CMP_LTEQ => op_helpers::lteq(lhs, rhs),
+        CMP_GTEQ => op_helpers::gteq(lhs, rhs),
+        CMP_EQEQ => op_helpers::eq(lhs, rhs),
+        _ => op_helpers::neq(lhs, rhs),
+    }
+}
+
+pub fn rel_jump_if_cmp_num<const CMP: u8, const JUMP_IF: bool>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let right = vm.pop_num();
+    let left = vm.pop_num();
+    if compare::<CMP, f64>(left, right) == JUMP_IF {
+        offset_ip(ip, unsafe { imm.offset })
+    } else {
+        ip + 1
+    }
+}
+
+// Same comparison as the binop! subroutines
+pub fn rel_jump_if_cmp_var<const CMP: u8, const JUMP_IF: bool>(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let right = vm.pop_unknown();
+    let left = vm.pop_unknown();
+    let res = if vm.val_is_numeric(&left) && vm.val_is_numeric(&right) {
+        let left = vm.val_to_num(left);
+        let right = vm.val_to_num(right);
+        compare::<CMP, f64>(left, right)
+    } else {
+        let left = vm.val_to_string(left);
+        let right = vm.val_to_string(right);
+        compare::<CMP, RcAwkStr>(left, right)
+    };
+    if res == JUMP_IF {
+        offset_ip(ip, unsafe { imm.offset })
+    } else {
+        ip + 1
+    }
+}
+
+pub fn add_const(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let num = vm.pop_num();
+    vm.push_num(op_helpers::add(num, unsafe { imm.num }));
+    ip + 1
+}
+
+pub fn add_gscl_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let GsclAdd { id, delta } = unsafe { imm.gscl_add };
+    let id = GlobalScalarId { id: id as usize };
+    let num = match vm.gscl(id) {
+        RuntimeScalar::Num(num) => *num,
+        _ => unsafe { std::hint::unreachable_unchecked() },
+    };
+    vm.assign_gscl(id, RuntimeScalar::Num(op_helpers::add(num, delta as f64)));
+    ip + 1
+}
+
 pub fn print(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
     let value = vm.pop_string();
     vm.stdout.write_all(&value).unwrap();
-    if !value.bytes().ends_with(&[10]) {
-        vm.stdout.write_all(&[10]).unwrap();
-    }
+    vm.stdout.write_all(&vm.rt.ors).unwrap();
+    ip + 1
+}
+
+// Printing a number on its own converts it with OFMT instead of CONVFMT
+pub fn print_num(vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
+    let num = vm.pop_num();
+    let value = vm.rt.converter.num_to_str_output(num);
+    vm.stdout.write_all(value).unwrap();
+    vm.stdout.write_all(&vm.rt.ors).unwrap();
     ip + 1
 }
 
@@ -746,11 +1397,6 @@ pub fn printf(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
     ip + 1
 }
 
-pub fn noop(_vm: &mut VirtualMachine, ip: usize, _imm: Immed) -> usize {
-    // TODO: remove no-op entirely
-    ip + 1
-}
-
 pub fn ret(_vm: &mut VirtualMachine, _ip: usize, _imm: Immed) -> usize {
     usize::MAX
 }
@@ -776,28 +1422,109 @@ pub fn const_num(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
 }
 
 pub fn call(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
-    let target = unsafe { imm.call_target };
-    let target = &vm.vm_program.functions[target as usize];
+    let call_target = unsafe { imm.call };
+    let program = vm.vm_program.clone();
+    let target = &program.functions[call_target.target as usize];
+
+    // Args the caller didn't pass are locals. Scalars start uninitialized and arrays empty.
+    let mut local_arrays = 0;
+    for (arg, elem_type) in target.args().iter().zip(target.elem_types().iter()).skip(call_target.args as usize) {
+        match arg.typ {
+            ArgT::Scalar => {
+                let uninit = vm.shitty_malloc.get().rc();
+                vm.push_unknown(RuntimeScalar::StrNum(uninit));
+            }
+            ArgT::Array => {
+                local_arrays += 1;
+                let array = vm.rt.arrays.allocate_local(*elem_type);
+                vm.push_arr(array);
+            }
+            ArgT::Unknown => {}
+        }
+    }
+
+    let arg_stacks = target.arg_stacks();
     let new_scope = FunctionScope {
-        unknown_stack_base_offset: vm.unknown_stack.len() - target.num_scalar_args(),
-        str_stack_base_offset: vm.str_stack.len(),
-        num_stack_base_offset: vm.num_stack.len(),
-        array_base_offset: vm.arr_stack.len() - target.num_array_args(),
+        unknown_stack_base_offset: vm.unknown_stack.len() - arg_stacks.count(StackT::Var),
+        str_stack_base_offset: vm.str_stack.len() - arg_stacks.count(StackT::Str),
+        num_stack_base_offset: vm.num_stack.len() - arg_stacks.count(StackT::Num),
+        array_base_offset: vm.arr_stack.len() - arg_stacks.count(StackT::Array),
+        local_arrays,
     };
     vm.scopes.push(new_scope);
 
     vm.run_function(target);
+    if vm.error.is_some() {
+        // The callee stopped early so its frame isn't what's expected, unwinding is skipped
+        return usize::MAX;
+    }
 
-    let return_value = vm.pop_unknown();
+    // The return value is on top of the stack for the function's return type
     let scope = vm.scopes.pop().unwrap();
+    match target.return_type() {
+        ScalarType::Num => {
+            let return_value = vm.pop_num();
+            drop_frame(vm, scope);
+            vm.push_num(return_value);
+        }
+        ScalarType::Str => {
+            let return_value = vm.pop_string();
+            drop_frame(vm, scope);
+            vm.push_str(return_value);
+        }
+        ScalarType::Var => {
+            let return_value = vm.pop_unknown();
+            drop_frame(vm, scope);
+            vm.push_unknown(return_value);
+        }
+    }
+    ip + 1
+}
+
+pub fn call_native(vm: &mut VirtualMachine, ip: usize, imm: Immed) -> usize {
+    let id = unsafe { imm.native_id };
+    let program = vm.vm_program.clone();
+    let native = unwrap(program.func_map.native_by_id(id));
+
+    // Each arg is on the stack for its type, pop them last first
+    let mut args = Vec::with_capacity(native.args.len());
+    for typ in native.args.iter().rev() {
+        args.push(match typ {
+            NativeArgT::Num => NativeArg::Num(vm.pop_num()),
+            NativeArgT::Str => NativeArg::Str(vm.pop_string()),
+            NativeArgT::Array => NativeArg::Array(vm.pop_array()),
+        });
+    }
+    args.reverse();
+
+    let func = vm.natives[id].clone();
+    let returned = func(&mut NativeArgs::new(args, &mut vm.rt.arrays));
+    let value = returned.into_scalar();
+    match native.returns {
+        ScalarType::Num => {
+            let num = vm.val_to_num(value);
+            vm.push_num(num);
+        }
+        ScalarType::Str => {
+            let str = vm.val_to_string_scalar(value);
+            vm.push_str(str);
+        }
+        ScalarType::Var => vm.push_unknown(value),
+    }
+    ip + 1
+}
 
+fn drop_frame(vm: &mut VirtualMachine, scope: FunctionScope) {
     vm.unknown_stack.truncate(scope.unknown_stack_base_offset); // remove args from the stack
     vm.str_stack.truncate(scope.str_stack_base_offset);
     vm.num_stack.truncate(scope.num_stack_base_offset);
+    for _ in 0..scope.local_arrays {
+        let array = vm.pop_array();
+        for value in vm.rt.arrays.free_local(array) {
+            vm.shitty_malloc.drop_scalar(value);
+        }
+    }
     vm.arr_stack.truncate(scope.array_base_offset); // remove array args from the stack
-
-    vm.push_unknown(return_value);
-    ip + 1
 }
 
 
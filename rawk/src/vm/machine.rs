@@ -1,20 +1,19 @@
 use std::io::{Write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::os::unix::ffi::OsStringExt;
+use std::rc::Rc;
 use crate::awk_str::{AwkStr, RcAwkStr};
-use crate::runtime::arrays::{Arrays, split_on_regex, split_on_string};
-use crate::runtime::columns::Columns;
+use crate::runtime::arrays::ArrayKey;
 use crate::runtime::rc_manager::RcManager;
-use crate::runtime::regex_cache::RegexCache;
-use crate::runtime::converter::Converter;
 use crate::vm::{RuntimeScalar, StringScalar};
-use crate::{binop, mathop, specials};
 use crate::parser::{SclSpecial};
 use crate::printable_error::PrintableError;
+use crate::embed::NativeFn;
 use crate::runtime::special_manager::SpecialManager;
-use crate::runtime::{RecordState, VmRuntime};
+use crate::runtime::{GetlineRead, Input, NextInput, RecordState, VmRuntime};
+use crate::specials::ArrSpecial;
 use crate::typing::{GlobalArrayId, GlobalScalarId};
-use crate::vm::{Code, VmFunc, VmProgram};
-use crate::util::{clamp_to_max_len, clamp_to_slice_index, index_of, unwrap};
+use crate::vm::{Indices, VmFunc, VmProgram};
+use crate::util::unwrap;
 
 
 pub struct FunctionScope {
@@ -22,13 +21,20 @@ pub struct FunctionScope {
     pub str_stack_base_offset: usize,
     pub num_stack_base_offset: usize,
     pub array_base_offset: usize,
+    // Trailing array args allocated for this call which must be freed on return
+    pub local_arrays: usize,
 }
 
+// Keys of a running for (k in arr) loop, saved when the loop starts
+pub struct ForInKeys {
+    pub keys: Vec<RcAwkStr>,
+    pub next: usize,
+}
 
 pub struct VirtualMachine {
-    // Just leak VmProgram it so we don't need to litter the program with lifetimes on the
-    // VirtualMachine type.
-    pub vm_program: &'static VmProgram,
+    // Shared so an embedded program can be run many times without copying it, and so
+    // subroutines can hold a function while the vm is borrowed mutably.
+    pub vm_program: Rc<VmProgram>,
 
     pub global_scalars: Vec<RuntimeScalar>,
     pub special_scalars: SpecialManager,
@@ -45,28 +51,50 @@ pub struct VirtualMachine {
     // Scopes
     pub scopes: Vec<FunctionScope>,
 
+    // What the last getline read and returned, pushed by GetlineRecord and GetlineResult
+    pub getline_record: Vec<u8>,
+    pub getline_result: f64,
+
+    // Innermost for (k in arr) loop last
+    pub for_in: Vec<ForInKeys>,
+
     // Runtime modules managing various piece of state
     pub rt: VmRuntime,
 
     // IO
     pub stdout: Box<dyn Write>,
     stderr: Box<dyn Write>,
+    // First error that stopped the program, the caller reports it
+    pub error: Option<PrintableError>,
+
+    // Functions registered by the embedding program, indexed by native id
+    pub natives: Vec<NativeFn>,
+
+    // Native code for each function when running with --jit
+    #[cfg(feature = "jit")]
+    jit: Option<crate::jit::JitProgram>,
 }
 
 
 impl VirtualMachine {
-    pub fn new(vm_program: VmProgram, files: Vec<String>, stdout: Box<dyn Write>, stderr: Box<dyn Write>) -> Self {
-        unsafe { libc::srand(09171998) }
-        let vm_program = Box::leak(Box::new(vm_program));
+    pub fn new(vm_program: VmProgram, files: Vec<Input>, stdout: Box<dyn Write>, stderr: Box<dyn Write>) -> Self {
+        Self::with_program(Rc::new(vm_program), files, stdout, stderr)
+    }
+
+    // For running one program many times
+    pub fn with_program(vm_program: Rc<VmProgram>, files: Vec<Input>, stdout: Box<dyn Write>, stderr: Box<dyn Write>) -> Self {
+        unsafe { libc::srand(9171998) }
 
         let num_gscls = vm_program.analysis.global_scalars.len();
         let mut global_scalars = Vec::with_capacity(num_gscls);
         for _ in 0..num_gscls {
-            global_scalars.push(RuntimeScalar::Str(RcAwkStr::new_bytes(vec![])));
+            global_scalars.push(RuntimeScalar::StrNum(RcAwkStr::new_bytes(vec![])));
         }
-        let mut special_scalars = SpecialManager::new(1 + files.len());
+        let special_scalars = SpecialManager::new(1 + files.len());
+        let argv: Vec<String> = files.iter().map(|input| input.arg().to_string()).collect();
+        let rt = VmRuntime::new(files, &vm_program.analysis.array_types);
 
-        let s = Self {
+        let mut s = Self {
             vm_program,
             global_scalars,
             special_scalars,
@@ -76,15 +104,86 @@ impl VirtualMachine {
             str_stack: vec![],
             arr_stack: vec![],
             scopes: vec![],
-            rt: VmRuntime::new(files, vm_program.analysis.global_arrays.len()),
+            getline_record: vec![],
+            getline_result: 0.0,
+            for_in: vec![],
+            rt,
             stdout,
             stderr,
+            error: None,
+            natives: vec![],
+            #[cfg(feature = "jit")]
+            jit: None,
         };
+        s.init_argv(argv);
+        s.init_environ();
+        s.init_procinfo();
         s
     }
-    pub fn run(mut self) -> (Box<dyn Write>, Box<dyn Write>) {
-        self.run_function(self.vm_program.main());
-        (self.stdout, self.stderr)
+
+    // ARGV[0] is the interpreter then each operand, all strnums like other input
+    fn init_argv(&mut self, operands: Vec<String>) {
+        let argv = GlobalArrayId { id: ArrSpecial::ARGV as usize };
+        let args = std::iter::once("rawk".to_string()).chain(operands);
+        for (idx, arg) in args.enumerate() {
+            let key = ArrayKey::Str(RcAwkStr::new_bytes(idx.to_string().into_bytes()));
+            let value = StringScalar::input(AwkStr::new_string(arg)).into();
+            self.rt.arrays.assign(argv, key, value);
+        }
+    }
+
+    // Values are strnums, names and values that aren't utf-8 are kept as bytes
+    fn init_environ(&mut self) {
+        let environ = GlobalArrayId { id: ArrSpecial::ENVIRON as usize };
+        for (name, value) in std::env::vars_os() {
+            let key = ArrayKey::Str(RcAwkStr::new_bytes(name.into_vec()));
+            let value = StringScalar::input(AwkStr::new_from_vec(value.into_vec())).into();
+            self.rt.arrays.assign(environ, key, value);
+        }
+    }
+
+    // The keys gawk sets about the process, sorted_in is left for the program to set
+    fn init_procinfo(&mut self) {
+        let procinfo = GlobalArrayId { id: ArrSpecial::PROCINFO as usize };
+        let version = RuntimeScalar::Str(RcAwkStr::new_bytes(env!("CARGO_PKG_VERSION").as_bytes().to_vec()));
+        let ids = unsafe {
+            [("pid", libc::getpid()), ("ppid", libc::getppid()), ("pgrpid", libc::getpgrp()),
+             ("uid", libc::getuid() as i32), ("euid", libc::geteuid() as i32),
+             ("gid", libc::getgid() as i32), ("egid", libc::getegid() as i32)]
+        };
+        let entries = std::iter::once(("version", version))
+            .chain(ids.into_iter().map(|(name, id)| (name, RuntimeScalar::Num(id as f64))));
+        for (name, value) in entries {
+            let key = ArrayKey::Str(RcAwkStr::new_bytes(name.as_bytes().to_vec()));
+            self.rt.arrays.assign(procinfo, key, value);
+        }
+    }
+    #[cfg(feature = "jit")]
+    pub fn enable_jit(&mut self) {
+        self.jit = Some(crate::jit::JitProgram::new(self.vm_program.clone()));
+    }
+    pub fn enable_csv(&mut self) {
+        self.rt.columns.enable_csv();
+    }
+    pub fn run(mut self) -> (Box<dyn Write>, Box<dyn Write>, Option<PrintableError>) {
+        self.run_main();
+        (self.stdout, self.stderr, self.error)
+    }
+
+    pub fn run_main(&mut self) {
+        let program = self.vm_program.clone();
+        self.run_function(program.main());
+    }
+
+    // Non fatal, printed to stderr and the program carries on
+    pub fn warning(&mut self, msg: &str) {
+        let _ = writeln!(self.stderr, "rawk: warning: {}", msg);
+    }
+
+    pub fn runtime_error(&mut self, err: PrintableError) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
     }
 
     pub fn gscl(&mut self, idx: GlobalScalarId) -> &RuntimeScalar {
@@ -97,6 +196,10 @@ impl VirtualMachine {
     }
 
     pub fn special(&mut self, special: SclSpecial) -> RuntimeScalar {
+        if special == SclSpecial::NF {
+            // NF depends on the current record so it isn't stored with the other specials
+            return RuntimeScalar::Num(self.rt.columns.nf() as f64);
+        }
         self.special_scalars.get(special)
     }
     pub fn assign_special(&mut self, special: SclSpecial, value: RuntimeScalar) {
@@ -104,6 +207,22 @@ impl VirtualMachine {
         self.shitty_malloc.drop_scalar(prior_value);
     }
 
+    // -v, -F and name=value operands. The value is a strnum like other input. A variable the
+    // program never uses as a scalar can't be read so the assignment is a no-op.
+    pub fn assign_input(&mut self, name: &str, value: AwkStr) {
+        let value = StringScalar::input(value).into();
+        if let Ok(special) = SclSpecial::try_from(name) {
+            self.assign_special(special, value);
+            return;
+        }
+        let id = self.vm_program.analysis.global_scalars.mapping().iter()
+            .find(|(symbol, _id)| symbol.to_str() == name)
+            .map(|(_symbol, id)| *id);
+        if let Some(id) = id {
+            self.assign_gscl(id, value);
+        }
+    }
+
     pub fn push_unknown(&mut self, scalar: RuntimeScalar) { self.unknown_stack.push(scalar) }
     pub fn push_num(&mut self, num: f64) {
         self.num_stack.push(num)
@@ -131,16 +250,6 @@ impl VirtualMachine {
         unwrap(self.str_stack.pop())
     }
 
-    pub fn peek_unknown(&self) -> &RuntimeScalar {
-        unwrap(self.unknown_stack.last())
-    }
-    pub fn peek_num(&self) -> &f64 {
-        unwrap(self.num_stack.last())
-    }
-    pub fn peek_str(&self) -> &StringScalar {
-        unwrap(self.str_stack.last())
-    }
-
     pub fn set_scalar_arg(&mut self, idx: usize, value: RuntimeScalar) {
         let idx = unwrap(self.scopes.last()).unknown_stack_base_offset + idx;
         self.unknown_stack[idx] = value;
@@ -151,21 +260,31 @@ impl VirtualMachine {
         self.unknown_stack[idx].clone()
     }
 
+    pub fn get_num_arg(&mut self, idx: usize) -> f64 {
+        let idx = unwrap(self.scopes.last()).num_stack_base_offset + idx;
+        self.num_stack[idx]
+    }
+
+    pub fn get_str_arg(&mut self, idx: usize) -> StringScalar {
+        let idx = unwrap(self.scopes.last()).str_stack_base_offset + idx;
+        self.str_stack[idx].clone()
+    }
+
     pub fn get_array_arg(&mut self, idx: usize) -> GlobalArrayId {
         let idx = unwrap(self.scopes.last()).array_base_offset + idx;
-        self.arr_stack[idx].clone()
+        self.arr_stack[idx]
     }
 
     pub fn val_to_num(&mut self, value: RuntimeScalar) -> f64 {
         match value {
-            RuntimeScalar::Str(s) => self.rt.converter.str_to_num(&*s).unwrap_or(0.0),
-            RuntimeScalar::StrNum(s) => self.rt.converter.str_to_num(&*s).unwrap_or(0.0),
+            RuntimeScalar::Str(s) => self.rt.converter.str_to_num(&s).unwrap_or(0.0),
+            RuntimeScalar::StrNum(s) => self.rt.converter.str_to_num(&s).unwrap_or(0.0),
             RuntimeScalar::Num(n) => n,
         }
     }
 
     pub fn str_to_num(&mut self, s: &RcAwkStr) -> f64 {
-        self.rt.converter.str_to_num(&*s).unwrap_or(0.0)
+        self.rt.converter.str_to_num(s).unwrap_or(0.0)
     }
 
     pub fn val_to_string(&mut self, value: RuntimeScalar) -> RcAwkStr {
@@ -190,40 +309,164 @@ impl VirtualMachine {
             RuntimeScalar::Str(_) => false,
             RuntimeScalar::StrNum(ptr) => {
                 // TODO: Changing each occurrence of the decimal point character from the current locale to a period.
-                if ptr.len() == 0 {
+                if ptr.is_empty() {
+                    // Uninitialized, compares as both 0 and ""
                     true
                 } else {
-                    self.rt.converter.str_to_num(ptr).is_some()
+                    self.rt.converter.looks_numeric(ptr)
                 }
             }
         }
     }
 
+    // Input that looks numeric is true unless it's 0, so "0.0" from a field is false like 0
+    pub fn val_truthy(&mut self, value: &RuntimeScalar) -> bool {
+        match value {
+            RuntimeScalar::StrNum(s) => self.strnum_truthy(s),
+            _ => value.truthy(),
+        }
+    }
+    pub fn str_truthy(&mut self, value: &StringScalar) -> bool {
+        match value {
+            StringScalar::StrNum(s) => self.strnum_truthy(s),
+            StringScalar::Str(s) => s.truthy(),
+        }
+    }
+    fn strnum_truthy(&mut self, s: &RcAwkStr) -> bool {
+        if s.truthy() && self.rt.converter.looks_numeric(s) {
+            return self.str_to_num(s) != 0.0;
+        }
+        s.truthy()
+    }
+
+    pub fn array_key(&mut self, indices: Indices) -> ArrayKey {
+        match indices {
+            Indices::Str(count) => ArrayKey::Str(self.concat_array_indices(count as usize).rc()),
+            Indices::Num => {
+                let num = self.pop_num();
+                self.num_key(num)
+            }
+            Indices::Var => match self.pop_unknown() {
+                RuntimeScalar::Num(num) => self.num_key(num),
+                RuntimeScalar::Str(str) | RuntimeScalar::StrNum(str) => ArrayKey::Str(str),
+            },
+        }
+    }
+
+    fn num_key(&mut self, num: f64) -> ArrayKey {
+        match ArrayKey::from_num(num) {
+            Some(key) => key,
+            None => ArrayKey::Str(self.val_to_string(RuntimeScalar::Num(num))),
+        }
+    }
+
     pub fn concat_array_indices(&mut self, count: usize) -> AwkStr {
         let subsep = self.special_scalars.get(SclSpecial::SUBSEP);
         let subsep = self.val_to_string(subsep);
-        let mut string = self.pop_string().downgrade_or_clone();
-        for _ in 0..count - 1 {
-            let addition = self.pop_string();
+        // Indices are popped last to first
+        let mut indices: Vec<StringScalar> = (0..count).map(|_| self.pop_string()).collect();
+        let mut string = indices.pop().unwrap().downgrade_or_clone();
+        for addition in indices.iter().rev() {
             string.push_str(&subsep);
-            string.push_str(&*addition);
+            string.push_str(addition);
         }
         string
     }
 
     pub fn next_line(&mut self) -> Result<bool, PrintableError> {
-        let FNR = self.val_to_num(self.special_scalars.get(SclSpecial::FNR));
-        let NR = self.val_to_num(self.special_scalars.get(SclSpecial::NR));
-        let record_state = self.rt.columns.next_record(RecordState::new(NR, FNR))?;
+        loop {
+            let fnr = self.val_to_num(self.special_scalars.get(SclSpecial::FNR));
+            let nr = self.val_to_num(self.special_scalars.get(SclSpecial::NR));
+            let record_state = self.rt.columns.next_record(RecordState::new(nr, fnr))?;
+            if let Some(filename) = record_state.new_file {
+                self.special_scalars.assign(SclSpecial::FILENAME, RuntimeScalar::Str(filename), &mut self.rt);
+            }
+            self.special_scalars.assign(SclSpecial::FNR, RuntimeScalar::Num(record_state.FNR), &mut self.rt);
+            self.special_scalars.assign(SclSpecial::NR, RuntimeScalar::Num(record_state.NR), &mut self.rt);
+            match record_state.assignment {
+                Some((name, value)) => self.assign_input(&name, value),
+                None => return Ok(record_state.next_record),
+            }
+        }
+    }
+
+    // Switches to the next file before BEGINFILE runs, false if there are none left
+    pub fn next_file(&mut self) -> bool {
+        let (filename, errno) = loop {
+            match self.rt.columns.start_next_file() {
+                Some(NextInput::File(filename, errno)) => break (filename, errno),
+                Some(NextInput::Assignment(name, value)) => self.assign_input(&name, value),
+                None => return false,
+            }
+        };
+        let errno = RcAwkStr::new_bytes(errno.unwrap_or_default().into_bytes());
+        self.special_scalars.assign(SclSpecial::FILENAME, RuntimeScalar::Str(filename), &mut self.rt);
+        self.special_scalars.assign(SclSpecial::FNR, RuntimeScalar::Num(0.0), &mut self.rt);
+        self.special_scalars.assign(SclSpecial::ERRNO, RuntimeScalar::Str(errno), &mut self.rt);
+        true
+    }
+
+    // next_line without moving on to the next file
+    pub fn next_line_in_file(&mut self) -> Result<bool, PrintableError> {
+        let fnr = self.val_to_num(self.special_scalars.get(SclSpecial::FNR));
+        let nr = self.val_to_num(self.special_scalars.get(SclSpecial::NR));
+        let record_state = self.rt.columns.next_record_in_file(RecordState::new(nr, fnr))?;
         self.special_scalars.assign(SclSpecial::FNR, RuntimeScalar::Num(record_state.FNR), &mut self.rt);
         self.special_scalars.assign(SclSpecial::NR, RuntimeScalar::Num(record_state.NR), &mut self.rt);
         Ok(record_state.next_record)
     }
 
+    // getline from the main input, NR and FNR count the record. Unless the record is going into
+    // $0 it's saved for GetlineRecord and $0 is left alone.
+    pub fn getline_input(&mut self, into_record: bool) -> Result<bool, PrintableError> {
+        let mut prior_record = vec![];
+        self.rt.columns.get_into_buf(0, &mut prior_record);
+        let read = self.next_line()?;
+        if read && !into_record {
+            self.rt.columns.get_into_buf(0, &mut self.getline_record);
+        }
+        if !read || !into_record {
+            self.rt.columns.set(0, &prior_record);
+        }
+        self.getline_result = if read { 1.0 } else { 0.0 };
+        Ok(read)
+    }
+
+    // getline < file and cmd | getline, NR only counts records from commands
+    pub fn getline_redirect(&mut self, read: GetlineRead, counts_nr: bool) -> bool {
+        self.getline_result = match read {
+            GetlineRead::Record(record) => {
+                self.getline_record = record;
+                if counts_nr {
+                    let nr = self.val_to_num(self.special_scalars.get(SclSpecial::NR));
+                    self.special_scalars.assign(SclSpecial::NR, RuntimeScalar::Num(nr + 1.0), &mut self.rt);
+                }
+                1.0
+            }
+            GetlineRead::End => 0.0,
+            GetlineRead::Error => -1.0,
+        };
+        self.getline_result == 1.0
+    }
+
     pub fn run_function(&mut self, function: &VmFunc) {
+        #[cfg(feature = "jit")]
+        if let Some(entry) = self.jit.as_ref().map(|jit| jit.entry(function.id())) {
+            entry(self);
+            return;
+        }
         let mut ip = 0;
+        #[cfg(feature = "op_profile")]
+        let mut prev_ip: Option<usize> = None;
 
         loop {
+            #[cfg(feature = "op_profile")]
+            {
+                if let Some(prev_ip) = prev_ip {
+                    crate::vm::op_profile::record(&function.chunk()[prev_ip], &function.chunk()[ip]);
+                }
+                prev_ip = Some(ip);
+            }
             #[cfg(test)]
             {
                 // Coloring makes it easier to match up scalar stack and array stack visually when debugging
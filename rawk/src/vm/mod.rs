@@ -2,9 +2,12 @@ mod bytecode;
 mod vm_func;
 mod vm_program;
 mod machine;
+#[cfg(feature = "op_profile")]
+pub mod op_profile;
+mod precompiled;
 pub mod runtime_scalar;
 
-pub use bytecode::{Code, Label, LabelId};
+pub use bytecode::{op_helpers, Cmp, Code, Incr, Indices, Label};
 pub use vm_func::VmFunc;
 pub use vm_program::VmProgram;
 pub use machine::VirtualMachine;
@@ -0,0 +1,28 @@
+use std::sync::Mutex;
+use hashbrown::HashMap;
+use crate::vm::Code;
+
+// Counts of adjacent pairs of executed ops, by op name, for every program run by this process.
+// Built with the op_profile feature. Ops aren't fused into superinstructions in that build so
+// the counts show which pairs are worth fusing.
+static PAIRS: Mutex<Option<HashMap<(String, String), u64>>> = Mutex::new(None);
+
+// Variant name without its fields
+fn op_name(code: &Code) -> String {
+    let debug = format!("{:?}", code);
+    debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default().to_string()
+}
+
+pub fn record(prev: &Code, next: &Code) {
+    let mut pairs = PAIRS.lock().unwrap();
+    let pairs = pairs.get_or_insert_with(HashMap::new);
+    *pairs.entry((op_name(prev), op_name(next))).or_insert(0) += 1;
+}
+
+// The most common pairs so far, most common first
+pub fn report(limit: usize) -> String {
+    let pairs = PAIRS.lock().unwrap();
+    let mut counts: Vec<_> = pairs.iter().flatten().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    counts.iter().take(limit).map(|((prev, next), count)| format!("{:>10} {} {}\n", count, prev, next)).collect()
+}
@@ -0,0 +1,668 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+use hashbrown::HashMap;
+use crate::awk_str::RcAwkStr;
+use crate::compiler::{validate_program, Chunk};
+use crate::global_scalars::SymbolMapping;
+use crate::parser::{Arg, ArgT, ScalarType, SclSpecial};
+use crate::printable_error::PrintableError;
+use crate::symbolizer::Symbol;
+use crate::typing::{AnalysisResults, FunctionMap, GlobalArrayId, GlobalScalarId, ITypedFunction, TypedUserFunction};
+use crate::vm::{Cmp, Code, Incr, Indices, VmFunc, VmProgram};
+use crate::Symbolizer;
+
+// On disk format of a compiled program, written by --compile and loaded by -c. Ints are little
+// endian and strings are a u32 length followed by their bytes.
+//   magic, format version, version of rawk that wrote it
+//   names of the scalar specials, codes refer to specials by their index in this list
+//   global scalar names in id order
+//   global array names and element types in id order
+//   functions in id order: name, return type, args, then the bytecode
+// Each code is a tag byte followed by its operands. Programs are only loaded by the rawk version
+// that saved them since the meaning of an op can change between versions.
+
+const MAGIC: &[u8] = b"\x7frawkc";
+const FORMAT_VERSION: u32 = 1;
+
+// Codes without operands
+macro_rules! simple_codes {
+    ($($code:ident = $tag:literal,)*) => {
+        fn simple_tag(code: &Code) -> Option<u8> {
+            match code {
+                $(Code::$code => Some($tag),)*
+                _ => None,
+            }
+        }
+        fn simple_code(tag: u8) -> Option<Code> {
+            match tag {
+                $($tag => Some(Code::$code),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+simple_codes! {
+    NumToVar = 0, NumToStr = 1, StrToVar = 2, StrToNum = 3, VarToNum = 4, VarToStr = 5,
+    Pop = 6, PopStr = 7, PopNum = 8,
+    Column = 9, AssignColumn = 10, AssignRetColumn = 11,
+    Exp = 12, Mult = 13, Div = 14, Mod = 15, Add = 16, Minus = 17,
+    Lt = 18, Gt = 19, LtEq = 20, GtEq = 21, EqEq = 22, Neq = 23,
+    LtNum = 24, GtNum = 25, LtEqNum = 26, GtEqNum = 27, EqEqNum = 28, NeqNum = 29,
+    Matches = 30, NMatches = 31,
+    Print = 32, Ret = 33, RetNum = 34, RetStr = 35,
+    BuiltinAtan2 = 36, BuiltinCos = 37, BuiltinExp = 38, BuiltinSubstr2 = 39, BuiltinSubstr3 = 40,
+    BuiltinIndex = 41, BuiltinInt = 42, BuiltinLength0 = 43, BuiltinLength1 = 44, BuiltinLog = 45,
+    BuiltinRand = 46, BuiltinSin = 47, BuiltinSplit2 = 48, BuiltinSplit3 = 49, BuiltinSqrt = 50,
+    BuiltinSrand0 = 51, BuiltinSrand1 = 52, BuiltinTolower = 53, BuiltinToupper = 54, BuiltinMatches = 55,
+    ColumnNum = 56, PrintNum = 57,
+    GetlineInput = 58, GetlineInputVar = 59, GetlineFile = 60, GetlineCommand = 61,
+    GetlineRecord = 62, GetlineResult = 63, BuiltinGensub3 = 64, BuiltinGensub4 = 65,
+    BuiltinAsort1 = 66, BuiltinAsort2 = 67, BuiltinAsort3 = 68,
+    BuiltinAsorti1 = 69, BuiltinAsorti2 = 70, BuiltinAsorti3 = 71,
+    ForInStart = 72, ForInNext = 73, ForInKey = 74, ForInEnd = 75,
+    BuiltinSystime = 76, BuiltinMktime = 77, BuiltinStrftime = 78,
+    BuiltinAnd = 79, BuiltinOr = 80, BuiltinXor = 81, BuiltinLshift = 82, BuiltinRshift = 83,
+    BuiltinCompl = 84, BuiltinStrtonum = 85,
+    BuiltinLengthArr = 86, BuiltinTypeof = 87,
+    NextFile = 88, CheckFileOpened = 89, NextRecordInFile = 90, SkipFile = 91,
+}
+
+impl VmProgram {
+    pub fn save(&self) -> Vec<u8> {
+        let mut writer = Writer { bytes: vec![] };
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.u32(FORMAT_VERSION);
+        writer.str(env!("CARGO_PKG_VERSION").as_bytes());
+
+        let specials = SclSpecial::variants();
+        writer.len(specials.len());
+        for (name, _special) in specials {
+            writer.str(name.as_bytes());
+        }
+
+        let mut scalars: Vec<(&Symbol, &GlobalScalarId)> = self.analysis.global_scalars.mapping().iter().collect();
+        scalars.sort_by_key(|(_name, id)| id.id);
+        writer.len(scalars.len());
+        for (name, _id) in scalars {
+            writer.str(name.to_str().as_bytes());
+        }
+
+        let mut arrays: Vec<(&Symbol, &GlobalArrayId)> = self.analysis.global_arrays.mapping().iter().collect();
+        arrays.sort_by_key(|(_name, id)| id.id);
+        writer.len(arrays.len());
+        for (name, id) in arrays {
+            writer.str(name.to_str().as_bytes());
+            writer.scalar_type(self.analysis.array_types[id.id]);
+        }
+
+        writer.len(self.functions.len());
+        for func in &self.functions {
+            writer.str(func.name().to_str().as_bytes());
+            writer.scalar_type(func.return_type());
+            let args = func.args();
+            writer.len(args.len());
+            for (idx, arg) in args.iter().enumerate() {
+                writer.str(arg.name.to_str().as_bytes());
+                writer.u8(match arg.typ {
+                    ArgT::Scalar => 0,
+                    ArgT::Array => 1,
+                    ArgT::Unknown => 2,
+                });
+                writer.scalar_type(func.arg_types()[idx]);
+                writer.scalar_type(func.elem_types()[idx]);
+            }
+            writer.len(func.chunk().len());
+            for code in func.chunk().iter() {
+                writer.code(code);
+            }
+        }
+        writer.bytes
+    }
+
+    // Loads a program written by save and validates it
+    pub fn load(bytes: &[u8], symbolizer: &mut Symbolizer) -> Result<VmProgram, PrintableError> {
+        let mut reader = Reader { bytes, pos: 0, specials: vec![] };
+        let prog = reader.program(symbolizer).map_err(|msg| PrintableError::new(format!("rawk: invalid precompiled program, {}", msg)))?;
+        validate_program(&prog)?;
+        Ok(prog)
+    }
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+    fn str(&mut self, str: &[u8]) {
+        self.len(str.len());
+        self.bytes.extend_from_slice(str);
+    }
+    fn scalar_type(&mut self, typ: ScalarType) {
+        self.u8(typ as u8);
+    }
+    fn offset(&mut self, offset: isize) {
+        self.bytes.extend_from_slice(&(offset as i64).to_le_bytes());
+    }
+    fn incr(&mut self, incr: Incr) {
+        self.u8(match incr {
+            Incr::Inc => 0,
+            Incr::Dec => 1,
+            Incr::PreInc => 2,
+            Incr::PreDec => 3,
+            Incr::PostInc => 4,
+            Incr::PostDec => 5,
+        });
+    }
+    fn cmp(&mut self, cmp: Cmp) {
+        self.u8(match cmp {
+            Cmp::Lt => 0,
+            Cmp::Gt => 1,
+            Cmp::LtEq => 2,
+            Cmp::GtEq => 3,
+            Cmp::EqEq => 4,
+            Cmp::Neq => 5,
+        });
+    }
+    fn indices(&mut self, indices: Indices) {
+        match indices {
+            Indices::Str(count) => {
+                self.u8(0);
+                self.u32(count);
+            }
+            Indices::Num => self.u8(1),
+            Indices::Var => self.u8(2),
+        }
+    }
+    fn tagged(&mut self, tag: u8, operand: usize) {
+        self.u8(tag);
+        self.len(operand);
+    }
+
+    fn code(&mut self, code: &Code) {
+        if let Some(tag) = simple_tag(code) {
+            self.u8(tag);
+            return;
+        }
+        match code {
+            Code::ClearGscl(id) => self.tagged(100, id.id),
+            Code::ClearArgScl(arg_idx) => self.tagged(101, *arg_idx),
+            Code::AssignGsclVar(id) => self.tagged(102, id.id),
+            Code::AssignGsclNum(id) => self.tagged(103, id.id),
+            Code::AssignGsclStr(id) => self.tagged(104, id.id),
+            Code::AssignRetGsclVar(id) => self.tagged(105, id.id),
+            Code::AssignRetGsclNum(id) => self.tagged(106, id.id),
+            Code::AssignRetGsclStr(id) => self.tagged(107, id.id),
+            Code::GlobalArr(id) => self.tagged(108, id.id),
+            Code::GsclVar(id) => self.tagged(109, id.id),
+            Code::GsclNum(id) => self.tagged(110, id.id),
+            Code::GsclStr(id) => self.tagged(111, id.id),
+            Code::AssignArgVar { arg_idx } => self.tagged(112, *arg_idx),
+            Code::AssignArgStr { arg_idx } => self.tagged(113, *arg_idx),
+            Code::AssignArgNum { arg_idx } => self.tagged(114, *arg_idx),
+            Code::AssignRetArgVar { arg_idx } => self.tagged(115, *arg_idx),
+            Code::AssignRetArgStr { arg_idx } => self.tagged(116, *arg_idx),
+            Code::AssignRetArgNum { arg_idx } => self.tagged(117, *arg_idx),
+            Code::ArgVar { arg_idx } => self.tagged(118, *arg_idx),
+            Code::ArgNum { arg_idx } => self.tagged(119, *arg_idx),
+            Code::ArgStr { arg_idx } => self.tagged(120, *arg_idx),
+            Code::ArgArray { arg_idx } => self.tagged(121, *arg_idx),
+            Code::AssignSclSpecialVar(special) => self.tagged(122, *special as usize),
+            Code::AssignRetSclSpecialVar(special) => self.tagged(123, *special as usize),
+            Code::SclSpecialVar(special) => self.tagged(124, *special as usize),
+            Code::IncGscl { id, incr } => {
+                self.tagged(125, id.id);
+                self.incr(*incr);
+            }
+            Code::IncArg { arg_idx, incr } => {
+                self.tagged(126, *arg_idx);
+                self.incr(*incr);
+            }
+            Code::IncSclSpecial { special, incr } => {
+                self.tagged(127, *special as usize);
+                self.incr(*incr);
+            }
+            Code::IncArray { indices, incr } => {
+                self.u8(128);
+                self.indices(*indices);
+                self.incr(*incr);
+            }
+            Code::IncNumArray { indices, incr } => {
+                self.u8(129);
+                self.indices(*indices);
+                self.incr(*incr);
+            }
+            Code::IncColumn { incr } => {
+                self.u8(130);
+                self.incr(*incr);
+            }
+            Code::Concat { count } => self.tagged(131, *count),
+            Code::ArrayMember { indices } => self.indices_code(132, *indices),
+            Code::AssignArray { indices } => self.indices_code(133, *indices),
+            Code::AssignArrayNum { indices } => self.indices_code(134, *indices),
+            Code::AssignArrayStr { indices } => self.indices_code(135, *indices),
+            Code::AssignRetArray { indices } => self.indices_code(136, *indices),
+            Code::AssignRetArrayNum { indices } => self.indices_code(137, *indices),
+            Code::AssignRetArrayStr { indices } => self.indices_code(138, *indices),
+            Code::ArrayIndex { indices } => self.indices_code(139, *indices),
+            Code::AssignNumArray { indices } => self.indices_code(140, *indices),
+            Code::AssignRetNumArray { indices } => self.indices_code(141, *indices),
+            Code::NumArrayIndex { indices } => self.indices_code(142, *indices),
+            Code::Call { target, args } => {
+                self.tagged(143, *target);
+                self.len(*args);
+            }
+            Code::Printf { num_args } => self.tagged(144, *num_args),
+            Code::ConstStr { str } => {
+                self.u8(145);
+                self.str(str.bytes());
+            }
+            Code::ConstStrNum { strnum } => {
+                self.u8(146);
+                self.str(strnum.bytes());
+            }
+            Code::ConstNum { num } => {
+                self.u8(147);
+                self.bytes.extend_from_slice(&num.to_le_bytes());
+            }
+            Code::Sub3 { global } => {
+                self.u8(148);
+                self.bool(*global);
+            }
+            Code::ConstColumn { idx } => self.tagged(149, *idx),
+            Code::ConstColumnNum { idx } => self.tagged(150, *idx),
+            Code::AddConst { num } => {
+                self.u8(151);
+                self.bytes.extend_from_slice(&num.to_le_bytes());
+            }
+            Code::AddGsclNum { id, delta } => {
+                self.tagged(152, id.id);
+                self.bytes.extend_from_slice(&delta.to_le_bytes());
+            }
+            Code::RelJumpIfFalseNum { offset } => self.jump(153, *offset),
+            Code::RelJumpIfFalseVar { offset } => self.jump(154, *offset),
+            Code::RelJumpIfTrueNum { offset } => self.jump(155, *offset),
+            Code::RelJumpIfFalseStr { offset } => self.jump(156, *offset),
+            Code::RelJumpIfTrueStr { offset } => self.jump(157, *offset),
+            Code::RelJumpIfTrueVar { offset } => self.jump(158, *offset),
+            Code::RelJumpIfTrueNextLine { offset } => self.jump(159, *offset),
+            Code::RelJumpIfFalseNextLine { offset } => self.jump(160, *offset),
+            Code::RelJumpIfCmpNum { cmp, jump_if, offset } => {
+                self.u8(161);
+                self.cmp(*cmp);
+                self.bool(*jump_if);
+                self.offset(*offset);
+            }
+            Code::RelJumpIfCmpVar { cmp, jump_if, offset } => {
+                self.u8(162);
+                self.cmp(*cmp);
+                self.bool(*jump_if);
+                self.offset(*offset);
+            }
+            Code::RelJump { offset } => self.jump(163, *offset),
+            Code::CallNative { id } => self.tagged(164, *id),
+            _ => panic!("compiler bug: {:?} should have been resolved before saving", code),
+        }
+    }
+    fn indices_code(&mut self, tag: u8, indices: Indices) {
+        self.u8(tag);
+        self.indices(indices);
+    }
+    fn jump(&mut self, tag: u8, offset: isize) {
+        self.u8(tag);
+        self.offset(offset);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    // Indexed by the special numbers used in the file
+    specials: Vec<SclSpecial>,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.pos < len {
+            return Err("file is truncated".to_string());
+        }
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn len(&mut self) -> Result<usize, String> {
+        Ok(self.u32()? as usize)
+    }
+    fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(format!("{} is not a bool", other)),
+        }
+    }
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn str(&mut self) -> Result<&'a [u8], String> {
+        let len = self.len()?;
+        self.take(len)
+    }
+    fn name(&mut self, symbolizer: &mut Symbolizer) -> Result<Symbol, String> {
+        match std::str::from_utf8(self.str()?) {
+            Ok(name) => Ok(symbolizer.get(name)),
+            Err(_) => Err("name is not utf-8".to_string()),
+        }
+    }
+    fn scalar_type(&mut self) -> Result<ScalarType, String> {
+        let tag = self.u8()?;
+        [ScalarType::Str, ScalarType::Num, ScalarType::Var]
+            .into_iter()
+            .find(|typ| *typ as u8 == tag)
+            .ok_or_else(|| format!("{} is not a type", tag))
+    }
+    fn offset(&mut self) -> Result<isize, String> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()) as isize)
+    }
+    fn special(&mut self) -> Result<SclSpecial, String> {
+        let idx = self.len()?;
+        self.specials.get(idx).copied().ok_or_else(|| format!("special {} does not exist", idx))
+    }
+    fn gscl(&mut self) -> Result<GlobalScalarId, String> {
+        Ok(GlobalScalarId { id: self.len()? })
+    }
+    fn incr(&mut self) -> Result<Incr, String> {
+        Ok(match self.u8()? {
+            0 => Incr::Inc,
+            1 => Incr::Dec,
+            2 => Incr::PreInc,
+            3 => Incr::PreDec,
+            4 => Incr::PostInc,
+            5 => Incr::PostDec,
+            other => return Err(format!("{} is not an increment", other)),
+        })
+    }
+    fn cmp(&mut self) -> Result<Cmp, String> {
+        Ok(match self.u8()? {
+            0 => Cmp::Lt,
+            1 => Cmp::Gt,
+            2 => Cmp::LtEq,
+            3 => Cmp::GtEq,
+            4 => Cmp::EqEq,
+            5 => Cmp::Neq,
+            other => return Err(format!("{} is not a comparison", other)),
+        })
+    }
+    fn indices(&mut self) -> Result<Indices, String> {
+        Ok(match self.u8()? {
+            0 => Indices::Str(self.u32()?),
+            1 => Indices::Num,
+            2 => Indices::Var,
+            other => return Err(format!("{} is not an array index kind", other)),
+        })
+    }
+
+    fn code(&mut self) -> Result<Code, String> {
+        let tag = self.u8()?;
+        if let Some(code) = simple_code(tag) {
+            return Ok(code);
+        }
+        Ok(match tag {
+            100 => Code::ClearGscl(self.gscl()?),
+            101 => Code::ClearArgScl(self.len()?),
+            102 => Code::AssignGsclVar(self.gscl()?),
+            103 => Code::AssignGsclNum(self.gscl()?),
+            104 => Code::AssignGsclStr(self.gscl()?),
+            105 => Code::AssignRetGsclVar(self.gscl()?),
+            106 => Code::AssignRetGsclNum(self.gscl()?),
+            107 => Code::AssignRetGsclStr(self.gscl()?),
+            108 => Code::GlobalArr(GlobalArrayId { id: self.len()? }),
+            109 => Code::GsclVar(self.gscl()?),
+            110 => Code::GsclNum(self.gscl()?),
+            111 => Code::GsclStr(self.gscl()?),
+            112 => Code::AssignArgVar { arg_idx: self.len()? },
+            113 => Code::AssignArgStr { arg_idx: self.len()? },
+            114 => Code::AssignArgNum { arg_idx: self.len()? },
+            115 => Code::AssignRetArgVar { arg_idx: self.len()? },
+            116 => Code::AssignRetArgStr { arg_idx: self.len()? },
+            117 => Code::AssignRetArgNum { arg_idx: self.len()? },
+            118 => Code::ArgVar { arg_idx: self.len()? },
+            119 => Code::ArgNum { arg_idx: self.len()? },
+            120 => Code::ArgStr { arg_idx: self.len()? },
+            121 => Code::ArgArray { arg_idx: self.len()? },
+            122 => Code::AssignSclSpecialVar(self.special()?),
+            123 => Code::AssignRetSclSpecialVar(self.special()?),
+            124 => Code::SclSpecialVar(self.special()?),
+            125 => Code::IncGscl { id: self.gscl()?, incr: self.incr()? },
+            126 => Code::IncArg { arg_idx: self.len()?, incr: self.incr()? },
+            127 => Code::IncSclSpecial { special: self.special()?, incr: self.incr()? },
+            128 => Code::IncArray { indices: self.indices()?, incr: self.incr()? },
+            129 => Code::IncNumArray { indices: self.indices()?, incr: self.incr()? },
+            130 => Code::IncColumn { incr: self.incr()? },
+            131 => Code::Concat { count: self.len()? },
+            132 => Code::ArrayMember { indices: self.indices()? },
+            133 => Code::AssignArray { indices: self.indices()? },
+            134 => Code::AssignArrayNum { indices: self.indices()? },
+            135 => Code::AssignArrayStr { indices: self.indices()? },
+            136 => Code::AssignRetArray { indices: self.indices()? },
+            137 => Code::AssignRetArrayNum { indices: self.indices()? },
+            138 => Code::AssignRetArrayStr { indices: self.indices()? },
+            139 => Code::ArrayIndex { indices: self.indices()? },
+            140 => Code::AssignNumArray { indices: self.indices()? },
+            141 => Code::AssignRetNumArray { indices: self.indices()? },
+            142 => Code::NumArrayIndex { indices: self.indices()? },
+            143 => Code::Call { target: self.len()?, args: self.len()? },
+            144 => Code::Printf { num_args: self.len()? },
+            145 => Code::ConstStr { str: RcAwkStr::new_bytes(self.str()?.to_vec()) },
+            146 => Code::ConstStrNum { strnum: RcAwkStr::new_bytes(self.str()?.to_vec()) },
+            147 => Code::ConstNum { num: self.f64()? },
+            148 => Code::Sub3 { global: self.bool()? },
+            149 => Code::ConstColumn { idx: self.len()? },
+            150 => Code::ConstColumnNum { idx: self.len()? },
+            151 => Code::AddConst { num: self.f64()? },
+            152 => Code::AddGsclNum { id: self.gscl()?, delta: f32::from_le_bytes(self.take(4)?.try_into().unwrap()) },
+            153 => Code::RelJumpIfFalseNum { offset: self.offset()? },
+            154 => Code::RelJumpIfFalseVar { offset: self.offset()? },
+            155 => Code::RelJumpIfTrueNum { offset: self.offset()? },
+            156 => Code::RelJumpIfFalseStr { offset: self.offset()? },
+            157 => Code::RelJumpIfTrueStr { offset: self.offset()? },
+            158 => Code::RelJumpIfTrueVar { offset: self.offset()? },
+            159 => Code::RelJumpIfTrueNextLine { offset: self.offset()? },
+            160 => Code::RelJumpIfFalseNextLine { offset: self.offset()? },
+            161 => Code::RelJumpIfCmpNum { cmp: self.cmp()?, jump_if: self.bool()?, offset: self.offset()? },
+            162 => Code::RelJumpIfCmpVar { cmp: self.cmp()?, jump_if: self.bool()?, offset: self.offset()? },
+            163 => Code::RelJump { offset: self.offset()? },
+            164 => Code::CallNative { id: self.len()? },
+            other => return Err(format!("{} is not an op", other)),
+        })
+    }
+
+    fn header(&mut self) -> Result<(), String> {
+        if self.take(MAGIC.len()).ok() != Some(MAGIC) {
+            return Err("the file was not written by rawk --compile".to_string());
+        }
+        let format_version = self.u32()?;
+        let rawk_version = String::from_utf8_lossy(self.str()?).to_string();
+        if format_version != FORMAT_VERSION || rawk_version != env!("CARGO_PKG_VERSION") {
+            return Err(format!("it was compiled by rawk {} and this is rawk {}. Recompile it with --compile", rawk_version, env!("CARGO_PKG_VERSION")));
+        }
+        Ok(())
+    }
+
+    fn program(&mut self, symbolizer: &mut Symbolizer) -> Result<VmProgram, String> {
+        self.header()?;
+
+        for _ in 0..self.len()? {
+            let name = String::from_utf8_lossy(self.str()?).to_string();
+            match SclSpecial::try_from(name.as_str()) {
+                Ok(special) => self.specials.push(special),
+                Err(_) => return Err(format!("special variable {} does not exist", name)),
+            }
+        }
+
+        let mut global_scalars = SymbolMapping::new();
+        for _ in 0..self.len()? {
+            global_scalars.insert(&self.name(symbolizer)?);
+        }
+        let mut global_arrays = SymbolMapping::new();
+        let mut array_types = vec![];
+        for _ in 0..self.len()? {
+            global_arrays.insert(&self.name(symbolizer)?);
+            array_types.push(self.scalar_type()?);
+        }
+        if global_arrays.len() != array_types.len() {
+            return Err("global array names are not unique".to_string());
+        }
+        let mut analysis = AnalysisResults::new(global_scalars, global_arrays, HashSet::new());
+        analysis.array_types = array_types;
+
+        let mut functions = vec![];
+        for _ in 0..self.len()? {
+            let name = self.name(symbolizer)?;
+            let return_type = self.scalar_type()?;
+            let (mut args, mut arg_types, mut elem_types) = (vec![], vec![], vec![]);
+            for _ in 0..self.len()? {
+                let name = self.name(symbolizer)?;
+                let typ = match self.u8()? {
+                    0 => ArgT::Scalar,
+                    1 => ArgT::Array,
+                    2 => ArgT::Unknown,
+                    other => return Err(format!("{} is not an arg type", other)),
+                };
+                args.push(Arg::new_optional(name, typ));
+                arg_types.push(self.scalar_type()?);
+                elem_types.push(self.scalar_type()?);
+            }
+            let mut chunk = Chunk::new();
+            for _ in 0..self.len()? {
+                chunk.push(self.code()?);
+            }
+            let func = TypedUserFunction::precompiled(name, args, arg_types, elem_types, return_type);
+            functions.push((Rc::new(func), chunk));
+        }
+        if self.pos != self.bytes.len() {
+            return Err("file has trailing bytes".to_string());
+        }
+        if functions.iter().filter(|(func, _chunk)| func.is_main()).count() != 1 {
+            return Err("there must be exactly one main function".to_string());
+        }
+
+        let mut func_map = FunctionMap::new(HashMap::new(), symbolizer);
+        let mut vm_funcs = vec![];
+        for (func, chunk) in functions {
+            if func_map.get_user_function(&func.name()).is_some() {
+                return Err(format!("function {} is defined twice", func.name()));
+            }
+            let id = func_map.insert(func.clone());
+            vm_funcs.push(VmFunc::new(chunk, id, func));
+        }
+        Ok(VmProgram::new(vm_funcs, analysis, func_map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::{compile, Chunk};
+    use crate::vm::{Cmp, Code, Incr, Indices, VmFunc, VmProgram};
+    use crate::awk_str::RcAwkStr;
+    use crate::parser::SclSpecial;
+    use crate::typing::{GlobalArrayId, GlobalScalarId};
+    use crate::vm::precompiled::{Reader, Writer};
+    use crate::{analyze, lex, parse, Symbolizer};
+
+    fn compiled(prog: &str) -> (VmProgram, Symbolizer) {
+        let mut symbolizer = Symbolizer::new();
+        let ast = analyze(parse(lex(prog, &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
+        (compile(ast, &mut symbolizer, 4).unwrap(), symbolizer)
+    }
+
+    #[test]
+    fn test_code_round_trip() {
+        let gscl = GlobalScalarId { id: 3 };
+        let codes = vec![
+            Code::NumToVar, Code::ColumnNum, Code::BuiltinMatches, Code::RetStr,
+            Code::PrintNum, Code::GetlineInput, Code::GetlineInputVar, Code::GetlineFile, Code::GetlineCommand,
+            Code::GetlineRecord, Code::GetlineResult, Code::BuiltinGensub4,
+            Code::BuiltinAsort3, Code::BuiltinAsorti1, Code::ForInStart, Code::ForInNext, Code::ForInKey, Code::ForInEnd,
+            Code::BuiltinSystime, Code::BuiltinMktime, Code::BuiltinStrftime,
+            Code::BuiltinAnd, Code::BuiltinOr, Code::BuiltinXor, Code::BuiltinLshift, Code::BuiltinRshift,
+            Code::BuiltinCompl, Code::BuiltinStrtonum, Code::BuiltinLengthArr, Code::BuiltinTypeof,
+            Code::NextFile, Code::CheckFileOpened, Code::NextRecordInFile, Code::SkipFile,
+            Code::ClearGscl(gscl), Code::ClearArgScl(2), Code::AssignRetGsclStr(gscl), Code::GlobalArr(GlobalArrayId { id: 1 }),
+            Code::GsclNum(gscl), Code::AssignRetArgNum { arg_idx: 4 }, Code::ArgArray { arg_idx: 1 },
+            Code::AssignRetSclSpecialVar(SclSpecial::SUBSEP), Code::SclSpecialVar(SclSpecial::NR),
+            Code::IncGscl { id: gscl, incr: Incr::PostDec }, Code::IncArg { arg_idx: 0, incr: Incr::PreInc },
+            Code::IncSclSpecial { special: SclSpecial::NF, incr: Incr::Dec }, Code::IncArray { indices: Indices::Str(3), incr: Incr::Inc },
+            Code::IncNumArray { indices: Indices::Num, incr: Incr::PostInc }, Code::IncColumn { incr: Incr::PreDec },
+            Code::Concat { count: 5 }, Code::ArrayMember { indices: Indices::Var }, Code::AssignRetArrayStr { indices: Indices::Str(1) },
+            Code::NumArrayIndex { indices: Indices::Num }, Code::Call { target: 7, args: 2 }, Code::CallNative { id: 3 }, Code::Printf { num_args: 3 },
+            Code::ConstStr { str: RcAwkStr::new_str("abc") }, Code::ConstStrNum { strnum: RcAwkStr::new_str("1.5") },
+            Code::ConstNum { num: -0.25 }, Code::Sub3 { global: true }, Code::ConstColumn { idx: 9 }, Code::ConstColumnNum { idx: 0 },
+            Code::AddConst { num: 1e300 }, Code::AddGsclNum { id: gscl, delta: -2.5 },
+            Code::RelJumpIfFalseNextLine { offset: -12 }, Code::RelJumpIfCmpNum { cmp: Cmp::GtEq, jump_if: false, offset: 4 },
+            Code::RelJumpIfCmpVar { cmp: Cmp::Neq, jump_if: true, offset: -1 }, Code::RelJump { offset: 1 << 40 },
+        ];
+        let mut writer = Writer { bytes: vec![] };
+        for code in &codes {
+            writer.code(code);
+        }
+        let specials = SclSpecial::variants().iter().map(|(_name, special)| *special).collect();
+        let mut reader = Reader { bytes: &writer.bytes, pos: 0, specials };
+        for code in &codes {
+            assert_eq!(reader.code().unwrap(), *code);
+        }
+        assert_eq!(reader.pos, writer.bytes.len());
+    }
+
+    #[test]
+    fn test_program_round_trip() {
+        let (prog, _symbolizer) = compiled("function f(a, arr) { arr[a] = a; return a \"x\" } BEGIN { NR = 2; b[1]; print f(1, b) + FS }");
+        let saved = prog.save();
+        let loaded = VmProgram::load(&saved, &mut Symbolizer::new()).unwrap();
+        assert_eq!(loaded.pretty_print(), prog.pretty_print());
+        assert_eq!(loaded.save(), saved);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let (prog, mut symbolizer) = compiled("BEGIN { a = 1; print a }");
+        let saved = prog.save();
+        let err = |bytes: &[u8]| VmProgram::load(bytes, &mut Symbolizer::new()).err().unwrap().msg;
+
+        assert!(err(b"BEGIN { print 1 }").ends_with("the file was not written by rawk --compile"));
+        assert!(err(&saved[..saved.len() - 1]).ends_with("file is truncated"));
+
+        let mut trailing = saved.clone();
+        trailing.push(0);
+        assert!(err(&trailing).ends_with("file has trailing bytes"));
+
+        let mut old_version = saved.clone();
+        old_version[super::MAGIC.len()] += 1;
+        assert!(err(&old_version).contains("Recompile it with --compile"));
+
+        // A jump past the end of the function
+        let ast = analyze(parse(lex("BEGIN { }", &mut symbolizer).unwrap(), &mut symbolizer).unwrap(), &mut symbolizer).unwrap();
+        let mut prog = compile(ast, &mut symbolizer, 4).unwrap();
+        let mut chunk = Chunk::new();
+        chunk.push(Code::RelJump { offset: 5 });
+        prog.functions[0] = VmFunc::new(chunk, 0, prog.func_map.get_by_id(0).unwrap().clone());
+        assert!(err(&prog.save()).contains("jump outside of chunk"));
+    }
+}
@@ -7,6 +7,8 @@ use crate::awk_str::{AwkStr, RcAwkStr};
 #[derive(Clone, PartialEq)]
 pub enum RuntimeScalar {
     Str(RcAwkStr),
+    // Input from the user (fields, split(), -v) which compares as a number if it
+    // looks like one. The empty StrNum is the uninitialized value, it compares as both 0 and "".
     StrNum(RcAwkStr),
     Num(f64),
 }
@@ -26,6 +28,15 @@ pub enum StringScalar {
     StrNum(RcAwkStr),
 }
 impl StringScalar {
+    // Non-empty input is a strnum. An empty field is just the empty string it must not compare
+    // as the uninitialized value.
+    pub fn input(str: AwkStr) -> Self {
+        if str.is_empty() {
+            StringScalar::Str(str.rc())
+        } else {
+            StringScalar::StrNum(str.rc())
+        }
+    }
     pub fn downgrade_or_clone(self) -> AwkStr {
         let s = match self {
             StringScalar::Str(s) => s,
@@ -33,12 +44,6 @@ impl StringScalar {
         };
         s.downgrade_or_clone()
     }
-    pub fn truthy(&self) -> bool {
-       match self {
-           StringScalar::Str(s) => s.truthy(),
-           StringScalar::StrNum(s) => s.truthy(),
-       }
-    }
 }
 impl Deref for StringScalar {
     type Target = RcAwkStr;
@@ -49,9 +54,9 @@ impl Deref for StringScalar {
         }
     }
 }
-impl Into<RuntimeScalar> for StringScalar {
-    fn into(self) -> RuntimeScalar {
-        match self {
+impl From<StringScalar> for RuntimeScalar {
+    fn from(val: StringScalar) -> Self {
+        match val {
             StringScalar::Str(s) => RuntimeScalar::Str(s),
             StringScalar::StrNum(s) => RuntimeScalar::StrNum(s),
         }
@@ -1,10 +1,11 @@
+use std::cell::Ref;
 use std::ops::Deref;
 use std::rc::Rc;
 use crate::typing::{ITypedFunction, TypedUserFunction};
 use crate::compiler::Chunk;
-#[cfg(test)]
+use crate::parser::{Arg, ScalarType};
+use crate::stack_counter::StackCounter;
 use crate::symbolizer::Symbol;
-#[cfg(test)]
 use crate::vm::{VmProgram};
 use crate::vm::bytecode::CodeAndImmed;
 
@@ -29,22 +30,29 @@ impl VmFunc {
     }
 
 
-    pub fn num_scalar_args(&self) -> usize {
-        self.parser_func.num_scalar_args()
+    pub fn args(&self) -> Ref<'_, Vec<Arg>> {
+        self.parser_func.args()
     }
-    pub fn num_array_args(&self) -> usize {
-        self.parser_func.num_array_args()
+    pub fn arg_types(&self) -> &[ScalarType] {
+        self.parser_func.arg_types()
+    }
+    pub fn elem_types(&self) -> Ref<'_, Vec<ScalarType>> {
+        self.parser_func.elem_types()
+    }
+    pub fn return_type(&self) -> ScalarType {
+        self.parser_func.return_type()
+    }
+    pub fn arg_stacks(&self) -> StackCounter {
+        self.parser_func.arg_stacks()
     }
 
     pub fn chunk(&self) -> &Chunk {
         &self.chunk
     }
 
-    #[cfg(test)]
     pub fn name(&self) -> Symbol {
         self.parser_func.name()
     }
-    #[cfg(test)]
     pub fn pretty_print(&self, func: &VmFunc, prog: &VmProgram, output: &mut Vec<u8>) {
         self.chunk.pretty_print(func, prog, output)
     }